@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Warnings acknowledged with `layer ack`, one id per line in the git dir.
+pub const ACKS_FILE: &str = "LAYER_ACKS";
+
+/// Id of the cross-filesystem warning on WSL.
+pub const WSL_ID: &str = "wsl";
+
+/// Id of a layered entry that git still tracks.
+pub fn exposed_id(entry: &str) -> String {
+    format!("exposed:{entry}")
+}
+
+/// Id of a tracked context file that isn't layered.
+pub fn tracked_id(path: &str) -> String {
+    format!("tracked:{path}")
+}
+
+/// Id of a context file that isn't layered yet.
+pub fn discovered_id(path: &str) -> String {
+    format!("discovered:{path}")
+}
+
+/// Whether `id` has the shape of a warning id layer prints.
+pub fn is_known_id(id: &str) -> bool {
+    id == WSL_ID
+        || ["exposed:", "tracked:", "discovered:"]
+            .iter()
+            .any(|prefix| id.strip_prefix(prefix).is_some_and(|rest| !rest.is_empty()))
+}
+
+pub fn load(git_dir: &Path) -> BTreeSet<String> {
+    fs::read_to_string(git_dir.join(ACKS_FILE))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn save(git_dir: &Path, acks: &BTreeSet<String>) -> Result<()> {
+    let path = git_dir.join(ACKS_FILE);
+    if acks.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        return Ok(());
+    }
+    let mut content = acks.iter().cloned().collect::<Vec<_>>().join("\n");
+    content.push('\n');
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_ids() {
+        assert!(is_known_id("wsl"));
+        assert!(is_known_id(&exposed_id("CLAUDE.md")));
+        assert!(is_known_id(&discovered_id(".cursor/")));
+        assert!(!is_known_id("exposed:"));
+        assert!(!is_known_id("CLAUDE.md"));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).is_empty());
+        let acks: BTreeSet<String> = [tracked_id("AGENTS.md"), WSL_ID.to_string()].into_iter().collect();
+        save(dir.path(), &acks).unwrap();
+        assert_eq!(load(dir.path()), acks);
+        save(dir.path(), &BTreeSet::new()).unwrap();
+        assert!(!dir.path().join(ACKS_FILE).exists());
+    }
+}
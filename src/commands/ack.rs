@@ -0,0 +1,51 @@
+use crate::acks;
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{bail, Result};
+
+/// Acknowledge warnings so `status` and the hook line summarize them as a
+/// count instead of repeating them; `remove` brings them back.
+pub fn run(ids: Vec<String>, remove: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let mut acked = acks::load(&ctx.git_dir);
+
+    if ids.is_empty() {
+        if acked.is_empty() {
+            outln!("No acknowledged warnings.");
+            return Ok(2);
+        }
+        for id in &acked {
+            outln!("  {} {id}", ui::info());
+        }
+        return Ok(0);
+    }
+
+    let mut changed = 0usize;
+    for id in ids.iter().map(|id| id.trim()) {
+        if remove {
+            if acked.remove(id) {
+                outln!("  {} Un-acknowledged {id}", ui::ok());
+                changed += 1;
+            } else {
+                outln!("  {} {id} was not acknowledged", ui::info());
+            }
+            continue;
+        }
+        if !acks::is_known_id(id) {
+            bail!("'{id}' is not a warning id. Ids look like exposed:<entry>, tracked:<path>, discovered:<path>, or wsl");
+        }
+        if acked.insert(id.to_string()) {
+            outln!("  {} Acknowledged {id}", ui::ok());
+            changed += 1;
+        } else {
+            outln!("  {} {id} is already acknowledged", ui::info());
+        }
+    }
+
+    if changed == 0 {
+        return Ok(2);
+    }
+    acks::save(&ctx.git_dir, &acked)?;
+    Ok(0)
+}
@@ -1,29 +1,199 @@
-use crate::commands::scan;
+use crate::commands::patterns::json_escape;
+use crate::commands::why_cmd::relativize;
+use crate::config;
+use crate::discovery::{self, Candidate, Filter};
 use crate::exclude_file::{ensure_exclude_file_for_write, normalize_entry, ExcludeFile};
 use crate::git;
 use crate::git::RepoContext;
-use crate::patterns::PatternCategory;
+use crate::meta;
+use crate::session;
 use crate::tree_picker;
 use crate::ui;
-use anyhow::{anyhow, Result};
-use std::collections::{BTreeMap, HashSet};
+use crate::ui::outln;
+use anyhow::{anyhow, bail, Result};
+use dialoguer::{Confirm, Select};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 #[derive(Debug, Default)]
 pub struct AddSummary {
     pub added: usize,
-    pub skipped: usize,
+    pub already_layered: usize,
+    pub covered: usize,
+    pub invalid: usize,
+    pub tracked_warnings: usize,
+}
+
+impl AddSummary {
+    pub fn skipped(&self) -> usize {
+        self.already_layered + self.covered + self.invalid
+    }
+
+    fn record(&mut self, outcome: &AddOutcome) {
+        match outcome {
+            AddOutcome::Add { tracked } => {
+                self.added += 1;
+                if *tracked {
+                    self.tracked_warnings += 1;
+                }
+            }
+            AddOutcome::AlreadyLayered => self.already_layered += 1,
+            AddOutcome::Covered(_) => self.covered += 1,
+            AddOutcome::Invalid => self.invalid += 1,
+        }
+    }
+
+    /// One-line breakdown, e.g. "2 added · 3 skipped (1 already layered, 2 covered by a broader entry)".
+    fn describe(&self) -> String {
+        let mut parts = vec![format!("{} added", self.added)];
+        if self.skipped() > 0 {
+            let mut reasons = Vec::new();
+            if self.already_layered > 0 {
+                reasons.push(format!("{} already layered", self.already_layered));
+            }
+            if self.covered > 0 {
+                reasons.push(format!("{} covered by a broader entry", self.covered));
+            }
+            if self.invalid > 0 {
+                reasons.push(format!("{} empty or invalid", self.invalid));
+            }
+            parts.push(format!("{} skipped ({})", self.skipped(), reasons.join(", ")));
+        }
+        if self.tracked_warnings > 0 {
+            parts.push(format!(
+                "{} tracked {}",
+                self.tracked_warnings,
+                if self.tracked_warnings == 1 { "warning" } else { "warnings" }
+            ));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// What `layer add` will do with one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddOutcome {
+    Add { tracked: bool },
+    AlreadyLayered,
+    Covered(String),
+    Invalid,
+}
+
+impl AddOutcome {
+    pub fn reason(&self) -> Option<&'static str> {
+        match self {
+            AddOutcome::Add { .. } => None,
+            AddOutcome::AlreadyLayered => Some("already_layered"),
+            AddOutcome::Covered(_) => Some("covered"),
+            AddOutcome::Invalid => Some("invalid"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-struct InteractiveCandidate {
-    path: String,
-    category: &'static str,
+pub struct PlannedAdd {
+    pub input: String,
+    pub entry: String,
+    pub outcome: AddOutcome,
+}
+
+/// Why an entry would have no effect if it were layered (`add --check-only`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckProblem {
+    Invalid,
+    AlreadyLayered,
+    Covered(String),
+    Tracked,
+    /// Already ignored by another rule, given as `source:line`.
+    Ignored(String),
+    /// Re-included by a negation that outranks the layer section.
+    Negated(String),
 }
 
-pub fn run(files: Vec<String>, interactive: bool, dry_run: bool) -> Result<i32> {
+impl CheckProblem {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CheckProblem::Invalid => "invalid",
+            CheckProblem::AlreadyLayered => "already_layered",
+            CheckProblem::Covered(_) => "covered",
+            CheckProblem::Tracked => "tracked",
+            CheckProblem::Ignored(_) => "ignored",
+            CheckProblem::Negated(_) => "negated",
+        }
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            CheckProblem::Covered(by) | CheckProblem::Ignored(by) | CheckProblem::Negated(by) => Some(by),
+            _ => None,
+        }
+    }
+
+    fn describe(&self, entry: &str) -> String {
+        match self {
+            CheckProblem::Invalid => format!("'{entry}' is empty or not a valid entry"),
+            CheckProblem::AlreadyLayered => format!("'{entry}' already layered"),
+            CheckProblem::Covered(by) => format!("'{entry}' already covered by '{by}'"),
+            CheckProblem::Tracked => format!("'{entry}' is tracked by Git — layering won't hide it until untracked"),
+            CheckProblem::Ignored(at) => format!("'{entry}' is already ignored by {at}"),
+            CheckProblem::Negated(at) => format!("'{entry}' would be re-included by the negation at {at}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckedAdd {
+    pub input: String,
+    pub entry: String,
+    pub problem: Option<CheckProblem>,
+}
+
+/// Flags for `layer add`.
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    pub interactive: bool,
+    pub dry_run: bool,
+    pub check_only: bool,
+    pub force: bool,
+    pub untrack: bool,
+    pub session: bool,
+    pub note: Option<String>,
+}
+
+pub fn run(files: Vec<String>, options: AddOptions, format: ui::Format) -> Result<i32> {
+    let AddOptions {
+        interactive,
+        dry_run,
+        check_only,
+        force,
+        untrack,
+        session,
+        note,
+    } = options;
+    if format.is_json() && !check_only && (!dry_run || interactive) {
+        bail!("JSON output for add is only available with --dry-run or --check-only, without --interactive");
+    }
     let ctx = git::ensure_repo()?;
+
+    if check_only {
+        if files.is_empty() {
+            bail!("no entries provided. Use 'layer add --check-only <entries...>'");
+        }
+        let exclude = ExcludeFile::load(&ctx.exclude_path)?;
+        return run_check(&ctx, &exclude, &files, format);
+    }
     let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
 
+    if dry_run && format != ui::Format::Pretty && !interactive {
+        let plan = plan_add_entries(&ctx, &exclude, &files)?;
+        if format.is_json() {
+            print_plan_json(&plan);
+        } else {
+            print_plan_porcelain(&plan);
+        }
+        let added = plan.iter().any(|p| matches!(p.outcome, AddOutcome::Add { .. }));
+        return Ok(if added { 0 } else { 2 });
+    }
+
     if interactive || (files.is_empty() && ui::is_stdout_tty()) {
         return run_interactive(&ctx, &mut exclude, dry_run);
     }
@@ -32,6 +202,26 @@ pub fn run(files: Vec<String>, interactive: bool, dry_run: bool) -> Result<i32>
         return Err(anyhow!("no files provided. Use 'layer add <files...>' or run in a terminal for interactive mode"));
     }
 
+    let files = if dry_run || format != ui::Format::Pretty || !ui::is_stdout_tty() {
+        files
+    } else {
+        choose_anchoring(&ctx, files)?
+    };
+
+    let plan = plan_add_entries(&ctx, &exclude, &files)?;
+    let tracked: Vec<&str> = plan
+        .iter()
+        .filter(|p| p.outcome == AddOutcome::Add { tracked: true })
+        .map(|p| p.entry.as_str())
+        .collect();
+    let untrack = !tracked.is_empty()
+        && (untrack || (!dry_run && format == ui::Format::Pretty && ui::is_stdout_tty() && confirm_untrack(&tracked)?));
+    if untrack {
+        untrack_entries(&ctx, &tracked, dry_run)?;
+    } else if !force {
+        refuse_tracked(&ctx, &plan)?;
+    }
+
     let summary = apply_add_entries(&ctx, &mut exclude, &files, dry_run)?;
     if dry_run {
         ui::print_dry_run_notice();
@@ -39,6 +229,21 @@ pub fn run(files: Vec<String>, interactive: bool, dry_run: bool) -> Result<i32>
     if summary.added == 0 {
         return Ok(2);
     }
+    let added: Vec<String> = plan
+        .iter()
+        .filter(|p| matches!(p.outcome, AddOutcome::Add { .. }))
+        .map(|p| p.entry.clone())
+        .collect();
+    if let Some(note) = note.filter(|_| !dry_run) {
+        meta::set_note(&ctx.exclude_path, &added, &note)?;
+    }
+    if session && !dry_run {
+        session::record(&ctx.git_dir, &session::current_id()?, &added)?;
+        outln!("  {}", ui::dim_text("Only for this session: removed once this shell exits."));
+        if let Some(warning) = session::fallback_warning() {
+            ui::print_stderr_warning(&warning);
+        }
+    }
 
     Ok(0)
 }
@@ -50,61 +255,468 @@ pub fn apply_add_entries(
     dry_run: bool,
 ) -> Result<AddSummary> {
     let mut summary = AddSummary::default();
+
+    for planned in plan_add_entries(ctx, exclude, entries)? {
+        summary.record(&planned.outcome);
+        let normalized = &planned.entry;
+        match &planned.outcome {
+            AddOutcome::Invalid => {
+                outln!("  {} '{}' is empty or not a valid entry", ui::info(), planned.input.trim());
+                continue;
+            }
+            AddOutcome::AlreadyLayered => {
+                outln!("  {} '{normalized}' already layered", ui::info());
+                continue;
+            }
+            AddOutcome::Covered(by) => {
+                outln!("  {} '{normalized}' already covered by '{by}'", ui::info());
+                continue;
+            }
+            AddOutcome::Add { tracked } => {
+                if *tracked {
+                    ui::print_warning(&format!("'{normalized}' is tracked by Git — layering won't hide it until untracked"));
+                    outln!("  {}", ui::warn_text(&format!("git rm --cached {normalized}")));
+                }
+            }
+        }
+
+        if dry_run {
+            outln!("  {} Would layer '{normalized}'", ui::discovered());
+        } else {
+            exclude.append_entry(normalized);
+            outln!("  {} Layered '{normalized}'", ui::ok());
+        }
+    }
+
+    if summary.added > 0 && !dry_run {
+        exclude.write(&ctx.exclude_path)?;
+    }
+
+    if entries.len() > 1 && (summary.skipped() > 0 || summary.tracked_warnings > 0) {
+        outln!();
+        outln!("  {}", ui::dim_text(&summary.describe()));
+    }
+
+    Ok(summary)
+}
+
+/// Decide what to do with each input without touching the exclude file.
+pub fn plan_add_entries(ctx: &RepoContext, exclude: &ExcludeFile, entries: &[String]) -> Result<Vec<PlannedAdd>> {
     let mut known_entries = exclude.entry_set();
+    let mut plan = Vec::new();
 
     for raw in entries {
         let normalized = normalize_entry(raw);
-        if normalized.is_empty() {
-            summary.skipped += 1;
-            continue;
+        let outcome = if normalized.is_empty() || normalized == "." || normalized.starts_with('#') {
+            AddOutcome::Invalid
+        } else if known_entries.contains(&normalized) {
+            AddOutcome::AlreadyLayered
+        } else if let Some(by) = covering_entry(&known_entries, &normalized) {
+            AddOutcome::Covered(by)
+        } else {
+            AddOutcome::Add {
+                tracked: git::is_tracked(&ctx.root, &normalized)?,
+            }
+        };
+
+        if matches!(outcome, AddOutcome::Add { .. }) {
+            known_entries.insert(normalized.clone());
         }
+        plan.push(PlannedAdd {
+            input: raw.clone(),
+            entry: normalized,
+            outcome,
+        });
+    }
 
-        if known_entries.contains(&normalized) {
-            println!("  {} '{normalized}' already layered", ui::info());
-            summary.skipped += 1;
+    Ok(plan)
+}
+
+/// With `add.strict_exposed` set, refuse a plan that would layer tracked
+/// files instead of just warning about them.
+fn confirm_untrack(tracked: &[&str]) -> Result<bool> {
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "{} tracked by git, so layering alone won't hide {}. Untrack with git rm --cached now?",
+            if tracked.len() == 1 { format!("'{}' is", tracked[0]) } else { format!("{} entries are", tracked.len()) },
+            if tracked.len() == 1 { "it" } else { "them" }
+        ))
+        .default(true)
+        .interact()?;
+    Ok(confirmed)
+}
+
+/// `git rm --cached` each tracked entry so the layer actually hides it.
+/// Glob entries are left alone: git pathspecs don't match the way ignore
+/// patterns do, so untracking them is the user's call.
+fn untrack_entries(ctx: &RepoContext, tracked: &[&str], dry_run: bool) -> Result<()> {
+    for entry in tracked {
+        if git::contains_glob(entry) {
+            outln!("  {} '{entry}' is a pattern; untrack the files it matches with git rm --cached", ui::info());
             continue;
         }
+        let path = entry.trim_start_matches('/').trim_end_matches('/');
+        if dry_run {
+            outln!("  {} Would untrack '{path}'", ui::discovered());
+        } else {
+            git::untrack(&ctx.root, path)?;
+            outln!("  {} Untracked '{path}'", ui::ok());
+        }
+    }
+    Ok(())
+}
 
-        if git::is_tracked(&ctx.root, &normalized)? {
-            ui::print_warning(&format!("'{normalized}' is tracked by Git — layering won't hide it until untracked"));
-            println!("  {}", ui::warn_text(&format!("git rm --cached {normalized}")));
+pub fn refuse_tracked(ctx: &RepoContext, plan: &[PlannedAdd]) -> Result<()> {
+    let tracked: Vec<&str> = plan
+        .iter()
+        .filter(|p| p.outcome == AddOutcome::Add { tracked: true })
+        .map(|p| p.entry.as_str())
+        .collect();
+    if tracked.is_empty() || !config::load(Some(&ctx.root))?.get_bool("add.strict_exposed") {
+        return Ok(());
+    }
+    bail!(
+        "refusing to layer tracked {} {} (add.strict_exposed is on). Untrack with 'git rm --cached', or pass --force",
+        if tracked.len() == 1 { "file" } else { "files" },
+        tracked.join(", ")
+    )
+}
+
+/// A layered directory or glob entry that already hides `entry`, if any.
+fn covering_entry(known: &HashSet<String>, entry: &str) -> Option<String> {
+    let mut covering: Vec<&String> = known
+        .iter()
+        .filter(|k| k.as_str() != entry)
+        .filter(|k| {
+            if k.ends_with('/') {
+                return entry.starts_with(k.as_str());
+            }
+            if !git::contains_glob(k) {
+                return false;
+            }
+            let target = entry.trim_end_matches('/');
+            if k.contains('/') {
+                discovery::wildcard_match(k.trim_start_matches('/'), target)
+            } else {
+                discovery::wildcard_match(k, target.rsplit('/').next().unwrap_or(target))
+            }
+        })
+        .collect();
+    covering.sort();
+    covering.first().map(|k| k.to_string())
+}
+
+/// Validate entries without writing: each one must be valid, new, untracked,
+/// not already ignored, and not undone by a negation. Exits 1 if any isn't.
+fn run_check(ctx: &RepoContext, exclude: &ExcludeFile, entries: &[String], format: ui::Format) -> Result<i32> {
+    let checked = check_add_entries(ctx, exclude, entries)?;
+    let failed = checked.iter().filter(|c| c.problem.is_some()).count();
+
+    match format {
+        ui::Format::Json => print_check_json(&checked),
+        ui::Format::Porcelain => {
+            for c in &checked {
+                match &c.problem {
+                    None => ui::porcelain(&["ok", &c.entry]),
+                    Some(problem) => {
+                        let mut fields = vec!["fail", c.entry.as_str(), problem.reason()];
+                        fields.extend(problem.detail());
+                        ui::porcelain(&fields);
+                    }
+                }
+            }
+        }
+        ui::Format::Pretty | ui::Format::Gha | ui::Format::Template => {
+            for c in &checked {
+                match &c.problem {
+                    None => outln!("  {} '{}' would be layered", ui::ok(), c.entry),
+                    Some(problem @ (CheckProblem::Tracked | CheckProblem::Negated(_))) => {
+                        ui::print_warning(&problem.describe(&c.entry))
+                    }
+                    Some(problem) => outln!("  {} {}", ui::info(), problem.describe(&c.entry)),
+                }
+            }
+            outln!();
+            outln!(
+                "  {}",
+                ui::dim_text(&format!("{} effective · {} not (nothing written)", checked.len() - failed, failed))
+            );
         }
+    }
 
-        if dry_run {
-            println!("  {} Would layer '{normalized}'", ui::discovered());
-        } else {
-            exclude.append_entry(&normalized);
-            println!("  {} Layered '{normalized}'", ui::ok());
+    Ok(if failed == 0 { 0 } else { 1 })
+}
+
+/// The add plan, with each entry that would be added also checked against
+/// the rules git already applies to it.
+pub fn check_add_entries(ctx: &RepoContext, exclude: &ExcludeFile, entries: &[String]) -> Result<Vec<CheckedAdd>> {
+    let plan = plan_add_entries(ctx, exclude, entries)?;
+
+    let probes: Vec<String> = plan
+        .iter()
+        .filter(|p| p.outcome == AddOutcome::Add { tracked: false } && !git::contains_glob(&p.entry))
+        .map(|p| p.entry.trim_start_matches('/').to_string())
+        .collect();
+    let matches = git::check_ignore_bulk(&ctx.root, &probes, true)?;
+
+    Ok(plan
+        .into_iter()
+        .map(|planned| {
+            let problem = match &planned.outcome {
+                AddOutcome::Invalid => Some(CheckProblem::Invalid),
+                AddOutcome::AlreadyLayered => Some(CheckProblem::AlreadyLayered),
+                AddOutcome::Covered(by) => Some(CheckProblem::Covered(by.clone())),
+                AddOutcome::Add { tracked: true } => Some(CheckProblem::Tracked),
+                AddOutcome::Add { tracked: false } => {
+                    matches.get(planned.entry.trim_start_matches('/')).and_then(|m| {
+                        let at = format!("{}:{}", relativize(&ctx.root, &m.source.replace('\\', "/")), m.line);
+                        if !m.pattern.starts_with('!') {
+                            Some(CheckProblem::Ignored(at))
+                        } else if outranks_layer(ctx, &m.source) {
+                            Some(CheckProblem::Negated(at))
+                        } else {
+                            None
+                        }
+                    })
+                }
+            };
+            CheckedAdd {
+                input: planned.input,
+                entry: planned.entry,
+                problem,
+            }
+        })
+        .collect())
+}
+
+/// A negation in a `.gitignore` wins over `.git/info/exclude`, and one in the
+/// exclude file itself may sit after the layer section. Only the global
+/// excludes file ranks below the layer.
+fn outranks_layer(ctx: &RepoContext, source: &str) -> bool {
+    let source = source.replace('\\', "/");
+    git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &source)
+        || source.rsplit('/').next() == Some(".gitignore")
+}
+
+fn print_check_json(checked: &[CheckedAdd]) {
+    let failed = checked.iter().filter(|c| c.problem.is_some()).count();
+    let mut json = String::from("{\n  \"check_only\": true,\n  \"entries\": [\n");
+    for (i, c) in checked.iter().enumerate() {
+        let reason = c.problem.as_ref().map_or("null".to_string(), |p| json_escape(p.reason()));
+        let detail = c
+            .problem
+            .as_ref()
+            .and_then(CheckProblem::detail)
+            .map_or("null".to_string(), json_escape);
+        json.push_str(&format!(
+            "    {{ \"input\": {}, \"entry\": {}, \"effective\": {}, \"reason\": {reason}, \"detail\": {detail} }}",
+            json_escape(&c.input),
+            json_escape(&c.entry),
+            c.problem.is_none(),
+        ));
+        if i + 1 < checked.len() {
+            json.push(',');
         }
-        known_entries.insert(normalized);
-        summary.added += 1;
+        json.push('\n');
     }
+    json.push_str("  ],\n");
+    json.push_str(&format!(
+        "  \"summary\": {{ \"effective\": {}, \"ineffective\": {failed} }}\n",
+        checked.len() - failed
+    ));
+    json.push('}');
+    outln!("{json}");
+}
 
-    if summary.added > 0 && !dry_run {
-        exclude.write(&ctx.exclude_path)?;
+/// One way to anchor a floating entry, and what it would hide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AnchorVariant {
+    entry: String,
+    scope: &'static str,
+    matches: Vec<String>,
+}
+
+impl AnchorVariant {
+    fn describe(&self) -> String {
+        let shown: Vec<&str> = self.matches.iter().take(3).map(String::as_str).collect();
+        let more = self.matches.len().saturating_sub(shown.len());
+        let mut text = format!("{}: {}", self.scope, shown.join(", "));
+        if more > 0 {
+            text.push_str(&format!(" +{more} more"));
+        }
+        text
     }
+}
 
-    Ok(summary)
+/// For entries that match both at the top level and deeper (`docs/` hides
+/// every `docs` directory), show what each anchoring would hide and write
+/// the form the user picks. Escape keeps the entry as typed.
+fn choose_anchoring(ctx: &RepoContext, files: Vec<String>) -> Result<Vec<String>> {
+    if !files.iter().any(|f| is_floating(&normalize_entry(f))) {
+        return Ok(files);
+    }
+    let mut paths: Vec<String> = git::list_tracked(&ctx.root)?.into_iter().collect();
+    paths.extend(git::list_untracked(&ctx.root)?);
+
+    let theme = ui::layer_theme();
+    let mut chosen = Vec::new();
+    for file in files {
+        let entry = normalize_entry(&file);
+        let Some(variants) = anchoring_variants(&entry, &paths) else {
+            chosen.push(file);
+            continue;
+        };
+        let width = variants.iter().map(|v| v.entry.len()).max().unwrap_or(0);
+        let items: Vec<String> = variants
+            .iter()
+            .map(|v| format!("{:<width$}  {}", v.entry, ui::dim_text(&v.describe())))
+            .collect();
+        let default = variants.iter().position(|v| v.entry == entry).unwrap_or(0);
+        let choice = Select::with_theme(&theme)
+            .with_prompt(format!("'{entry}' matches at more than one level. Which did you mean?"))
+            .items(&items)
+            .default(default)
+            .interact_opt()?;
+        chosen.push(choice.map_or(file, |i| variants[i].entry.clone()));
+    }
+    Ok(chosen)
+}
+
+/// A plain name with no slash except a trailing one, which git matches at
+/// any depth.
+fn is_floating(entry: &str) -> bool {
+    let name = entry.trim_end_matches('/');
+    !name.is_empty() && !name.contains('/') && !git::contains_glob(name)
+}
+
+/// The anchorings worth offering for `entry`, or `None` when it only
+/// matches at one level and the choice doesn't matter.
+fn anchoring_variants(entry: &str, paths: &[String]) -> Option<Vec<AnchorVariant>> {
+    if !is_floating(entry) {
+        return None;
+    }
+    let dir_only = entry.ends_with('/');
+    let name = entry.trim_end_matches('/');
+
+    let mut locations = BTreeSet::new();
+    for path in paths {
+        let parts: Vec<&str> = path.split('/').collect();
+        for (i, part) in parts.iter().enumerate() {
+            let is_dir = i + 1 < parts.len();
+            if *part == name && (is_dir || !dir_only) {
+                let location = parts[..=i].join("/");
+                locations.insert(if is_dir { format!("{location}/") } else { location });
+            }
+        }
+    }
+
+    let at_depth = |depth: usize| -> Vec<String> {
+        locations
+            .iter()
+            .filter(|l| l.trim_end_matches('/').matches('/').count() == depth)
+            .cloned()
+            .collect()
+    };
+    let top = at_depth(0);
+    if top.is_empty() || top.len() == locations.len() {
+        return None;
+    }
+
+    let mut variants = vec![
+        AnchorVariant {
+            entry: format!("/{entry}"),
+            scope: "top level only",
+            matches: top,
+        },
+        AnchorVariant {
+            entry: entry.to_string(),
+            scope: "every level",
+            matches: locations.iter().cloned().collect(),
+        },
+    ];
+    let one_down = at_depth(1);
+    if !one_down.is_empty() {
+        variants.push(AnchorVariant {
+            entry: format!("*/{entry}"),
+            scope: "one level down",
+            matches: one_down,
+        });
+    }
+    Some(variants)
+}
+
+fn print_plan_json(plan: &[PlannedAdd]) {
+    let mut summary = AddSummary::default();
+    let mut json = String::from("{\n  \"dry_run\": true,\n  \"entries\": [\n");
+    for (i, planned) in plan.iter().enumerate() {
+        summary.record(&planned.outcome);
+        let (action, tracked) = match planned.outcome {
+            AddOutcome::Add { tracked } => ("add", tracked),
+            _ => ("skip", false),
+        };
+        let reason = planned.outcome.reason().map_or("null".to_string(), json_escape);
+        let covered_by = match &planned.outcome {
+            AddOutcome::Covered(by) => json_escape(by),
+            _ => "null".to_string(),
+        };
+        json.push_str(&format!(
+            "    {{ \"input\": {}, \"entry\": {}, \"action\": \"{action}\", \"reason\": {reason}, \"covered_by\": {covered_by}, \"tracked\": {tracked} }}",
+            json_escape(&planned.input),
+            json_escape(&planned.entry),
+        ));
+        if i + 1 < plan.len() {
+            json.push(',');
+        }
+        json.push('\n');
+    }
+    json.push_str("  ],\n");
+    json.push_str(&format!(
+        "  \"summary\": {{ \"added\": {}, \"already_layered\": {}, \"covered\": {}, \"invalid\": {}, \"tracked_warnings\": {} }}\n",
+        summary.added, summary.already_layered, summary.covered, summary.invalid, summary.tracked_warnings
+    ));
+    json.push('}');
+    outln!("{json}");
+}
+
+/// `add <entry>` or `skip <entry> <reason>`, one input per line.
+fn print_plan_porcelain(plan: &[PlannedAdd]) {
+    for planned in plan {
+        match planned.outcome.reason() {
+            None => ui::porcelain(&["add", &planned.entry]),
+            Some(reason) => ui::porcelain(&["skip", &planned.entry, reason]),
+        }
+    }
 }
 
 fn run_interactive(ctx: &RepoContext, exclude: &mut ExcludeFile, dry_run: bool) -> Result<i32> {
     ui::require_tty("interactive mode requires a TTY. Use 'layer add <files...>' instead")?;
 
-    let candidates = collect_candidates(ctx, exclude)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+    let options = discovery::Options { filter: Filter::New, untracked: true, tool: None };
+    let candidates = discovery::discover(ctx, &exclude.entry_set(), &tracked, options)?.candidates();
     if candidates.is_empty() {
-        println!("No context files found.");
+        outln!("No context files found.");
         return Ok(2);
     }
 
-    let nodes = build_tree(candidates);
+    outln!("{}", ui::heading("Select files to add to your local layer"));
+    pick_candidates(ctx, exclude, candidates, dry_run)
+}
 
-    println!("{}", ui::heading("Select files to add to your local layer"));
+/// Show `candidates` in the tree picker and layer whatever is chosen.
+pub fn pick_candidates(
+    ctx: &RepoContext,
+    exclude: &mut ExcludeFile,
+    candidates: Vec<Candidate>,
+    dry_run: bool,
+) -> Result<i32> {
+    let nodes = build_tree(candidates);
     ui::print_tree_picker_hint();
 
     let chosen = match tree_picker::run(&nodes)? {
         Some(paths) if !paths.is_empty() => paths,
         _ => {
-            println!("No files selected.");
+            outln!("No files selected.");
             return Ok(2);
         }
     };
@@ -123,13 +735,13 @@ fn run_interactive(ctx: &RepoContext, exclude: &mut ExcludeFile, dry_run: bool)
 /// Groups flat candidates into a recursive tree structure for the tree picker.
 /// At each level: root-level files come first, then BTreeMap-sorted directory
 /// groups. Directories with only 1 file are promoted to the parent level.
-fn build_tree(candidates: Vec<InteractiveCandidate>) -> Vec<tree_picker::TreeNode> {
+fn build_tree(candidates: Vec<Candidate>) -> Vec<tree_picker::TreeNode> {
     build_subtree(candidates, "")
 }
 
-fn build_subtree(candidates: Vec<InteractiveCandidate>, prefix: &str) -> Vec<tree_picker::TreeNode> {
+fn build_subtree(candidates: Vec<Candidate>, prefix: &str) -> Vec<tree_picker::TreeNode> {
     let mut root_files: Vec<tree_picker::TreeNode> = Vec::new();
-    let mut dir_groups: BTreeMap<String, Vec<InteractiveCandidate>> = BTreeMap::new();
+    let mut dir_groups: BTreeMap<String, Vec<Candidate>> = BTreeMap::new();
 
     for c in candidates {
         let relative = &c.path[prefix.len()..];
@@ -139,7 +751,7 @@ fn build_subtree(candidates: Vec<InteractiveCandidate>, prefix: &str) -> Vec<tre
         } else {
             root_files.push(tree_picker::TreeNode {
                 path: c.path,
-                category: c.category.to_string(),
+                category: c.category,
                 children: Vec::new(),
             });
         }
@@ -178,38 +790,38 @@ fn count_leaf_files(nodes: &[tree_picker::TreeNode]) -> usize {
         .sum()
 }
 
-fn collect_candidates(ctx: &RepoContext, exclude: &ExcludeFile) -> Result<Vec<InteractiveCandidate>> {
-    let excluded = exclude.entry_set();
-    let mut seen = HashSet::new();
-    let mut out = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for found in scan::discover_known_files(ctx, &excluded)? {
-        if found.already_excluded || found.is_gitignored || found.is_tracked {
-            continue;
-        }
-        if seen.insert(found.path.clone()) {
-            let category = match found.category {
-                PatternCategory::AiConfig => "context file",
-            };
-            out.push(InteractiveCandidate {
-                path: found.path,
-                category,
-            });
-        }
+    fn paths(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
     }
 
-    for file in git::list_untracked(&ctx.root)? {
-        let normalized = normalize_entry(&file);
-        if normalized.is_empty() || excluded.contains(&normalized) {
-            continue;
-        }
-        if seen.insert(normalized.clone()) {
-            out.push(InteractiveCandidate {
-                path: normalized,
-                category: "untracked",
-            });
-        }
+    #[test]
+    fn anchoring_variants_split_matches_by_depth() {
+        let files = paths(&["docs/a.md", "pkg/docs/b.md", "pkg/web/docs/c.md", "README.md"]);
+        let variants = anchoring_variants("docs/", &files).expect("ambiguous");
+        let entries: Vec<&str> = variants.iter().map(|v| v.entry.as_str()).collect();
+        assert_eq!(entries, ["/docs/", "docs/", "*/docs/"]);
+        assert_eq!(variants[0].matches, ["docs/"]);
+        assert_eq!(variants[1].matches, ["docs/", "pkg/docs/", "pkg/web/docs/"]);
+        assert_eq!(variants[2].matches, ["pkg/docs/"]);
     }
 
-    Ok(out)
+    #[test]
+    fn anchoring_variants_skip_unambiguous_entries() {
+        let files = paths(&["docs/a.md", "CLAUDE.md", "pkg/notes.md"]);
+        assert_eq!(anchoring_variants("docs/", &files), None);
+        assert_eq!(anchoring_variants("/docs/", &files), None);
+        assert_eq!(anchoring_variants("pkg/docs/", &files), None);
+        assert_eq!(anchoring_variants("*.md", &files), None);
+    }
+
+    #[test]
+    fn anchoring_variants_dir_entries_ignore_files_of_the_same_name() {
+        let files = paths(&["build/out.txt", "src/build"]);
+        assert_eq!(anchoring_variants("build/", &files), None);
+        assert!(anchoring_variants("build", &files).is_some());
+    }
 }
@@ -1,106 +1,150 @@
+use crate::config;
 use crate::exclude_file::{ensure_exclude_file, ensure_exclude_file_for_write};
 use crate::git;
+use crate::meta;
+use crate::signing;
 use crate::ui;
-use anyhow::{Context, Result};
-use dialoguer::Confirm;
+use crate::ui::outln;
+use anyhow::{bail, Context, Result};
+use dialoguer::{Confirm, MultiSelect};
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
 use time::format_description::well_known::Rfc3339;
-use time::OffsetDateTime;
+use time::{OffsetDateTime, UtcOffset};
 
-pub fn backup() -> Result<i32> {
+/// Write a backup to `~/.layer-backups/<repo>.txt`, or to `output` for
+/// sharing. With `sign`, also write a detached signature next to it.
+pub fn backup(output: Option<PathBuf>, sign: Option<PathBuf>) -> Result<i32> {
     let ctx = git::ensure_repo()?;
-    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
-    let entries = exclude
-        .entries()
-        .into_iter()
-        .map(|e| e.value)
-        .collect::<Vec<_>>();
-
-    let identity = current_repo_identity(&ctx)?;
-    let backup_dir = backup_dir_path()?;
-    fs::create_dir_all(&backup_dir)
-        .with_context(|| format!("failed to create {}", backup_dir.display()))?;
-
-    let backup_path = backup_dir.join(format!("{}.txt", identity.repo_name));
-    let existed = backup_path.exists();
+    let written = write_backup(&ctx, output.as_deref())?;
+    let backup_path = written.path;
 
-    let now = OffsetDateTime::now_utc().format(&Rfc3339)?;
-    let source = identity
-        .source
-        .as_deref()
-        .unwrap_or("(no origin remote)");
-
-    let mut out = String::new();
-    out.push_str("# layer backup\n");
-    out.push_str(&format!("# repo: {}\n", identity.repo_name));
-    out.push_str(&format!("# source: {}\n", source));
-    out.push_str(&format!("# date: {}\n", now));
-    out.push_str(&format!("# entries: {}\n", entries.len()));
-    for entry in &entries {
-        out.push_str(entry);
-        out.push('\n');
-    }
-
-    fs::write(&backup_path, out)
-        .with_context(|| format!("failed to write {}", backup_path.display()))?;
-
-    if existed {
-        println!(
+    if output.is_some() {
+        outln!(
+            "  {} Exported {} entries to {}",
+            ui::ok(),
+            written.entries,
+            backup_path.display()
+        );
+    } else if written.replaced {
+        outln!(
             "  {} Updated backup for '{}' at {}",
             ui::ok(),
-            identity.repo_name,
+            written.repo_name,
             backup_path.display()
         );
     } else {
-        println!(
+        outln!(
             "  {} Backed up {} entries to {}",
             ui::ok(),
-            entries.len(),
+            written.entries,
             backup_path.display()
         );
     }
 
+    if let Some(key) = sign {
+        let sig_path = signing::sign(&backup_path, &key)?;
+        outln!("  {} Signed {}", ui::ok(), sig_path.display());
+    }
+
     Ok(0)
 }
 
-pub fn restore(list: bool) -> Result<i32> {
+/// Flags for `layer restore`.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    pub list: bool,
+    pub from: Option<PathBuf>,
+    pub verify: Option<PathBuf>,
+    pub yes: bool,
+    pub replace: bool,
+    pub utc: bool,
+    pub machine: Option<String>,
+}
+
+/// Restore entries from the repo's backup, the backup called `name`, or a
+/// shared file with `from`. With `verify`, refuse unless the file's signature
+/// checks out against the trusted keys. `yes` skips the confirmation.
+pub fn restore(name: Option<String>, options: RestoreOptions) -> Result<i32> {
+    let RestoreOptions {
+        list,
+        from,
+        verify,
+        yes,
+        replace,
+        utc,
+        machine,
+    } = options;
     if list {
-        return list_backups();
+        return list_backups(utc, machine.as_deref());
     }
 
     let ctx = git::ensure_repo()?;
     let identity = current_repo_identity(&ctx)?;
-    let backup_path = backup_dir_path()?.join(format!("{}.txt", identity.repo_name));
+    let backup_path = match &from {
+        Some(path) if !path.exists() => bail!("{} does not exist", path.display()),
+        Some(path) => path.clone(),
+        None => {
+            let found = find_backup(&backup_dir_path()?, machine.as_deref(), name.as_deref(), &identity)?;
+            let on = machine.as_deref().map(|m| format!(" from machine '{m}'")).unwrap_or_default();
+            match (found, &name) {
+                (Some(path), _) => path,
+                (None, Some(name)) => {
+                    bail!("no backup named '{name}'{on}. Run 'layer restore --list' to see available backups")
+                }
+                (None, None) => {
+                    outln!(
+                        "No backup found for '{}'{on}. Run 'layer backup' to create one.",
+                        identity.repo_name
+                    );
+                    return Ok(2);
+                }
+            }
+        }
+    };
 
-    if !backup_path.exists() {
-        println!(
-            "No backup found for '{}'. Run 'layer backup' to create one.",
-            identity.repo_name
-        );
-        return Ok(2);
+    match &verify {
+        Some(trusted) => {
+            let signer = signing::verify(&backup_path, trusted)?;
+            outln!("  {} Signature verified — signed by {}", ui::ok(), signer);
+        }
+        None if signing::has_signature(&backup_path) => {
+            ui::print_warning("This backup is signed but was not verified. Pass --verify <allowed_signers> to check it.");
+        }
+        None => {}
     }
 
     let backup = parse_backup_file(&backup_path)?;
-    println!(
+    meta::set_source(&backup.entries, &backup_source(&backup, &backup_path));
+    outln!(
         "{}",
         ui::heading(&format!(
             "Found backup for '{}' ({} entries, saved {})",
-            identity.repo_name,
+            backup.repo,
             backup.entries.len(),
             format_backup_date(&backup.date)
         ))
     );
 
-    ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal")?;
+    if !yes {
+        ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal or use --yes")?;
+    }
+
+    if replace {
+        return restore_replace(&ctx, &backup.entries, yes);
+    }
 
-    let confirmed = Confirm::new()
-        .with_prompt("Restore these entries?")
-        .default(false)
-        .interact()?;
+    let confirmed = yes
+        || Confirm::new()
+            .with_prompt("Restore these entries?")
+            .default(false)
+            .interact()?;
 
     if !confirmed {
-        println!("No changes made.");
+        outln!("No changes made.");
         return Ok(2);
     }
 
@@ -118,58 +162,257 @@ pub fn restore(list: bool) -> Result<i32> {
     }
 
     if added == 0 {
-        println!("All backup entries are already present in .git/info/exclude.");
+        outln!("All backup entries are already present in .git/info/exclude.");
         return Ok(2);
     }
 
     exclude.write(&ctx.exclude_path)?;
 
-    println!("  {} Restored {} entries.", ui::ok(), added);
+    outln!("  {} Restored {} entries.", ui::ok(), added);
+    Ok(0)
+}
+
+/// How restored entries' metadata names the backup: by the day it was
+/// saved, or by file name for a backup without a date.
+fn backup_source(backup: &ParsedBackup, path: &Path) -> String {
+    match backup.date.as_deref().and_then(|date| date.get(..10)) {
+        Some(day) => format!("backup {day}"),
+        None => format!("backup {}", path.file_name().unwrap_or_default().to_string_lossy()),
+    }
+}
+
+/// One line of the difference between the current layer and a backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RestoreChange {
+    Add(String),
+    Remove(String),
+}
+
+impl RestoreChange {
+    fn label(&self) -> String {
+        match self {
+            RestoreChange::Add(entry) => format!("+ {entry}  {}", ui::dim_text("(in backup)")),
+            RestoreChange::Remove(entry) => format!("- {entry}  {}", ui::dim_text("(only in your layer)")),
+        }
+    }
+}
+
+/// What it takes to make `current` match `backup`: additions in backup
+/// order, then removals in layer order.
+fn replace_changes(current: &[String], backup: &[String]) -> Vec<RestoreChange> {
+    let have: HashSet<&str> = current.iter().map(String::as_str).collect();
+    let want: HashSet<&str> = backup.iter().map(String::as_str).collect();
+    let mut seen = HashSet::new();
+    let mut changes: Vec<RestoreChange> = backup
+        .iter()
+        .filter(|e| !have.contains(e.as_str()) && seen.insert(e.as_str()))
+        .map(|e| RestoreChange::Add(e.clone()))
+        .collect();
+    changes.extend(
+        current
+            .iter()
+            .filter(|e| !want.contains(e.as_str()))
+            .map(|e| RestoreChange::Remove(e.clone())),
+    );
+    changes
+}
+
+/// `restore --replace`: make the layer match the backup. Every addition and
+/// removal is listed and checked; unchecking one keeps things as they are for
+/// that entry. `--yes` accepts them all.
+fn restore_replace(ctx: &git::RepoContext, backup: &[String], yes: bool) -> Result<i32> {
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let current: Vec<String> = exclude.entries().into_iter().map(|e| e.value).collect();
+    let changes = replace_changes(&current, backup);
+    if changes.is_empty() {
+        outln!("Your layer already matches this backup.");
+        return Ok(2);
+    }
+
+    let accepted: Vec<RestoreChange> = if yes {
+        changes
+    } else {
+        let items: Vec<String> = changes.iter().map(RestoreChange::label).collect();
+        outln!("{}", ui::heading("Review changes (uncheck any you want to skip)"));
+        let theme = ui::layer_theme();
+        ui::print_select_hint();
+        let defaults = vec![true; items.len()];
+        let Some(selected) = MultiSelect::with_theme(&theme)
+            .items(&items)
+            .defaults(&defaults)
+            .report(false)
+            .interact_opt()?
+        else {
+            outln!("No changes made.");
+            return Ok(2);
+        };
+        selected.into_iter().map(|idx| changes[idx].clone()).collect()
+    };
+
+    let mut removals = HashSet::new();
+    let mut added = 0usize;
+    for change in &accepted {
+        match change {
+            RestoreChange::Add(entry) => {
+                exclude.append_entry(entry);
+                added += 1;
+            }
+            RestoreChange::Remove(entry) => {
+                removals.insert(entry.clone());
+            }
+        }
+    }
+    let removed = exclude.remove_exact(&removals).len();
+    if added == 0 && removed == 0 {
+        outln!("No changes made.");
+        return Ok(2);
+    }
+
+    exclude.write(&ctx.exclude_path)?;
+    outln!("  {} Restored {} entries, removed {}.", ui::ok(), added, removed);
     Ok(0)
 }
 
-fn list_backups() -> Result<i32> {
+fn list_backups(utc: bool, machine: Option<&str>) -> Result<i32> {
     let dir = backup_dir_path()?;
     if !dir.exists() {
-        println!("No backups found in {}.", dir.display());
+        outln!("No backups found in {}.", dir.display());
         return Ok(2);
     }
 
+    let this_machine = machine_name();
     let mut backups = Vec::new();
-    for item in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
-        let item = item?;
-        let path = item.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+    for (saved_by, path) in saved_backups(&dir)? {
+        if machine.is_some_and(|m| saved_by.as_deref() != Some(m)) {
             continue;
         }
-        let parsed = parse_backup_file(&path)?;
-        backups.push(parsed);
+        backups.push((saved_by, parse_backup_file(&path)?));
     }
 
     if backups.is_empty() {
-        println!("No backups found in {}.", dir.display());
+        match machine {
+            Some(machine) => outln!("No backups from machine '{machine}' in {}.", dir.display()),
+            None => outln!("No backups found in {}.", dir.display()),
+        }
         return Ok(2);
     }
 
-    backups.sort_by(|a, b| a.repo.cmp(&b.repo));
+    // This machine's backup of a repo first, then older shared ones, then
+    // other machines'.
+    backups.sort_by(|(a_machine, a), (b_machine, b)| {
+        let rank = |m: &Option<String>| match m {
+            Some(m) if *m == this_machine => 0,
+            None => 1,
+            Some(_) => 2,
+        };
+        a.repo
+            .cmp(&b.repo)
+            .then(rank(a_machine).cmp(&rank(b_machine)))
+            .then(a_machine.cmp(b_machine))
+    });
+    let machine_width = backups
+        .iter()
+        .map(|(m, _)| m.as_deref().map_or(1, str::len))
+        .max()
+        .unwrap_or(1);
+    let others = backups
+        .iter()
+        .any(|(m, _)| m.as_deref().is_some_and(|m| m != this_machine));
+
+    // Inside a repo, show what restoring each backup would change.
+    let current: Option<Vec<String>> = git::ensure_repo()
+        .ok()
+        .and_then(|ctx| ensure_exclude_file(&ctx.exclude_path).ok())
+        .map(|exclude| exclude.entries().into_iter().map(|e| e.value).collect());
+    let now = OffsetDateTime::now_utc();
+    let offset = if utc { UtcOffset::UTC } else { local_offset() };
 
-    println!("Available backups:");
-    for backup in backups {
-        println!(
-            "  {:<20} {:>3} entries    {}",
+    outln!("Available backups (this machine is '{this_machine}'):");
+    for (saved_by, backup) in backups {
+        let delta = current
+            .as_ref()
+            .map(|current| format!("    {}", ui::dim_text(&backup_delta(current, &backup.entries))))
+            .unwrap_or_default();
+        outln!(
+            "  {:<20} {:<machine_width$} {:>3} entries    {}{delta}",
             backup.repo,
+            saved_by.as_deref().unwrap_or("-"),
             backup.entries.len(),
-            format_backup_date(&backup.date)
+            format_backup_time(&backup.date, now, offset)
+        );
+    }
+    if others {
+        outln!();
+        outln!(
+            "{}",
+            ui::dim_text("Restore another machine's backup with: layer restore [name] --machine <machine>")
         );
     }
 
     Ok(0)
 }
 
+/// A backup file that was just written.
+pub struct WrittenBackup {
+    pub path: PathBuf,
+    pub repo_name: String,
+    pub entries: usize,
+    pub replaced: bool,
+}
+
+/// Save the current entries to the repo's backup file, or to `output`.
+pub fn write_backup(ctx: &git::RepoContext, output: Option<&Path>) -> Result<WrittenBackup> {
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let entries = exclude
+        .entries()
+        .into_iter()
+        .map(|e| e.value)
+        .collect::<Vec<_>>();
+
+    let identity = current_repo_identity(ctx)?;
+    let backup_path = match output {
+        Some(path) => path.to_path_buf(),
+        None => locate_own_backup(&machine_backup_dir(&writable_backup_dir()?, &identity)?, &identity),
+    };
+    let replaced = backup_path.exists();
+
+    let now = OffsetDateTime::now_utc().format(&Rfc3339)?;
+    let source = identity
+        .source
+        .as_deref()
+        .unwrap_or("(no origin remote)");
+
+    let mut out = String::new();
+    out.push_str("# layer backup\n");
+    out.push_str(&format!("# repo: {}\n", identity.repo_name));
+    out.push_str(&format!("# source: {}\n", source));
+    if let Some(id) = &identity.id {
+        out.push_str(&format!("# id: {id}\n"));
+    }
+    out.push_str(&format!("# date: {}\n", now));
+    out.push_str(&format!("# entries: {}\n", entries.len()));
+    for entry in &entries {
+        out.push_str(entry);
+        out.push('\n');
+    }
+
+    fs::write(&backup_path, out)
+        .with_context(|| format!("failed to write {}", backup_path.display()))?;
+
+    Ok(WrittenBackup {
+        path: backup_path,
+        repo_name: identity.repo_name,
+        entries: entries.len(),
+        replaced,
+    })
+}
+
 #[derive(Debug, Clone)]
 struct RepoIdentity {
     repo_name: String,
     source: Option<String>,
+    /// Stable across renames and moves; `None` before the first commit.
+    id: Option<String>,
 }
 
 fn current_repo_identity(ctx: &git::RepoContext) -> Result<RepoIdentity> {
@@ -196,7 +439,245 @@ fn current_repo_identity(ctx: &git::RepoContext) -> Result<RepoIdentity> {
         sanitize_repo_name(fallback)
     };
 
-    Ok(RepoIdentity { repo_name, source })
+    let id = repo_id(&ctx.root, source.as_deref());
+    Ok(RepoIdentity { repo_name, source, id })
+}
+
+/// `<root commit>-<remote hash>`: the first twelve hex digits of the
+/// repository's root commit, plus a hash of the normalized origin URL so
+/// forks that share history still differ. Paths play no part, so the id
+/// survives moving the clone or pruning worktrees.
+fn repo_id(root: &Path, source: Option<&str>) -> Option<String> {
+    let roots = git::git_stdout(&["rev-list", "--max-parents=0", "HEAD"], Some(root)).ok()?;
+    let first = roots.lines().map(str::trim).filter(|l| !l.is_empty()).min()?;
+    let remote = source.map(normalize_remote).unwrap_or_default();
+    Some(format!("{}-{:08x}", &first[..first.len().min(12)], fnv1a(remote.as_bytes()) as u32))
+}
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same value on every
+/// platform and Rust release.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn normalize_remote(url: &str) -> String {
+    url.trim()
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_ascii_lowercase()
+}
+
+/// This repo's backup in `dir`, found by repo id so it follows the repo when
+/// the directory or remote is renamed. Returns where the backup belongs and,
+/// when it was saved under an older name, where it is now. When another repo
+/// already owns `<name>.txt`, the id's first eight digits are appended to
+/// keep them apart. Backups written before ids existed are matched by name alone.
+fn own_backup(dir: &Path, identity: &RepoIdentity) -> (PathBuf, Option<PathBuf>) {
+    let named = dir.join(format!("{}.txt", identity.repo_name));
+    let Some(id) = &identity.id else {
+        return (named, None);
+    };
+    let id_of = |path: &Path| parse_backup_file(path).ok().and_then(|b| b.id);
+
+    let preferred = match id_of(&named) {
+        Some(other) if other != *id => dir.join(format!("{}-{}.txt", identity.repo_name, &id[..id.len().min(8)])),
+        _ => named,
+    };
+    if preferred.exists() {
+        return (preferred, None);
+    }
+
+    let previous = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.ok().map(|i| i.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("txt"))
+        .find(|path| id_of(path).as_deref() == Some(id.as_str()));
+    (preferred, previous)
+}
+
+/// This repo's backup in `dir` for reading, wherever it's saved now.
+fn find_own_backup(dir: &Path, identity: &RepoIdentity) -> PathBuf {
+    let (preferred, previous) = own_backup(dir, identity);
+    previous.unwrap_or(preferred)
+}
+
+/// This repo's backup in `dir` for writing. One saved under an older name is
+/// moved to the current one first, so the next backup replaces it.
+fn locate_own_backup(dir: &Path, identity: &RepoIdentity) -> PathBuf {
+    let (preferred, previous) = own_backup(dir, identity);
+    if let Some(previous) = previous {
+        if fs::rename(&previous, &preferred).is_ok() {
+            outln!(
+                "  {} Moved this repo's backup from {} to {}",
+                ui::info(),
+                previous.display(),
+                preferred.display()
+            );
+        } else {
+            return previous;
+        }
+    }
+    preferred
+}
+
+/// This machine's directory under the backup directory, so machines that
+/// share a home (NFS, dotfile sync) don't overwrite each other's backups.
+/// This repo's backup from before machines had their own directories moves
+/// into it, so the next backup replaces it instead of leaving it behind.
+fn machine_backup_dir(root: &Path, identity: &RepoIdentity) -> Result<PathBuf> {
+    let dir = root.join(machine_name());
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let shared = locate_own_backup(root, identity);
+    if let Some(file_name) = shared.file_name().filter(|_| shared.is_file()) {
+        let moved = dir.join(file_name);
+        if !moved.exists() && fs::rename(&shared, &moved).is_ok() {
+            outln!(
+                "  {} Moved this repo's backup from {} to {}",
+                ui::info(),
+                shared.display(),
+                moved.display()
+            );
+        }
+    }
+    Ok(dir)
+}
+
+/// The backup `restore` reads: from `machine`'s directory when given, else
+/// this machine's, else one saved before machines had their own directories,
+/// else another machine's when exactly one has it.
+fn find_backup(
+    dir: &Path,
+    machine: Option<&str>,
+    name: Option<&str>,
+    identity: &RepoIdentity,
+) -> Result<Option<PathBuf>> {
+    let lookup = |dir: &Path| {
+        let path = match name {
+            Some(name) => dir.join(format!("{}.txt", sanitize_repo_name(name.trim_end_matches(".txt")))),
+            None => find_own_backup(dir, identity),
+        };
+        path.is_file().then_some(path)
+    };
+    if let Some(machine) = machine {
+        return Ok(lookup(&dir.join(sanitize_repo_name(machine))));
+    }
+
+    let this_machine = machine_name();
+    if let Some(path) = lookup(&dir.join(&this_machine)).or_else(|| lookup(dir)) {
+        return Ok(Some(path));
+    }
+    let mut others: Vec<(String, PathBuf)> = machine_dirs(dir)
+        .into_iter()
+        .filter(|m| *m != this_machine)
+        .filter_map(|m| lookup(&dir.join(&m)).map(|path| (m, path)))
+        .collect();
+    match others.len() {
+        0 => Ok(None),
+        1 => {
+            let (machine, path) = others.remove(0);
+            outln!("  {} No backup from this machine; using the one from '{machine}'", ui::info());
+            Ok(Some(path))
+        }
+        _ => {
+            let names: Vec<&str> = others.iter().map(|(m, _)| m.as_str()).collect();
+            bail!(
+                "backups from several machines match: {}. Pick one with --machine <name>",
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Every backup in `dir` and the machine that saved it: this machine's
+/// first, then ones saved before machines had their own directories (no
+/// machine), then other machines'.
+pub fn saved_backups(dir: &Path) -> Result<Vec<(Option<String>, PathBuf)>> {
+    let this_machine = machine_name();
+    let mut found: Vec<(Option<String>, PathBuf)> = backup_files(&dir.join(&this_machine))?
+        .into_iter()
+        .map(|path| (Some(this_machine.clone()), path))
+        .collect();
+    found.extend(backup_files(dir)?.into_iter().map(|path| (None, path)));
+    for machine in machine_dirs(dir).into_iter().filter(|m| *m != this_machine) {
+        for path in backup_files(&dir.join(&machine))? {
+            found.push((Some(machine.clone()), path));
+        }
+    }
+    Ok(found)
+}
+
+/// `*.txt` backup files directly in `dir`, sorted.
+fn backup_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths = Vec::new();
+    for item in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = item?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("txt") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Names of the per-machine directories in `dir`, sorted.
+fn machine_dirs(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.ok())
+        .filter(|item| item.path().is_dir())
+        .filter_map(|item| item.file_name().to_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+/// This machine's name for its backup directory: `backup.machine` when
+/// set, else the hostname without its domain.
+pub fn machine_name() -> String {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        // A personal setting: read from user and system config, env, and -c only.
+        let configured = config::load(None)
+            .map(|config| config.get_str("backup.machine").trim().to_string())
+            .unwrap_or_default();
+        let name = if configured.is_empty() {
+            hostname().split('.').next().unwrap_or_default().to_string()
+        } else {
+            configured
+        };
+        if name.trim().is_empty() {
+            "unknown".to_string()
+        } else {
+            sanitize_repo_name(&name)
+        }
+    })
+    .clone()
+}
+
+fn hostname() -> String {
+    if let Some(name) = env_value("COMPUTERNAME") {
+        return name;
+    }
+    if let Ok(name) = fs::read_to_string("/proc/sys/kernel/hostname") {
+        if !name.trim().is_empty() {
+            return name.trim().to_string();
+        }
+    }
+    Command::new("hostname")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| env_value("HOSTNAME"))
+        .unwrap_or_default()
 }
 
 fn sanitize_repo_name(name: &str) -> String {
@@ -217,21 +698,109 @@ fn sanitize_repo_name(name: &str) -> String {
     }
 }
 
-fn backup_dir_path() -> Result<PathBuf> {
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .context("could not determine home directory")?;
-    Ok(PathBuf::from(home).join(".layer-backups"))
+/// Directory to read backups from: the first candidate that already holds
+/// backups, else the preferred one.
+pub fn backup_dir_path() -> Result<PathBuf> {
+    let (candidates, _) = current_backup_dir_candidates();
+    candidates
+        .iter()
+        .find(|dir| dir.is_dir())
+        .or_else(|| candidates.first())
+        .cloned()
+        .ok_or_else(no_backup_dir)
+}
+
+/// Directory to write a backup to: the first candidate layer can write,
+/// with a notice when that isn't `backup.dir` or `~/.layer-backups`.
+fn writable_backup_dir() -> Result<PathBuf> {
+    let (candidates, configured) = current_backup_dir_candidates();
+    let Some(index) = candidates.iter().position(|dir| is_writable_dir(dir)) else {
+        return Err(no_backup_dir());
+    };
+    let dir = candidates[index].clone();
+    if index > 0 || !(has_home() || configured) {
+        let reason = if has_home() {
+            format!("{} is not writable", candidates[0].display())
+        } else {
+            "HOME is not set".to_string()
+        };
+        ui::print_stderr_warning(&format!("{reason}; saving backups in {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+fn has_home() -> bool {
+    env_value("HOME").or_else(|| env_value("USERPROFILE")).is_some()
+}
+
+/// Candidate backup directories, and whether `backup.dir` is set.
+fn current_backup_dir_candidates() -> (Vec<PathBuf>, bool) {
+    let ctx = git::ensure_repo().ok();
+    let configured = config::load(ctx.as_ref().map(|ctx| ctx.root.as_path()))
+        .map(|config| config.get_str("backup.dir").trim().to_string())
+        .unwrap_or_default();
+    let git_dir = ctx.map(|ctx| ctx.git_dir);
+    (backup_dir_candidates(env_value, &configured, git_dir.as_deref()), !configured.is_empty())
+}
+
+/// Backup locations, most preferred first: `backup.dir` when set, then
+/// `~/.layer-backups`, then `$XDG_DATA_HOME/layer/backups` or
+/// `%APPDATA%/layer/backups`, then `.git/info/layer-backups` in the current
+/// repo for containers and services without a home directory.
+fn backup_dir_candidates(
+    env: impl Fn(&str) -> Option<String>,
+    configured: &str,
+    git_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = env("HOME").or_else(|| env("USERPROFILE"));
+    match (configured.strip_prefix("~/"), &home) {
+        (Some(rest), Some(home)) => dirs.push(PathBuf::from(home).join(rest)),
+        _ if !configured.is_empty() => dirs.push(PathBuf::from(configured)),
+        _ => {}
+    }
+    if let Some(home) = home {
+        dirs.push(PathBuf::from(home).join(".layer-backups"));
+    }
+    for key in ["XDG_DATA_HOME", "APPDATA"] {
+        if let Some(base) = env(key) {
+            dirs.push(PathBuf::from(base).join("layer").join("backups"));
+        }
+    }
+    if let Some(git_dir) = git_dir {
+        dirs.push(git_dir.join("info").join("layer-backups"));
+    }
+    dirs
+}
+
+fn is_writable_dir(dir: &Path) -> bool {
+    if fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(".layer-write-test");
+    let writable = fs::write(&probe, "").is_ok();
+    let _ = fs::remove_file(&probe);
+    writable
+}
+
+fn no_backup_dir() -> anyhow::Error {
+    anyhow::anyhow!("no usable backup directory: set HOME or XDG_DATA_HOME, or run inside a git repository")
 }
 
 #[derive(Debug, Clone)]
-struct ParsedBackup {
-    repo: String,
-    date: Option<String>,
-    entries: Vec<String>,
+pub struct ParsedBackup {
+    pub repo: String,
+    pub source: Option<String>,
+    pub id: Option<String>,
+    pub date: Option<String>,
+    pub entries: Vec<String>,
 }
 
-fn parse_backup_file(path: &Path) -> Result<ParsedBackup> {
+pub fn parse_backup_file(path: &Path) -> Result<ParsedBackup> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("failed to read {}", path.display()))?;
 
@@ -240,6 +809,8 @@ fn parse_backup_file(path: &Path) -> Result<ParsedBackup> {
         .and_then(|s| s.to_str())
         .unwrap_or("unknown")
         .to_string();
+    let mut source = None;
+    let mut id = None;
     let mut date = None;
     let mut entries = Vec::new();
 
@@ -249,6 +820,14 @@ fn parse_backup_file(path: &Path) -> Result<ParsedBackup> {
             repo = value.trim().to_string();
             continue;
         }
+        if let Some(value) = trimmed.strip_prefix("# source:") {
+            source = Some(value.trim().to_string());
+            continue;
+        }
+        if let Some(value) = trimmed.strip_prefix("# id:") {
+            id = Some(value.trim().to_string());
+            continue;
+        }
         if let Some(value) = trimmed.strip_prefix("# date:") {
             date = Some(value.trim().to_string());
             continue;
@@ -260,39 +839,174 @@ fn parse_backup_file(path: &Path) -> Result<ParsedBackup> {
         entries.push(trimmed.to_string());
     }
 
-    Ok(ParsedBackup { repo, date, entries })
+    Ok(ParsedBackup {
+        repo,
+        source,
+        id,
+        date,
+        entries,
+    })
 }
 
-fn format_backup_date(raw: &Option<String>) -> String {
+pub fn format_backup_date(raw: &Option<String>) -> String {
     let Some(raw) = raw else {
         return "unknown date".to_string();
     };
 
     if let Ok(dt) = OffsetDateTime::parse(raw, &Rfc3339) {
-        let month = match dt.month() {
-            time::Month::January => "Jan",
-            time::Month::February => "Feb",
-            time::Month::March => "Mar",
-            time::Month::April => "Apr",
-            time::Month::May => "May",
-            time::Month::June => "Jun",
-            time::Month::July => "Jul",
-            time::Month::August => "Aug",
-            time::Month::September => "Sep",
-            time::Month::October => "Oct",
-            time::Month::November => "Nov",
-            time::Month::December => "Dec",
-        };
-        return format!("{} {}, {}", month, dt.day(), dt.year());
+        return format!("{} {}, {}", month_abbrev(dt.month()), dt.day(), dt.year());
     }
 
     raw.to_string()
 }
 
+fn month_abbrev(month: time::Month) -> &'static str {
+    match month {
+        time::Month::January => "Jan",
+        time::Month::February => "Feb",
+        time::Month::March => "Mar",
+        time::Month::April => "Apr",
+        time::Month::May => "May",
+        time::Month::June => "Jun",
+        time::Month::July => "Jul",
+        time::Month::August => "Aug",
+        time::Month::September => "Sep",
+        time::Month::October => "Oct",
+        time::Month::November => "Nov",
+        time::Month::December => "Dec",
+    }
+}
+
+/// `restore --list` timestamps: "2 days ago (Feb 6, 2026 14:03 +01:00)",
+/// shown at `offset`.
+fn format_backup_time(raw: &Option<String>, now: OffsetDateTime, offset: UtcOffset) -> String {
+    let Some(dt) = raw.as_deref().and_then(|raw| OffsetDateTime::parse(raw, &Rfc3339).ok()) else {
+        return format_backup_date(raw);
+    };
+    let dt = dt.to_offset(offset);
+    let zone = if offset.is_utc() {
+        "UTC".to_string()
+    } else {
+        let (hours, minutes, _) = offset.as_hms();
+        let sign = if offset.is_negative() { '-' } else { '+' };
+        format!("{sign}{:02}:{:02}", hours.unsigned_abs(), minutes.unsigned_abs())
+    };
+    format!(
+        "{} ({} {}, {} {:02}:{:02} {zone})",
+        relative_age(now - dt),
+        month_abbrev(dt.month()),
+        dt.day(),
+        dt.year(),
+        dt.hour(),
+        dt.minute()
+    )
+}
+
+/// "just now", "5 minutes ago", "2 days ago", ... Coarser the older it is.
+fn relative_age(elapsed: time::Duration) -> String {
+    let seconds = elapsed.whole_seconds();
+    let (count, unit) = match seconds {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3600, "hour"),
+        s if s < 7 * 86_400 => (s / 86_400, "day"),
+        s if s < 30 * 86_400 => (s / (7 * 86_400), "week"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+/// What restoring a backup with `--replace` would change: "+2 -1 vs. your
+/// layer", or that it already matches.
+fn backup_delta(current: &[String], backup: &[String]) -> String {
+    let changes = replace_changes(current, backup);
+    if changes.is_empty() {
+        return "matches your layer".to_string();
+    }
+    let added = changes.iter().filter(|c| matches!(c, RestoreChange::Add(_))).count();
+    format!("+{added} -{} vs. your layer", changes.len() - added)
+}
+
+/// The local UTC offset, read from `date` since the time crate won't look it
+/// up in a multi-threaded process. Falls back to UTC. Today's offset is used
+/// for every backup, so times across a DST change can be off by an hour.
+fn local_offset() -> UtcOffset {
+    Command::new("date")
+        .arg("+%z")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_utc_offset(String::from_utf8_lossy(&output.stdout).trim()))
+        .unwrap_or(UtcOffset::UTC)
+}
+
+/// Parse `date +%z` output such as "+0530" or "-0800".
+fn parse_utc_offset(raw: &str) -> Option<UtcOffset> {
+    let (sign, digits) = if let Some(digits) = raw.strip_prefix('+') {
+        (1, digits)
+    } else {
+        (-1, raw.strip_prefix('-')?)
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i8 = digits[..2].parse().ok()?;
+    let minutes: i8 = digits[2..].parse().ok()?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn backup_dir_candidates_prefer_home() {
+        let env = |key: &str| match key {
+            "HOME" => Some("/home/me".to_string()),
+            "XDG_DATA_HOME" => Some("/data".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            backup_dir_candidates(env, "", Some(Path::new("/repo/.git"))),
+            vec![
+                PathBuf::from("/home/me/.layer-backups"),
+                PathBuf::from("/data/layer/backups"),
+                PathBuf::from("/repo/.git/info/layer-backups"),
+            ]
+        );
+    }
+
+    #[test]
+    fn backup_dir_candidates_without_home_fall_back_to_repo() {
+        assert_eq!(
+            backup_dir_candidates(|_| None, "", Some(Path::new("/repo/.git"))),
+            vec![PathBuf::from("/repo/.git/info/layer-backups")]
+        );
+        assert!(backup_dir_candidates(|_| None, "", None).is_empty());
+    }
+
+    #[test]
+    fn backup_dir_candidates_put_configured_dir_first() {
+        let env = |key: &str| (key == "HOME").then(|| "/home/me".to_string());
+        assert_eq!(
+            backup_dir_candidates(env, "~/sync/layer", None),
+            vec![PathBuf::from("/home/me/sync/layer"), PathBuf::from("/home/me/.layer-backups")]
+        );
+        assert_eq!(backup_dir_candidates(|_| None, "/srv/layer", None), vec![PathBuf::from("/srv/layer")]);
+    }
+
+    #[test]
+    fn replace_changes_adds_missing_and_removes_extra() {
+        let current = vec!["a.md".to_string(), "mine.md".to_string()];
+        let backup = vec!["b.md".to_string(), "a.md".to_string(), "b.md".to_string()];
+        assert_eq!(
+            replace_changes(&current, &backup),
+            vec![RestoreChange::Add("b.md".to_string()), RestoreChange::Remove("mine.md".to_string())]
+        );
+        assert!(replace_changes(&backup, &backup).is_empty());
+    }
+
     #[test]
     fn sanitize_repo_name_simple() {
         assert_eq!(sanitize_repo_name("my-project"), "my-project");
@@ -316,6 +1030,12 @@ mod tests {
         assert_eq!(sanitize_repo_name("///"), "repo");
     }
 
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
     #[test]
     fn format_backup_date_none() {
         assert_eq!(format_backup_date(&None), "unknown date");
@@ -332,4 +1052,44 @@ mod tests {
         let date = Some("not-a-date".to_string());
         assert_eq!(format_backup_date(&date), "not-a-date");
     }
+
+    #[test]
+    fn format_backup_time_shows_age_and_offset() {
+        let now = OffsetDateTime::parse("2026-02-10T12:00:00Z", &Rfc3339).unwrap();
+        let date = Some("2026-02-08T11:30:00Z".to_string());
+        assert_eq!(
+            format_backup_time(&date, now, UtcOffset::UTC),
+            "2 days ago (Feb 8, 2026 11:30 UTC)"
+        );
+        let offset = UtcOffset::from_hms(-8, 0, 0).unwrap();
+        assert_eq!(
+            format_backup_time(&date, now, offset),
+            "2 days ago (Feb 8, 2026 03:30 -08:00)"
+        );
+        assert_eq!(format_backup_time(&None, now, offset), "unknown date");
+    }
+
+    #[test]
+    fn relative_age_picks_a_coarse_unit() {
+        assert_eq!(relative_age(time::Duration::seconds(30)), "just now");
+        assert_eq!(relative_age(time::Duration::minutes(1)), "1 minute ago");
+        assert_eq!(relative_age(time::Duration::hours(5)), "5 hours ago");
+        assert_eq!(relative_age(time::Duration::days(15)), "2 weeks ago");
+        assert_eq!(relative_age(time::Duration::days(400)), "1 year ago");
+    }
+
+    #[test]
+    fn parse_utc_offset_reads_date_output() {
+        assert_eq!(parse_utc_offset("+0530"), UtcOffset::from_hms(5, 30, 0).ok());
+        assert_eq!(parse_utc_offset("-0800"), UtcOffset::from_hms(-8, 0, 0).ok());
+        assert_eq!(parse_utc_offset("UTC"), None);
+    }
+
+    #[test]
+    fn backup_delta_counts_additions_and_removals() {
+        let current = vec!["a.md".to_string(), "mine.md".to_string()];
+        let backup = vec!["a.md".to_string(), "b.md".to_string(), "c.md".to_string()];
+        assert_eq!(backup_delta(&current, &backup), "+2 -1 vs. your layer");
+        assert_eq!(backup_delta(&current, &current), "matches your layer");
+    }
 }
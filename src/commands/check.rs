@@ -0,0 +1,163 @@
+use crate::commands::hook;
+use crate::discovery::{self, Filter};
+use crate::commands::status::classify_entry;
+use crate::exclude_file::ensure_exclude_file;
+use crate::git;
+use crate::json::Json;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// What `check` looks for. Both by default; the toggles narrow it down.
+#[derive(Debug, Clone, Copy)]
+pub struct CheckScope {
+    pub exposed: bool,
+    pub discovered: bool,
+}
+
+/// Gate for CI and pre-commit hooks: exit 1 when any layered entry or context
+/// file is exposed (tracked), or a context file is discovered but not layered.
+/// Prints nothing but a one-line ok when there's nothing to report.
+pub fn run(scope: CheckScope, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+
+    let mut exposed = Vec::new();
+    if scope.exposed {
+        let pattern_index = git::build_pattern_match_index(&ctx.root, &ctx.exclude_path, &tracked)?;
+        let mut layered = Vec::new();
+        let mut exposed_entries = Vec::new();
+        for entry in exclude.entries() {
+            classify_entry(
+                &ctx.root,
+                &entry.value,
+                &tracked,
+                &pattern_index,
+                &mut layered,
+                &mut exposed_entries,
+            );
+        }
+        exposed.extend(exposed_entries.into_iter().map(|(entry, _, _)| entry));
+    }
+
+    let mut discovered = Vec::new();
+    for item in discovery::known_files(&ctx, &exclude.entry_set(), &tracked, Filter::Visible)? {
+        if item.category.is_optional() {
+            continue;
+        }
+        if item.is_tracked && scope.exposed {
+            exposed.push(item.path);
+        } else if !item.is_tracked && scope.discovered {
+            discovered.push(item.path);
+        }
+    }
+    exposed.sort();
+    exposed.dedup();
+    discovered.sort();
+    discovered.dedup();
+
+    let clean = exposed.is_empty() && discovered.is_empty();
+    match format {
+        ui::Format::Json => {
+            let value = Json::object(vec![
+                ("ok", Json::Bool(clean)),
+                ("exposed", Json::strings(exposed.iter().cloned())),
+                ("discovered", Json::strings(discovered.iter().cloned())),
+            ]);
+            outln!("{}", value.pretty());
+        }
+        ui::Format::Porcelain => {
+            for entry in &exposed {
+                ui::porcelain(&["exposed", entry]);
+            }
+            for path in &discovered {
+                ui::porcelain(&["discovered", path]);
+            }
+        }
+        ui::Format::Gha => {
+            for entry in &exposed {
+                let file = (!git::contains_glob(entry)).then(|| entry.trim_end_matches('/'));
+                ui::gha_annotation(
+                    "error",
+                    file,
+                    "Exposed context file",
+                    &format!("{entry} is tracked by git, so layering can't hide it. Untrack it with git rm --cached."),
+                );
+            }
+            for path in &discovered {
+                ui::gha_annotation(
+                    "warning",
+                    Some(path.trim_end_matches('/')),
+                    "Context file not layered",
+                    &format!("{path} is a local context file. Run layer add {path} to keep it out of commits."),
+                );
+            }
+        }
+        ui::Format::Pretty if clean => {
+            outln!("  {} No exposed or unlayered context files.", ui::ok());
+        }
+        ui::Format::Pretty | ui::Format::Template => {
+            for entry in &exposed {
+                outln!("  {} {entry}  {}", ui::exposed(), ui::warn_text("exposed — tracked by git"));
+            }
+            for path in &discovered {
+                outln!(
+                    "  {} {path}  {}",
+                    ui::discovered(),
+                    ui::dim_text(&format!("not layered — layer add {path}"))
+                );
+            }
+        }
+    }
+
+    Ok(if clean { 0 } else { 1 })
+}
+
+/// `check --hook`: the pre-commit framework passes the staged files as
+/// arguments. Fail only when one of them is layered or matches a known
+/// context pattern; the rest of the repo is not looked at.
+pub fn run_hook(files: Vec<String>, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let files: Vec<String> = files
+        .iter()
+        .map(|f| f.trim_start_matches("./").to_string())
+        .filter(|f| !f.is_empty())
+        .collect();
+    let blocked = hook::staged_context_files(&ctx, &files)?;
+
+    match format {
+        ui::Format::Json => {
+            let items = blocked
+                .iter()
+                .map(|(path, reason)| {
+                    Json::object(vec![
+                        ("path", Json::String(path.clone())),
+                        ("reason", Json::String(reason.clone())),
+                    ])
+                })
+                .collect();
+            let value = Json::object(vec![("ok", Json::Bool(blocked.is_empty())), ("blocked", Json::Array(items))]);
+            outln!("{}", value.pretty());
+        }
+        ui::Format::Porcelain => {
+            for (path, reason) in &blocked {
+                ui::porcelain(&["blocked", path, reason]);
+            }
+        }
+        ui::Format::Gha => {
+            for (path, reason) in &blocked {
+                ui::gha_annotation(
+                    "error",
+                    Some(path),
+                    "Context file staged",
+                    &format!("{path} is a local context file ({reason}). Unstage it with git rm --cached."),
+                );
+            }
+        }
+        ui::Format::Pretty if blocked.is_empty() => {}
+        ui::Format::Pretty | ui::Format::Template => hook::print_blocked(&blocked),
+    }
+
+    Ok(if blocked.is_empty() { 0 } else { 1 })
+}
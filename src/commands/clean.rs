@@ -1,13 +1,16 @@
+use crate::config;
 use crate::exclude_file::{ensure_exclude_file, Entry};
 use crate::git;
 use crate::git::RepoContext;
 use crate::ui;
+use crate::ui::outln;
 use anyhow::Result;
 use dialoguer::Confirm;
 use std::collections::HashSet;
 
-pub fn run(dry_run: bool, all: bool) -> Result<i32> {
+pub fn run(dry_run: bool, all: bool, yes: bool) -> Result<i32> {
     let ctx = git::ensure_repo()?;
+    let all = all || config::load(Some(&ctx.root))?.get_bool("clean.all");
     let mut exclude = ensure_exclude_file(&ctx.exclude_path)?;
     let entries = exclude.entries();
 
@@ -21,41 +24,44 @@ pub fn run(dry_run: bool, all: bool) -> Result<i32> {
     };
 
     if stale_managed.is_empty() && stale_user.is_empty() {
-        println!("  {} No stale entries found.", ui::ok());
+        outln!("  {} No stale entries found.", ui::ok());
         return Ok(2);
     }
 
     if dry_run {
         let total = stale_managed.len() + stale_user.len();
-        println!("{}", ui::heading(&format!("Would remove {} stale entries:", total)));
+        outln!("{}", ui::heading(&format!("Would remove {} stale entries:", total)));
         for item in &stale_managed {
-            println!("  {} {}", ui::stale(), item);
+            outln!("  {} {}", ui::stale(), item);
         }
         for item in &stale_user {
-            println!("  {} {} {}", ui::stale(), item, ui::dim_text("(manual)"));
+            outln!("  {} {} {}", ui::stale(), item, ui::dim_text("(manual)"));
         }
         ui::print_dry_run_notice();
         return Ok(0);
     }
 
     let total = stale_managed.len() + stale_user.len();
-    println!("{}", ui::heading(&format!("Found {} stale entries:", total)));
+    outln!("{}", ui::heading(&format!("Found {} stale entries:", total)));
     for item in &stale_managed {
-        println!("  {} {}", ui::stale(), item);
+        outln!("  {} {}", ui::stale(), item);
     }
     for item in &stale_user {
-        println!("  {} {} {}", ui::stale(), item, ui::dim_text("(manual)"));
+        outln!("  {} {} {}", ui::stale(), item, ui::dim_text("(manual)"));
     }
 
-    ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal or use --dry-run")?;
+    if !yes {
+        ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal, or use --yes or --dry-run")?;
+    }
 
-    let confirmed = Confirm::new()
-        .with_prompt("Remove these entries?")
-        .default(false)
-        .interact()?;
+    let confirmed = yes
+        || Confirm::new()
+            .with_prompt("Remove these entries?")
+            .default(false)
+            .interact()?;
 
     if !confirmed {
-        println!("No changes made.");
+        outln!("No changes made.");
         return Ok(2);
     }
 
@@ -74,13 +80,13 @@ pub fn run(dry_run: bool, all: bool) -> Result<i32> {
     }
 
     if total_removed == 0 {
-        println!("No stale entries removed.");
+        outln!("No stale entries removed.");
         return Ok(2);
     }
 
     exclude.write(&ctx.exclude_path)?;
 
-    println!("  {} Removed {} stale entries.", ui::ok(), total_removed);
+    outln!("  {} Removed {} stale entries.", ui::ok(), total_removed);
     Ok(0)
 }
 
@@ -0,0 +1,108 @@
+use crate::config;
+use crate::config::Value;
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git::{self, RepoContext};
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Print the effective configuration, optionally with where each value came from.
+pub fn list(show_origin: bool) -> Result<i32> {
+    let repo_root = git::ensure_repo().ok().map(|ctx| ctx.root);
+    let config = config::load(repo_root.as_deref())?;
+
+    let values: Vec<(&str, String, String)> = config
+        .iter()
+        .map(|(key, value, origin)| (key, value.to_string(), origin.to_string()))
+        .collect();
+    let key_width = values.iter().map(|(k, _, _)| k.len()).max().unwrap_or(0);
+    let value_width = values.iter().map(|(_, v, _)| v.len()).max().unwrap_or(0);
+
+    for (key, value, origin) in &values {
+        if show_origin {
+            outln!(
+                "  {:<kw$} = {:<vw$}  {}",
+                key,
+                value,
+                ui::dim_text(origin),
+                kw = key_width,
+                vw = value_width
+            );
+        } else {
+            outln!("  {:<kw$} = {}", key, value, kw = key_width);
+        }
+    }
+
+    Ok(0)
+}
+
+/// Print the effective value of one key.
+pub fn get(key: &str) -> Result<i32> {
+    let spec = config::key_spec(key).ok_or_else(|| config::unknown_key(key))?;
+    let repo_root = git::ensure_repo().ok().map(|ctx| ctx.root);
+    let config = config::load(repo_root.as_deref())?;
+
+    if let Some((value, _)) = config.get(spec.key) {
+        match value {
+            Value::Str(s) => outln!("{s}"),
+            other => outln!("{other}"),
+        }
+    }
+    Ok(0)
+}
+
+/// Validate and write one key to the repo `.layer.toml`, or the user config with `user`.
+pub fn set(key: &str, raw: &str, user: bool) -> Result<i32> {
+    let spec = config::key_spec(key).ok_or_else(|| config::unknown_key(key))?;
+    let value = config::parse_for_key(spec, raw)?;
+    let (repo, path) = target(user)?;
+
+    config::write_value(&path, spec.key, &value)?;
+    outln!(
+        "{} Set {} = {} in {}",
+        ui::ok(),
+        spec.key,
+        value,
+        ui::dim_text(&path.display().to_string())
+    );
+    if let Some(ctx) = repo {
+        layer_repo_config(&ctx)?;
+    }
+    Ok(0)
+}
+
+/// The config file a write goes to: the repo's `.layer.toml`, or the user
+/// config with `user`. The repo comes back too when it's the target.
+pub fn target(user: bool) -> Result<(Option<RepoContext>, PathBuf)> {
+    let repo = if user {
+        None
+    } else {
+        Some(git::ensure_repo().map_err(|err| anyhow!("{err:#}. Use --user to set it in your user config"))?)
+    };
+    let path = match &repo {
+        Some(ctx) => ctx.root.join(config::REPO_CONFIG_FILE),
+        None => config::user_config_path()
+            .ok_or_else(|| anyhow!("could not determine the user config directory (set HOME or XDG_CONFIG_HOME)"))?,
+    };
+    Ok((repo, path))
+}
+
+/// `.layer.toml` is your own project config, so layer it the first time it's
+/// written, unless the team already tracks it or something else ignores it.
+pub fn layer_repo_config(ctx: &RepoContext) -> Result<()> {
+    layer_own_file(ctx, config::REPO_CONFIG_FILE)
+}
+
+/// Layer one of layer's own files in the repo root, like `.layer.toml` or
+/// `.layerignore`, unless it's tracked or already ignored.
+pub fn layer_own_file(ctx: &RepoContext, entry: &str) -> Result<()> {
+    if git::is_tracked(&ctx.root, entry)? || git::check_ignore_verbose(&ctx.root, entry)?.is_some() {
+        return Ok(());
+    }
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    exclude.append_entry(entry);
+    exclude.write(&ctx.exclude_path)?;
+    outln!("{} Layered {entry} so it stays out of commits", ui::ok());
+    Ok(())
+}
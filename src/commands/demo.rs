@@ -0,0 +1,90 @@
+use crate::exclude_file::{entry_order, write_contents, write_settings, ExcludeFile};
+use crate::git;
+use crate::git::RepoContext;
+use crate::journal;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// Snapshot of the exclude file taken by `demo on`, kept in the git dir.
+const DEMO_SNAPSHOT_FILE: &str = "LAYER_DEMO";
+
+pub fn on() -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let snapshot = snapshot_path(&ctx);
+
+    if snapshot.exists() {
+        bail!("demo mode is already on. Run 'layer demo off' to restore your entries");
+    }
+
+    if !ctx.exclude_path.exists() {
+        outln!("No active entries to disable.");
+        return Ok(2);
+    }
+
+    let original = fs::read_to_string(&ctx.exclude_path)
+        .with_context(|| format!("failed to read {}", ctx.exclude_path.display()))?;
+    let mut exclude = ExcludeFile::parse(&original);
+    if exclude.entries().is_empty() {
+        outln!("No active entries to disable.");
+        return Ok(2);
+    }
+
+    fs::write(&snapshot, &original)
+        .with_context(|| format!("failed to write {}", snapshot.display()))?;
+
+    let disabled = exclude.disable_all();
+    exclude.write(&ctx.exclude_path)?;
+
+    outln!(
+        "  {} Demo mode on — {} entries visible to git and editors.",
+        ui::ok(),
+        disabled.len()
+    );
+    outln!("  {}", ui::dim_text("Run 'layer demo off' to restore the exact previous state."));
+    Ok(0)
+}
+
+pub fn off(force: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let snapshot = snapshot_path(&ctx);
+
+    if !snapshot.exists() {
+        outln!("Demo mode is not on.");
+        return Ok(2);
+    }
+
+    let original = fs::read_to_string(&snapshot)
+        .with_context(|| format!("failed to read {}", snapshot.display()))?;
+
+    // Refuse to clobber edits made while the demo was running.
+    // `demo on` wrote it in the configured order, so compare in that order too.
+    let mut expected = ExcludeFile::parse(&original);
+    expected.disable_all();
+    expected.apply_order(entry_order());
+    let current = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+    if current != expected.render() && !force {
+        bail!(
+            ".git/info/exclude changed during demo mode. Re-run with --force to restore the snapshot anyway"
+        );
+    }
+
+    write_contents(&ctx.exclude_path, &original, write_settings().symlink)?;
+    journal::record_change(&ctx.exclude_path, &current, &original);
+    fs::remove_file(&snapshot)
+        .with_context(|| format!("failed to remove {}", snapshot.display()))?;
+
+    outln!("  {} Demo mode off — previous entries restored.", ui::ok());
+    Ok(0)
+}
+
+/// Whether `demo on` is currently in effect for this repo.
+pub fn is_active(ctx: &RepoContext) -> bool {
+    snapshot_path(ctx).exists()
+}
+
+fn snapshot_path(ctx: &RepoContext) -> PathBuf {
+    ctx.git_dir.join(DEMO_SNAPSHOT_FILE)
+}
@@ -0,0 +1,171 @@
+use crate::exclude_file::{ensure_exclude_file_for_write, normalize_entry};
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Directories and files only one person's editor creates.
+const EDITOR_PATTERNS: &[&str] = &[
+    ".idea", ".vscode", ".fleet", ".zed", ".vim", ".nvim", ".emacs.d", "*.swp", "*.swo", "*~",
+    "*.sublime-project", "*.sublime-workspace",
+];
+
+/// Name prefixes people use for throwaway files.
+const SCRATCH_PREFIXES: &[&str] = &["todo", "scratch", "notes", "my-", "my_", "tmp-", "tmp_", "wip"];
+
+/// A root `.gitignore` line that looks like it belongs in one person's layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersonalLine {
+    pub line: usize,
+    pub pattern: String,
+    pub reason: &'static str,
+}
+
+/// Scan the root `.gitignore` for lines that look personal rather than shared.
+pub fn personal_gitignore_lines(repo_root: &Path) -> Vec<PersonalLine> {
+    let Ok(content) = fs::read_to_string(repo_root.join(".gitignore")) else {
+        return Vec::new();
+    };
+    let names = user_names(repo_root);
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let pattern = line.trim();
+            personal_reason(pattern, &names).map(|reason| PersonalLine {
+                line: idx + 1,
+                pattern: pattern.to_string(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Why `pattern` looks personal, if it does. `names` are lowercase login and
+/// git user names of whoever runs layer.
+pub fn personal_reason(pattern: &str, names: &[String]) -> Option<&'static str> {
+    if pattern.is_empty() || pattern.starts_with('#') || pattern.starts_with('!') {
+        return None;
+    }
+    let lower = pattern.to_lowercase();
+    let bare = lower.trim_start_matches('/').trim_end_matches('/');
+    let basename = bare.rsplit('/').next().unwrap_or(bare);
+
+    if names.iter().any(|name| name.len() >= 3 && lower.contains(name.as_str())) {
+        return Some("contains your username");
+    }
+    if basename.contains(".local.") || basename.ends_with(".local") {
+        return Some("local-only file");
+    }
+    if EDITOR_PATTERNS.contains(&basename) {
+        return Some("editor files");
+    }
+    if SCRATCH_PREFIXES.iter().any(|prefix| basename.starts_with(prefix)) {
+        return Some("scratch file");
+    }
+    None
+}
+
+fn user_names(repo_root: &Path) -> Vec<String> {
+    let mut names: Vec<String> = ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .collect();
+    if let Ok(name) = git::git_stdout(&["config", "user.name"], Some(repo_root)) {
+        names.extend(name.split_whitespace().map(str::to_string));
+    }
+    let mut names: Vec<String> = names.into_iter().map(|n| n.trim().to_lowercase()).collect();
+    names.retain(|n| !n.is_empty());
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Move lines out of the shared root `.gitignore` and into your layer.
+pub fn run(patterns: Vec<String>, dry_run: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let gitignore_path = ctx.root.join(".gitignore");
+    let content = fs::read_to_string(&gitignore_path)
+        .with_context(|| format!("failed to read {}", gitignore_path.display()))?;
+
+    let mut targets = HashSet::new();
+    for raw in &patterns {
+        let pattern = raw.trim().to_string();
+        if content.lines().any(|l| l.trim() == pattern) {
+            targets.insert(pattern);
+        } else {
+            outln!("  {} '{}' is not a line in .gitignore", ui::info(), pattern);
+        }
+    }
+    if targets.is_empty() {
+        return Ok(2);
+    }
+
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let mut known = exclude.entry_set();
+    let mut moved = Vec::new();
+    for raw in &patterns {
+        let pattern = raw.trim();
+        if !targets.contains(pattern) || moved.iter().any(|m| m == pattern) {
+            continue;
+        }
+        moved.push(pattern.to_string());
+        let entry = normalize_entry(pattern);
+        if dry_run {
+            outln!("  {} Would move '{pattern}' from .gitignore to your layer", ui::discovered());
+        } else if known.insert(entry.clone()) {
+            exclude.append_entry(&entry);
+        }
+    }
+
+    if dry_run {
+        ui::print_dry_run_notice();
+        return Ok(0);
+    }
+
+    let mut remaining: String = content
+        .lines()
+        .filter(|l| !targets.contains(l.trim()))
+        .map(|l| format!("{l}\n"))
+        .collect();
+    if !content.ends_with('\n') && remaining.ends_with('\n') {
+        remaining.pop();
+    }
+    exclude.write(&ctx.exclude_path)?;
+    fs::write(&gitignore_path, remaining)
+        .with_context(|| format!("failed to write {}", gitignore_path.display()))?;
+
+    for pattern in &moved {
+        outln!("  {} Moved '{pattern}' from .gitignore to your layer", ui::ok());
+    }
+    outln!("  {}", ui::dim_text("Commit the .gitignore change to share it."));
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn personal_reason_flags_local_editor_and_scratch_lines() {
+        let names = vec!["alice".to_string()];
+        assert_eq!(personal_reason("alice-notes/", &names), Some("contains your username"));
+        assert_eq!(personal_reason("*.local.json", &names), Some("local-only file"));
+        assert_eq!(personal_reason(".env.local", &names), Some("local-only file"));
+        assert_eq!(personal_reason(".idea/", &names), Some("editor files"));
+        assert_eq!(personal_reason("*.swp", &names), Some("editor files"));
+        assert_eq!(personal_reason("TODO.md", &names), Some("scratch file"));
+        assert_eq!(personal_reason("/scratch/", &names), Some("scratch file"));
+    }
+
+    #[test]
+    fn personal_reason_leaves_shared_lines_alone() {
+        let names = vec!["alice".to_string(), "al".to_string()];
+        for pattern in ["target/", "node_modules/", "*.log", "# .idea", "!keep.md", "", "dist/"] {
+            assert_eq!(personal_reason(pattern, &names), None, "{pattern}");
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::commands::config::layer_own_file;
+use crate::exclude_file::normalize_entry;
+use crate::git;
+use crate::layerignore::{self, LAYERIGNORE_FILE};
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// `layer dismiss`: record discovered files you've decided not to layer in
+/// `.layerignore`, so scan and status stop suggesting them. Lists what's
+/// dismissed without arguments.
+pub fn run(paths: Vec<String>) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let dismissed = layerignore::load(&ctx.root);
+
+    if paths.is_empty() {
+        if dismissed.is_empty() {
+            outln!("Nothing dismissed. Use 'layer dismiss <path>' to stop scan and status suggesting a file.");
+            return Ok(2);
+        }
+        for pattern in &dismissed {
+            outln!("  {} {pattern}", ui::info());
+        }
+        return Ok(0);
+    }
+
+    let mut added: Vec<String> = Vec::new();
+    for path in &paths {
+        let pattern = normalize_entry(path);
+        if pattern.is_empty() {
+            continue;
+        }
+        if dismissed.contains(&pattern) || added.contains(&pattern) {
+            outln!("  {} '{pattern}' is already dismissed", ui::info());
+            continue;
+        }
+        added.push(pattern);
+    }
+    if added.is_empty() {
+        return Ok(2);
+    }
+
+    let created = !ctx.root.join(LAYERIGNORE_FILE).exists();
+    layerignore::append(&ctx.root, &added)?;
+    for pattern in &added {
+        outln!("  {} Dismissed '{pattern}'; scan and status won't suggest it", ui::ok());
+    }
+    if created {
+        layer_own_file(&ctx, LAYERIGNORE_FILE)?;
+    }
+    Ok(0)
+}
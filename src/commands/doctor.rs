@@ -1,23 +1,54 @@
-use crate::exclude_file::ensure_exclude_file;
+use crate::acks;
+use crate::discovery::wildcard_match;
+use crate::commands::demote::{personal_gitignore_lines, PersonalLine};
+use crate::commands::global;
+use crate::commands::status::entry_matches_path;
+use crate::exclude_file::{ensure_exclude_file, Entry};
 use crate::git;
-use crate::git::PatternMatchSummary;
+use crate::git::{IgnoreDiscrepancy, PatternMatchSummary};
+use crate::json::Json;
 use crate::ui;
+use crate::ui::outln;
+use crate::wsl;
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use walkdir::WalkDir;
 
-pub fn run() -> Result<i32> {
+pub fn run(references: bool, format: ui::Format) -> Result<i32> {
     let ctx = git::ensure_repo()?;
     let exclude = ensure_exclude_file(&ctx.exclude_path)?;
     let entries = exclude.entries();
+    let json = format.is_json();
+
+    if format == ui::Format::Pretty && !acks::load(&ctx.git_dir).contains(acks::WSL_ID) {
+        if let Some(warning) = wsl::cross_filesystem_warning(&ctx.root, wsl::is_wsl()) {
+            ui::print_warning(&warning);
+            outln!();
+        }
+    }
+
+    let personal = personal_gitignore_lines(&ctx.root);
+    let (global_path, global_entries) = global::effective_entries()?;
+    let overlaps = global_overlaps(&entries, &global_entries);
+
+    if entries.is_empty() && json {
+        outln!("{}", diagnoses_json(&[], &personal, &overlaps, None).pretty());
+        return Ok(2);
+    }
+
+    if entries.is_empty() && format.is_porcelain() {
+        print_personal_porcelain(&personal);
+        return Ok(2);
+    }
 
     if entries.is_empty() {
-        println!(
+        outln!(
             "No layered entries. Run {} or {} to get started.",
             ui::brand("layer add"),
             ui::brand("layer scan")
         );
+        print_personal(&personal);
         return Ok(2);
     }
 
@@ -30,7 +61,15 @@ pub fn run() -> Result<i32> {
     let mut n_exposed = 0usize;
     let mut n_stale = 0usize;
     let mut n_redundant = 0usize;
-
+    let mut n_overridden = 0usize;
+    let discrepancies = git::ignore_discrepancies(
+        &ctx.root,
+        &ctx.exclude_path,
+        &git::discrepancy_targets(entries.iter().map(|e| e.value.as_str())),
+    )?;
+
+    let references = if references { Some(find_references(&ctx.root, &entries)?) } else { None };
+    let mut diagnoses = Vec::new();
     for entry in entries {
         let diagnosis = diagnose_entry(
             &ctx.root,
@@ -38,12 +77,34 @@ pub fn run() -> Result<i32> {
             &tracked,
             &gitignore_entries,
             &pattern_match_index,
+            discrepancies.get(entry.value.trim_end_matches('/')),
         )?;
+        diagnoses.push((entry, diagnosis));
+    }
 
+    if json {
+        outln!("{}", diagnoses_json(&diagnoses, &personal, &overlaps, references.as_deref()).pretty());
+        return Ok(exit_code(&diagnoses));
+    }
+    if format.is_porcelain() {
+        for (entry, diagnosis) in &diagnoses {
+            ui::porcelain(&[diagnosis.kind.label(), &entry.value]);
+        }
+        print_personal_porcelain(&personal);
+        for overlap in &overlaps {
+            ui::porcelain(&[overlap.porcelain_label(), &overlap.entry, &overlap.fix()]);
+        }
+        for reference in references.iter().flatten() {
+            ui::porcelain(&["referenced", &reference.entry, &format!("{}:{}", reference.path, reference.line)]);
+        }
+        return Ok(exit_code(&diagnoses));
+    }
+
+    for (entry, diagnosis) in diagnoses {
         match diagnosis.kind {
             DiagnosisKind::Layered => {
                 n_layered += 1;
-                println!(
+                outln!(
                     "  {} {} — layered",
                     ui::layered(),
                     entry.value
@@ -51,38 +112,50 @@ pub fn run() -> Result<i32> {
             }
             DiagnosisKind::Exposed => {
                 n_exposed += 1;
-                println!(
+                outln!(
                     "  {} {} — {}",
                     ui::exposed(),
                     entry.value,
                     ui::warn_text(&diagnosis.message)
                 );
                 for line in diagnosis.details {
-                    println!("    {}", ui::warn_text(&line));
+                    outln!("    {}", ui::warn_text(&line));
                 }
             }
             DiagnosisKind::Stale => {
                 n_stale += 1;
-                println!(
+                outln!(
                     "  {} {} — {}",
                     ui::stale(),
                     entry.value,
                     ui::err_text("stale — file not found")
                 );
-                println!(
+                outln!(
                     "    {}",
                     ui::dim_text(&format!("layer rm {}", entry.value))
                 );
             }
+            DiagnosisKind::Overridden => {
+                n_overridden += 1;
+                outln!(
+                    "  {} {} — {}",
+                    ui::exposed(),
+                    entry.value,
+                    ui::warn_text(&diagnosis.message)
+                );
+                for line in diagnosis.details {
+                    outln!("    {}", ui::dim_text(&line));
+                }
+            }
             DiagnosisKind::Redundant => {
                 n_redundant += 1;
-                println!(
+                outln!(
                     "  {} {} — {}",
                     ui::info(),
                     entry.value,
                     ui::dim_text("redundant — already in .gitignore")
                 );
-                println!(
+                outln!(
                     "    {}",
                     ui::dim_text(&format!("layer rm {}", entry.value))
                 );
@@ -90,8 +163,7 @@ pub fn run() -> Result<i32> {
         }
     }
 
-    println!();
-    print!("  ");
+    outln!();
     let mut parts = Vec::new();
     if n_layered > 0 {
         parts.push(format!("{} layered", n_layered));
@@ -99,15 +171,23 @@ pub fn run() -> Result<i32> {
     if n_exposed > 0 {
         parts.push(ui::warn_text(&format!("{} exposed", n_exposed)));
     }
+    if n_overridden > 0 {
+        parts.push(ui::warn_text(&format!("{} overridden", n_overridden)));
+    }
     if n_stale > 0 {
         parts.push(ui::err_text(&format!("{} stale", n_stale)));
     }
     if n_redundant > 0 {
         parts.push(ui::dim_text(&format!("{} redundant", n_redundant)));
     }
-    println!("{}", parts.join(" · "));
+    outln!("  {}", parts.join(" · "));
+    print_personal(&personal);
+    print_global_overlaps(&overlaps, &global_path);
+    if let Some(references) = &references {
+        print_references(references);
+    }
 
-    if n_exposed > 0 || n_stale > 0 {
+    if n_exposed > 0 || n_stale > 0 || n_overridden > 0 {
         return Ok(1);
     }
 
@@ -118,6 +198,312 @@ pub fn run() -> Result<i32> {
     Ok(0)
 }
 
+/// `.gitignore` lines that look like they belong in one person's layer.
+/// Suggestions only; they never change the exit code.
+fn print_personal(personal: &[PersonalLine]) {
+    if personal.is_empty() {
+        return;
+    }
+    outln!();
+    outln!("  {} .gitignore lines that look personal:", ui::info());
+    for item in personal {
+        outln!(
+            "    {} {}",
+            item.pattern,
+            ui::dim_text(&format!("— {} (line {}): layer demote {}", item.reason, item.line, item.pattern))
+        );
+    }
+}
+
+/// Where tracked files name layered paths. Warnings only; they never
+/// change the exit code.
+fn print_references(references: &[Reference]) {
+    outln!();
+    if references.is_empty() {
+        outln!("  {} No tracked file mentions a layered path.", ui::ok());
+        return;
+    }
+    let mut by_entry: Vec<(&str, Vec<&Reference>)> = Vec::new();
+    for reference in references {
+        match by_entry.iter_mut().find(|(entry, _)| *entry == reference.entry) {
+            Some((_, list)) => list.push(reference),
+            None => by_entry.push((&reference.entry, vec![reference])),
+        }
+    }
+    outln!(
+        "  {} Layered paths that tracked files mention — collaborators and CI won't have them:",
+        ui::exposed()
+    );
+    for (entry, list) in by_entry {
+        let mut places: Vec<String> = list.iter().take(3).map(|r| format!("{}:{}", r.path, r.line)).collect();
+        if list.len() > 3 {
+            places.push(format!("+{} more", list.len() - 3));
+        }
+        outln!("    {} {}", entry, ui::dim_text(&format!("— {}", places.join(", "))));
+    }
+}
+
+/// Layered entries the global gitignore already handles, and which of the
+/// two should keep each. Suggestions only; they never change the exit code.
+fn print_global_overlaps(overlaps: &[GlobalOverlap], global_path: &Path) {
+    if overlaps.is_empty() {
+        return;
+    }
+    outln!();
+    outln!("  {} Overlap with your global gitignore ({}):", ui::info(), global_path.display());
+    for overlap in overlaps {
+        outln!(
+            "    {} {}",
+            overlap.entry,
+            ui::dim_text(&format!("— {}: {}", overlap.reason(), overlap.fix()))
+        );
+    }
+}
+
+/// A layered entry that the global gitignore also ignores.
+#[derive(Debug, PartialEq)]
+struct GlobalOverlap {
+    entry: String,
+    /// The global pattern that covers it.
+    global: String,
+    owner: Scope,
+}
+
+/// Which file should own an overlapping pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A broader or repo-agnostic global pattern makes the layered entry redundant.
+    Global,
+    /// A path inside this repo; the global copy is what's redundant.
+    Local,
+}
+
+impl GlobalOverlap {
+    fn reason(&self) -> String {
+        match (self.owner, self.entry == self.global) {
+            (Scope::Local, _) => "also in the global gitignore, but it's a path in this repo; keep it layered".to_string(),
+            (Scope::Global, true) => "also in the global gitignore, which covers every repo".to_string(),
+            (Scope::Global, false) => format!("covered by global {}", self.global),
+        }
+    }
+
+    fn fix(&self) -> String {
+        match self.owner {
+            Scope::Global => format!("layer rm {}", self.entry),
+            Scope::Local => format!("layer global rm {}", self.global),
+        }
+    }
+
+    fn porcelain_label(&self) -> &'static str {
+        if self.entry == self.global {
+            "global-duplicate"
+        } else {
+            "global-covered"
+        }
+    }
+}
+
+/// Match each layered entry against the global gitignore. An identical
+/// pattern belongs in the global file unless it names a path in this repo
+/// (a slash before the end), which is meaningless elsewhere. A global pattern
+/// without such a slash matches at any depth, so it also covers layered
+/// entries whose last component it matches, like `*.log` and `logs/debug.log`.
+fn global_overlaps(entries: &[Entry], global_entries: &[String]) -> Vec<GlobalOverlap> {
+    let global: Vec<&str> = global_entries
+        .iter()
+        .map(String::as_str)
+        .filter(|g| !g.starts_with('!'))
+        .collect();
+    let mut overlaps = Vec::new();
+    for entry in entries {
+        let value = entry.value.as_str();
+        if let Some(same) = global.iter().find(|g| **g == value) {
+            overlaps.push(GlobalOverlap {
+                entry: value.to_string(),
+                global: same.to_string(),
+                owner: if anchored(value) { Scope::Local } else { Scope::Global },
+            });
+            continue;
+        }
+        let name = value.trim_end_matches('/').rsplit('/').next().unwrap_or(value);
+        let covering = global.iter().find(|g| {
+            let dir_only = g.ends_with('/');
+            !anchored(g) && (!dir_only || value.ends_with('/')) && wildcard_match(g.trim_end_matches('/'), name)
+        });
+        if let Some(covering) = covering {
+            overlaps.push(GlobalOverlap {
+                entry: value.to_string(),
+                global: covering.to_string(),
+                owner: Scope::Global,
+            });
+        }
+    }
+    overlaps
+}
+
+/// Whether a gitignore pattern is tied to a place in the repo rather than
+/// matching at any depth.
+fn anchored(pattern: &str) -> bool {
+    pattern.trim_end_matches('/').contains('/')
+}
+
+fn print_personal_porcelain(personal: &[PersonalLine]) {
+    for item in personal {
+        ui::porcelain(&["personal", &item.pattern]);
+    }
+}
+
+fn exit_code(diagnoses: &[(Entry, Diagnosis)]) -> i32 {
+    let count = |kind: DiagnosisKind| diagnoses.iter().filter(|(_, d)| d.kind == kind).count();
+    if count(DiagnosisKind::Exposed) + count(DiagnosisKind::Stale) + count(DiagnosisKind::Overridden) > 0 {
+        1
+    } else if diagnoses.is_empty() || (count(DiagnosisKind::Layered) == 0 && count(DiagnosisKind::Redundant) > 0) {
+        2
+    } else {
+        0
+    }
+}
+
+/// Per-entry diagnoses plus a summary of counts per state.
+fn diagnoses_json(
+    diagnoses: &[(Entry, Diagnosis)],
+    personal: &[PersonalLine],
+    overlaps: &[GlobalOverlap],
+    references: Option<&[Reference]>,
+) -> Json {
+    let kinds = [
+        DiagnosisKind::Layered,
+        DiagnosisKind::Exposed,
+        DiagnosisKind::Overridden,
+        DiagnosisKind::Stale,
+        DiagnosisKind::Redundant,
+    ];
+    let summary = kinds
+        .iter()
+        .map(|kind| {
+            let n = diagnoses.iter().filter(|(_, d)| d.kind == *kind).count();
+            (kind.label().to_string(), Json::Number(n as f64))
+        })
+        .collect();
+
+    let entries = diagnoses
+        .iter()
+        .map(|(entry, diagnosis)| {
+            Json::object(vec![
+                ("entry", Json::String(entry.value.clone())),
+                ("kind", Json::String(diagnosis.kind.label().to_string())),
+                ("message", Json::String(diagnosis.summary())),
+                ("fix", diagnosis.fix(&entry.value).map_or(Json::Null, Json::String)),
+                ("details", Json::strings(diagnosis.details.iter().cloned())),
+            ])
+        })
+        .collect();
+
+    let personal = personal
+        .iter()
+        .map(|item| {
+            Json::object(vec![
+                ("pattern", Json::String(item.pattern.clone())),
+                ("line", Json::Number(item.line as f64)),
+                ("reason", Json::String(item.reason.to_string())),
+                ("fix", Json::String(format!("layer demote {}", item.pattern))),
+            ])
+        })
+        .collect();
+
+    let overlaps = overlaps
+        .iter()
+        .map(|overlap| {
+            Json::object(vec![
+                ("entry", Json::String(overlap.entry.clone())),
+                ("global", Json::String(overlap.global.clone())),
+                ("owner", Json::String(if overlap.owner == Scope::Global { "global" } else { "local" }.to_string())),
+                ("fix", Json::String(overlap.fix())),
+            ])
+        })
+        .collect();
+
+    let mut fields = vec![
+        ("entries", Json::Array(entries)),
+        ("summary", Json::Object(summary)),
+        ("gitignore_personal", Json::Array(personal)),
+        ("global_overlap", Json::Array(overlaps)),
+    ];
+    if let Some(references) = references {
+        let references = references
+            .iter()
+            .map(|r| {
+                Json::object(vec![
+                    ("entry", Json::String(r.entry.clone())),
+                    ("path", Json::String(r.path.clone())),
+                    ("line", Json::Number(r.line as f64)),
+                ])
+            })
+            .collect();
+        fields.push(("references", Json::Array(references)));
+    }
+    Json::object(fields)
+}
+
+/// A line in a tracked file that names a layered entry.
+#[derive(Debug, PartialEq)]
+struct Reference {
+    entry: String,
+    path: String,
+    line: usize,
+}
+
+/// Tracked files that mention a layered path by name, e.g. a build script
+/// that reads `PROMPT.md`. Hiding that file only on your machine can leave
+/// the code working for you and broken for everyone else.
+fn find_references(repo_root: &Path, entries: &[Entry]) -> Result<Vec<Reference>> {
+    let needles: Vec<(&str, String)> = entries
+        .iter()
+        .filter_map(|entry| reference_needle(&entry.value).map(|needle| (entry.value.as_str(), needle)))
+        .collect();
+    let patterns: Vec<String> = needles.iter().map(|(_, needle)| needle.clone()).collect();
+
+    let mut references = Vec::new();
+    for hit in git::grep_tracked(repo_root, &patterns)? {
+        if hit.path.rsplit('/').next() == Some(".gitignore") {
+            continue;
+        }
+        for (entry, needle) in &needles {
+            if entry_matches_path(entry, &hit.path) || !mentions(&hit.text, needle) {
+                continue;
+            }
+            references.push(Reference {
+                entry: entry.to_string(),
+                path: hit.path.clone(),
+                line: hit.line,
+            });
+        }
+    }
+    Ok(references)
+}
+
+/// The text to look for in tracked files, or `None` for globs and names
+/// too short to tell apart from ordinary words.
+fn reference_needle(entry: &str) -> Option<String> {
+    let needle = entry.trim_start_matches('/');
+    if git::contains_glob(needle) || needle.starts_with('!') || needle.trim_end_matches('/').len() < 3 {
+        return None;
+    }
+    Some(needle.to_string())
+}
+
+/// Whether `text` names `needle` as a whole path, not as part of a longer
+/// name (`notes.md` shouldn't match `mynotes.md`).
+fn mentions(text: &str, needle: &str) -> bool {
+    let part_of_name = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    text.match_indices(needle).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + needle.len()..].chars().next();
+        !before.is_some_and(part_of_name)
+            && (needle.ends_with('/') || !after.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-')))
+    })
+}
+
 #[derive(Debug)]
 struct Diagnosis {
     kind: DiagnosisKind,
@@ -125,21 +511,71 @@ struct Diagnosis {
     details: Vec<String>,
 }
 
-#[derive(Debug)]
+impl Diagnosis {
+    /// One-line description, matching what the text output shows.
+    fn summary(&self) -> String {
+        match self.kind {
+            DiagnosisKind::Layered => "layered".to_string(),
+            DiagnosisKind::Stale => "stale — file not found".to_string(),
+            DiagnosisKind::Redundant => "redundant — already in .gitignore".to_string(),
+            DiagnosisKind::Exposed | DiagnosisKind::Overridden => self.message.clone(),
+        }
+    }
+
+    /// The command that resolves this diagnosis, when there is a single one.
+    fn fix(&self, entry: &str) -> Option<String> {
+        match self.kind {
+            DiagnosisKind::Layered | DiagnosisKind::Overridden => None,
+            DiagnosisKind::Stale | DiagnosisKind::Redundant => Some(format!("layer rm {entry}")),
+            DiagnosisKind::Exposed if git::contains_glob(entry) => None,
+            DiagnosisKind::Exposed if entry.ends_with('/') => {
+                Some(format!("git rm --cached -r {}", entry.trim_end_matches('/')))
+            }
+            DiagnosisKind::Exposed => Some(format!("git rm --cached {entry}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DiagnosisKind {
     Layered,
     Exposed,
+    Overridden,
     Stale,
     Redundant,
 }
 
+impl DiagnosisKind {
+    fn label(self) -> &'static str {
+        match self {
+            DiagnosisKind::Layered => "layered",
+            DiagnosisKind::Exposed => "exposed",
+            DiagnosisKind::Overridden => "overridden",
+            DiagnosisKind::Stale => "stale",
+            DiagnosisKind::Redundant => "redundant",
+        }
+    }
+}
+
 fn diagnose_entry(
     repo_root: &Path,
     entry: &str,
     tracked: &HashSet<String>,
     gitignore_entries: &HashSet<String>,
     pattern_match_index: &HashMap<String, PatternMatchSummary>,
+    discrepancy: Option<&IgnoreDiscrepancy>,
 ) -> Result<Diagnosis> {
+    if let Some(negated @ IgnoreDiscrepancy::Negated { source, .. }) = discrepancy {
+        return Ok(Diagnosis {
+            kind: DiagnosisKind::Overridden,
+            message: format!("overridden — {}", negated.describe()),
+            details: vec![format!(
+                "Git never ignores it while that negation exists; remove it from {} or drop this entry",
+                source.replace('\\', "/")
+            )],
+        });
+    }
+
     let resolved = resolve_entry(repo_root, entry, tracked, pattern_match_index)?;
 
     if !resolved.exists {
@@ -159,13 +595,8 @@ fn diagnose_entry(
                 resolved.tracked_matches.len(),
                 resolved.total_matches
             ));
-        } else if entry.ends_with('/') {
-            details.push(format!(
-                "Fix: git rm --cached -r {}",
-                entry.trim_end_matches('/')
-            ));
         } else {
-            details.push(format!("Fix: git rm --cached {}", entry));
+            details.push(format!("Fix: layer untrack {}", entry.trim_end_matches('/')));
         }
 
         if resolved.tracked_matches.len() <= 3 {
@@ -182,6 +613,8 @@ fn diagnose_entry(
                     resolved.total_matches,
                     resolved.tracked_matches.len()
                 )
+            } else if discrepancy == Some(&IgnoreDiscrepancy::Tracked) {
+                format!("exposed — {}", IgnoreDiscrepancy::Tracked.describe())
             } else {
                 "exposed — tracked by git".to_string()
             },
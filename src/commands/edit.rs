@@ -1,23 +1,135 @@
-use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::exclude_file::{ensure_exclude_file_for_write, line_entry, markers, normalize_entry};
 use crate::git;
-use anyhow::{anyhow, Context, Result};
+use crate::journal;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{anyhow, bail, Context, Result};
 use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
 use std::process::Command;
 
-pub fn run() -> Result<i32> {
+/// Scratch file for `--managed-only`, kept in the git dir like COMMIT_EDITMSG.
+const MANAGED_EDIT_FILE: &str = "LAYER_EDIT";
+
+pub fn run(managed_only: bool, allow_negation: bool) -> Result<i32> {
     let ctx = git::ensure_repo()?;
-    let _exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+
+    let editor = resolve_editor();
+
+    if !managed_only {
+        outln!("Opening .git/info/exclude in {editor}...");
+        let before = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+        launch_editor(&editor, &ctx.exclude_path)?;
+        let after = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+        journal::record_change(&ctx.exclude_path, &before, &after);
+        return Ok(0);
+    }
+
+    // A scratch file left by a rejected edit still holds the user's work, so
+    // reopen it instead of starting over from the managed section.
+    let scratch = ctx.git_dir.join(MANAGED_EDIT_FILE);
+    if scratch.exists() {
+        outln!("Reopening your previous edits from {}...", scratch.display());
+    } else {
+        let mut content = exclude.managed.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        fs::write(&scratch, &content)
+            .with_context(|| format!("failed to write {}", scratch.display()))?;
+    }
+
+    outln!("Opening managed section in {editor}...");
+    launch_editor(&editor, &scratch)?;
+    let edited = fs::read_to_string(&scratch)
+        .with_context(|| format!("failed to read {}", scratch.display()))?;
+
+    let mut lines: Vec<String> = edited.lines().map(ToOwned::to_owned).collect();
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+
+    if let Err(err) = validate_managed(&lines, allow_negation) {
+        bail!("{err}. Your edits are kept in {}; run 'layer edit --managed-only' again to fix them", scratch.display());
+    }
 
-    let editor = env::var("VISUAL")
+    if lines == exclude.managed {
+        let _ = fs::remove_file(&scratch);
+        outln!("No changes made.");
+        return Ok(2);
+    }
+
+    exclude.managed = lines;
+    exclude.write(&ctx.exclude_path)?;
+    let _ = fs::remove_file(&scratch);
+
+    outln!(
+        "  {} Updated managed section ({} entries).",
+        ui::ok(),
+        exclude.entries().len()
+    );
+    Ok(0)
+}
+
+pub fn resolve_editor() -> String {
+    env::var("VISUAL")
         .ok()
         .filter(|v| !v.trim().is_empty())
         .or_else(|| env::var("EDITOR").ok().filter(|v| !v.trim().is_empty()))
-        .unwrap_or_else(|| "vi".to_string());
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open `.git/info/exclude` with the cursor on `entry`'s line, for editors
+/// that take a line number; others just open the file.
+pub fn open_at(entry: &str) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let before = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+    let wanted = [entry.trim().to_string(), normalize_entry(entry)];
+    let Some(line) = before
+        .lines()
+        .position(|l| line_entry(l).is_some_and(|value| wanted.iter().any(|w| w == value)))
+        .map(|idx| idx + 1)
+    else {
+        bail!("'{}' is not in .git/info/exclude. Run 'layer ls' to see entries", entry.trim());
+    };
+
+    let editor = resolve_editor();
+    outln!("Opening .git/info/exclude at line {line} in {editor}...");
+    run_editor(&editor, goto_args(&editor, &ctx.exclude_path, line))?;
+    let after = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+    journal::record_change(&ctx.exclude_path, &before, &after);
+    Ok(0)
+}
+
+/// Arguments that open `path` at `line`: `+N file` for terminal editors,
+/// `file:N` for the GUI ones that understand it, and just the file otherwise.
+fn goto_args(editor: &str, path: &Path, line: usize) -> Vec<OsString> {
+    let name = Path::new(editor)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut at_line = path.as_os_str().to_owned();
+    at_line.push(format!(":{line}"));
+    match name.as_str() {
+        "vi" | "vim" | "nvim" | "gvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" | "joe" | "mg" => {
+            vec![format!("+{line}").into(), path.into()]
+        }
+        "code" | "code-insiders" | "codium" | "cursor" => vec!["--goto".into(), at_line],
+        "subl" | "zed" | "hx" | "helix" => vec![at_line],
+        _ => vec![path.into()],
+    }
+}
 
-    println!("Opening .git/info/exclude in {editor}...");
+pub fn launch_editor(editor: &str, path: &Path) -> Result<()> {
+    run_editor(editor, vec![path.into()])
+}
 
-    let status = Command::new(&editor)
-        .arg(&ctx.exclude_path)
+fn run_editor(editor: &str, args: Vec<OsString>) -> Result<()> {
+    let status = Command::new(editor)
+        .args(args)
         .status()
         .with_context(|| format!("failed to launch editor '{editor}'"))?;
 
@@ -25,5 +137,66 @@ pub fn run() -> Result<i32> {
         return Err(anyhow!("editor exited with status {status}"));
     }
 
-    Ok(0)
+    Ok(())
+}
+
+/// Reject edits that would break section ownership or silently un-ignore files.
+fn validate_managed(lines: &[String], allow_negation: bool) -> Result<()> {
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if markers().is_marker(trimmed) {
+            bail!(
+                "line {}: section markers can't appear inside the managed section. No changes made",
+                idx + 1
+            );
+        }
+        if trimmed.starts_with('!') && !allow_negation {
+            bail!(
+                "line {}: '{}' is a negation and would un-ignore files. Re-run with --allow-negation if intended. No changes made",
+                idx + 1,
+                trimmed
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn goto_args_match_editor_conventions() {
+        let path = Path::new("/repo/.git/info/exclude");
+        let strings = |args: Vec<OsString>| args.into_iter().map(|a| a.into_string().unwrap()).collect::<Vec<_>>();
+        assert_eq!(strings(goto_args("/usr/bin/nvim", path, 7)), vec!["+7", "/repo/.git/info/exclude"]);
+        assert_eq!(strings(goto_args("code", path, 7)), vec!["--goto", "/repo/.git/info/exclude:7"]);
+        assert_eq!(strings(goto_args("hx", path, 7)), vec!["/repo/.git/info/exclude:7"]);
+        assert_eq!(strings(goto_args("ed", path, 7)), vec!["/repo/.git/info/exclude"]);
+    }
+
+    #[test]
+    fn validate_accepts_entries_and_comments() {
+        let edited = lines(&["CLAUDE.md", "# note", "# [off] Agents.md", ""]);
+        assert!(validate_managed(&edited, false).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_section_markers() {
+        let edited = lines(&["CLAUDE.md", "# end layer", "stray.md"]);
+        let err = validate_managed(&edited, false).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn validate_negation_requires_opt_in() {
+        let edited = lines(&["*.md", "!README.md"]);
+        assert!(validate_managed(&edited, false).is_err());
+        assert!(validate_managed(&edited, true).is_ok());
+    }
 }
@@ -0,0 +1,50 @@
+use crate::exclude_file::{entry_order, ensure_exclude_file, EntryOrder};
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// Rewrite the layer section in the configured `exclude.order`.
+pub fn run(dry_run: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let order = entry_order();
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+
+    let mut ordered = exclude.clone();
+    ordered.apply_order(order);
+    if ordered.managed == exclude.managed {
+        outln!(
+            "Entries are already in {} order. Nothing to do.",
+            order_name(order)
+        );
+        return Ok(2);
+    }
+
+    let moved = exclude
+        .managed
+        .iter()
+        .zip(&ordered.managed)
+        .filter(|(a, b)| a != b)
+        .count();
+    if dry_run {
+        outln!("Would reorder {moved} lines into {} order.", order_name(order));
+        ui::print_dry_run_notice();
+        return Ok(0);
+    }
+
+    exclude.write(&ctx.exclude_path)?;
+    outln!(
+        "  {} Reordered {moved} lines into {} order",
+        ui::ok(),
+        order_name(order)
+    );
+    Ok(0)
+}
+
+fn order_name(order: EntryOrder) -> &'static str {
+    match order {
+        EntryOrder::Insertion => "insertion",
+        EntryOrder::Alphabetical => "alphabetical",
+        EntryOrder::Tool => "tool",
+    }
+}
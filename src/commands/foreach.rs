@@ -0,0 +1,161 @@
+use crate::commands::global::expand_tilde;
+use crate::json;
+use crate::json::Json;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// `layer foreach`: run a layer subcommand in every repo the manifest lists,
+/// then summarize. Exits 1 if any repo had a problem, 2 if none had
+/// anything to do, and 0 otherwise. `flags` are the global flags each run
+/// gets along with `-C`.
+///
+/// With `--format json` the result is one array of `{repo, exit_code,
+/// output}`; other machine formats keep the children's stdout and move the
+/// headings and summary to stderr.
+pub fn run(manifest: &Path, args: Vec<String>, flags: &[String], format: ui::Format) -> Result<i32> {
+    if args.is_empty() {
+        bail!("no subcommand given. Use 'layer foreach --manifest <file> -- <subcommand...>'");
+    }
+    let repos = read_manifest(manifest)?;
+    if repos.is_empty() {
+        if format.is_json() {
+            outln!("{}", Json::Array(Vec::new()).pretty());
+        } else {
+            outln!("{} lists no repositories.", manifest.display());
+        }
+        return Ok(2);
+    }
+    let exe = std::env::current_exe().context("failed to locate the layer executable")?;
+    let pretty = format == ui::Format::Pretty;
+    let note = |line: &str| {
+        if pretty {
+            outln!("{line}");
+        } else {
+            ui::print_stderr(line);
+        }
+    };
+
+    let mut results: Vec<(String, Outcome)> = Vec::new();
+    let mut reports = Vec::new();
+    for (label, path) in repos {
+        if !format.is_json() {
+            note(&ui::heading(&label));
+        }
+        let mut output = Json::Null;
+        let outcome = if !path.is_dir() {
+            if !format.is_json() {
+                note(&format!("  {} {}", ui::stale(), ui::err_text("not a directory")));
+            }
+            Outcome::Missing
+        } else {
+            let mut command = Command::new(&exe);
+            command.arg("-C").arg(&path).args(flags).args(&args);
+            let status = if format.is_json() {
+                let run = command
+                    .stderr(Stdio::inherit())
+                    .output()
+                    .with_context(|| format!("failed to run layer in {}", path.display()))?;
+                let stdout = String::from_utf8_lossy(&run.stdout).into_owned();
+                output = json::parse(&stdout).unwrap_or(Json::String(stdout));
+                run.status
+            } else {
+                command
+                    .status()
+                    .with_context(|| format!("failed to run layer in {}", path.display()))?
+            };
+            Outcome::Exited(status.code().unwrap_or(1))
+        };
+        if format.is_json() {
+            let exit_code = match outcome {
+                Outcome::Exited(code) => Json::Number(f64::from(code)),
+                Outcome::Missing => Json::Null,
+            };
+            let mut fields = vec![
+                ("repo", Json::String(label.clone())),
+                ("exit_code", exit_code),
+                ("output", output),
+            ];
+            if outcome == Outcome::Missing {
+                fields.push(("error", Json::String("not a directory".to_string())));
+            }
+            reports.push(Json::object(fields));
+        } else {
+            note("");
+        }
+        results.push((label, outcome));
+    }
+
+    if format.is_json() {
+        outln!("{}", Json::Array(reports).pretty());
+        return Ok(combined_exit_code(results.iter().map(|(_, outcome)| outcome)));
+    }
+
+    note(&ui::heading(&format!("layer {} in {} repos:", args.join(" "), results.len())));
+    for (label, outcome) in &results {
+        let (marker, text) = match outcome {
+            Outcome::Exited(0) => (ui::ok(), "ok".to_string()),
+            Outcome::Exited(2) => (ui::info(), "nothing to do".to_string()),
+            Outcome::Exited(code) => (ui::exposed(), ui::warn_text(&format!("exit {code}"))),
+            Outcome::Missing => (ui::stale(), ui::err_text("not a directory")),
+        };
+        note(&format!("  {marker} {label} — {text}"));
+    }
+    Ok(combined_exit_code(results.iter().map(|(_, outcome)| outcome)))
+}
+
+/// How one repo's run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Exited(i32),
+    Missing,
+}
+
+/// 1 if any repo failed, else 0 if any did something, else 2.
+fn combined_exit_code<'a>(outcomes: impl Iterator<Item = &'a Outcome>) -> i32 {
+    let mut code = 2;
+    for outcome in outcomes {
+        match outcome {
+            Outcome::Exited(0) => code = 0,
+            Outcome::Exited(2) => {}
+            _ => return 1,
+        }
+    }
+    code
+}
+
+/// Repos in a manifest, one path per line; blank lines and `#` comments are
+/// skipped, `~/` is expanded, and relative paths are relative to the
+/// manifest. Each comes back with the line as written, for display.
+fn read_manifest(manifest: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let content = fs::read_to_string(manifest)
+        .with_context(|| format!("failed to read {}", manifest.display()))?;
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let path = expand_tilde(line);
+            let path = if path.is_relative() { base.join(path) } else { path };
+            (line.to_string(), path)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_exit_code_prefers_failures_then_work_done() {
+        let code = |outcomes: &[Outcome]| combined_exit_code(outcomes.iter());
+        assert_eq!(code(&[Outcome::Exited(2), Outcome::Exited(2)]), 2);
+        assert_eq!(code(&[Outcome::Exited(2), Outcome::Exited(0)]), 0);
+        assert_eq!(code(&[Outcome::Exited(0), Outcome::Exited(1)]), 1);
+        assert_eq!(code(&[Outcome::Exited(0), Outcome::Missing]), 1);
+    }
+}
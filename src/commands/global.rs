@@ -1,5 +1,7 @@
 use crate::exclude_file::{normalize_entry, ExcludeFile};
+use crate::git;
 use crate::ui;
+use crate::ui::outln;
 use anyhow::{anyhow, Context, Result};
 use dialoguer::MultiSelect;
 use std::collections::HashSet;
@@ -13,7 +15,7 @@ pub fn add(files: Vec<String>) -> Result<i32> {
     }
 
     let path = global_ignore_path()?;
-    let mut file = ensure_global_file(&path)?;
+    let (mut file, loaded) = ensure_global_file(&path)?;
     let mut known = all_entry_set(&file);
     let mut added = 0usize;
 
@@ -24,13 +26,13 @@ pub fn add(files: Vec<String>) -> Result<i32> {
         }
 
         if known.contains(&normalized) {
-            println!("  {} '{normalized}' already in global gitignore", ui::info());
+            outln!("  {} '{normalized}' already in global gitignore", ui::info());
             continue;
         }
 
         file.append_entry(&normalized);
         known.insert(normalized.clone());
-        println!(
+        outln!(
             "  {} Added '{normalized}' to global gitignore {}",
             ui::ok(),
             ui::dim_text(&format!("({})", path.display()))
@@ -42,27 +44,27 @@ pub fn add(files: Vec<String>) -> Result<i32> {
         return Ok(2);
     }
 
-    file.write(&path)?;
+    file.write_global_if_unchanged(&path, &loaded)?;
     Ok(0)
 }
 
 pub fn ls() -> Result<i32> {
     let path = global_ignore_path()?;
-    let file = ensure_global_file(&path)?;
+    let (file, _) = ensure_global_file(&path)?;
     let managed = file.entries();
     let external = file.user_entries();
 
     if managed.is_empty() && external.is_empty() {
-        println!("Global gitignore ({}) is empty.", path.display());
+        outln!("Global gitignore ({}) is empty.", path.display());
         return Ok(2);
     }
 
-    println!("{}", ui::heading(&format!("Global gitignore ({}):", path.display())));
+    outln!("{}", ui::heading(&format!("Global gitignore ({}):", path.display())));
     for entry in &managed {
-        println!("  {}", entry.value);
+        outln!("  {}", entry.value);
     }
     for entry in &external {
-        println!("  {}  {}", entry.value, ui::dim_text("(external)"));
+        outln!("  {}  {}", entry.value, ui::dim_text("(external)"));
     }
 
     Ok(0)
@@ -70,11 +72,11 @@ pub fn ls() -> Result<i32> {
 
 pub fn rm(files: Vec<String>) -> Result<i32> {
     let path = global_ignore_path()?;
-    let mut file = ensure_global_file(&path)?;
+    let (mut file, loaded) = ensure_global_file(&path)?;
     let all_entries = all_entries_vec(&file);
 
     if all_entries.is_empty() {
-        println!("Global gitignore ({}) is empty. Nothing to remove.", path.display());
+        outln!("Global gitignore ({}) is empty. Nothing to remove.", path.display());
         return Ok(2);
     }
 
@@ -82,7 +84,7 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
         ui::require_tty("interactive mode requires a TTY. Use 'layer global rm <files...>' instead")?;
 
         let items = all_entries;
-        println!("{}", ui::heading("Select entries to remove from global gitignore"));
+        outln!("{}", ui::heading("Select entries to remove from global gitignore"));
         let theme = ui::layer_theme();
         ui::print_select_hint();
         let selections = MultiSelect::with_theme(&theme)
@@ -95,7 +97,7 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
         };
 
         if selected.is_empty() {
-            println!("No entries selected.");
+            outln!("No entries selected.");
             return Ok(2);
         }
 
@@ -110,9 +112,9 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
             return Ok(2);
         }
 
-        file.write(&path)?;
+        file.write_global_if_unchanged(&path, &loaded)?;
         for item in removed {
-            println!("  {} Removed '{item}' from global gitignore.", ui::ok());
+            outln!("  {} Removed '{item}' from global gitignore.", ui::ok());
         }
 
         return Ok(0);
@@ -129,7 +131,7 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
 
     if !has_any {
         for target in targets {
-            println!("  {} '{target}' not in global gitignore", ui::info());
+            outln!("  {} '{target}' not in global gitignore", ui::info());
         }
         return Ok(2);
     }
@@ -138,13 +140,13 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
     removed.extend(file.remove_from_user(&targets));
     let removed_set = removed.iter().cloned().collect::<HashSet<_>>();
 
-    file.write(&path)?;
+    file.write_global_if_unchanged(&path, &loaded)?;
 
     for target in targets {
         if removed_set.contains(&target) {
-            println!("  {} Removed '{target}' from global gitignore.", ui::ok());
+            outln!("  {} Removed '{target}' from global gitignore.", ui::ok());
         } else {
-            println!("  {} '{target}' not in global gitignore", ui::info());
+            outln!("  {} '{target}' not in global gitignore", ui::info());
         }
     }
 
@@ -152,9 +154,27 @@ pub fn rm(files: Vec<String>) -> Result<i32> {
 }
 
 pub fn global_ignore_path() -> Result<PathBuf> {
-    let output = Command::new("git")
-        .args(["config", "--global", "core.excludesFile"])
-        .output()
+    excludes_file_path(&["config", "--global", "core.excludesFile"])
+}
+
+/// The global ignore file git reads in the current repo, which may point
+/// `core.excludesFile` somewhere else in its own config.
+pub fn effective_path() -> Result<PathBuf> {
+    excludes_file_path(&["config", "core.excludesFile"])
+}
+
+/// [`effective_path`] and every entry in it, managed by layer or not.
+pub fn effective_entries() -> Result<(PathBuf, Vec<String>)> {
+    let path = effective_path()?;
+    if !path.is_file() {
+        return Ok((path, Vec::new()));
+    }
+    let (file, _) = ExcludeFile::load_global_with_content(&path)?;
+    Ok((path, all_entries_vec(&file)))
+}
+
+fn excludes_file_path(args: &[&str]) -> Result<PathBuf> {
+    let output = git::output(Command::new("git").args(args))
         .context("failed to read git global excludesFile")?;
 
     let configured = if output.status.success() {
@@ -189,7 +209,9 @@ fn all_entries_vec(file: &ExcludeFile) -> Vec<String> {
         .collect()
 }
 
-fn ensure_global_file(path: &Path) -> Result<ExcludeFile> {
+/// The global ignore file and the content it was loaded from, so writes can
+/// detect edits made in the meantime.
+fn ensure_global_file(path: &Path) -> Result<(ExcludeFile, String)> {
     if !path.exists() {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
@@ -198,10 +220,10 @@ fn ensure_global_file(path: &Path) -> Result<ExcludeFile> {
         fs::write(path, "").with_context(|| format!("failed to create {}", path.display()))?;
     }
 
-    ExcludeFile::load(path)
+    ExcludeFile::load_global_with_content(path)
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
+pub fn expand_tilde(path: &str) -> PathBuf {
     if path == "~" {
         if let Ok(home) = std::env::var("HOME") {
             return PathBuf::from(home);
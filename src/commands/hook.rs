@@ -0,0 +1,342 @@
+use crate::config;
+use crate::git;
+use crate::git::RepoContext;
+use crate::json::Json;
+use crate::patterns::{enabled_patterns, known_pattern_for, KnownPattern, PatternCategory};
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A git hook layer knows how to install, and the command the hook runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    PostMerge,
+    PreCommit,
+}
+
+impl HookKind {
+    fn file_name(self) -> &'static str {
+        match self {
+            HookKind::PostMerge => "post-merge",
+            HookKind::PreCommit => "pre-commit",
+        }
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            HookKind::PostMerge => "layer hook post-merge",
+            HookKind::PreCommit => "layer hook pre-commit",
+        }
+    }
+
+    /// The shell line that runs `command`. Only pre-commit may fail the git
+    /// operation; either way a missing `layer` binary is not an error.
+    fn invocation(self) -> String {
+        match self {
+            HookKind::PostMerge => format!("command -v layer >/dev/null 2>&1 && {} || true", self.command()),
+            HookKind::PreCommit => format!(
+                "if command -v layer >/dev/null 2>&1; then {} || exit 1; fi",
+                self.command()
+            ),
+        }
+    }
+
+    fn block_start(self) -> String {
+        format!("# >>> layer {} >>>", self.file_name())
+    }
+
+    fn block_end(self) -> String {
+        format!("# <<< layer {} <<<", self.file_name())
+    }
+
+    /// The marked block layer owns inside the hook script. Everything outside
+    /// it belongs to the user or other tools and is left alone.
+    fn block(self) -> String {
+        format!("{}\n{}\n{}\n", self.block_start(), self.invocation(), self.block_end())
+    }
+}
+
+pub fn install(kind: HookKind) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let path = hook_path(&ctx, kind)?;
+    let existing = fs::read_to_string(&path).ok();
+
+    if existing.as_deref().is_some_and(|c| c.contains(&kind.block_start())) {
+        outln!("The layer {} hook is already installed at {}.", kind.file_name(), path.display());
+        return Ok(2);
+    }
+
+    let content = match &existing {
+        Some(current) => insert_block(current, kind),
+        None => format!("#!/bin/sh\n{}", kind.block()),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    make_executable(&path)?;
+
+    if existing.is_some() {
+        outln!(
+            "  {} Added layer to the existing {} hook at {}",
+            ui::ok(),
+            kind.file_name(),
+            path.display()
+        );
+    } else {
+        outln!("  {} Installed {} hook at {}", ui::ok(), kind.file_name(), path.display());
+    }
+    Ok(0)
+}
+
+pub fn uninstall(kind: HookKind) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let path = hook_path(&ctx, kind)?;
+    let Some(current) = fs::read_to_string(&path).ok().filter(|c| c.contains(&kind.block_start())) else {
+        outln!("The layer {} hook is not installed.", kind.file_name());
+        return Ok(2);
+    };
+
+    let remaining = remove_block(&current, kind);
+    let only_shebang = remaining
+        .lines()
+        .all(|l| l.trim().is_empty() || l.starts_with("#!"));
+    if only_shebang {
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        outln!("  {} Removed {} hook at {}", ui::ok(), kind.file_name(), path.display());
+    } else {
+        fs::write(&path, remaining).with_context(|| format!("failed to write {}", path.display()))?;
+        outln!(
+            "  {} Removed layer from the {} hook at {} (other commands kept)",
+            ui::ok(),
+            kind.file_name(),
+            path.display()
+        );
+    }
+    Ok(0)
+}
+
+/// Put the block right after the shebang rather than at the end: a hook that
+/// ends in `exec ...` or `exit 0` would never reach an appended block.
+fn insert_block(content: &str, kind: HookKind) -> String {
+    let split = match content.lines().next() {
+        Some(first) if first.starts_with("#!") => content.find('\n').map_or(content.len(), |i| i + 1),
+        _ => 0,
+    };
+    let (shebang, rest) = content.split_at(split);
+    let mut out = shebang.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&kind.block());
+    out.push_str(rest);
+    out
+}
+
+fn remove_block(content: &str, kind: HookKind) -> String {
+    let (start, end) = (kind.block_start(), kind.block_end());
+    let mut out = String::new();
+    let mut inside = false;
+    for line in content.lines() {
+        if line.trim() == start {
+            inside = true;
+            continue;
+        }
+        if inside {
+            if line.trim() == end {
+                inside = false;
+            }
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Hooks live in `core.hooksPath` when set, otherwise `.git/hooks`.
+fn hook_path(ctx: &RepoContext, kind: HookKind) -> Result<PathBuf> {
+    let raw = git::git_stdout(&["rev-parse", "--git-path", "hooks"], Some(&ctx.root))?;
+    let dir = PathBuf::from(raw.trim());
+    let dir = if dir.is_absolute() { dir } else { ctx.root.join(dir) };
+    Ok(dir.join(kind.file_name()))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o755);
+    fs::set_permissions(path, perms).with_context(|| format!("failed to chmod {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Run from the post-merge hook: look at files the merge added and warn when
+/// upstream started tracking a context file. Never fails the merge.
+pub fn post_merge() -> Result<i32> {
+    let Ok(ctx) = git::ensure_repo() else {
+        return Ok(0);
+    };
+    let Ok(diff) = git::git_stdout(
+        &["diff", "--name-only", "--diff-filter=A", "ORIG_HEAD", "HEAD"],
+        Some(&ctx.root),
+    ) else {
+        return Ok(0);
+    };
+    let added: Vec<String> = diff.lines().map(str::to_string).filter(|l| !l.is_empty()).collect();
+    if added.is_empty() {
+        return Ok(0);
+    }
+
+    let layered = git::check_ignore_bulk(&ctx.root, &added, true).unwrap_or_default();
+    let patterns = enabled_patterns(&config::load(Some(&ctx.root))?);
+    for path in &added {
+        let rule = layered.get(path).filter(|m| {
+            !m.pattern.starts_with('!') && git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &m.source)
+        });
+        if let Some(rule) = rule {
+            outln!(
+                "layer: upstream now tracks {path} — your layer entry '{}' is ineffective",
+                rule.pattern
+            );
+            outln!("  keep local edits out of commits: git update-index --skip-worktree {path}");
+            outln!("  or stop layering it: layer rm {}", rule.pattern);
+        } else if let Some(pattern) = context_pattern_for(path, &patterns) {
+            outln!(
+                "layer: upstream now tracks {path} ({}) — run 'layer status' to review",
+                pattern.label
+            );
+        }
+    }
+
+    Ok(0)
+}
+
+/// The pattern among the repo's enabled ones `path` falls under, leaving
+/// out OS files (noise, not context, and opt-in everywhere else) and
+/// optional agent artifacts.
+fn context_pattern_for(path: &str, patterns: &[&'static KnownPattern]) -> Option<&'static KnownPattern> {
+    known_pattern_for(path, patterns)
+        .filter(|pattern| pattern.category != PatternCategory::OsFiles && !pattern.category.is_optional())
+}
+
+/// Run from the pre-commit hook: refuse a commit that adds a known context
+/// file or a path the layer ignores (e.g. staged with `git add -f`).
+pub fn pre_commit() -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let diff = git::git_stdout(
+        &["diff", "--cached", "--name-only", "--diff-filter=A"],
+        Some(&ctx.root),
+    )?;
+    let added: Vec<String> = diff.lines().map(str::to_string).filter(|l| !l.is_empty()).collect();
+    let blocked = staged_context_files(&ctx, &added)?;
+    if blocked.is_empty() {
+        return Ok(0);
+    }
+    print_blocked(&blocked);
+    Ok(1)
+}
+
+/// Which of `paths` (relative to the repo root, about to be committed) are
+/// layered or look like context files, each with the reason.
+pub fn staged_context_files(ctx: &RepoContext, paths: &[String]) -> Result<Vec<(String, String)>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let layered = git::check_ignore_bulk(&ctx.root, paths, true)?;
+    let patterns = enabled_patterns(&config::load(Some(&ctx.root))?);
+    let mut blocked = Vec::new();
+    for path in paths {
+        let rule = layered.get(path).filter(|m| {
+            !m.pattern.starts_with('!') && git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &m.source)
+        });
+        if let Some(rule) = rule {
+            blocked.push((path.clone(), format!("layered by '{}'", rule.pattern)));
+        } else if let Some(pattern) = context_pattern_for(path, &patterns) {
+            blocked.push((path.clone(), pattern.label.to_string()));
+        }
+    }
+    Ok(blocked)
+}
+
+pub fn print_blocked(blocked: &[(String, String)]) {
+    outln!("layer: this commit would add local context files:");
+    for (path, reason) in blocked {
+        outln!("  {path} ({reason})");
+    }
+    outln!("  unstage them: git rm --cached <file>");
+    outln!("  or commit anyway: git commit --no-verify");
+}
+
+/// Print a `postCreateCommand` for devcontainer.json that re-seeds the
+/// layer from `from` after a rebuild recreates `.git/info`. It never fails
+/// the container build: a missing `layer` or backup file is skipped.
+pub fn devcontainer(from: &str) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let command = devcontainer_command(from);
+
+    outln!("Add this to .devcontainer/devcontainer.json so your layer comes back after a rebuild:");
+    outln!();
+    outln!("  \"postCreateCommand\": {}", Json::String(command));
+    outln!();
+    if !ctx.root.join(from).exists() {
+        outln!(
+            "  {} {from} doesn't exist yet. Create it, hidden by its own entry: {}",
+            ui::info(),
+            ui::brand(&format!("layer add {from} && layer backup -o {from}"))
+        );
+    }
+    outln!(
+        "  {}",
+        ui::dim_text(&format!("Re-run 'layer backup -o {from}' after changing your layer to keep it current."))
+    );
+    Ok(0)
+}
+
+fn devcontainer_command(from: &str) -> String {
+    let from = shell_quote(from);
+    format!("command -v layer >/dev/null 2>&1 && [ -f {from} ] && layer init --from {from} || true")
+}
+
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "._-/+@%:,".contains(c)) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_block_keeps_other_commands() {
+        let content = format!("#!/bin/sh\nnpm test\n{}echo done\n", HookKind::PostMerge.block());
+        assert_eq!(remove_block(&content, HookKind::PostMerge), "#!/bin/sh\nnpm test\necho done\n");
+    }
+
+    #[test]
+    fn only_pre_commit_block_can_fail() {
+        assert!(HookKind::PostMerge.block().contains("|| true"));
+        assert!(HookKind::PreCommit.block().contains("layer hook pre-commit || exit 1"));
+    }
+
+    #[test]
+    fn devcontainer_command_quotes_the_path_and_never_fails() {
+        assert_eq!(
+            devcontainer_command(".layer-backup.txt"),
+            "command -v layer >/dev/null 2>&1 && [ -f .layer-backup.txt ] && layer init --from .layer-backup.txt || true"
+        );
+        assert!(devcontainer_command("my layers.txt").contains("[ -f 'my layers.txt' ]"));
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::commands::add::apply_add_entries;
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git;
+use crate::meta;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{Context, Result};
+use dialoguer::MultiSelect;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Ignore files of AI tools that use gitignore syntax, read when `--from` is not given.
+pub const TOOL_IGNORE_FILES: &[&str] = &[".cursorignore", ".aiderignore", ".codeiumignore"];
+
+/// Patterns that would hide the whole repo from git rather than a few files.
+pub const WHOLE_REPO: &[&str] = &["*", "**", "/", "/*", "*/", "**/*", "/**"];
+
+#[derive(Debug, PartialEq)]
+enum Translation {
+    Entry(String),
+    Skip(&'static str),
+    Nothing,
+}
+
+/// Read other tools' ignore files and layer the patterns that make sense as
+/// exclude entries. Negations and patterns that would hide everything are skipped.
+pub fn run(from: Vec<PathBuf>, dry_run: bool, yes: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let sources: Vec<PathBuf> = if from.is_empty() {
+        TOOL_IGNORE_FILES.iter().map(|name| ctx.root.join(name)).filter(|p| p.is_file()).collect()
+    } else {
+        from
+    };
+    if sources.is_empty() {
+        outln!(
+            "No {} found. Pass the file to read with --from.",
+            TOOL_IGNORE_FILES.join(", ")
+        );
+        return Ok(2);
+    }
+
+    let mut entries: Vec<String> = Vec::new();
+    for source in &sources {
+        let content = fs::read_to_string(source).with_context(|| format!("failed to read {}", source.display()))?;
+        let base = base_dir(&ctx.root, source);
+        let name = source.strip_prefix(&ctx.root).unwrap_or(source).to_string_lossy().replace('\\', "/");
+        for (idx, line) in content.lines().enumerate() {
+            match translate_line(line, &base) {
+                Translation::Entry(entry) if !entries.contains(&entry) => {
+                    meta::set_source([&entry], &name);
+                    entries.push(entry);
+                }
+                Translation::Skip(reason) => outln!(
+                    "  {} Skipped '{}' ({}:{}) — {reason}",
+                    ui::info(),
+                    line.trim(),
+                    source.display(),
+                    idx + 1
+                ),
+                _ => {}
+            }
+        }
+    }
+    if entries.is_empty() {
+        outln!("Nothing to import.");
+        return Ok(2);
+    }
+
+    let chosen = if yes || dry_run {
+        entries
+    } else {
+        ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal or use --yes")?;
+        let theme = ui::layer_theme();
+        outln!(
+            "  {} Found {} {} to import — select for your local layer",
+            ui::discovered(),
+            entries.len(),
+            if entries.len() == 1 { "pattern" } else { "patterns" }
+        );
+        ui::print_select_hint();
+        let selections = MultiSelect::with_theme(&theme)
+            .items(&entries)
+            .defaults(&vec![true; entries.len()])
+            .report(false)
+            .interact_opt()?
+            .unwrap_or_default();
+        if selections.is_empty() {
+            outln!("No patterns selected.");
+            return Ok(2);
+        }
+        selections.into_iter().map(|i| entries[i].clone()).collect()
+    };
+
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let summary = apply_add_entries(&ctx, &mut exclude, &chosen, dry_run)?;
+    if dry_run {
+        ui::print_dry_run_notice();
+    }
+    if summary.added == 0 {
+        return Ok(2);
+    }
+    Ok(0)
+}
+
+/// Directory of an ignore file relative to the repo root, "" at the root or outside it.
+fn base_dir(root: &Path, source: &Path) -> String {
+    let parent = match source.canonicalize() {
+        Ok(path) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        Err(_) => return String::new(),
+    };
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    match parent.strip_prefix(&root) {
+        Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+        Err(_) => String::new(),
+    }
+}
+
+/// Turn one ignore-file line into an exclude entry, rooted at `base` for
+/// ignore files that live in a subdirectory.
+fn translate_line(line: &str, base: &str) -> Translation {
+    let pattern = line.trim();
+    if pattern.is_empty() || pattern.starts_with('#') {
+        return Translation::Nothing;
+    }
+    if pattern.starts_with('!') {
+        return Translation::Skip("negations would un-ignore files other tools still see");
+    }
+    if WHOLE_REPO.contains(&pattern) {
+        return Translation::Skip("would hide the whole repo from git");
+    }
+    if base.is_empty() {
+        return Translation::Entry(pattern.to_string());
+    }
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let rest = pattern.trim_start_matches('/');
+    if anchored {
+        Translation::Entry(format!("{base}/{rest}"))
+    } else {
+        Translation::Entry(format!("{base}/**/{rest}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_line_keeps_patterns_and_skips_what_does_not_fit() {
+        assert_eq!(translate_line("  .env  ", ""), Translation::Entry(".env".to_string()));
+        assert_eq!(translate_line("# secrets", ""), Translation::Nothing);
+        assert_eq!(translate_line("", ""), Translation::Nothing);
+        assert!(matches!(translate_line("!keep.md", ""), Translation::Skip(_)));
+        assert!(matches!(translate_line("**/*", ""), Translation::Skip(_)));
+    }
+
+    #[test]
+    fn translate_line_roots_patterns_from_subdirectories() {
+        assert_eq!(translate_line("/build/", "web"), Translation::Entry("web/build/".to_string()));
+        assert_eq!(translate_line("gen/out.js", "web"), Translation::Entry("web/gen/out.js".to_string()));
+        assert_eq!(translate_line("*.log", "web"), Translation::Entry("web/**/*.log".to_string()));
+        assert_eq!(translate_line("dist/", "web"), Translation::Entry("web/**/dist/".to_string()));
+    }
+}
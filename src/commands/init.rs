@@ -0,0 +1,161 @@
+use crate::commands::backup::{self, normalize_remote};
+use crate::exclude_file::{ensure_exclude_file_for_write, ExcludeFile};
+use crate::git;
+use crate::signing;
+use crate::ui;
+use crate::ui::outln;
+use crate::wsl;
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Entries to seed a fresh clone with, and where they came from.
+struct Source {
+    label: String,
+    entries: Vec<String>,
+    disabled: Vec<String>,
+}
+
+/// Seed this clone's layer from another local clone, a backup or exported
+/// file, or the saved backup whose origin matches a remote URL.
+pub fn run(from: &str) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let source = resolve_source(&ctx, from)?;
+
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let mut present: HashSet<String> = exclude
+        .entry_set()
+        .union(&exclude.disabled_entry_set())
+        .cloned()
+        .collect();
+
+    let mut added = 0usize;
+    for entry in &source.entries {
+        if present.insert(entry.clone()) {
+            exclude.append_entry(entry);
+            added += 1;
+        }
+    }
+    let mut to_disable = HashSet::new();
+    for entry in &source.disabled {
+        if present.insert(entry.clone()) {
+            exclude.append_entry(entry);
+            to_disable.insert(entry.clone());
+        }
+    }
+    let disabled = exclude.disable_entries(&to_disable).len();
+
+    if added + disabled == 0 {
+        outln!("Already up to date — every entry from {} is present.", source.label);
+        return Ok(2);
+    }
+
+    exclude.write(&ctx.exclude_path)?;
+
+    outln!(
+        "  {} Initialized {} {} from {}",
+        ui::ok(),
+        added + disabled,
+        if added + disabled == 1 { "entry" } else { "entries" },
+        source.label
+    );
+    if disabled > 0 {
+        outln!(
+            "  {} {} kept disabled ({})",
+            ui::disabled(),
+            disabled,
+            ui::brand("layer on")
+        );
+    }
+    Ok(0)
+}
+
+fn resolve_source(ctx: &git::RepoContext, from: &str) -> Result<Source> {
+    let path = Path::new(from);
+
+    if path.is_dir() {
+        return from_clone(ctx, path);
+    }
+
+    if path.is_file() {
+        if signing::has_signature(path) {
+            ui::print_warning(&format!(
+                "{from} is signed but was not verified. Use 'layer restore --from {from} --verify <allowed_signers>' to check it."
+            ));
+        }
+        let parsed = backup::parse_backup_file(path)?;
+        return Ok(Source {
+            label: path.display().to_string(),
+            entries: parsed.entries,
+            disabled: Vec::new(),
+        });
+    }
+
+    from_remote_url(from)
+}
+
+/// Read another clone's exclude file, keeping disabled entries disabled.
+fn from_clone(ctx: &git::RepoContext, path: &Path) -> Result<Source> {
+    let git_dir = git::git_stdout(&["rev-parse", "--absolute-git-dir"], Some(path))
+        .map_err(|_| anyhow!("{} is not a git repository", path.display()))?;
+    let git_dir = wsl::normalize_git_path(git_dir.trim());
+
+    if same_path(&git_dir, &ctx.git_dir) {
+        bail!("{} is this repository. Point --from at another clone", path.display());
+    }
+
+    let exclude = ExcludeFile::load(&git_dir.join("info").join("exclude"))?;
+    Ok(Source {
+        label: path.display().to_string(),
+        entries: exclude.entries().into_iter().map(|e| e.value).collect(),
+        disabled: exclude.disabled_entries().into_iter().map(|e| e.value).collect(),
+    })
+}
+
+/// Find the saved backup in `~/.layer-backups/` whose recorded origin is
+/// `url`, preferring this machine's.
+fn from_remote_url(url: &str) -> Result<Source> {
+    let dir = backup::backup_dir_path()?;
+    let wanted = normalize_remote(url);
+
+    for (_, path) in backup::saved_backups(&dir)? {
+        let parsed = backup::parse_backup_file(&path)?;
+        if parsed.source.as_deref().map(normalize_remote).as_deref() == Some(wanted.as_str()) {
+            return Ok(Source {
+                label: format!("the backup of '{}'", parsed.repo),
+                entries: parsed.entries,
+                disabled: Vec::new(),
+            });
+        }
+    }
+
+    bail!(
+        "'{url}' is not a local clone or file, and no backup in {} was taken from that remote",
+        dir.display()
+    )
+}
+
+/// Compare remotes loosely: `https://host/a/b.git` and `https://host/a/b/` match.
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_remote_ignores_suffixes_and_case() {
+        assert_eq!(
+            normalize_remote("https://github.com/Me/Repo.git"),
+            normalize_remote("https://github.com/me/repo/")
+        );
+        assert_ne!(
+            normalize_remote("git@github.com:me/repo.git"),
+            normalize_remote("https://github.com/me/repo")
+        );
+    }
+}
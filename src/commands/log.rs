@@ -0,0 +1,119 @@
+use crate::exclude_file::normalize_entry;
+use crate::git;
+use crate::journal::{self, Record};
+use crate::json::Json;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Show state-changing operations from the journal, oldest first.
+pub fn run(entry: Option<String>, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let entry = entry.map(|e| normalize_entry(&e));
+    let records: Vec<Record> = journal::read(&ctx.git_dir)?
+        .into_iter()
+        .filter(|r| entry.as_deref().map_or(true, |e| r.touches(e)))
+        .collect();
+
+    if format.is_json() {
+        let only = |values: &[String]| {
+            Json::strings(
+                values
+                    .iter()
+                    .filter(|v| entry.as_deref().map_or(true, |e| e == v.as_str()))
+                    .cloned(),
+            )
+        };
+        let items = records
+            .iter()
+            .map(|record| {
+                Json::object(vec![
+                    ("time", Json::String(record.time.clone())),
+                    ("command", Json::String(record.command.clone())),
+                    ("added", only(&record.added)),
+                    ("removed", only(&record.removed)),
+                    ("disabled", only(&record.disabled)),
+                    ("enabled", only(&record.enabled)),
+                ])
+            })
+            .collect();
+        outln!("{}", Json::Array(items).pretty());
+        return Ok(if records.is_empty() { 2 } else { 0 });
+    }
+
+    if format.is_porcelain() {
+        for record in &records {
+            for (symbol, value) in record.changes() {
+                if entry.as_deref().map_or(true, |e| e == value) {
+                    ui::porcelain(&[&record.time, &symbol.to_string(), value]);
+                }
+            }
+        }
+        return Ok(if records.is_empty() { 2 } else { 0 });
+    }
+
+    if records.is_empty() {
+        match &entry {
+            Some(entry) => outln!("No recorded history for '{entry}'."),
+            None => outln!("No recorded operations yet."),
+        }
+        return Ok(2);
+    }
+
+    for record in &records {
+        outln!(
+            "  {}  {}",
+            ui::dim_text(&format_time(&record.time)),
+            record.command
+        );
+        for (symbol, value) in record.changes() {
+            if entry.as_deref().is_some_and(|e| e != value) {
+                continue;
+            }
+            outln!("      {} {}", ui::dim_text(&symbol.to_string()), describe(symbol, value));
+        }
+    }
+
+    Ok(0)
+}
+
+fn describe(symbol: char, entry: &str) -> String {
+    match symbol {
+        '+' => format!("{entry} added"),
+        '-' => format!("{entry} removed"),
+        '~' => format!("{entry} disabled"),
+        _ => format!("{entry} enabled"),
+    }
+}
+
+fn format_time(raw: &str) -> String {
+    match OffsetDateTime::parse(raw, &Rfc3339) {
+        Ok(dt) => format!(
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+            dt.year(),
+            u8::from(dt.month()),
+            dt.day(),
+            dt.hour(),
+            dt.minute(),
+            dt.second()
+        ),
+        Err(_) => raw.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_time_valid_rfc3339() {
+        assert_eq!(format_time("2026-02-08T09:05:03Z"), "2026-02-08 09:05:03");
+    }
+
+    #[test]
+    fn format_time_unparseable_returns_raw() {
+        assert_eq!(format_time("yesterday"), "yesterday");
+    }
+}
@@ -1,21 +1,100 @@
-use crate::exclude_file::ensure_exclude_file;
+use crate::commands::backup::format_backup_date;
+use crate::commands::status::entry_matches_path;
+use crate::config;
+use crate::exclude_file::{ensure_exclude_file, Entry, ExcludeFile};
 use crate::git;
-use crate::git::PatternMatchSummary;
+use crate::git::{IgnoreDiscrepancy, PatternMatchSummary};
+use crate::json::Json;
+use crate::meta::{self, EntryMeta};
+use crate::patterns::{enabled_patterns, known_pattern_for, tool_labels_in, KnownPattern};
 use crate::ui;
+use crate::ui::outln;
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
+use std::time::SystemTime;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use walkdir::WalkDir;
 
-pub fn run() -> Result<i32> {
+/// How `ls --sort` orders entries within each section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Alphabetically
+    Name,
+    /// Problems first: ineffective, overridden, exposed, stale, then layered
+    Status,
+    /// Largest on disk first
+    Size,
+    /// Most recently added first
+    Added,
+}
+
+/// States `ls --status` can keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StateFilter {
+    Layered,
+    Exposed,
+    Overridden,
+    Ineffective,
+    Stale,
+    Disabled,
+    Manual,
+}
+
+impl StateFilter {
+    fn as_str(self) -> &'static str {
+        match self {
+            StateFilter::Layered => "layered",
+            StateFilter::Exposed => "exposed",
+            StateFilter::Overridden => "overridden",
+            StateFilter::Ineffective => "ineffective",
+            StateFilter::Stale => "stale",
+            StateFilter::Disabled => "disabled",
+            StateFilter::Manual => "manual",
+        }
+    }
+}
+
+pub fn run(
+    verify: bool,
+    verbose: bool,
+    sort: Option<SortKey>,
+    states: &[StateFilter],
+    paths_only: bool,
+    long: bool,
+    format: ui::Format,
+) -> Result<i32> {
+    if format == ui::Format::Template {
+        ui::check_template(TEMPLATE_FIELDS)?;
+    }
     let ctx = git::ensure_repo()?;
     let exclude = ensure_exclude_file(&ctx.exclude_path)?;
     let entries = exclude.entries();
     let disabled = exclude.disabled_entries();
     let user_entries = exclude.user_entries();
+    let empty = entries.is_empty() && disabled.is_empty() && user_entries.is_empty();
+
+    // Nothing to classify: skip the git calls so pipelines stay fast.
+    if paths_only && states.is_empty() && sort.is_none() {
+        for entry in entries.iter().chain(&disabled).chain(&user_entries) {
+            ui::porcelain(&[&entry.value]);
+        }
+        return Ok(if empty { 2 } else { 0 });
+    }
+
+    if empty && format == ui::Format::Template {
+        return Ok(2);
+    }
+
+    if empty && format != ui::Format::Pretty {
+        print_report(format, &[], &[], &[], &BTreeMap::new(), &HashMap::new(), &[]);
+        return Ok(2);
+    }
 
-    if entries.is_empty() && disabled.is_empty() && user_entries.is_empty() {
-        println!(
+    if empty {
+        outln!(
             "No layered entries. Run {} or {} to get started.",
             ui::brand("layer add"),
             ui::brand("layer scan")
@@ -23,6 +102,7 @@ pub fn run() -> Result<i32> {
         return Ok(2);
     }
 
+    let patterns = enabled_patterns(&config::load(Some(&ctx.root))?);
     let tracked = git::list_tracked(&ctx.root)?;
     let gitignore_entries = git::read_root_gitignore_entries(&ctx.root)?;
     let pattern_match_index =
@@ -34,102 +114,569 @@ pub fn run() -> Result<i32> {
         .chain(disabled.iter().map(|e| e.value.len()))
         .chain(user_entries.iter().map(|e| e.value.len()));
     let max_name = all_names.max().unwrap_or(10);
+    let discrepancies = git::ignore_discrepancies(
+        &ctx.root,
+        &ctx.exclude_path,
+        &git::discrepancy_targets(entries.iter().map(|e| e.value.as_str())),
+    )?;
 
-    for entry in &entries {
-        let status = classify_entry(&ctx.root, &entry.value, &tracked, &pattern_match_index);
+    let mut ineffective = if verify {
+        verify_entries(&ctx.root, &ctx.exclude_path, &entries, &tracked)?
+    } else {
+        HashMap::new()
+    };
+    let statuses: Vec<EntryStatus> = entries
+        .iter()
+        .map(|entry| match ineffective.remove(&entry.value) {
+            Some(detail) => EntryStatus::Ineffective(detail),
+            None => classify_entry(
+                &ctx.root,
+                &entry.value,
+                &tracked,
+                &pattern_match_index,
+                &discrepancies,
+            ),
+        })
+        .collect();
+    let code = if statuses.iter().any(|s| matches!(s, EntryStatus::Ineffective(_))) { 1 } else { 0 };
+    let metas = meta::load(&ctx.exclude_path);
 
-        let gitignore_note = if gitignore_entries.contains(&entry.value) {
-            format!("  {}", ui::dim_text("redundant (in .gitignore)"))
-        } else {
-            String::new()
-        };
+    // Filtering and sorting only change what's shown; the exit code still
+    // covers every entry.
+    let keep = |state: &str| states.is_empty() || states.iter().any(|s| s.as_str() == state);
+    let mut rows: Vec<(&Entry, EntryStatus)> =
+        entries.iter().zip(statuses).filter(|(_, status)| keep(status.state())).collect();
+    let mut disabled: Vec<&Entry> = disabled.iter().filter(|_| keep("disabled")).collect();
+    let mut user_entries: Vec<&Entry> = user_entries.iter().filter(|_| keep("manual")).collect();
+    let usage = if long || sort == Some(SortKey::Size) {
+        entry_usage(&ctx.root, &tracked, &exclude)?
+    } else {
+        HashMap::new()
+    };
+    if let Some(key) = sort {
+        let order = |a: &Entry, b: &Entry| compare_entries(key, &a.value, &b.value, &metas, &usage);
+        rows.sort_by(|(a, a_status), (b, b_status)| {
+            if key == SortKey::Status {
+                a_status.rank().cmp(&b_status.rank()).then_with(|| a.value.cmp(&b.value))
+            } else {
+                order(a, b)
+            }
+        });
+        disabled.sort_by(|a, b| order(a, b));
+        user_entries.sort_by(|a, b| order(a, b));
+    }
+
+    if paths_only {
+        for entry in rows.iter().map(|(entry, _)| *entry).chain(disabled.iter().copied()).chain(user_entries.iter().copied()) {
+            ui::porcelain(&[&entry.value]);
+        }
+        let shown = !rows.is_empty() || !disabled.is_empty() || !user_entries.is_empty();
+        return Ok(if shown || code != 0 { code } else { 2 });
+    }
 
-        let name = format!("{:<width$}", entry.value, width = max_name);
+    if format == ui::Format::Template {
+        let matches = |entry: &str| pattern_match_index.get(entry).map_or(0, |s| s.total).to_string();
+        let sections = rows
+            .iter()
+            .map(|(entry, status)| (*entry, status.state(), status.detail(), matches(&entry.value)))
+            .chain(disabled.iter().map(|entry| (*entry, "disabled", "", "0".to_string())))
+            .chain(user_entries.iter().map(|entry| (*entry, "manual", "", matches(&entry.value))));
+        for (entry, state, detail, matches) in sections {
+            let meta = metas.get(&entry.value);
+            ui::template_line(&[
+                ("entry", &entry.value),
+                ("status", state),
+                ("detail", detail),
+                ("tool", tool_for(&entry.value, &patterns).unwrap_or("")),
+                ("category", category_for(&entry.value, &patterns).unwrap_or("")),
+                ("matches", &matches),
+                ("added", meta.map_or("", |m| m.added.as_str())),
+                ("origin", meta.map_or("", |m| m.origin.as_str())),
+                ("source", meta.and_then(|m| m.source.as_deref()).unwrap_or("")),
+                ("note", meta.and_then(|m| m.note.as_deref()).unwrap_or("")),
+            ]);
+        }
+        return Ok(code);
+    }
 
-        match status {
-            EntryStatus::Layered(detail) => {
-                println!(
-                    "  {} {}  {}{}",
-                    ui::layered(),
-                    name,
-                    ui::dim_text(&detail),
-                    gitignore_note
-                );
+    if format != ui::Format::Pretty {
+        let rows: Vec<(&str, &EntryStatus, bool)> = rows
+            .iter()
+            .map(|(entry, status)| {
+                (entry.value.as_str(), status, gitignore_entries.contains(&entry.value))
+            })
+            .collect();
+        let disabled: Vec<&str> = disabled.iter().map(|e| e.value.as_str()).collect();
+        let manual: Vec<&str> = user_entries.iter().map(|e| e.value.as_str()).collect();
+        let usage = if long { usage } else { HashMap::new() };
+        print_report(format, &rows, &disabled, &manual, &metas, &usage, &patterns);
+        return Ok(code);
+    }
+
+    if rows.is_empty() && disabled.is_empty() && user_entries.is_empty() {
+        let wanted: Vec<&str> = states.iter().map(|s| s.as_str()).collect();
+        outln!("No {} entries.", wanted.join(" or "));
+        return Ok(if code == 0 { 2 } else { code });
+    }
+
+    // Tool groups would split up a `--sort` order, so sorted output is flat.
+    let groups = if sort.is_some() {
+        vec![(OTHER_GROUP, (0..rows.len()).collect())]
+    } else {
+        group_by_tool(rows.iter().map(|(entry, _)| entry.value.as_str()), &patterns)
+    };
+    let headings = groups.iter().any(|(label, _)| *label != OTHER_GROUP);
+    for (index, (label, members)) in groups.iter().enumerate() {
+        if headings {
+            if index > 0 {
+                outln!();
             }
-            EntryStatus::Exposed(detail) => {
-                println!(
-                    "  {} {}  {}{}",
-                    ui::exposed(),
-                    name,
-                    ui::warn_text(&detail),
-                    gitignore_note
-                );
+            outln!("{}", ui::heading(label));
+        }
+        for &member in members {
+            let (entry, status) = &rows[member];
+            let mut gitignore_note = usage_label(long, usage.get(&entry.value));
+            if gitignore_entries.contains(&entry.value) {
+                gitignore_note.push_str(&format!("  {}", ui::dim_text("redundant (in .gitignore)")));
             }
-            EntryStatus::Stale(detail) => {
-                println!(
-                    "  {} {}  {}{}",
-                    ui::stale(),
-                    name,
-                    ui::err_text(&detail),
-                    gitignore_note
-                );
+            if let Some(meta) = metas.get(&entry.value) {
+                if let Some(text) = meta_label(meta, verbose, long) {
+                    gitignore_note.push_str(&format!("  {}", ui::dim_text(&text)));
+                }
+            }
+
+            let name = format!("{:<width$}", entry.value, width = max_name);
+
+            match status {
+                EntryStatus::Layered(detail) => {
+                    outln!(
+                        "  {} {}  {}{}",
+                        ui::layered(),
+                        name,
+                        ui::dim_text(detail),
+                        gitignore_note
+                    );
+                }
+                EntryStatus::Exposed(detail) => {
+                    outln!(
+                        "  {} {}  {}{}",
+                        ui::exposed(),
+                        name,
+                        ui::warn_text(detail),
+                        gitignore_note
+                    );
+                }
+                EntryStatus::Overridden(detail) | EntryStatus::Ineffective(detail) => {
+                    outln!(
+                        "  {} {}  {}{}",
+                        ui::exposed(),
+                        name,
+                        ui::warn_text(detail),
+                        gitignore_note
+                    );
+                }
+                EntryStatus::Stale(detail) => {
+                    outln!(
+                        "  {} {}  {}{}",
+                        ui::stale(),
+                        name,
+                        ui::err_text(detail),
+                        gitignore_note
+                    );
+                }
             }
         }
     }
 
     if !disabled.is_empty() {
-        if !entries.is_empty() {
-            println!();
+        if !rows.is_empty() {
+            outln!();
         }
         for entry in &disabled {
             let name = format!("{:<width$}", entry.value, width = max_name);
-            println!(
-                "  {} {}  {}",
+            outln!(
+                "  {} {}  {}{}",
                 ui::disabled(),
                 name,
-                ui::dim_text("(disabled)")
+                ui::dim_text("(disabled)"),
+                usage_label(long, usage.get(&entry.value))
             );
         }
     }
 
     if !user_entries.is_empty() {
-        if !entries.is_empty() || !disabled.is_empty() {
-            println!();
+        if !rows.is_empty() || !disabled.is_empty() {
+            outln!();
         }
         for entry in &user_entries {
             let name = format!("{:<width$}", entry.value, width = max_name);
-            println!("  {} {}  {}", ui::manual(), name, ui::dim_text("(manual)"));
+            outln!(
+                "  {} {}  {}{}",
+                ui::manual(),
+                name,
+                ui::dim_text("(manual)"),
+                usage_label(long, usage.get(&entry.value))
+            );
+        }
+    }
+
+    Ok(code)
+}
+
+/// Machine-readable listing: `state entry` lines, or one JSON document.
+fn print_report(
+    format: ui::Format,
+    rows: &[(&str, &EntryStatus, bool)],
+    disabled: &[&str],
+    manual: &[&str],
+    metas: &BTreeMap<String, EntryMeta>,
+    usage: &HashMap<String, DiskUsage>,
+    patterns: &[&'static KnownPattern],
+) {
+    if format.is_porcelain() {
+        for (entry, status, _) in rows {
+            ui::porcelain(&[status.state(), entry]);
+        }
+        for entry in disabled {
+            ui::porcelain(&["disabled", entry]);
+        }
+        for entry in manual {
+            ui::porcelain(&["manual", entry]);
+        }
+        return;
+    }
+
+    let entries = rows
+        .iter()
+        .map(|(entry, status, in_gitignore)| {
+            let mut fields = vec![
+                ("entry", Json::String(entry.to_string())),
+                ("state", Json::String(status.state().to_string())),
+                ("detail", Json::String(status.detail().to_string())),
+                ("in_gitignore", Json::Bool(*in_gitignore)),
+                ("tool", tool_for(entry, patterns).map_or(Json::Null, |label| Json::String(label.to_string()))),
+                ("category", category_for(entry, patterns).map_or(Json::Null, |category| Json::String(category.to_string()))),
+            ];
+            if let Some(meta) = metas.get(*entry) {
+                fields.push(("added", Json::String(meta.added.clone())));
+                fields.push(("origin", Json::String(meta.origin.clone())));
+                if let Some(source) = &meta.source {
+                    fields.push(("source", Json::String(source.clone())));
+                }
+                if let Some(note) = &meta.note {
+                    fields.push(("note", Json::String(note.clone())));
+                }
+            }
+            if let Some(usage) = usage.get(*entry) {
+                fields.push(("size", Json::Number(usage.bytes as f64)));
+                fields.push(("files", Json::Number(usage.files as f64)));
+                fields.push(("modified", usage.modified.and_then(rfc3339).map_or(Json::Null, Json::String)));
+            }
+            Json::object(fields)
+        })
+        .collect();
+    let value = Json::object(vec![
+        ("entries", Json::Array(entries)),
+        ("disabled", Json::strings(disabled.iter().map(|e| e.to_string()))),
+        ("manual", Json::strings(manual.iter().map(|e| e.to_string()))),
+    ]);
+    outln!("{}", value.pretty());
+}
+
+/// The entry's `add --note`; with `long` also where a restore or import
+/// took it from (`from backup 2025-01-03`), and with `verbose` also `added
+/// Feb 8, 2026 by scan`, from the metadata sidecar.
+fn meta_label(meta: &EntryMeta, verbose: bool, long: bool) -> Option<String> {
+    let mut parts = Vec::new();
+    if verbose {
+        parts.push(format!("added {} by {}", format_backup_date(&Some(meta.added.clone())), meta.origin));
+    }
+    if let Some(source) = meta.source.as_ref().filter(|_| verbose || long) {
+        parts.push(format!("from {source}"));
+    }
+    if let Some(note) = &meta.note {
+        parts.push(format!("— {note}"));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Placeholders `--format '{...}'` can use.
+const TEMPLATE_FIELDS: &[&str] = &["entry", "status", "detail", "tool", "category", "matches", "added", "origin", "source", "note"];
+
+/// Heading for entries that don't belong to a known tool.
+const OTHER_GROUP: &str = "Other";
+
+/// Row indices grouped under the tool whose known pattern each entry falls
+/// under, in `tool_labels_in` order with "Other" last. Rows keep their order
+/// within a group.
+fn group_by_tool<'a>(
+    entries: impl Iterator<Item = &'a str>,
+    patterns: &[&'static KnownPattern],
+) -> Vec<(&'static str, Vec<usize>)> {
+    let mut groups: Vec<(&'static str, Vec<usize>)> =
+        tool_labels_in(patterns).into_iter().chain([OTHER_GROUP]).map(|label| (label, Vec::new())).collect();
+    for (index, entry) in entries.enumerate() {
+        let label = tool_for(entry, patterns).unwrap_or(OTHER_GROUP);
+        if let Some((_, members)) = groups.iter_mut().find(|(l, _)| *l == label) {
+            members.push(index);
+        }
+    }
+    groups.retain(|(_, members)| !members.is_empty());
+    groups
+}
+
+fn tool_for(entry: &str, patterns: &[&'static KnownPattern]) -> Option<&'static str> {
+    known_pattern_for(entry.trim_start_matches('/'), patterns).map(|pattern| pattern.label)
+}
+
+fn category_for(entry: &str, patterns: &[&'static KnownPattern]) -> Option<&'static str> {
+    known_pattern_for(entry.trim_start_matches('/'), patterns).map(|pattern| pattern.category.as_str())
+}
+
+/// `--sort` order for two entries, falling back to the name. Entries
+/// without a recorded date sort after dated ones.
+fn compare_entries(
+    key: SortKey,
+    a: &str,
+    b: &str,
+    metas: &BTreeMap<String, EntryMeta>,
+    usage: &HashMap<String, DiskUsage>,
+) -> Ordering {
+    let by_key = match key {
+        SortKey::Name | SortKey::Status => Ordering::Equal,
+        SortKey::Size => {
+            let bytes = |entry: &str| usage.get(entry).map(|u| u.bytes);
+            bytes(b).cmp(&bytes(a))
+        }
+        SortKey::Added => {
+            let added = |entry: &str| metas.get(entry).map(|m| m.added.clone());
+            added(b).cmp(&added(a))
+        }
+    };
+    by_key.then_with(|| a.cmp(b))
+}
+
+/// Total size, file count, and newest mtime of the files an entry matches.
+#[derive(Debug, Default, Clone, Copy)]
+struct DiskUsage {
+    bytes: u64,
+    files: usize,
+    modified: Option<SystemTime>,
+}
+
+/// Disk usage of every file each entry in the exclude file matches,
+/// tracked or not.
+fn entry_usage(
+    repo_root: &Path,
+    tracked: &HashSet<String>,
+    exclude: &ExcludeFile,
+) -> Result<HashMap<String, DiskUsage>> {
+    let mut files: Vec<String> = tracked.iter().cloned().collect();
+    files.extend(git::list_all_untracked(repo_root)?);
+    let entries: Vec<String> = exclude
+        .entries()
+        .into_iter()
+        .chain(exclude.disabled_entries())
+        .chain(exclude.user_entries())
+        .map(|e| e.value)
+        .collect();
+
+    let mut usage: HashMap<String, DiskUsage> = HashMap::new();
+    for file in &files {
+        let matching: Vec<&String> = entries.iter().filter(|e| entry_matches_path(e, file)).collect();
+        if matching.is_empty() {
+            continue;
         }
+        let metadata = repo_root.join(file).metadata().ok();
+        let len = metadata.as_ref().map_or(0, |m| m.len());
+        let modified = metadata.and_then(|m| m.modified().ok());
+        for entry in matching {
+            let total = usage.entry(entry.clone()).or_default();
+            total.bytes += len;
+            total.files += 1;
+            total.modified = total.modified.max(modified);
+        }
+    }
+    Ok(usage)
+}
+
+/// `  1.2 MB, 3 files, modified Feb 8, 2026` for `ls --long`; empty without
+/// `--long` or when the entry matches no files.
+fn usage_label(long: bool, usage: Option<&DiskUsage>) -> String {
+    let Some(usage) = usage.filter(|u| long && u.files > 0) else {
+        return String::new();
+    };
+    let mut text = format!(
+        "{}, {} {}",
+        format_size(usage.bytes),
+        usage.files,
+        if usage.files == 1 { "file" } else { "files" }
+    );
+    if let Some(modified) = usage.modified.and_then(rfc3339) {
+        text.push_str(&format!(", modified {}", format_backup_date(&Some(modified))));
     }
+    format!("  {}", ui::dim_text(&text))
+}
 
-    Ok(0)
+fn rfc3339(time: SystemTime) -> Option<String> {
+    OffsetDateTime::from(time).format(&Rfc3339).ok()
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
 }
 
 enum EntryStatus {
     Layered(String),
     Exposed(String),
+    /// A higher-precedence negation re-includes the path.
+    Overridden(String),
+    /// `--verify` found that git doesn't apply the entry to a path it
+    /// looks like it should match.
+    Ineffective(String),
     Stale(String),
 }
 
+impl EntryStatus {
+    fn state(&self) -> &'static str {
+        match self {
+            EntryStatus::Layered(_) => "layered",
+            EntryStatus::Exposed(_) => "exposed",
+            EntryStatus::Overridden(_) => "overridden",
+            EntryStatus::Ineffective(_) => "ineffective",
+            EntryStatus::Stale(_) => "stale",
+        }
+    }
+
+    /// Position under `--sort status`: problems first.
+    fn rank(&self) -> u8 {
+        match self {
+            EntryStatus::Ineffective(_) => 0,
+            EntryStatus::Overridden(_) => 1,
+            EntryStatus::Exposed(_) => 2,
+            EntryStatus::Stale(_) => 3,
+            EntryStatus::Layered(_) => 4,
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            EntryStatus::Layered(detail)
+            | EntryStatus::Exposed(detail)
+            | EntryStatus::Overridden(detail)
+            | EntryStatus::Ineffective(detail)
+            | EntryStatus::Stale(detail) => detail,
+        }
+    }
+}
+
+/// Run each entry through `git check-ignore` against a representative path:
+/// the entry itself for a literal file, otherwise the first existing file it
+/// looks like it should match. Entries git doesn't honor for that path (bad
+/// anchoring or escaping, or a negation that wins) map to a description.
+fn verify_entries(
+    repo_root: &Path,
+    exclude_path: &Path,
+    entries: &[Entry],
+    tracked: &HashSet<String>,
+) -> Result<HashMap<String, String>> {
+    let needs_files = entries.iter().any(|e| e.value.ends_with('/') || git::contains_glob(&e.value));
+    let mut files: Vec<String> = Vec::new();
+    if needs_files {
+        files.extend(tracked.iter().cloned());
+        files.extend(git::list_all_untracked(repo_root)?);
+        files.sort();
+        files.dedup();
+    }
+
+    let mut probes: Vec<(&str, String)> = Vec::new();
+    for entry in entries {
+        let value = entry.value.as_str();
+        let probe = if value.ends_with('/') || git::contains_glob(value) {
+            files.iter().find(|f| entry_matches_path(value, f)).cloned()
+        } else {
+            Some(value.trim_start_matches('/').to_string())
+        };
+        if let Some(probe) = probe {
+            probes.push((value, probe));
+        }
+    }
+
+    let paths: Vec<String> = probes.iter().map(|(_, p)| p.clone()).collect();
+    let hits = git::check_ignore_bulk(repo_root, &paths, true)?;
+
+    let mut out = HashMap::new();
+    for (entry, probe) in probes {
+        match hits.get(&probe) {
+            None => {
+                out.insert(
+                    entry.to_string(),
+                    format!("ineffective — git doesn't match {probe} (check anchoring and escaping)"),
+                );
+            }
+            Some(hit) if hit.pattern.starts_with('!') => {
+                let source = if git::is_local_exclude_source(repo_root, exclude_path, &hit.source) {
+                    ".git/info/exclude".to_string()
+                } else {
+                    hit.source.replace('\\', "/")
+                };
+                out.insert(
+                    entry.to_string(),
+                    format!("ineffective — {probe} re-included by {} ({source}:{})", hit.pattern, hit.line),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(out)
+}
+
 fn classify_entry(
     repo_root: &Path,
     entry: &str,
     tracked: &HashSet<String>,
     pattern_match_index: &HashMap<String, PatternMatchSummary>,
+    discrepancies: &HashMap<String, IgnoreDiscrepancy>,
 ) -> EntryStatus {
+    let discrepancy = discrepancies.get(entry.trim_end_matches('/'));
+    if let Some(negated @ IgnoreDiscrepancy::Negated { .. }) = discrepancy {
+        return EntryStatus::Overridden(format!("overridden — {}", negated.describe()));
+    }
     if entry.ends_with('/') {
         return classify_directory(repo_root, entry, tracked);
     }
     if git::contains_glob(entry) {
         return classify_pattern(entry, pattern_match_index);
     }
-    classify_literal(repo_root, entry, tracked)
+    classify_literal(repo_root, entry, tracked, discrepancy)
 }
 
-fn classify_literal(repo_root: &Path, entry: &str, tracked: &HashSet<String>) -> EntryStatus {
+fn classify_literal(
+    repo_root: &Path,
+    entry: &str,
+    tracked: &HashSet<String>,
+    discrepancy: Option<&IgnoreDiscrepancy>,
+) -> EntryStatus {
     let exists = repo_root.join(entry).exists();
     let is_tracked = tracked.contains(entry);
 
+    if let Some(IgnoreDiscrepancy::Tracked) = discrepancy {
+        return EntryStatus::Exposed(format!(
+            "exposed — {} (git rm --cached {entry})",
+            IgnoreDiscrepancy::Tracked.describe()
+        ));
+    }
+
     if is_tracked {
         return EntryStatus::Exposed(format!(
             "exposed — git rm --cached {entry}"
@@ -189,3 +736,43 @@ fn classify_pattern(
 
     EntryStatus::Layered(format!("layered ({} files)", summary.total))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_by_tool_orders_known_tools_first() {
+        let patterns: Vec<&'static KnownPattern> = crate::patterns::KNOWN_SCAN_PATTERNS.iter().collect();
+        let groups = group_by_tool(["notes/", ".cursorrules", "CLAUDE.md", ".claude/"].into_iter(), &patterns);
+        assert_eq!(
+            groups,
+            vec![("Claude Code", vec![2, 3]), ("Cursor / PearAI", vec![1]), ("Other", vec![0])]
+        );
+    }
+
+    #[test]
+    fn compare_entries_puts_undated_and_smaller_entries_last() {
+        let metas: BTreeMap<String, EntryMeta> = [(
+            "b.md".to_string(),
+            EntryMeta { added: "2026-02-08T09:00:00Z".to_string(), origin: "add".to_string(), source: None, note: None },
+        )]
+        .into_iter()
+        .collect();
+        let usage = |bytes| DiskUsage { bytes, files: 1, modified: None };
+        let sizes: HashMap<String, DiskUsage> =
+            [("a.md".to_string(), usage(10)), ("b.md".to_string(), usage(20))].into_iter().collect();
+
+        assert_eq!(compare_entries(SortKey::Added, "b.md", "a.md", &metas, &sizes), Ordering::Less);
+        assert_eq!(compare_entries(SortKey::Size, "b.md", "a.md", &metas, &sizes), Ordering::Less);
+        assert_eq!(compare_entries(SortKey::Size, "a.md", "c.md", &metas, &sizes), Ordering::Less);
+        assert_eq!(compare_entries(SortKey::Name, "b.md", "a.md", &metas, &sizes), Ordering::Greater);
+    }
+
+    #[test]
+    fn format_size_uses_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
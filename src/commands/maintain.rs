@@ -0,0 +1,167 @@
+use crate::commands::{backup, clean, status};
+use crate::config;
+use crate::exclude_file::ensure_exclude_file;
+use crate::git;
+use crate::git::RepoContext;
+use crate::journal;
+use crate::meta;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use time::OffsetDateTime;
+
+/// When `maintain` first saw each stale entry, kept in the git dir.
+const STALE_STATE_FILE: &str = "LAYER_STALE";
+
+/// Scratch files left behind by an interrupted command, removed once they
+/// are older than a day.
+const SCRATCH_FILES: &[&str] = &["LAYER_EDIT"];
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// One unattended pass for cron or launchd: back up, drop entries that have
+/// been stale for `maintain.stale_days`, refresh the `status --hook` cache,
+/// and clear leftovers. Prints a single summary line; a failed step is
+/// reported on stderr and the others still run.
+pub fn run() -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let stale_days = config::load(Some(&ctx.root))?.get_int("maintain.stale_days");
+    let mut summary = Vec::new();
+    let mut failed = false;
+
+    let mut step = |name: &str, result: Result<Option<String>>| match result {
+        Ok(Some(line)) => summary.push(line),
+        Ok(None) => {}
+        Err(err) => {
+            failed = true;
+            ui::print_stderr_warning(&format!("{name} failed: {err:#}"));
+        }
+    };
+
+    step("backup", run_backup(&ctx));
+    step("clean", clean_long_stale(&ctx, stale_days));
+    step(
+        "cache refresh",
+        status::hook_summary().map(|_| Some("status cache refreshed".to_string())),
+    );
+    step("gc", gc(&ctx));
+
+    outln!("layer maintain: {}", summary.join(" · "));
+    Ok(if failed { 1 } else { 0 })
+}
+
+fn run_backup(ctx: &RepoContext) -> Result<Option<String>> {
+    if !config::load(Some(&ctx.root))?.get_bool("backup.auto") {
+        return Ok(Some("backup skipped (backup.auto is off)".to_string()));
+    }
+    // Never replace a useful backup with an empty one.
+    if ensure_exclude_file(&ctx.exclude_path)?.entries().is_empty() {
+        return Ok(Some("backup skipped (no entries)".to_string()));
+    }
+    let written = backup::write_backup(ctx, None)?;
+    Ok(Some(format!("backed up {} entries", written.entries)))
+}
+
+fn clean_long_stale(ctx: &RepoContext, stale_days: i64) -> Result<Option<String>> {
+    let mut exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    // Preset entries are stale on purpose until the tool creates its files.
+    let metas = meta::load(&ctx.exclude_path);
+    let stale: Vec<String> = clean::collect_stale_entries(ctx, &exclude.entries())?
+        .into_iter()
+        .filter(|entry| {
+            !metas
+                .get(entry)
+                .and_then(|m| m.source.as_deref())
+                .is_some_and(|source| source.starts_with("preset"))
+        })
+        .collect();
+
+    let state_path = ctx.git_dir.join(STALE_STATE_FILE);
+    let previous = fs::read_to_string(&state_path).unwrap_or_default();
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let (expired, state) = age_stale(&previous, &stale, now, stale_days * SECONDS_PER_DAY);
+
+    if !expired.is_empty() {
+        let targets: HashSet<String> = expired.iter().cloned().collect();
+        exclude.remove_exact(&targets);
+        exclude.write(&ctx.exclude_path)?;
+    }
+    fs::write(&state_path, state).with_context(|| format!("failed to write {}", state_path.display()))?;
+
+    let waiting = stale.len() - expired.len();
+    let mut line = format!(
+        "removed {} long-stale {}",
+        expired.len(),
+        if expired.len() == 1 { "entry" } else { "entries" }
+    );
+    if waiting > 0 {
+        line.push_str(&format!(" ({waiting} stale for less than {stale_days} days)"));
+    }
+    Ok(Some(line))
+}
+
+/// Carry first-seen times forward for entries that are still stale, start
+/// the clock for new ones, and split off those stale for at least `max_age`
+/// seconds. Returns the expired entries and the new state file contents.
+fn age_stale(previous: &str, stale: &[String], now: i64, max_age: i64) -> (Vec<String>, String) {
+    let seen: BTreeMap<&str, i64> = previous
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter_map(|(entry, since)| Some((entry, since.trim().parse().ok()?)))
+        .collect();
+
+    let mut expired = Vec::new();
+    let mut state = String::new();
+    for entry in stale {
+        let since = seen.get(entry.as_str()).copied().unwrap_or(now);
+        if now - since >= max_age {
+            expired.push(entry.clone());
+        } else {
+            state.push_str(&format!("{entry}\t{since}\n"));
+        }
+    }
+    (expired, state)
+}
+
+fn gc(ctx: &RepoContext) -> Result<Option<String>> {
+    let mut removed = journal::compact(&ctx.git_dir)?;
+    let day_ago = SystemTime::now() - Duration::from_secs(SECONDS_PER_DAY as u64);
+    for name in SCRATCH_FILES {
+        let path = ctx.git_dir.join(name);
+        let old = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified < day_ago);
+        if old {
+            fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok((removed > 0).then(|| format!("gc removed {removed} leftover {}", if removed == 1 { "item" } else { "items" })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn age_stale_keeps_first_seen_and_expires_old_entries() {
+        let stale = vec!["old.md".to_string(), "new.md".to_string()];
+        let (expired, state) = age_stale("old.md\t100\nfixed.md\t50\n", &stale, 1000, 500);
+        assert_eq!(expired, vec!["old.md"]);
+        assert_eq!(state, "new.md\t1000\n");
+
+        let (expired, state) = age_stale(&state, &stale, 1200, 500);
+        assert!(expired.is_empty());
+        assert_eq!(state, "old.md\t1200\nnew.md\t1000\n");
+    }
+
+    #[test]
+    fn age_stale_with_zero_days_expires_immediately() {
+        let (expired, state) = age_stale("", &["gone.md".to_string()], 1000, 0);
+        assert_eq!(expired, vec!["gone.md"]);
+        assert!(state.is_empty());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{Context, Result};
+use clap_mangen::Man;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Write a roff man page for `cmd` and every visible subcommand into
+/// `out_dir` (`layer.1`, `layer-add.1`, `layer-hook-install.1`, ...), for
+/// packagers to install under `man1/`.
+pub fn run(cmd: clap::Command, out_dir: PathBuf) -> Result<i32> {
+    fs::create_dir_all(&out_dir).with_context(|| format!("failed to create {}", out_dir.display()))?;
+
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+    let mut written = Vec::new();
+    generate(cmd, &out_dir, &mut written)?;
+
+    outln!("  {} Wrote {} man pages to {}", ui::ok(), written.len(), out_dir.display());
+    Ok(0)
+}
+
+fn generate(cmd: clap::Command, out_dir: &Path, written: &mut Vec<PathBuf>) -> Result<()> {
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()).cloned() {
+        generate(sub, out_dir, written)?;
+    }
+    let path = Man::new(cmd)
+        .generate_to(out_dir)
+        .with_context(|| format!("failed to write man page to {}", out_dir.display()))?;
+    written.push(path);
+    Ok(())
+}
@@ -1,14 +1,39 @@
+pub mod ack;
 pub mod add;
 pub mod backup;
+pub mod check;
 pub mod clean;
 pub mod clear;
+pub mod config;
+pub mod demo;
+pub mod demote;
+pub mod dismiss;
 pub mod doctor;
 pub mod edit;
+pub mod fmt;
+pub mod foreach;
 pub mod global;
+pub mod hook;
+pub mod import;
+pub mod init;
+pub mod log;
 pub mod ls;
+pub mod maintain;
+pub mod man;
+pub mod new;
 pub mod on_off;
 pub mod patterns;
+pub mod pick;
+pub mod preset;
+pub mod recent;
+pub mod report;
 pub mod rm;
 pub mod scan;
+pub mod serve;
+pub mod session;
 pub mod status;
+pub mod template;
+pub mod untrack;
+pub mod vault;
+pub mod which;
 pub mod why_cmd;
@@ -0,0 +1,108 @@
+use crate::commands::add::apply_add_entries;
+use crate::config;
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Context files `layer new` can start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ContextKind {
+    /// CLAUDE.md for Claude Code
+    Claude,
+    /// AGENTS.md for Codex and other agents
+    Agents,
+    /// .cursor/rules/project.mdc for Cursor
+    Cursor,
+}
+
+impl ContextKind {
+    /// Where the file goes, relative to the repo root.
+    fn path(self) -> &'static str {
+        match self {
+            ContextKind::Claude => "CLAUDE.md",
+            ContextKind::Agents => "AGENTS.md",
+            ContextKind::Cursor => ".cursor/rules/project.mdc",
+        }
+    }
+
+    fn builtin_template(self) -> &'static str {
+        match self {
+            ContextKind::Claude | ContextKind::Agents => {
+                "# {project}\n\n## Overview\n\n## Commands\n\n## Conventions\n"
+            }
+            ContextKind::Cursor => {
+                "---\ndescription: {project} conventions\nalwaysApply: true\n---\n\n## Conventions\n"
+            }
+        }
+    }
+}
+
+/// Create a context file from a template and layer it in one step. The
+/// template is the file of the same name in `new.templates`, if there is one.
+pub fn run(kind: ContextKind, no_layer: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let rel = kind.path();
+    let target = ctx.root.join(rel);
+    if target.exists() {
+        bail!("{rel} already exists. Run `layer add {rel}` to layer it");
+    }
+
+    let templates = config::load(Some(&ctx.root))?.get_str("new.templates").trim().to_string();
+    let template = match template_path(&templates, rel, std::env::var("HOME").ok()) {
+        Some(path) if path.is_file() => {
+            fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?
+        }
+        _ => kind.builtin_template().to_string(),
+    };
+    let project = ctx.root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&target, template.replace("{project}", &project))
+        .with_context(|| format!("failed to write {}", target.display()))?;
+    outln!("  {} Created {rel}", ui::ok());
+
+    if no_layer {
+        return Ok(0);
+    }
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    apply_add_entries(&ctx, &mut exclude, &[rel.to_string()], false)?;
+    Ok(0)
+}
+
+/// The template for `rel` inside the `new.templates` directory, with a
+/// leading `~/` expanded.
+fn template_path(templates: &str, rel: &str, home: Option<String>) -> Option<PathBuf> {
+    if templates.is_empty() {
+        return None;
+    }
+    let dir = match (templates.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(templates),
+    };
+    let name = Path::new(rel).file_name()?;
+    Some(dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_path_uses_the_file_name_inside_the_templates_dir() {
+        assert_eq!(template_path("", "CLAUDE.md", None), None);
+        assert_eq!(
+            template_path("~/templates", ".cursor/rules/project.mdc", Some("/home/me".to_string())),
+            Some(PathBuf::from("/home/me/templates/project.mdc"))
+        );
+        assert_eq!(
+            template_path("/etc/layer", "AGENTS.md", None),
+            Some(PathBuf::from("/etc/layer/AGENTS.md"))
+        );
+    }
+}
@@ -1,24 +1,37 @@
-use crate::exclude_file::{ensure_exclude_file_for_write, normalize_entry};
+use crate::exclude_file::{ensure_exclude_file_for_write, normalize_entry, ExcludeFile};
 use crate::git;
 use crate::ui;
-use anyhow::Result;
+use crate::ui::outln;
+use anyhow::{bail, Result};
 use std::collections::HashSet;
 
-pub fn run_off(files: Vec<String>, dry_run: bool) -> Result<i32> {
+pub fn run_off(files: Vec<String>, except: Vec<String>, dry_run: bool) -> Result<i32> {
     let ctx = git::ensure_repo()?;
     let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
     let active = exclude.entries();
 
     if active.is_empty() {
-        println!("No active entries to disable.");
+        outln!("No active entries to disable.");
         return Ok(2);
     }
 
+    let all_values: Vec<String> = active.iter().map(|e| e.value.clone()).collect();
+    let files = if except.is_empty() {
+        files
+    } else {
+        let remaining = apply_except(&exclude, &all_values, &except)?;
+        if remaining.is_empty() {
+            outln!("  {} Every active entry is excepted. Nothing to disable.", ui::info());
+            return Ok(2);
+        }
+        remaining
+    };
+
     if files.is_empty() {
         // Disable all
         if dry_run {
             for entry in &active {
-                println!("  {} Would disable {}", ui::info(), entry.value);
+                outln!("  {} Would disable {}", ui::info(), entry.value);
             }
             ui::print_dry_run_notice();
             return Ok(0);
@@ -27,7 +40,7 @@ pub fn run_off(files: Vec<String>, dry_run: bool) -> Result<i32> {
         let disabled = exclude.disable_all();
         exclude.write(&ctx.exclude_path)?;
         for entry in &disabled {
-            println!("  {} Disabled {entry}", ui::ok());
+            outln!("  {} Disabled {entry}", ui::ok());
         }
         Ok(0)
     } else {
@@ -39,9 +52,9 @@ pub fn run_off(files: Vec<String>, dry_run: bool) -> Result<i32> {
         for target in &targets {
             if !active_set.contains(target.as_str()) {
                 if disabled_set.contains(target.as_str()) {
-                    println!("  {} {target} is already disabled", ui::info());
+                    outln!("  {} {target} is already disabled", ui::info());
                 } else {
-                    println!("  {} {target} is not layered", ui::info());
+                    outln!("  {} {target} is not layered", ui::info());
                 }
             }
         }
@@ -56,7 +69,7 @@ pub fn run_off(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
         if dry_run {
             for target in &found {
-                println!("  {} Would disable {target}", ui::info());
+                outln!("  {} Would disable {target}", ui::info());
             }
             ui::print_dry_run_notice();
             return Ok(0);
@@ -65,27 +78,39 @@ pub fn run_off(files: Vec<String>, dry_run: bool) -> Result<i32> {
         let disabled = exclude.disable_entries(&found);
         exclude.write(&ctx.exclude_path)?;
         for entry in &disabled {
-            println!("  {} Disabled {entry}", ui::ok());
+            outln!("  {} Disabled {entry}", ui::ok());
         }
         Ok(0)
     }
 }
 
-pub fn run_on(files: Vec<String>, dry_run: bool) -> Result<i32> {
+pub fn run_on(files: Vec<String>, except: Vec<String>, dry_run: bool) -> Result<i32> {
     let ctx = git::ensure_repo()?;
     let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
     let disabled_list = exclude.disabled_entries();
 
     if disabled_list.is_empty() {
-        println!("No disabled entries to enable.");
+        outln!("No disabled entries to enable.");
         return Ok(2);
     }
 
+    let all_values: Vec<String> = disabled_list.iter().map(|e| e.value.clone()).collect();
+    let files = if except.is_empty() {
+        files
+    } else {
+        let remaining = apply_except(&exclude, &all_values, &except)?;
+        if remaining.is_empty() {
+            outln!("  {} Every disabled entry is excepted. Nothing to enable.", ui::info());
+            return Ok(2);
+        }
+        remaining
+    };
+
     if files.is_empty() {
         // Enable all
         if dry_run {
             for entry in &disabled_list {
-                println!("  {} Would enable {}", ui::info(), entry.value);
+                outln!("  {} Would enable {}", ui::info(), entry.value);
             }
             ui::print_dry_run_notice();
             return Ok(0);
@@ -94,7 +119,7 @@ pub fn run_on(files: Vec<String>, dry_run: bool) -> Result<i32> {
         let enabled = exclude.enable_all();
         exclude.write(&ctx.exclude_path)?;
         for entry in &enabled {
-            println!("  {} Enabled {entry}", ui::ok());
+            outln!("  {} Enabled {entry}", ui::ok());
         }
         Ok(0)
     } else {
@@ -107,9 +132,9 @@ pub fn run_on(files: Vec<String>, dry_run: bool) -> Result<i32> {
         for target in &targets {
             if !disabled_set.contains(target.as_str()) {
                 if active_set.contains(target.as_str()) {
-                    println!("  {} {target} is already enabled", ui::info());
+                    outln!("  {} {target} is already enabled", ui::info());
                 } else {
-                    println!("  {} {target} is not layered", ui::info());
+                    outln!("  {} {target} is not layered", ui::info());
                 }
             }
         }
@@ -124,7 +149,7 @@ pub fn run_on(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
         if dry_run {
             for target in &found {
-                println!("  {} Would enable {target}", ui::info());
+                outln!("  {} Would enable {target}", ui::info());
             }
             ui::print_dry_run_notice();
             return Ok(0);
@@ -133,8 +158,40 @@ pub fn run_on(files: Vec<String>, dry_run: bool) -> Result<i32> {
         let enabled = exclude.enable_entries(&found);
         exclude.write(&ctx.exclude_path)?;
         for entry in &enabled {
-            println!("  {} Enabled {entry}", ui::ok());
+            outln!("  {} Enabled {entry}", ui::ok());
         }
         Ok(0)
     }
 }
+
+/// Inverse selection for `--except`: every candidate not named in `except`.
+/// Every name must be a layer entry, active or disabled, so a typo can't
+/// quietly toggle the entry it meant to keep.
+fn apply_except(exclude: &ExcludeFile, candidates: &[String], except: &[String]) -> Result<Vec<String>> {
+    let known: HashSet<String> = exclude
+        .entries()
+        .into_iter()
+        .chain(exclude.disabled_entries())
+        .map(|e| e.value)
+        .collect();
+    let mut keep = HashSet::new();
+    let mut unknown = Vec::new();
+    for raw in except {
+        let entry = normalize_entry(raw);
+        if !known.contains(&entry) && !unknown.contains(&entry) {
+            unknown.push(entry.clone());
+        }
+        keep.insert(entry);
+    }
+    if !unknown.is_empty() {
+        bail!(
+            "--except {} not in .git/info/exclude. Run 'layer ls' to see entries. No changes made",
+            if unknown.len() == 1 { format!("'{}' is", unknown[0]) } else { format!("'{}' are", unknown.join("', '")) }
+        );
+    }
+    Ok(candidates
+        .iter()
+        .filter(|c| !keep.contains(c.as_str()))
+        .cloned()
+        .collect())
+}
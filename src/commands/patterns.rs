@@ -1,8 +1,11 @@
-use crate::commands::scan;
+use crate::commands::{config as config_cmd, import};
+use crate::config::{self, Origin, Value};
+use crate::discovery::{self, Discovery, Filter};
 use crate::exclude_file::ensure_exclude_file;
 use crate::git;
-use crate::patterns::KNOWN_SCAN_PATTERNS;
+use crate::patterns::{enabled_patterns, label_matches_tool, parse_custom_pattern, KnownPattern, KNOWN_SCAN_PATTERNS, PROJECT_LABEL};
 use crate::ui;
+use crate::ui::outln;
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 
@@ -16,90 +19,240 @@ fn detection_kind(entry: &str) -> &'static str {
     }
 }
 
-pub fn run(json: bool, matched: bool, show_files: bool) -> Result<i32> {
+pub fn run(format: ui::Format, matched: bool, show_files: bool, long: bool, tool: Option<String>) -> Result<i32> {
     if show_files && !matched {
         bail!("--show-files requires --matched");
     }
 
+    let patterns = select_patterns(tool.as_deref())?;
+
     if matched {
-        run_matched(json, show_files)
-    } else if json {
-        run_json_static()
+        run_matched(&patterns, format, show_files, long)
+    } else if format.is_json() {
+        run_json_static(&patterns)
+    } else if format.is_porcelain() {
+        for pat in &patterns {
+            if long {
+                ui::porcelain(&[pat.label, pat.entry, detection_kind(pat.entry), pat.description]);
+            } else {
+                ui::porcelain(&[pat.label, pat.entry, detection_kind(pat.entry)]);
+            }
+        }
+        Ok(0)
+    } else {
+        run_static(&patterns, long)
+    }
+}
+
+/// `layer patterns add`: register a custom pattern in `scan.patterns`, then
+/// show what it matches in this repo right away.
+pub fn add(entry: &str, label: Option<&str>, category: Option<&str>, user: bool) -> Result<i32> {
+    let entry = entry.trim();
+    if entry.starts_with('!') {
+        bail!("'{entry}' is a negation; scan patterns say which files to look for");
+    }
+    if import::WHOLE_REPO.contains(&entry) {
+        bail!("'{entry}' would match the whole repo");
+    }
+    if entry.contains('|') || label.is_some_and(|label| label.contains('|')) {
+        bail!("'|' separates the parts of a scan.patterns item, so it can't appear in the entry or label");
+    }
+    let item = match (label.map(str::trim).filter(|l| !l.is_empty()), category) {
+        (None, None) => entry.to_string(),
+        (Some(label), None) => format!("{entry} | {label}"),
+        (label, Some(category)) => format!("{entry} | {} | {}", label.unwrap_or(PROJECT_LABEL), category.trim()),
+    };
+    let label = parse_custom_pattern(&item)?.1.to_string();
+
+    let (repo, path) = config_cmd::target(user)?;
+    let mut items = custom_items(&path, repo.is_some())?;
+    let replaced = items.iter().position(|existing| custom_entry(existing) == entry);
+    match replaced {
+        Some(idx) if items[idx] == item => {
+            outln!("  {} '{entry}' is already a custom pattern", ui::info());
+            return Ok(2);
+        }
+        Some(idx) => items[idx] = item,
+        None => items.push(item),
+    }
+    config::write_value(&path, "scan.patterns", &Value::List(items))?;
+    outln!(
+        "  {} {} '{entry}' ({}, {label}) {} {}",
+        ui::ok(),
+        if replaced.is_some() { "Updated" } else { "Added" },
+        detection_kind(entry),
+        if replaced.is_some() { "in" } else { "to" },
+        ui::dim_text(&path.display().to_string())
+    );
+    if let Some(ctx) = &repo {
+        config_cmd::layer_repo_config(ctx)?;
+    }
+
+    let Ok(ctx) = git::ensure_repo() else {
+        return Ok(0);
+    };
+    let matches = discovery::paths_matching(&ctx, entry)?;
+    if matches.is_empty() {
+        outln!("  {} Nothing in this repo matches it yet", ui::info());
     } else {
-        run_static()
+        outln!(
+            "  {} Matches {} {} here:",
+            ui::discovered(),
+            matches.len(),
+            if matches.len() == 1 { "path" } else { "paths" }
+        );
+        for path in &matches {
+            outln!("      {}", ui::dim_text(path));
+        }
+    }
+    Ok(0)
+}
+
+/// `layer patterns rm`: drop a custom pattern from `scan.patterns`.
+pub fn rm(entry: &str, user: bool) -> Result<i32> {
+    let entry = entry.trim();
+    let (repo, path) = config_cmd::target(user)?;
+    let mut items = custom_items(&path, repo.is_some())?;
+    let before = items.len();
+    items.retain(|item| custom_entry(item) != entry);
+    if items.len() == before {
+        if KNOWN_SCAN_PATTERNS.iter().any(|pat| pat.entry == entry) {
+            outln!(
+                "  {} '{entry}' is built in. Turn off its tool with patterns.disabled instead",
+                ui::info()
+            );
+        } else {
+            outln!("  {} '{entry}' is not a custom pattern in {}", ui::info(), path.display());
+        }
+        return Ok(2);
+    }
+    config::write_value(&path, "scan.patterns", &Value::List(items))?;
+    outln!("  {} Removed '{entry}' from {}", ui::ok(), ui::dim_text(&path.display().to_string()));
+    Ok(0)
+}
+
+/// The `scan.patterns` list to edit in the config file at `path`. A repo
+/// that doesn't set its own starts from your user or system list, since its
+/// list replaces theirs once written.
+fn custom_items(path: &std::path::Path, repo: bool) -> Result<Vec<String>> {
+    if let Some(Value::List(items)) = config::read_value(path, "scan.patterns")? {
+        return Ok(items);
+    }
+    if !repo {
+        return Ok(Vec::new());
     }
+    let config = config::load(None)?;
+    Ok(match config.get("scan.patterns") {
+        Some((Value::List(items), Origin::User(_) | Origin::System(_))) => items.clone(),
+        _ => Vec::new(),
+    })
 }
 
-/// Default static listing grouped by tool label with kind annotations.
-fn run_static() -> Result<i32> {
+fn custom_entry(item: &str) -> &str {
+    item.split('|').next().unwrap_or_default().trim()
+}
+
+/// Patterns discovery looks for, including this repo's `scan.patterns`,
+/// optionally narrowed to a single tool via `--tool`.
+fn select_patterns(tool: Option<&str>) -> Result<Vec<&'static KnownPattern>> {
+    let root = git::ensure_repo().ok().map(|ctx| ctx.root);
+    let enabled = enabled_patterns(&config::load(root.as_deref())?);
+
+    // Keep each tool's patterns together even when `scan.patterns` interleaves labels.
+    let mut labels: Vec<&str> = Vec::new();
+    for pat in &enabled {
+        if !labels.contains(&pat.label) {
+            labels.push(pat.label);
+        }
+    }
+    let patterns: Vec<_> = labels
+        .iter()
+        .flat_map(|label| enabled.iter().copied().filter(move |p| p.label == *label))
+        .collect();
+    let Some(tool) = tool else {
+        return Ok(patterns);
+    };
+
+    let selected: Vec<_> = patterns.into_iter().filter(|p| label_matches_tool(p.label, tool)).collect();
+    if selected.is_empty() {
+        bail!(
+            "unknown tool '{}'. Known tools: {}",
+            tool,
+            labels.join(", ")
+        );
+    }
+
+    Ok(selected)
+}
+
+/// Default static listing grouped by tool label with kind annotations, and
+/// what each pattern holds with `long`.
+fn run_static(patterns: &[&KnownPattern], long: bool) -> Result<i32> {
     let mut current_label = "";
 
-    for pat in KNOWN_SCAN_PATTERNS {
+    for pat in patterns {
         if pat.label != current_label {
             if !current_label.is_empty() {
-                println!();
+                outln!();
             }
-            println!("{}", ui::heading(pat.label));
+            outln!("{}", ui::heading(pat.label));
             current_label = pat.label;
         }
-        println!("  {}  {}", pat.entry, ui::dim_text(&format!("({})", detection_kind(pat.entry))));
+        outln!("  {}  {}", pat.entry, ui::dim_text(&format!("({})", detection_kind(pat.entry))));
+        if long {
+            outln!("      {}", ui::dim_text(pat.description));
+        }
     }
 
     Ok(0)
 }
 
 /// JSON output for static pattern list.
-fn run_json_static() -> Result<i32> {
-    let groups = build_groups();
-
-    let mut json = String::from("[\n");
-    for (gi, (label, patterns)) in groups.iter().enumerate() {
-        json.push_str("  {\n");
-        json.push_str(&format!("    \"tool\": {},\n", json_escape(label)));
-        json.push_str("    \"patterns\": [\n");
-        for (pi, entry) in patterns.iter().enumerate() {
-            json.push_str(&format!(
-                "      {{ \"entry\": {}, \"kind\": {} }}",
-                json_escape(entry),
-                json_escape(detection_kind(entry))
-            ));
-            if pi + 1 < patterns.len() {
-                json.push(',');
-            }
-            json.push('\n');
+fn run_json_static(patterns: &[&KnownPattern]) -> Result<i32> {
+    let mut out = JsonLines::default();
+    out.line("[");
+    for (gi, (label, entries)) in build_groups(patterns).into_iter().enumerate() {
+        if gi > 0 {
+            out.comma();
         }
-        json.push_str("    ]\n");
-        json.push_str("  }");
-        if gi + 1 < groups.len() {
-            json.push(',');
+        out.open_group(&label);
+        for (pi, pat) in entries.into_iter().enumerate() {
+            if pi > 0 {
+                out.comma();
+            }
+            out.line(format!(
+                "      {{ \"entry\": {}, \"kind\": {}, \"description\": {} }}",
+                json_escape(pat.entry),
+                json_escape(detection_kind(pat.entry)),
+                json_escape(pat.description)
+            ));
         }
-        json.push('\n');
+        out.close_group();
     }
-    json.push(']');
-
-    println!("{json}");
+    out.line("]");
+    out.finish();
     Ok(0)
 }
 
 /// --matched mode: show patterns that have actual files in the current repo.
-fn run_matched(json: bool, show_files: bool) -> Result<i32> {
+fn run_matched(patterns: &[&KnownPattern], format: ui::Format, show_files: bool, long: bool) -> Result<i32> {
     let ctx = git::ensure_repo()?;
     let exclude = ensure_exclude_file(&ctx.exclude_path)?;
     let excluded = exclude.entry_set();
 
-    let discoveries = scan::discover_known_files(&ctx, &excluded)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+    let discoveries = discovery::known_files(&ctx, &excluded, &tracked, Filter::All)?;
+
+    if format.is_json() {
+        return print_matched_json(patterns, &discoveries, show_files);
+    }
 
     // Build a map from pattern label to list of matched entries.
     // Each matched entry has the pattern entry string and the list of discovered file paths.
     let mut match_map: HashMap<&str, Vec<MatchedPattern>> = HashMap::new();
 
-    for pat in KNOWN_SCAN_PATTERNS {
-        let files: Vec<String> = discoveries
-            .iter()
-            .filter(|d| d.label == pat.label && pattern_covers_discovery(pat.entry, &d.path))
-            .map(|d| d.path.clone())
-            .collect();
-
+    for pat in patterns {
+        let files = matched_files(pat.label, pat.entry, &discoveries);
         if !files.is_empty() {
             match_map
                 .entry(pat.label)
@@ -111,19 +264,19 @@ fn run_matched(json: bool, show_files: bool) -> Result<i32> {
         }
     }
 
-    if json {
-        return print_matched_json(&match_map, show_files);
+    if format.is_porcelain() {
+        return print_matched_porcelain(patterns, &match_map, show_files);
     }
 
     if match_map.is_empty() {
-        println!("No known patterns match files in this repository.");
+        outln!("No known patterns match files in this repository.");
         return Ok(2);
     }
 
     let mut has_section = false;
     let mut current_label = "";
 
-    for pat in KNOWN_SCAN_PATTERNS {
+    for pat in patterns {
         let Some(matched_list) = match_map.get(pat.label) else {
             continue;
         };
@@ -133,24 +286,27 @@ fn run_matched(json: bool, show_files: bool) -> Result<i32> {
 
         if pat.label != current_label {
             if has_section {
-                println!();
+                outln!();
             }
-            println!("{}", ui::heading(pat.label));
+            outln!("{}", ui::heading(pat.label));
             current_label = pat.label;
             has_section = true;
         }
 
         let count = mp.files.len();
-        println!(
+        outln!(
             "  {}  {} {}",
             pat.entry,
             ui::dim_text(&format!("({})", detection_kind(pat.entry))),
             ui::dim_text(&format!("[{count} match{}]", if count == 1 { "" } else { "es" }))
         );
+        if long {
+            outln!("      {}", ui::dim_text(pat.description));
+        }
 
         if show_files {
             for file in &mp.files {
-                println!("    {}", ui::dim_text(file));
+                outln!("    {}", ui::dim_text(file));
             }
         }
     }
@@ -163,6 +319,15 @@ struct MatchedPattern {
     files: Vec<String>,
 }
 
+/// Discovered paths that `entry` (one of `label`'s patterns) accounts for.
+fn matched_files(label: &str, entry: &str, discoveries: &[Discovery]) -> Vec<String> {
+    discoveries
+        .iter()
+        .filter(|d| d.label == label && pattern_covers_discovery(entry, &d.path))
+        .map(|d| d.path.clone())
+        .collect()
+}
+
 /// Check if a known pattern entry covers a discovered path.
 fn pattern_covers_discovery(pattern_entry: &str, discovered_path: &str) -> bool {
     // Directory patterns: the discovered path starts with the pattern prefix
@@ -179,94 +344,138 @@ fn pattern_covers_discovery(pattern_entry: &str, discovered_path: &str) -> bool
     discovered_path == pattern_entry
 }
 
-/// JSON output for --matched (and optionally --show-files).
-fn print_matched_json(
+/// `tool entry count` per matched pattern, or `tool entry file` per file
+/// with --show-files.
+fn print_matched_porcelain(
+    patterns: &[&KnownPattern],
     match_map: &HashMap<&str, Vec<MatchedPattern>>,
     show_files: bool,
 ) -> Result<i32> {
-    let groups = build_groups();
-
-    // Filter to only groups that have matches
-    let matched_groups: Vec<_> = groups
-        .iter()
-        .filter(|(label, _)| match_map.contains_key(label.as_str()))
-        .collect();
-
-    let mut json = String::from("[\n");
-    for (gi, (label, patterns)) in matched_groups.iter().enumerate() {
-        let matched_list = &match_map[label.as_str()];
-        json.push_str("  {\n");
-        json.push_str(&format!("    \"tool\": {},\n", json_escape(label)));
-        json.push_str("    \"patterns\": [\n");
-
-        let mut pi_count = 0;
-        let total_matched = patterns.iter().filter(|e| matched_list.iter().any(|m| m.entry == **e)).count();
-
-        for entry in patterns {
-            let mp = matched_list.iter().find(|m| m.entry == *entry);
-            let is_matched = mp.is_some();
+    let mut any = false;
+    for pat in patterns {
+        let Some(mp) = match_map
+            .get(pat.label)
+            .and_then(|list| list.iter().find(|m| m.entry == pat.entry))
+        else {
+            continue;
+        };
+        any = true;
+        if show_files {
+            for file in &mp.files {
+                ui::porcelain(&[pat.label, pat.entry, file]);
+            }
+        } else {
+            ui::porcelain(&[pat.label, pat.entry, &mp.files.len().to_string()]);
+        }
+    }
+    Ok(if any { 0 } else { 2 })
+}
 
-            if !is_matched {
+/// JSON output for --matched (and optionally --show-files). Discovery has
+/// already walked the repo once for every pattern, so nothing is printed
+/// before that finishes; after it, each pattern's files are matched and
+/// written in turn instead of building the whole document as one string.
+fn print_matched_json(patterns: &[&KnownPattern], discoveries: &[Discovery], show_files: bool) -> Result<i32> {
+    let mut out = JsonLines::default();
+    out.line("[");
+    let mut any_group = false;
+    for (label, entries) in build_groups(patterns) {
+        let mut any_pattern = false;
+        for pat in entries {
+            let entry = pat.entry;
+            let files = matched_files(&label, entry, discoveries);
+            if files.is_empty() {
                 continue;
             }
+            if any_pattern {
+                out.comma();
+            } else {
+                if any_group {
+                    out.comma();
+                }
+                out.open_group(&label);
+                any_group = true;
+                any_pattern = true;
+            }
 
-            json.push_str(&format!(
+            let mut object = format!(
                 "      {{ \"entry\": {}, \"kind\": {}, \"matched\": true",
                 json_escape(entry),
                 json_escape(detection_kind(entry))
-            ));
-
+            );
             if show_files {
-                if let Some(mp) = mp {
-                    json.push_str(", \"files\": [");
-                    for (fi, file) in mp.files.iter().enumerate() {
-                        json.push_str(&json_escape(file));
-                        if fi + 1 < mp.files.len() {
-                            json.push_str(", ");
-                        }
-                    }
-                    json.push(']');
-                }
+                let files: Vec<String> = files.iter().map(|file| json_escape(file)).collect();
+                object.push_str(&format!(", \"files\": [{}]", files.join(", ")));
             }
+            object.push_str(" }");
+            out.line(object);
+        }
+        if any_pattern {
+            out.close_group();
+        }
+    }
+    out.line("]");
+    out.finish();
+    Ok(0)
+}
 
-            json.push_str(" }");
-            pi_count += 1;
-            if pi_count < total_matched {
-                json.push(',');
-            }
-            json.push('\n');
+/// Pretty-printed JSON written line by line as it's produced. The last line
+/// is held back so a separating comma can still be added once the next
+/// element turns up.
+#[derive(Default)]
+struct JsonLines {
+    pending: Option<String>,
+}
+
+impl JsonLines {
+    fn line(&mut self, line: impl Into<String>) {
+        if let Some(previous) = self.pending.replace(line.into()) {
+            ui::json_line(&previous);
         }
+    }
 
-        json.push_str("    ]\n");
-        json.push_str("  }");
-        if gi + 1 < matched_groups.len() {
-            json.push(',');
+    fn comma(&mut self) {
+        if let Some(pending) = self.pending.as_mut() {
+            pending.push(',');
         }
-        json.push('\n');
     }
-    json.push(']');
 
-    println!("{json}");
-    Ok(0)
+    /// Start a `{ "tool": ..., "patterns": [` group.
+    fn open_group(&mut self, label: &str) {
+        self.line("  {");
+        self.line(format!("    \"tool\": {},", json_escape(label)));
+        self.line("    \"patterns\": [");
+    }
+
+    fn close_group(&mut self) {
+        self.line("    ]");
+        self.line("  }");
+    }
+
+    fn finish(mut self) {
+        if let Some(last) = self.pending.take() {
+            ui::json_line(&last);
+        }
+    }
 }
 
-/// Build ordered groups: [(label, [entries...])]
-fn build_groups() -> Vec<(String, Vec<&'static str>)> {
-    let mut groups: Vec<(String, Vec<&'static str>)> = Vec::new();
-    for pat in KNOWN_SCAN_PATTERNS {
+/// Build ordered groups: [(label, [patterns...])]
+fn build_groups<'a>(patterns: &[&'a KnownPattern]) -> Vec<(String, Vec<&'a KnownPattern>)> {
+    let mut groups: Vec<(String, Vec<&KnownPattern>)> = Vec::new();
+    for pat in patterns {
         if let Some(last) = groups.last_mut() {
             if last.0 == pat.label {
-                last.1.push(pat.entry);
+                last.1.push(pat);
                 continue;
             }
         }
-        groups.push((pat.label.to_string(), vec![pat.entry]));
+        groups.push((pat.label.to_string(), vec![pat]));
     }
     groups
 }
 
 /// Minimal JSON string escaping.
-fn json_escape(s: &str) -> String {
+pub fn json_escape(s: &str) -> String {
     let mut out = String::with_capacity(s.len() + 2);
     out.push('"');
     for c in s.chars() {
@@ -334,30 +543,77 @@ mod tests {
 
     #[test]
     fn build_groups_preserves_order() {
-        let groups = build_groups();
+        let groups = build_groups(&select_patterns(None).unwrap());
         assert!(!groups.is_empty());
-        assert_eq!(groups[0].0, "Claude Code");
-        assert!(groups[0].1.contains(&"CLAUDE.md"));
+        assert_eq!(groups[0].0, "Secrets");
+        assert_eq!(groups[1].0, "Claude Code");
+        assert!(groups[1].1.iter().any(|p| p.entry == "CLAUDE.md"));
     }
 
     #[test]
     fn static_run_succeeds() {
         // Just verify it doesn't panic
-        let result = run(false, false, false);
+        let result = run(ui::Format::Pretty, false, false, false, None);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 0);
     }
 
     #[test]
     fn json_static_run_succeeds() {
-        let result = run(true, false, false);
-        assert!(result.is_ok());
+        let (result, output) = ui::capture(|| run(ui::Format::Json, false, false, false, None));
+        assert_eq!(result.unwrap(), 0);
+        assert!(output.starts_with("[\n  {\n    \"tool\": \"Secrets\""));
+    }
+
+    #[test]
+    fn json_lines_puts_commas_between_elements_only() {
+        let ((), output) = ui::capture(|| {
+            let mut out = JsonLines::default();
+            out.line("[");
+            out.open_group("Aider");
+            out.line("      1");
+            out.comma();
+            out.line("      2");
+            out.close_group();
+            out.line("]");
+            out.finish();
+        });
+        assert_eq!(output, "[\n  {\n    \"tool\": \"Aider\",\n    \"patterns\": [\n      1,\n      2\n    ]\n  }\n]\n");
+    }
+
+    #[test]
+    fn porcelain_static_run_prints_tool_entry_kind() {
+        let (result, output) =
+            ui::capture(|| run(ui::Format::Porcelain, false, false, false, Some("aider".to_string())));
+        assert_eq!(result.unwrap(), 0);
+        assert!(output.lines().all(|line| line.starts_with("Aider\t")));
+        assert!(output.contains("Aider\t.aider*\tglob\n"), "{output}");
+    }
+
+    #[test]
+    fn long_porcelain_adds_the_description() {
+        let (result, output) =
+            ui::capture(|| run(ui::Format::Porcelain, false, false, true, Some("aider".to_string())));
         assert_eq!(result.unwrap(), 0);
+        assert!(output.contains("Aider\t.aiderignore\tfile\tFiles Aider should not add to the chat\n"), "{output}");
     }
 
     #[test]
     fn show_files_without_matched_errors() {
-        let result = run(false, false, true);
+        let result = run(ui::Format::Pretty, false, true, false, None);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn select_patterns_filters_by_tool() {
+        let patterns = select_patterns(Some("aider")).unwrap();
+        assert!(!patterns.is_empty());
+        assert!(patterns.iter().all(|p| p.label == "Aider"));
+    }
+
+    #[test]
+    fn select_patterns_unknown_tool_errors() {
+        let err = select_patterns(Some("not-a-tool")).unwrap_err();
+        assert!(err.to_string().contains("Known tools"));
+    }
 }
@@ -0,0 +1,136 @@
+use crate::commands::{add, edit, on_off, rm, why_cmd};
+use crate::discovery::{self, Filter};
+use crate::exclude_file::ensure_exclude_file;
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+use dialoguer::{FuzzySelect, Select};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Layered,
+    Disabled,
+    Discovered,
+}
+
+impl Kind {
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Layered => "layered",
+            Kind::Disabled => "disabled",
+            Kind::Discovered => "discovered",
+        }
+    }
+
+    fn actions(self) -> &'static [Action] {
+        match self {
+            Kind::Layered => &[Action::Why, Action::Off, Action::Rm, Action::Open],
+            Kind::Disabled => &[Action::Why, Action::On, Action::Open],
+            Kind::Discovered => &[Action::Why, Action::Add, Action::Open],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Why,
+    Add,
+    Rm,
+    Off,
+    On,
+    Open,
+}
+
+impl Action {
+    fn label(self) -> &'static str {
+        match self {
+            Action::Why => "why — explain how git treats it",
+            Action::Add => "add — layer it",
+            Action::Rm => "rm — stop layering it",
+            Action::Off => "off — disable it for now",
+            Action::On => "on — re-enable it",
+            Action::Open => "open — edit it in $EDITOR",
+        }
+    }
+}
+
+/// Fuzzy-find a layered, disabled, or discovered entry, then run an action
+/// on it.
+pub fn run() -> Result<i32> {
+    ui::require_tty("layer pick is interactive. Use 'layer ls' or 'layer status --format porcelain' in scripts")?;
+    let ctx = git::ensure_repo()?;
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+
+    let mut candidates: Vec<(String, Kind)> = Vec::new();
+    candidates.extend(exclude.entries().into_iter().map(|e| (e.value, Kind::Layered)));
+    candidates.extend(exclude.disabled_entries().into_iter().map(|e| (e.value, Kind::Disabled)));
+    let tracked = git::list_tracked(&ctx.root)?;
+    let mut discovered: Vec<String> = discovery::known_files(&ctx, &exclude.entry_set(), &tracked, Filter::Visible)?
+        .into_iter()
+        .map(|d| d.path)
+        .collect();
+    discovered.sort();
+    discovered.dedup();
+    candidates.extend(
+        discovered
+            .into_iter()
+            .filter(|path| !candidates.iter().any(|(c, _)| c == path))
+            .map(|path| (path, Kind::Discovered))
+            .collect::<Vec<_>>(),
+    );
+
+    if candidates.is_empty() {
+        outln!(
+            "Nothing to pick — no layered or discovered entries. Run {} to get started.",
+            ui::brand("layer scan")
+        );
+        return Ok(2);
+    }
+
+    let width = candidates.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|(path, kind)| format!("{path:<width$}  {}", ui::dim_text(kind.label())))
+        .collect();
+    let theme = ui::layer_theme();
+    let Some(index) = FuzzySelect::with_theme(&theme)
+        .with_prompt("Pick an entry (type to filter)")
+        .items(&items)
+        .default(0)
+        .interact_opt()?
+    else {
+        outln!("No changes made.");
+        return Ok(2);
+    };
+    let (path, kind) = candidates[index].clone();
+
+    let actions: Vec<Action> = kind
+        .actions()
+        .iter()
+        .copied()
+        .filter(|action| *action != Action::Open || ctx.root.join(&path).exists())
+        .collect();
+    let labels: Vec<&str> = actions.iter().map(|a| a.label()).collect();
+    let Some(choice) = Select::with_theme(&theme)
+        .with_prompt(format!("{path}:"))
+        .items(&labels)
+        .default(0)
+        .interact_opt()?
+    else {
+        outln!("No changes made.");
+        return Ok(2);
+    };
+
+    match actions[choice] {
+        Action::Why => why_cmd::run(path, false),
+        Action::Add => add::run(vec![path], add::AddOptions::default(), ui::Format::Pretty),
+        Action::Rm => rm::run(vec![path], false),
+        Action::Off => on_off::run_off(vec![path], Vec::new(), false),
+        Action::On => on_off::run_on(vec![path], Vec::new(), false),
+        Action::Open => {
+            edit::launch_editor(&edit::resolve_editor(), &ctx.root.join(&path))?;
+            Ok(0)
+        }
+    }
+}
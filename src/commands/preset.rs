@@ -0,0 +1,38 @@
+use crate::commands::add::apply_add_entries;
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git;
+use crate::meta;
+use crate::patterns::{find_preset, PRESETS};
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{anyhow, Result};
+
+/// `layer preset list`: every preset and the entries it layers.
+pub fn list() -> Result<i32> {
+    for preset in PRESETS {
+        outln!("{}  {}", ui::heading(preset.name), ui::dim_text(preset.description));
+        outln!("  {}", preset.entries().join(" "));
+    }
+    Ok(0)
+}
+
+/// `layer preset apply`: layer a tool's known entries whether or not its
+/// files exist yet, so a fresh clone is covered before the agent writes them.
+pub fn apply(name: &str, dry_run: bool) -> Result<i32> {
+    let preset = find_preset(name).ok_or_else(|| {
+        let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+        anyhow!("unknown preset '{}'. Available presets: {}", name.trim(), names.join(", "))
+    })?;
+    let ctx = git::ensure_repo()?;
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let entries: Vec<String> = preset.entries().into_iter().map(str::to_string).collect();
+    meta::set_source(&entries, &format!("preset {}", preset.name));
+    let summary = apply_add_entries(&ctx, &mut exclude, &entries, dry_run)?;
+    if summary.added == 0 {
+        return Ok(2);
+    }
+    if !dry_run {
+        outln!("  {}", ui::dim_text("Entries show as stale until the tool creates its files."));
+    }
+    Ok(0)
+}
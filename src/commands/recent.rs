@@ -0,0 +1,78 @@
+use crate::commands::backup::format_backup_date;
+use crate::exclude_file::ensure_exclude_file;
+use crate::git;
+use crate::json::Json;
+use crate::meta::{self, EntryMeta};
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// Show the most recently added layered entries, newest first, with the
+/// command that added them and any note.
+pub fn run(limit: usize, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let layered: Vec<String> = exclude
+        .entries()
+        .into_iter()
+        .chain(exclude.disabled_entries())
+        .map(|e| e.value)
+        .collect();
+
+    let metas = meta::load(&ctx.exclude_path);
+    let mut rows: Vec<(&String, &EntryMeta)> = metas
+        .iter()
+        .filter(|(entry, _)| layered.contains(entry))
+        .collect();
+    rows.sort_by(|a, b| b.1.added.cmp(&a.1.added));
+    rows.truncate(limit);
+    let code = if rows.is_empty() { 2 } else { 0 };
+
+    if format.is_json() {
+        let items = rows
+            .iter()
+            .map(|(entry, meta)| {
+                let mut fields = vec![
+                    ("entry", Json::String(entry.to_string())),
+                    ("added", Json::String(meta.added.clone())),
+                    ("origin", Json::String(meta.origin.clone())),
+                ];
+                if let Some(source) = &meta.source {
+                    fields.push(("source", Json::String(source.clone())));
+                }
+                if let Some(note) = &meta.note {
+                    fields.push(("note", Json::String(note.clone())));
+                }
+                Json::object(fields)
+            })
+            .collect();
+        outln!("{}", Json::Array(items).pretty());
+        return Ok(code);
+    }
+
+    if format.is_porcelain() {
+        for (entry, meta) in &rows {
+            ui::porcelain(&[&meta.added, &meta.origin, entry, meta.note.as_deref().unwrap_or("")]);
+        }
+        return Ok(code);
+    }
+
+    if rows.is_empty() {
+        outln!("No recorded additions yet. Entries added from now on will show up here.");
+        return Ok(2);
+    }
+
+    let width = rows.iter().map(|(entry, _)| entry.len()).max().unwrap_or(10);
+    for (entry, meta) in &rows {
+        let note = meta.note.as_deref().map(|n| format!("  {n}")).unwrap_or_default();
+        let source = meta.source.as_deref().map(|s| format!(" from {s}")).unwrap_or_default();
+        outln!(
+            "  {} {:<width$}  {}{}",
+            ui::layered(),
+            entry,
+            ui::dim_text(&format!("{} by {}{source}", format_backup_date(&Some(meta.added.clone())), meta.origin)),
+            note
+        );
+    }
+    Ok(0)
+}
@@ -0,0 +1,179 @@
+use crate::acks;
+use crate::discovery::{self, Filter};
+use crate::commands::status::entry_matches_path;
+use crate::config::{self, Origin, Value};
+use crate::exclude_file::ensure_exclude_file;
+use crate::git;
+use crate::journal;
+use crate::json::Json;
+use crate::session;
+use crate::ui;
+use crate::ui::outln;
+use crate::wsl;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// State files layer may keep in the git dir, listed by name only.
+const STATE_FILES: &[&str] = &[
+    "LAYER_JOURNAL",
+    "LAYER_ACKS",
+    "LAYER_SESSIONS",
+    "LAYER_STALE",
+    "LAYER_EDIT",
+    "LAYER_EXPOSED",
+    "LAYER_STATUS_CACHE",
+    "LAYER_RENDER_CACHE",
+];
+
+/// Write a diagnostic bundle for bug reports: versions, effective config,
+/// how long each phase of a status run takes, and counts. No paths or entry
+/// names are included, and nothing is sent anywhere.
+pub fn run(output: &Path) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let started = Instant::now();
+    let mut timings: Vec<(&str, Json)> = Vec::new();
+    let mut lap = Instant::now();
+    let mut time_phase = |name: &'static str, timings: &mut Vec<(&str, Json)>| {
+        timings.push((name, Json::Number(elapsed_ms(lap))));
+        lap = Instant::now();
+    };
+
+    let config = config::load(Some(&ctx.root))?;
+    time_phase("load_config", &mut timings);
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let entries: Vec<String> = exclude.entries().into_iter().map(|e| e.value).collect();
+    time_phase("read_exclude", &mut timings);
+    let tracked = git::list_tracked(&ctx.root)?;
+    time_phase("list_tracked", &mut timings);
+    let pattern_index = git::build_pattern_match_index(&ctx.root, &ctx.exclude_path, &tracked)?;
+    time_phase("pattern_index", &mut timings);
+    let discoveries = discovery::known_files(&ctx, &exclude.entry_set(), &tracked, Filter::All)?;
+    time_phase("discovery", &mut timings);
+    timings.push(("total", Json::Number(elapsed_ms(started))));
+
+    let exposed = entries
+        .iter()
+        .filter(|entry| match pattern_index.get(entry.as_str()) {
+            Some(summary) if git::contains_glob(entry) => summary.tracked_count() > 0,
+            _ => tracked.iter().any(|path| entry_matches_path(entry, path)),
+        })
+        .count();
+    let kind_count = |pred: fn(&str) -> bool| Json::Number(entries.iter().filter(|e| pred(e)).count() as f64);
+    let pending = discoveries.iter().filter(|d| !d.already_excluded && !d.is_gitignored);
+    let count = |n: usize| Json::Number(n as f64);
+    let counts = Json::object(vec![
+        ("entries", count(entries.len())),
+        ("files", kind_count(|e| !e.ends_with('/') && !git::contains_glob(e))),
+        ("directories", kind_count(|e| e.ends_with('/'))),
+        ("globs", kind_count(git::contains_glob)),
+        ("disabled", count(exclude.disabled_entries().len())),
+        ("manual", count(exclude.user_entries().len())),
+        ("exposed", count(exposed)),
+        ("discovered", count(pending.clone().filter(|d| !d.is_tracked).count())),
+        ("tracked_context_files", count(pending.filter(|d| d.is_tracked).count())),
+        ("tracked_files", count(tracked.len())),
+        ("acked", count(acks::load(&ctx.git_dir).len())),
+        ("session_entries", count(session::list(&ctx.git_dir).len())),
+        ("journal_records", count(journal::read(&ctx.git_dir).map(|r| r.len()).unwrap_or(0))),
+    ]);
+
+    let config_values = config
+        .iter()
+        .map(|(key, value, origin)| {
+            let mut fields = vec![("key", Json::String(key.to_string())), ("origin", Json::String(origin_kind(origin)))];
+            fields.extend(report_value(key, value));
+            Json::object(fields)
+        })
+        .collect();
+
+    let git_version = git::git_stdout(&["--version"], None).map(|v| v.trim().to_string()).unwrap_or_default();
+    let versions = Json::object(vec![
+        ("layer", Json::String(env!("CARGO_PKG_VERSION").to_string())),
+        ("git", Json::String(git_version)),
+        ("os", Json::String(std::env::consts::OS.to_string())),
+        ("arch", Json::String(std::env::consts::ARCH.to_string())),
+        ("wsl", Json::Bool(wsl::is_wsl())),
+    ]);
+
+    let state_files = STATE_FILES.iter().filter(|name| ctx.git_dir.join(name).exists()).map(|name| name.to_string());
+    let report = Json::object(vec![
+        ("generated", Json::String(OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default())),
+        ("versions", versions),
+        ("config", Json::Array(config_values)),
+        ("status_timing_ms", Json::object(timings)),
+        ("counts", counts),
+        ("state_files", Json::strings(state_files)),
+    ]);
+
+    fs::write(output, format!("{}\n", report.pretty()))
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    outln!("  {} Wrote diagnostic report to {}", ui::ok(), output.display());
+    outln!(
+        "  {}",
+        ui::dim_text("It has versions, config, timings, and counts — no paths or entry names. Review it, then attach it to your issue.")
+    );
+    Ok(0)
+}
+
+fn elapsed_ms(since: Instant) -> f64 {
+    (since.elapsed().as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+/// What the report says about a config value. Booleans, numbers, and
+/// choices from a fixed set are safe to share; free-form strings such as
+/// `backup.dir` or `backup.machine` are left out, and lists such as
+/// `scan.patterns` are reduced to how many items they have.
+fn report_value(key: &str, value: &Value) -> Option<(&'static str, Json)> {
+    match value {
+        Value::Bool(b) => Some(("value", Json::Bool(*b))),
+        Value::Int(i) => Some(("value", Json::Number(*i as f64))),
+        Value::Str(s) if config::key_spec(key).is_some_and(|spec| spec.choices.is_some()) => {
+            Some(("value", Json::String(s.clone())))
+        }
+        Value::Str(_) => None,
+        Value::List(items) => Some(("items", Json::Number(items.len() as f64))),
+    }
+}
+
+/// Where a config value came from, without the path of the file.
+fn origin_kind(origin: &Origin) -> String {
+    match origin {
+        Origin::Default => "default".to_string(),
+        Origin::System(_) => "system".to_string(),
+        Origin::User(_) => "user".to_string(),
+        Origin::Repo(_) => "repo".to_string(),
+        Origin::Env(var) => format!("env: {var}"),
+        Origin::Flag => "flag".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn origin_kind_drops_paths() {
+        assert_eq!(origin_kind(&Origin::User(PathBuf::from("/home/me/.config/layer/config.toml"))), "user");
+        assert_eq!(origin_kind(&Origin::Env("LAYER_SCAN_DEPTH".to_string())), "env: LAYER_SCAN_DEPTH");
+    }
+
+    #[test]
+    fn report_value_leaves_out_free_form_strings() {
+        assert!(report_value("backup.dir", &Value::Str("/home/me/backups".to_string())).is_none());
+        assert!(report_value("backup.machine", &Value::Str("me-laptop".to_string())).is_none());
+        assert_eq!(
+            report_value("exclude.order", &Value::Str("tool".to_string())),
+            Some(("value", Json::String("tool".to_string())))
+        );
+        assert_eq!(
+            report_value("scan.patterns", &Value::List(vec!["NOTES.md".to_string()])),
+            Some(("items", Json::Number(1.0)))
+        );
+        assert_eq!(report_value("scan.depth", &Value::Int(2)), Some(("value", Json::Number(2.0))));
+    }
+}
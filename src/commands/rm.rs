@@ -1,6 +1,7 @@
 use crate::exclude_file::ensure_exclude_file_for_write;
 use crate::git;
 use crate::ui;
+use crate::ui::outln;
 use anyhow::Result;
 use dialoguer::MultiSelect;
 use std::collections::HashSet;
@@ -11,7 +12,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
     let entries = exclude.entries();
 
     if entries.is_empty() {
-        println!("No layered entries to remove.");
+        outln!("No layered entries to remove.");
         return Ok(2);
     }
 
@@ -19,7 +20,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
         ui::require_tty("interactive mode requires a TTY. Use 'layer rm <files...>' instead")?;
 
         let items: Vec<String> = entries.iter().map(|e| e.value.clone()).collect();
-        println!("{}", ui::heading("Select entries to remove"));
+        outln!("{}", ui::heading("Select entries to remove"));
         let theme = ui::layer_theme();
         ui::print_select_hint();
         let selections = MultiSelect::with_theme(&theme)
@@ -32,7 +33,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
         };
 
         if selected.is_empty() {
-            println!("No entries selected.");
+            outln!("No entries selected.");
             return Ok(2);
         }
 
@@ -43,7 +44,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
         if dry_run {
             for item in &targets {
-                println!("  {} Would remove '{item}'", ui::info());
+                outln!("  {} Would remove '{item}'", ui::info());
             }
             ui::print_dry_run_notice();
             return Ok(0);
@@ -56,7 +57,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
         exclude.write(&ctx.exclude_path)?;
         for item in removed {
-            println!("  {} Removed '{item}'", ui::ok());
+            outln!("  {} Removed '{item}'", ui::ok());
         }
         return Ok(0);
     }
@@ -70,7 +71,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
     for target in &targets {
         if !current.contains(target) {
-            println!("  '{target}' is not layered");
+            outln!("  '{target}' is not layered");
         }
     }
 
@@ -84,7 +85,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
     if dry_run {
         for target in &found {
-            println!("  {} Would remove '{target}'", ui::info());
+            outln!("  {} Would remove '{target}'", ui::info());
         }
         ui::print_dry_run_notice();
         return Ok(0);
@@ -92,7 +93,7 @@ pub fn run(files: Vec<String>, dry_run: bool) -> Result<i32> {
 
     let removed = exclude.remove_exact(&found);
     for item in &removed {
-        println!("  {} Removed '{item}'", ui::ok());
+        outln!("  {} Removed '{item}'", ui::ok());
     }
 
     exclude.write(&ctx.exclude_path)?;
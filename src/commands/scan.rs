@@ -1,35 +1,51 @@
-use crate::commands::add;
-use crate::exclude_file::{ensure_exclude_file_for_write, normalize_entry};
+use crate::commands::{add, global};
+use crate::discovery::{self, Discovery, Filter, Found};
+use crate::exclude_file::{ensure_exclude_file, ensure_exclude_file_for_write, ExcludeFile};
 use crate::git;
 use crate::git::RepoContext;
-use crate::patterns::{PatternCategory, KNOWN_SCAN_PATTERNS};
+use crate::json::Json;
+use crate::patterns::{PatternCategory, SECRETS_NOTE};
 use crate::ui;
-use anyhow::{anyhow, Result};
-use dialoguer::MultiSelect;
-use std::collections::HashSet;
-use std::path::Path;
-use walkdir::WalkDir;
-
-#[derive(Debug, Clone)]
-pub struct AiDiscovery {
-    pub path: String,
-    pub label: String,
-    pub category: PatternCategory,
-    pub already_excluded: bool,
-    pub is_gitignored: bool,
-    pub is_tracked: bool,
-}
-
-pub fn run() -> Result<i32> {
+use crate::ui::outln;
+use anyhow::{anyhow, bail, Result};
+use dialoguer::{Confirm, MultiSelect};
+
+/// `layer scan`; with `tool`, only that tool's patterns are looked for. `yes`
+/// layers everything new without prompting, for bootstrap scripts.
+pub fn run(
+    fix_tracked: bool,
+    untracked_all: bool,
+    tool: Option<&str>,
+    yes: bool,
+    format: ui::Format,
+) -> Result<i32> {
     let ctx = git::ensure_repo()?;
+    if format != ui::Format::Pretty {
+        if fix_tracked {
+            bail!("--fix-tracked prompts and writes; it can't be combined with --format json or porcelain");
+        }
+        if untracked_all {
+            bail!("--untracked-all opens a picker; it can't be combined with --format json or porcelain");
+        }
+        if yes {
+            bail!("--yes layers files; it can't be combined with --format json or porcelain");
+        }
+        return run_report(&ctx, tool, format);
+    }
+
     let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
     let excluded = exclude.entry_set();
 
-    println!("{}", ui::heading("Scanning for context files..."));
-    let found = discover_known_files(&ctx, &excluded)?;
+    outln!("{}", ui::heading("Scanning for context files..."));
+    let tracked_files = git::list_tracked(&ctx.root)?;
+    let options = discovery::Options { filter: Filter::All, untracked: untracked_all, tool };
+    let found = discovery::discover(&ctx, &excluded, &tracked_files, options)?;
 
-    if found.is_empty() {
-        println!("No context files found in this repository.");
+    if found.known.is_empty() && !untracked_all {
+        match tool {
+            Some(tool) => outln!("No {tool} files found in this repository."),
+            None => outln!("No context files found in this repository."),
+        }
         return Ok(2);
     }
 
@@ -38,13 +54,16 @@ pub fn run() -> Result<i32> {
     let mut already_gitignored = Vec::new();
     let mut tracked = Vec::new();
 
-    for item in found {
+    for item in found.known.iter().cloned() {
         if item.already_excluded {
             already_excluded.push(item);
         } else if item.is_gitignored {
             already_gitignored.push(item);
         } else if item.is_tracked {
-            tracked.push(item);
+            // A committed agent artifact is most likely there on purpose.
+            if !item.category.is_optional() {
+                tracked.push(item);
+            }
         } else {
             selectable.push(item);
         }
@@ -54,10 +73,10 @@ pub fn run() -> Result<i32> {
     let mut has_section = false;
 
     if !tracked.is_empty() {
-        println!("  {} Exposed ({}) — tracked files can't be hidden by layering:", ui::exposed(), tracked.len());
+        outln!("  {} Exposed ({}) — tracked files can't be hidden by layering:", ui::exposed(), tracked.len());
         for item in &tracked {
-            println!("    {} {} ({})", ui::exposed(), item.path, item.label);
-            println!(
+            outln!("    {} {} ({})", ui::exposed(), item.path, item.label);
+            outln!(
                 "      {}",
                 ui::warn_text(&format!(
                     "git rm --cached {}",
@@ -65,57 +84,120 @@ pub fn run() -> Result<i32> {
                 ))
             );
         }
+        print_secrets_note(&tracked);
         has_section = true;
     }
 
     if !already_excluded.is_empty() {
-        if has_section { println!(); }
-        println!("  {} Already layered:", ui::layered());
+        if has_section { outln!(); }
+        outln!("  {} Already layered:", ui::layered());
         for item in &already_excluded {
-            println!("    {} {}", ui::layered(), ui::dim_text(&item.path));
+            outln!("    {} {}", ui::layered(), ui::dim_text(&item.path));
         }
         has_section = true;
     }
 
     if !already_gitignored.is_empty() {
-        if has_section { println!(); }
-        println!("  {} Already ignored by Git:", ui::info());
+        if has_section { outln!(); }
+        outln!("  {} Already ignored by Git:", ui::info());
         for item in &already_gitignored {
-            println!("    {} {}", ui::info(), ui::dim_text(&item.path));
+            outln!("    {} {}", ui::info(), ui::dim_text(&item.path));
         }
     }
 
-    if selectable.is_empty() {
-        println!();
-        println!("No new context files found.");
-        return Ok(2);
+    // Tracked files already in the exclude file are exposed too.
+    let to_untrack: Vec<&Discovery> = tracked
+        .iter()
+        .chain(already_excluded.iter().filter(|item| item.is_tracked))
+        .collect();
+    let mut fixed = 0;
+    if !to_untrack.is_empty() && (fix_tracked || (!yes && confirm_fix_tracked(to_untrack.len())?)) {
+        outln!();
+        fixed = fix_tracked_files(&ctx, &mut exclude, &to_untrack)?;
+    }
+
+    let (os_files, selectable): (Vec<Discovery>, Vec<Discovery>) =
+        selectable.into_iter().partition(|item| item.category == PatternCategory::OsFiles);
+    let mut globally_ignored = false;
+    if !os_files.is_empty() {
+        outln!();
+        globally_ignored = offer_global_ignore(&os_files, !yes)?;
+    }
+
+    if untracked_all {
+        return pick_with_untracked(&ctx, &mut exclude, &found, selectable.len());
+    }
+
+    let (artifacts, selectable): (Vec<Discovery>, Vec<Discovery>) =
+        selectable.into_iter().partition(|item| item.category.is_optional());
+
+    if selectable.is_empty() && (artifacts.is_empty() || !ui::is_stdout_tty() || yes) {
+        outln!();
+        outln!("No new context files found.");
+        if !artifacts.is_empty() {
+            print_artifacts(&artifacts);
+        }
+        return Ok(if fixed > 0 || globally_ignored { 0 } else { 2 });
+    }
+
+    // Discovery lists categories in priority order; name them once there's more than one.
+    let grouped = selectable.iter().any(|item| item.category != selectable[0].category);
+
+    if yes {
+        outln!();
+        outln!("  {} Discovered ({}):", ui::discovered(), selectable.len());
+        print_secrets_note(&selectable);
+        let paths: Vec<String> = selectable.iter().map(|item| item.path.clone()).collect();
+        let summary = add::apply_add_entries(&ctx, &mut exclude, &paths, false)?;
+        if !artifacts.is_empty() {
+            print_artifacts(&artifacts);
+        }
+        return Ok(if summary.added == 0 { 2 } else { 0 });
     }
 
     if !ui::is_stdout_tty() {
         // Non-TTY: list discovered files and exit
-        println!();
-        println!("  {} Discovered ({}):", ui::discovered(), selectable.len());
-        for item in &selectable {
-            println!("    {} {} ({})", ui::discovered(), item.path, item.label);
+        outln!();
+        outln!("  {} Discovered ({}):", ui::discovered(), selectable.len());
+        for (idx, item) in selectable.iter().enumerate() {
+            if grouped && (idx == 0 || selectable[idx - 1].category != item.category) {
+                outln!("    {}", ui::dim_text(item.category.title()));
+            }
+            outln!("    {} {} ({})", secret_marker(item), item.path, item.label);
+        }
+        print_secrets_note(&selectable);
+        if !artifacts.is_empty() {
+            print_artifacts(&artifacts);
         }
         return Err(anyhow!(
             "interactive mode requires a TTY. Run in a terminal to select files"
         ));
     }
 
-    // Interactive: multiselect IS the discovery UI
+    // Interactive: multiselect IS the discovery UI. Agent artifacts come last, unticked.
+    let defaults: Vec<bool> = selectable.iter().map(|_| true).chain(artifacts.iter().map(|_| false)).collect();
+    let selectable: Vec<Discovery> = selectable.into_iter().chain(artifacts).collect();
     let items: Vec<String> = selectable
         .iter()
-        .map(|item| format!("{} {}", item.path, ui::dim_text(&format!("({})", item.label))))
+        .map(|item| {
+            let tag = if item.category.is_optional() {
+                format!("{} · optional", item.label)
+            } else if grouped {
+                format!("{} · {}", item.label, item.category.title())
+            } else {
+                item.label.clone()
+            };
+            format!("{} {}", item.path, ui::dim_text(&format!("({tag}) — {}", item.description)))
+        })
         .collect();
-    let defaults = vec![true; items.len()];
 
-    println!(
+    outln!(
         "  {} Discovered {} context {} — select for your local layer",
         ui::discovered(),
         selectable.len(),
         if selectable.len() == 1 { "file" } else { "files" }
     );
+    print_secrets_note(&selectable);
     let theme = ui::layer_theme();
     ui::print_select_hint();
     let selections = MultiSelect::with_theme(&theme)
@@ -126,7 +208,7 @@ pub fn run() -> Result<i32> {
 
     let selected = selections.unwrap_or_default();
     if selected.is_empty() {
-        println!("No files selected. You can add files later with {}.", ui::brand("layer add"));
+        outln!("No files selected. You can add files later with {}.", ui::brand("layer add"));
         return Ok(2);
     }
 
@@ -143,265 +225,159 @@ pub fn run() -> Result<i32> {
     Ok(0)
 }
 
-pub fn discover_known_files(ctx: &RepoContext, excluded: &HashSet<String>) -> Result<Vec<AiDiscovery>> {
-    let tracked = git::list_tracked(&ctx.root)?;
-    discover_known_files_with_tracked(ctx, excluded, &tracked)
+/// Secrets are marked like exposed files: leaking one costs more than a stray
+/// context file.
+fn secret_marker(item: &Discovery) -> String {
+    if item.category == PatternCategory::Secrets {
+        ui::exposed()
+    } else {
+        ui::discovered()
+    }
 }
 
-pub fn discover_known_files_with_tracked(
-    ctx: &RepoContext,
-    excluded: &HashSet<String>,
-    tracked: &HashSet<String>,
-) -> Result<Vec<AiDiscovery>> {
-    let mut seen = HashSet::new();
-
-    // First pass: collect all candidate paths with their pattern metadata.
-    struct Candidate {
-        normalized: String,
-        label: String,
-        category: PatternCategory,
+/// Leftovers from agent sessions, listed apart from context files since
+/// layering them is a judgment call.
+fn print_artifacts(items: &[Discovery]) {
+    outln!();
+    outln!("  {} Agent artifacts ({}) — optional, layer any you don't mean to commit:", ui::info(), items.len());
+    for item in items {
+        outln!("    {} {} {}", ui::info(), item.path, ui::dim_text(&format!("— {}", item.description)));
     }
-    let mut candidates = Vec::new();
-    let mut check_ignore_paths = Vec::new();
-
-    for pattern in KNOWN_SCAN_PATTERNS {
-        for path in resolve_pattern_paths(&ctx.root, pattern.entry)? {
-            let normalized = normalize_entry(&path);
-            if normalized.is_empty() || !seen.insert(normalized.clone()) {
-                continue;
-            }
-            let ignore_target = normalized.trim_end_matches('/').to_string();
-            check_ignore_paths.push(ignore_target);
-            candidates.push(Candidate {
-                normalized,
-                label: pattern.label.to_string(),
-                category: pattern.category,
-            });
-        }
-    }
-
-    // Batch check-ignore call instead of per-file.
-    let ignore_results = git::check_ignore_bulk(&ctx.root, &check_ignore_paths, false)?;
+    let paths: Vec<&str> = items.iter().map(|item| item.path.as_str()).collect();
+    outln!("      {}", ui::dim_text(&format!("layer add {}", paths.join(" "))));
+}
 
-    let mut out = Vec::new();
-    debug_assert_eq!(candidates.len(), check_ignore_paths.len());
-    for (candidate, ignore_target) in candidates.into_iter().zip(check_ignore_paths.iter()) {
-        let tracked_match = if candidate.normalized.ends_with('/') {
-            tracked.iter().any(|p| p.starts_with(&candidate.normalized))
-        } else {
-            tracked.contains(&candidate.normalized)
-        };
-
-        let is_gitignored = ignore_results.contains_key(ignore_target);
-
-        out.push(AiDiscovery {
-            path: candidate.normalized.clone(),
-            label: candidate.label,
-            category: candidate.category,
-            already_excluded: excluded.contains(&candidate.normalized),
-            is_gitignored,
-            is_tracked: tracked_match,
-        });
+fn print_secrets_note(items: &[Discovery]) {
+    if items.iter().any(|item| item.category == PatternCategory::Secrets) {
+        outln!("  {} {}", ui::exposed(), ui::warn_text(SECRETS_NOTE));
     }
+}
 
-    // Second pass: for directory candidates not yet ignored, check if all
-    // files inside are already covered by ignore rules (e.g. global gitignore
-    // covers every file individually).
-    let dir_indices: Vec<usize> = out
+/// OS files turn up in every repo, so point them at the global gitignore
+/// instead of offering to layer them here; `prompt` asks whether to add them
+/// there. Returns whether they went there.
+fn offer_global_ignore(items: &[Discovery], prompt: bool) -> Result<bool> {
+    let mut names: Vec<String> = items
         .iter()
-        .enumerate()
-        .filter(|(_, item)| {
-            item.path.ends_with('/') && !item.already_excluded && !item.is_gitignored
-        })
-        .map(|(i, _)| i)
+        .map(|item| item.path.rsplit('/').next().unwrap_or(&item.path).to_string())
         .collect();
-
-    for idx in dir_indices {
-        let dir_path = ctx.root.join(out[idx].path.trim_end_matches('/'));
-        if !dir_path.is_dir() {
-            continue;
-        }
-
-        let mut files_in_dir = Vec::new();
-        for entry in WalkDir::new(&dir_path).min_depth(1) {
-            let entry = match entry {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-            if entry.file_type().is_file() {
-                if let Ok(rel) = entry.path().strip_prefix(&ctx.root) {
-                    files_in_dir.push(rel.to_string_lossy().replace('\\', "/"));
-                }
-            }
-        }
-
-        if files_in_dir.is_empty() {
-            out[idx].is_gitignored = true;
-            continue;
-        }
-
-        let file_ignore_results = git::check_ignore_bulk(&ctx.root, &files_in_dir, false)?;
-        if files_in_dir.iter().all(|f| file_ignore_results.contains_key(f)) {
-            out[idx].is_gitignored = true;
-        }
+    names.sort();
+    names.dedup();
+    outln!("  {} OS files ({}) — these belong in your global gitignore:", ui::info(), items.len());
+    for item in items {
+        outln!("    {} {}", ui::info(), ui::dim_text(&item.path));
     }
-
-    Ok(out)
-}
-
-pub fn resolve_pattern_paths(repo_root: &Path, pattern: &str) -> Result<Vec<String>> {
-    let discovered = discover_paths(repo_root);
-    let mut matches = Vec::new();
-
-    for item in discovered {
-        if pattern_matches_path(pattern, &item) {
-            matches.push(item.display);
-        }
+    let command = format!("layer global add {}", names.join(" "));
+    let confirmed = prompt
+        && ui::is_stdout_tty()
+        && Confirm::new()
+            .with_prompt(format!("Add {} to your global gitignore?", names.join(", ")))
+            .default(true)
+            .interact()?;
+    if !confirmed {
+        outln!("      {}", ui::dim_text(&command));
+        return Ok(false);
     }
-
-    Ok(matches)
+    Ok(global::add(names)? == 0)
 }
 
-#[derive(Debug, Clone)]
-struct DiscoveredPath {
-    display: String,
-    match_path: String,
-    depth: usize,
-    is_dir: bool,
-}
-
-fn discover_paths(repo_root: &Path) -> Vec<DiscoveredPath> {
-    let mut out = Vec::new();
-
-    // AI and config files live at the repo root or known subdirs like .github/.
-    for entry in WalkDir::new(repo_root).min_depth(1).max_depth(2) {
-        let entry = match entry {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        let path = entry.path();
-        if path
-            .components()
-            .any(|c| c.as_os_str().to_string_lossy() == ".git")
-        {
-            continue;
-        }
-
-        let rel = match path.strip_prefix(repo_root) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        let mut rel_str = rel.to_string_lossy().replace('\\', "/");
-        let is_dir = entry.file_type().is_dir();
-        if is_dir && !rel_str.ends_with('/') {
-            rel_str.push('/');
-        }
-        let depth = rel.components().count();
-
-        out.push(DiscoveredPath {
-            display: rel_str.clone(),
-            match_path: rel_str.trim_end_matches('/').to_string(),
-            depth,
-            is_dir,
-        });
+/// `--untracked-all`: discovered files, tagged with their tool, and every
+/// other untracked file in one tree picker, like `layer add -i`.
+fn pick_with_untracked(ctx: &RepoContext, exclude: &mut ExcludeFile, found: &Found, known: usize) -> Result<i32> {
+    let candidates = found.candidates();
+    outln!();
+    if candidates.is_empty() {
+        outln!("No new context files or untracked files found.");
+        return Ok(2);
     }
-
-    out
+    ui::require_tty("interactive mode requires a TTY. Run in a terminal to select files")?;
+    outln!(
+        "  {} {} context {} and {} other untracked — select for your local layer",
+        ui::discovered(),
+        known,
+        if known == 1 { "file" } else { "files" },
+        found.untracked.len()
+    );
+    add::pick_candidates(ctx, exclude, candidates, false)
 }
 
-fn pattern_matches_path(pattern: &str, item: &DiscoveredPath) -> bool {
-    let pattern_trimmed = pattern.trim_end_matches('/');
-    let wants_dir = pattern.ends_with('/');
-
-    if wants_dir && !item.is_dir {
-        return false;
-    }
-    if !pattern.contains('/') && item.depth != 1 {
-        return false;
-    }
-
-    if git::contains_glob(pattern_trimmed) {
-        if pattern.contains('/') {
-            return wildcard_match(pattern_trimmed, &item.match_path);
+/// Discovery results for scripts and agents; never prompts or writes.
+fn run_report(ctx: &RepoContext, tool: Option<&str>, format: ui::Format) -> Result<i32> {
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+    let options = discovery::Options { tool, ..discovery::Options::default() };
+    let found = discovery::discover(ctx, &exclude.entry_set(), &tracked, options)?.known;
+    if format.is_porcelain() {
+        for item in &found {
+            ui::porcelain(&[porcelain_state(item), &item.path, &item.label]);
         }
-        return wildcard_match(pattern_trimmed, item.match_path.rsplit('/').next().unwrap_or(""));
-    }
-
-    if pattern.contains('/') {
-        return item.match_path == pattern_trimmed;
+        return Ok(if found.is_empty() { 2 } else { 0 });
     }
-
-    item.match_path.rsplit('/').next().unwrap_or("") == pattern_trimmed
+    let items: Vec<Json> = found
+        .iter()
+        .map(|item| {
+            Json::object(vec![
+                ("path", Json::String(item.path.clone())),
+                ("label", Json::String(item.label.clone())),
+                ("category", Json::String(item.category.as_str().to_string())),
+                ("description", Json::String(item.description.to_string())),
+                ("already_excluded", Json::Bool(item.already_excluded)),
+                ("is_tracked", Json::Bool(item.is_tracked)),
+                ("is_gitignored", Json::Bool(item.is_gitignored)),
+            ])
+        })
+        .collect();
+    let empty = items.is_empty();
+    outln!("{}", Json::Array(items).pretty());
+    Ok(if empty { 2 } else { 0 })
 }
 
-fn wildcard_match(pattern: &str, text: &str) -> bool {
-    let p = pattern.as_bytes();
-    let t = text.as_bytes();
-    let (mut pi, mut ti) = (0usize, 0usize);
-    let mut star_idx = None;
-    let mut match_idx = 0usize;
-
-    while ti < t.len() {
-        if pi < p.len() && (p[pi] == b'?' || p[pi] == t[ti]) {
-            pi += 1;
-            ti += 1;
-        } else if pi < p.len() && p[pi] == b'*' {
-            star_idx = Some(pi);
-            pi += 1;
-            match_idx = ti;
-        } else if let Some(star) = star_idx {
-            pi = star + 1;
-            match_idx += 1;
-            ti = match_idx;
-        } else {
-            return false;
-        }
-    }
-
-    while pi < p.len() && p[pi] == b'*' {
-        pi += 1;
+/// The one state a porcelain line reports; tracked wins since it needs action.
+fn porcelain_state(item: &Discovery) -> &'static str {
+    if item.is_tracked {
+        "tracked"
+    } else if item.already_excluded {
+        "layered"
+    } else if item.is_gitignored {
+        "ignored"
+    } else {
+        "new"
     }
-
-    pi == p.len()
 }
 
-// Simple wildcard matcher for scanning known patterns against discovered paths.
-// This intentionally doesn't delegate to git check-ignore because the patterns
-// are controlled by us (KNOWN_SCAN_PATTERNS), not user input, and are simple
-// enough that this matcher handles them correctly.
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn wildcard_exact_match() {
-        assert!(wildcard_match("CLAUDE.md", "CLAUDE.md"));
-        assert!(!wildcard_match("CLAUDE.md", "claude.md"));
-    }
-
-    #[test]
-    fn wildcard_star() {
-        assert!(wildcard_match(".aider*", ".aider"));
-        assert!(wildcard_match(".aider*", ".aider.conf.yml"));
-        assert!(wildcard_match(".env.*", ".env.local"));
-        assert!(wildcard_match(".env.*", ".env.production"));
-        assert!(!wildcard_match(".env.*", ".env"));
-    }
-
-    #[test]
-    fn wildcard_question_mark() {
-        assert!(wildcard_match("file?.txt", "file1.txt"));
-        assert!(!wildcard_match("file?.txt", "file12.txt"));
+fn confirm_fix_tracked(count: usize) -> Result<bool> {
+    if !ui::is_stdout_tty() {
+        return Ok(false);
     }
+    outln!();
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "Untrack {count} tracked context {} with git rm --cached and layer {}?",
+            if count == 1 { "file" } else { "files" },
+            if count == 1 { "it" } else { "them" }
+        ))
+        .default(false)
+        .interact()?;
+    Ok(confirmed)
+}
 
-    #[test]
-    fn wildcard_empty_strings() {
-        assert!(wildcard_match("", ""));
-        assert!(!wildcard_match("a", ""));
-        assert!(wildcard_match("*", ""));
-        assert!(wildcard_match("*", "anything"));
+/// Untrack each item (keeping it on disk) and layer it. Returns how many
+/// were untracked.
+fn fix_tracked_files(
+    ctx: &RepoContext,
+    exclude: &mut ExcludeFile,
+    items: &[&Discovery],
+) -> Result<usize> {
+    let mut paths = Vec::new();
+    for item in items {
+        git::untrack(&ctx.root, item.path.trim_end_matches('/'))?;
+        outln!("  {} Untracked '{}'", ui::ok(), item.path);
+        paths.push(item.path.clone());
     }
+    add::apply_add_entries(ctx, exclude, &paths, false)?;
+    outln!(
+        "  {}",
+        ui::dim_text("Files stay on disk. Commit to remove them from the repository.")
+    );
+    Ok(paths.len())
 }
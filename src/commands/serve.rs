@@ -0,0 +1,393 @@
+use crate::commands::{add, status};
+use crate::discovery::{self, Filter};
+use crate::exclude_file::{ensure_exclude_file, ensure_exclude_file_for_write, normalize_entry, ExcludeFile};
+use crate::git;
+use crate::git::RepoContext;
+use crate::json::{self, Json};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How often the watcher thread checks the exclude file for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+const METHODS: &[&str] = &[
+    "initialize",
+    "status",
+    "classify",
+    "add",
+    "rm",
+    "off",
+    "on",
+    "shutdown",
+];
+
+type Client = Arc<Mutex<Box<dyn Write + Send>>>;
+type Clients = Arc<Mutex<Vec<Client>>>;
+
+/// JSON-RPC 2.0 error codes used by the server.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, format!("{err:#}"))
+    }
+}
+
+/// Serve newline-delimited JSON-RPC 2.0 on stdio, or on a unix socket when
+/// `socket` is set. Every client also receives `layer/excludeChanged`
+/// notifications when the exclude file changes on disk.
+pub fn run(socket: Option<PathBuf>) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+    spawn_watcher(ctx.exclude_path.clone(), Arc::clone(&clients));
+
+    match socket {
+        Some(path) => serve_socket(&ctx, &path, &clients),
+        None => {
+            let client: Client = Arc::new(Mutex::new(Box::new(std::io::stdout())));
+            clients.lock().expect("clients lock").push(Arc::clone(&client));
+            serve_connection(&ctx, std::io::stdin().lock(), &client);
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(ctx: &RepoContext, path: &Path, clients: &Clients) -> Result<i32> {
+    use anyhow::Context;
+    use std::os::unix::net::UnixListener;
+
+    if path.exists() {
+        // A previous daemon may have left its socket behind.
+        fs::remove_file(path).with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("failed to listen on {}", path.display()))?;
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let Ok(writer) = stream.try_clone() else { continue };
+        let client: Client = Arc::new(Mutex::new(Box::new(writer)));
+        clients.lock().expect("clients lock").push(Arc::clone(&client));
+
+        let ctx = ctx.clone();
+        let socket_path = path.to_path_buf();
+        thread::spawn(move || {
+            if serve_connection(&ctx, BufReader::new(stream), &client) {
+                let _ = fs::remove_file(&socket_path);
+                std::process::exit(0);
+            }
+        });
+    }
+
+    Ok(0)
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_ctx: &RepoContext, _path: &Path, _clients: &Clients) -> Result<i32> {
+    anyhow::bail!("--socket is only supported on unix; use stdio instead")
+}
+
+/// Handle requests until EOF or `shutdown`. Returns true on `shutdown`.
+fn serve_connection(ctx: &RepoContext, reader: impl BufRead, client: &Client) -> bool {
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, shutdown) = handle_line(ctx, &line);
+        if let Some(response) = response {
+            if send(client, &response).is_err() {
+                break;
+            }
+        }
+        if shutdown {
+            return true;
+        }
+    }
+    false
+}
+
+fn send(client: &Client, message: &Json) -> std::io::Result<()> {
+    let mut writer = client.lock().expect("client lock");
+    writeln!(writer, "{message}")?;
+    writer.flush()
+}
+
+fn spawn_watcher(exclude_path: PathBuf, clients: Clients) {
+    let mut last = fs::read_to_string(&exclude_path).ok();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current = fs::read_to_string(&exclude_path).ok();
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            let notification = Json::object(vec![
+                ("jsonrpc", Json::String("2.0".to_string())),
+                ("method", Json::String("layer/excludeChanged".to_string())),
+                ("params", entries_snapshot(&exclude_path)),
+            ]);
+            // Drop clients whose connection has gone away.
+            clients
+                .lock()
+                .expect("clients lock")
+                .retain(|client| send(client, &notification).is_ok());
+        }
+    });
+}
+
+fn entries_snapshot(exclude_path: &Path) -> Json {
+    match ensure_exclude_file(exclude_path) {
+        Ok(exclude) => Json::object(vec![
+            ("entries", Json::strings(exclude.entries().into_iter().map(|e| e.value))),
+            ("disabled", Json::strings(exclude.disabled_entries().into_iter().map(|e| e.value))),
+        ]),
+        Err(_) => Json::object(vec![]),
+    }
+}
+
+/// Returns the response (None for notifications) and whether to shut down.
+fn handle_line(ctx: &RepoContext, line: &str) -> (Option<Json>, bool) {
+    let request = match json::parse(line) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = RpcError::new(PARSE_ERROR, format!("parse error: {err:#}"));
+            return (Some(error_response(Json::Null, error)), false);
+        }
+    };
+
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Json::as_str) else {
+        let error = RpcError::new(INVALID_REQUEST, "missing method");
+        return (Some(error_response(id.unwrap_or(Json::Null), error)), false);
+    };
+    let params = request.get("params");
+
+    let result = dispatch(ctx, method, params);
+    let shutdown = method == "shutdown";
+    let Some(id) = id else {
+        return (None, shutdown);
+    };
+
+    let response = match result {
+        Ok(result) => Json::object(vec![
+            ("jsonrpc", Json::String("2.0".to_string())),
+            ("id", id),
+            ("result", result),
+        ]),
+        Err(error) => error_response(id, error),
+    };
+    (Some(response), shutdown)
+}
+
+fn error_response(id: Json, error: RpcError) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::String("2.0".to_string())),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(error.code as f64)),
+                ("message", Json::String(error.message)),
+            ]),
+        ),
+    ])
+}
+
+fn dispatch(ctx: &RepoContext, method: &str, params: Option<&Json>) -> Result<Json, RpcError> {
+    match method {
+        "initialize" => Ok(Json::object(vec![
+            ("name", Json::String("layer".to_string())),
+            ("version", Json::String(env!("CARGO_PKG_VERSION").to_string())),
+            ("root", Json::String(ctx.root.display().to_string())),
+            ("methods", Json::strings(METHODS.iter().copied())),
+        ])),
+        "status" => status_result(ctx),
+        "classify" => classify(ctx, &string_list(params, "paths")?.unwrap_or_default()),
+        "add" => add_entries(ctx, &required_list(params, "entries")?),
+        "rm" => remove_entries(ctx, &required_list(params, "entries")?),
+        "off" => toggle(ctx, string_list(params, "entries")?, false),
+        "on" => toggle(ctx, string_list(params, "entries")?, true),
+        "shutdown" => Ok(Json::Null),
+        other => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{other}'"))),
+    }
+}
+
+fn string_list(params: Option<&Json>, key: &str) -> Result<Option<Vec<String>>, RpcError> {
+    let Some(value) = params.and_then(|p| p.get(key)) else {
+        return Ok(None);
+    };
+    let invalid = || RpcError::new(INVALID_PARAMS, format!("'{key}' must be an array of strings"));
+    let items = value.as_array().ok_or_else(invalid)?;
+    items
+        .iter()
+        .map(|item| item.as_str().map(ToOwned::to_owned).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+fn required_list(params: Option<&Json>, key: &str) -> Result<Vec<String>, RpcError> {
+    string_list(params, key)?
+        .filter(|items| !items.is_empty())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("'{key}' is required")))
+}
+
+fn status_result(ctx: &RepoContext) -> Result<Json, RpcError> {
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+    let pattern_index = git::build_pattern_match_index(&ctx.root, &ctx.exclude_path, &tracked)?;
+
+    let mut layered = Vec::new();
+    let mut exposed = Vec::new();
+    for entry in exclude.entries() {
+        status::classify_entry(&ctx.root, &entry.value, &tracked, &pattern_index, &mut layered, &mut exposed);
+    }
+
+    let mut discovered: Vec<String> = discovery::known_files(ctx, &exclude.entry_set(), &tracked, Filter::New)?
+        .into_iter()
+        .map(|item| item.path)
+        .collect();
+    discovered.sort();
+    discovered.dedup();
+
+    Ok(Json::object(vec![
+        ("layered", Json::strings(layered)),
+        ("exposed", Json::strings(exposed.into_iter().map(|(entry, _, _)| entry))),
+        ("disabled", Json::strings(exclude.disabled_entries().into_iter().map(|e| e.value))),
+        ("discovered", Json::strings(discovered)),
+    ]))
+}
+
+/// Badge state for each path: "layered", "exposed", "discovered", or "none".
+fn classify(ctx: &RepoContext, paths: &[String]) -> Result<Json, RpcError> {
+    let normalized: Vec<String> = paths
+        .iter()
+        .map(|p| normalize_entry(p).trim_end_matches('/').to_string())
+        .collect();
+    let tracked = git::list_tracked(&ctx.root)?;
+    let matches = git::check_ignore_bulk(&ctx.root, &normalized, true)?;
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let discovered: HashSet<String> = discovery::known_files(ctx, &exclude.entry_set(), &tracked, Filter::Visible)?
+        .into_iter()
+        .map(|item| item.path.trim_end_matches('/').to_string())
+        .collect();
+
+    let fields = paths
+        .iter()
+        .zip(&normalized)
+        .map(|(raw, path)| {
+            let dir_prefix = format!("{path}/");
+            let is_tracked = tracked.contains(path) || tracked.iter().any(|t| t.starts_with(&dir_prefix));
+            let excluded_locally = matches
+                .get(path)
+                .is_some_and(|hit| git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &hit.source));
+            let state = match (excluded_locally, is_tracked) {
+                (true, true) => "exposed",
+                (true, false) => "layered",
+                _ if discovered.contains(path) => "discovered",
+                _ => "none",
+            };
+            (raw.clone(), Json::String(state.to_string()))
+        })
+        .collect();
+    Ok(Json::Object(fields))
+}
+
+/// Held while a request edits the exclude file, so socket clients served on
+/// separate threads take turns instead of dropping each other's changes.
+static EDIT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Load the exclude file, let `edit` change it, and write it back when `edit`
+/// says it changed, one request at a time. An edit made outside the server in
+/// between fails the request rather than being overwritten.
+fn edit_exclude<T>(
+    ctx: &RepoContext,
+    edit: impl FnOnce(&mut ExcludeFile) -> Result<(T, bool), RpcError>,
+) -> Result<T, RpcError> {
+    let _turn = EDIT_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let loaded = fs::read_to_string(&ctx.exclude_path).unwrap_or_default();
+    let (result, changed) = edit(&mut exclude)?;
+    if changed {
+        exclude.write_if_unchanged(&ctx.exclude_path, &loaded)?;
+    }
+    Ok(result)
+}
+
+fn add_entries(ctx: &RepoContext, entries: &[String]) -> Result<Json, RpcError> {
+    edit_exclude(ctx, |exclude| {
+        let plan = add::plan_add_entries(ctx, exclude, entries)?;
+        add::refuse_tracked(ctx, &plan)?;
+
+        let mut added = Vec::new();
+        let mut skipped = Vec::new();
+        for planned in plan {
+            match planned.outcome.reason() {
+                None => {
+                    exclude.append_entry(&planned.entry);
+                    added.push(planned.entry);
+                }
+                Some(reason) => skipped.push(Json::object(vec![
+                    ("entry", Json::String(planned.input)),
+                    ("reason", Json::String(reason.to_string())),
+                ])),
+            }
+        }
+        let changed = !added.is_empty();
+        let result = Json::object(vec![
+            ("added", Json::strings(added)),
+            ("skipped", Json::Array(skipped)),
+        ]);
+        Ok((result, changed))
+    })
+}
+
+fn remove_entries(ctx: &RepoContext, entries: &[String]) -> Result<Json, RpcError> {
+    edit_exclude(ctx, |exclude| {
+        let targets: HashSet<String> = entries.iter().map(|e| normalize_entry(e)).collect();
+        let removed = exclude.remove_exact(&targets);
+        let changed = !removed.is_empty();
+        Ok((Json::object(vec![("removed", Json::strings(removed))]), changed))
+    })
+}
+
+fn toggle(ctx: &RepoContext, entries: Option<Vec<String>>, enable: bool) -> Result<Json, RpcError> {
+    edit_exclude(ctx, |exclude| {
+        let changed = match (entries, enable) {
+            (Some(list), true) => exclude.enable_entries(&list.iter().map(|e| normalize_entry(e)).collect()),
+            (Some(list), false) => exclude.disable_entries(&list.iter().map(|e| normalize_entry(e)).collect()),
+            (None, true) => exclude.enable_all(),
+            (None, false) => exclude.disable_all(),
+        };
+        let key = if enable { "enabled" } else { "disabled" };
+        let any = !changed.is_empty();
+        Ok((Json::object(vec![(key, Json::strings(changed))]), any))
+    })
+}
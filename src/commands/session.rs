@@ -0,0 +1,42 @@
+use crate::git;
+use crate::session;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// `layer session end`: drop this session's entries now. Meant for a shell
+/// `trap ... EXIT`, so sessions that aren't a pid get cleaned up too.
+pub fn end() -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let current = session::current_id()?;
+    let removed = session::remove_sessions(&ctx, |id| id == current)?;
+    if removed.is_empty() {
+        outln!("No session entries to remove.");
+        return Ok(2);
+    }
+    for entry in &removed {
+        outln!("  {} Removed session entry '{entry}'", ui::ok());
+    }
+    Ok(0)
+}
+
+/// `layer session ls`: session entries in this repo and which session owns each.
+pub fn list() -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let records = session::list(&ctx.git_dir);
+    if records.is_empty() {
+        outln!("No session entries.");
+        return Ok(2);
+    }
+    let current = session::current_id().ok();
+    let width = records.iter().map(|(_, entry)| entry.len()).max().unwrap_or(0);
+    for (id, entry) in &records {
+        let owner = if current.as_deref() == Some(id.as_str()) {
+            "this session".to_string()
+        } else {
+            format!("session {id}")
+        };
+        outln!("  {} {entry:<width$}  {}", ui::layered(), ui::dim_text(&owner), width = width);
+    }
+    Ok(0)
+}
@@ -1,13 +1,38 @@
-use crate::commands::scan;
+use crate::acks;
+use crate::commands::backup::fnv1a;
+use crate::commands::demo;
+use crate::discovery::{self, Filter};
+use crate::config;
 use crate::exclude_file::ensure_exclude_file;
 use crate::git;
 use crate::git::PatternMatchSummary;
+use crate::json::Json;
+use crate::notify;
+use crate::patterns::{enabled_patterns, known_pattern_for, KnownPattern, PatternCategory, SECRETS_NOTE};
+use crate::render_cache;
 use crate::ui;
+use crate::ui::outln;
+use crate::wsl;
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
 
-pub fn run() -> Result<i32> {
+/// Cached `--hook` result, keyed by mtimes of the inputs it depends on.
+const HOOK_CACHE_FILE: &str = "LAYER_STATUS_CACHE";
+
+/// Placeholders `--format '{...}'` can use.
+const TEMPLATE_FIELDS: &[&str] = &["status", "entry", "tool", "matches"];
+
+pub fn run(full: bool, show_acked: bool, short: bool, format: ui::Format) -> Result<i32> {
+    if format == ui::Format::Template {
+        ui::check_template(TEMPLATE_FIELDS)?;
+    }
     let ctx = git::ensure_repo()?;
+    let config = config::load(Some(&ctx.root))?;
+    let max_discovered = config.get_int("status.max_discovered") as usize;
+    let patterns = enabled_patterns(&config);
     let exclude = ensure_exclude_file(&ctx.exclude_path)?;
     let entries = exclude.entries();
     let disabled = exclude.disabled_entries();
@@ -30,74 +55,135 @@ pub fn run() -> Result<i32> {
     }
 
     let excluded_set = exclude.entry_set();
-    let discovered_items = scan::discover_known_files_with_tracked(&ctx, &excluded_set, &tracked)?;
+    let discovered_items = discovery::known_files(&ctx, &excluded_set, &tracked, Filter::All)?;
     let gitignored_count = discovered_items
         .iter()
         .filter(|item| !item.already_excluded && item.is_gitignored)
         .count();
-    let not_excluded: Vec<_> = discovered_items
-        .into_iter()
-        .filter(|item| !item.already_excluded && !item.is_gitignored)
-        .collect();
+    let not_excluded: Vec<_> = discovered_items.into_iter().filter(|item| item.is_visible()).collect();
+    let categories: HashMap<String, PatternCategory> =
+        not_excluded.iter().map(|i| (i.path.clone(), i.category)).collect();
+    // Agent artifacts are optional suggestions, left to `layer scan`.
     let mut discovered: Vec<_> = not_excluded
         .iter()
-        .filter(|i| !i.is_tracked)
+        .filter(|i| !i.is_tracked && !i.category.is_optional())
         .map(|i| i.path.clone())
         .collect();
-    discovered.sort();
+    let artifacts = not_excluded
+        .iter()
+        .filter(|i| !i.is_tracked && i.category.is_optional())
+        .count();
+    discovered.sort_by(|a, b| categories[a].cmp(&categories[b]).then_with(|| a.cmp(b)));
     discovered.dedup();
     let mut tracked_ctx: Vec<_> = not_excluded
         .iter()
-        .filter(|i| i.is_tracked)
+        .filter(|i| i.is_tracked && !i.category.is_optional())
         .map(|i| i.path.clone())
         .collect();
     tracked_ctx.sort();
     tracked_ctx.dedup();
 
+    if format != ui::Format::Pretty && !short {
+        let report = StatusReport {
+            demo: demo::is_active(&ctx),
+            layered: &layered,
+            disabled: &disabled.iter().map(|e| e.value.clone()).collect::<Vec<_>>(),
+            exposed: &exposed,
+            pattern_index: &pattern_index,
+            discovered: &discovered,
+            tracked_ctx: &tracked_ctx,
+            categories: &categories,
+            gitignored: gitignored_count,
+            patterns: &patterns,
+        };
+        if format.is_json() {
+            outln!("{}", report.to_json().pretty());
+        } else if format == ui::Format::Template {
+            report.print_template(&ctx.root);
+        } else {
+            report.print_porcelain();
+        }
+        return Ok(if exposed.is_empty() && tracked_ctx.is_empty() { 0 } else { 1 });
+    }
+
+    // Acknowledged warnings only change what the dashboard shows, not the exit code.
+    let needs_attention = !exposed.is_empty() || !tracked_ctx.is_empty();
+    let acked = if show_acked { Default::default() } else { acks::load(&ctx.git_dir) };
+    let shown_before = exposed.len() + tracked_ctx.len() + discovered.len();
+    exposed.retain(|(entry, _, _)| !acked.contains(&acks::exposed_id(entry)));
+    tracked_ctx.retain(|path| !acked.contains(&acks::tracked_id(path)));
+    discovered.retain(|path| !acked.contains(&acks::discovered_id(path)));
+    let mut hidden = shown_before - (exposed.len() + tracked_ctx.len() + discovered.len());
+
+    if short {
+        outln!("{}", short_line(layered.len(), disabled.len(), exposed.len() + tracked_ctx.len(), discovered.len()));
+        return Ok(if needs_attention { 1 } else { 0 });
+    }
+
+    if demo::is_active(&ctx) {
+        outln!(
+            "  {} Demo mode on — run {} to restore your entries.",
+            ui::disabled(),
+            ui::brand("layer demo off")
+        );
+        outln!();
+    }
+
+    if let Some(warning) = wsl::cross_filesystem_warning(&ctx.root, wsl::is_wsl()) {
+        if acked.contains(acks::WSL_ID) {
+            hidden += 1;
+        } else {
+            ui::print_warning(&warning);
+            outln!();
+        }
+    }
+
     if exposed.is_empty() && discovered.is_empty() && tracked_ctx.is_empty() {
         if layered.is_empty() && gitignored_count == 0 {
-            println!(
+            outln!(
                 "No context files found. Run {} to get started.",
                 ui::brand("layer scan")
             );
         } else if layered.is_empty() {
-            println!(
+            outln!(
                 "  {} All clear — {} already ignored by .gitignore.",
                 ui::ok(),
                 gitignored_count
             );
         } else if gitignored_count > 0 {
-            println!(
+            outln!(
                 "  {} {} files in your local layer. ({} others ignored by .gitignore)",
                 ui::ok(),
                 layered.len(),
                 gitignored_count
             );
         } else {
-            println!(
+            outln!(
                 "  {} {} files in your local layer.",
                 ui::ok(),
                 layered.len()
             );
         }
         if !disabled.is_empty() {
-            println!(
+            outln!(
                 "  {} {} disabled ({})",
                 ui::disabled(),
                 disabled.len(),
                 ui::brand("layer on"),
             );
         }
-        return Ok(0);
+        print_artifacts_hint(artifacts);
+        print_acked_summary(hidden);
+        return Ok(if needs_attention { 1 } else { 0 });
     }
 
     let mut has_section = false;
 
     // Layered section — dim, these are fine
     if !layered.is_empty() {
-        println!("  {} Layered ({}):", ui::layered(), layered.len());
+        outln!("  {} Layered ({}):", ui::layered(), layered.len());
         for entry in &layered {
-            println!("    {}", ui::dim_text(entry));
+            outln!("    {}", ui::dim_text(entry));
         }
         has_section = true;
     }
@@ -105,33 +191,33 @@ pub fn run() -> Result<i32> {
     // Disabled section — temporarily turned off entries
     if !disabled.is_empty() {
         if has_section {
-            println!();
+            outln!();
         }
-        println!(
+        outln!(
             "  {} Disabled ({}):",
             ui::disabled(),
             disabled.len()
         );
         for entry in &disabled {
-            println!("    {}", ui::dim_text(&entry.value));
+            outln!("    {}", ui::dim_text(&entry.value));
         }
         has_section = true;
     }
 
     // Exposed section — excluded entries that are still tracked
     if !exposed.is_empty() {
-        if has_section { println!(); }
-        println!("  {} Exposed ({}):", ui::exposed(), exposed.len());
+        if has_section { outln!(); }
+        outln!("  {} Exposed ({}):", ui::exposed(), exposed.len());
         let width = exposed.iter().map(|(e, _, _)| e.len()).max().unwrap_or(0);
         for (entry, fix, tracked_files) in &exposed {
-            println!(
+            outln!(
                 "    {:<width$}  {}",
                 entry,
                 ui::warn_text(fix),
                 width = width
             );
             for file in tracked_files {
-                println!(
+                outln!(
                     "      {}",
                     ui::warn_text(&format!("git rm --cached {file}"))
                 );
@@ -142,31 +228,43 @@ pub fn run() -> Result<i32> {
 
     // Discovered section — context files not yet layered
     if !discovered.is_empty() {
-        if has_section { println!(); }
-        println!("  {} {}:", ui::discovered(), ui::warn_text(&format!("Discovered ({})", discovered.len())));
-        let width = discovered.iter().map(|e| e.len()).max().unwrap_or(0);
-        for entry in &discovered {
-            println!(
-                "    {:<width$}  {}",
-                entry,
-                ui::dim_text(&format!("layer add {entry}")),
-                width = width
+        if has_section { outln!(); }
+        outln!("  {} {}:", ui::discovered(), ui::warn_text(&format!("Discovered ({})", discovered.len())));
+        let shown = visible_count(discovered.len(), max_discovered, full);
+        let width = discovered[..shown].iter().map(|e| e.len()).max().unwrap_or(0);
+        let grouped = discovered.iter().any(|path| categories[path] != categories[&discovered[0]]);
+        for (idx, entry) in discovered[..shown].iter().enumerate() {
+            if grouped && (idx == 0 || categories[&discovered[idx - 1]] != categories[entry]) {
+                outln!("    {}", ui::dim_text(categories[entry].title()));
+            }
+            let path = format!("{entry:<width$}");
+            let path = if categories[entry] == PatternCategory::Secrets { ui::warn_text(&path) } else { path };
+            outln!("    {path}  {}", ui::dim_text(&discovered_fix(entry, categories[entry])));
+        }
+        if shown < discovered.len() {
+            outln!(
+                "    {}",
+                ui::dim_text(&format!(
+                    "… and {} more (run 'layer scan', or 'layer status --full')",
+                    discovered.len() - shown
+                ))
             );
         }
+        print_secrets_note(&discovered, &categories);
         has_section = true;
     }
 
     // Tracked context files — exposed because they're tracked
     if !tracked_ctx.is_empty() {
-        if has_section { println!(); }
-        println!(
+        if has_section { outln!(); }
+        outln!(
             "  {} Exposed — tracked ({}):",
             ui::exposed(),
             tracked_ctx.len()
         );
         let width = tracked_ctx.iter().map(|e| e.len()).max().unwrap_or(0);
         for entry in &tracked_ctx {
-            println!(
+            outln!(
                 "    {:<width$}  {}",
                 entry,
                 ui::warn_text(&format!(
@@ -176,17 +274,441 @@ pub fn run() -> Result<i32> {
                 width = width
             );
         }
+        print_secrets_note(&tracked_ctx, &categories);
+    }
+
+    let first_id = exposed
+        .first()
+        .map(|(entry, _, _)| acks::exposed_id(entry))
+        .or_else(|| tracked_ctx.first().map(|path| acks::tracked_id(path)));
+    if let Some(id) = first_id {
+        outln!();
+        outln!("  {}", ui::dim_text(&format!("Seen these before? 'layer ack {id}' stops repeating one.")));
+    }
+    print_artifacts_hint(artifacts);
+    print_acked_summary(hidden);
+
+    Ok(if needs_attention { 1 } else { 0 })
+}
+
+/// What to run about a discovered path. OS files turn up in every repo, so
+/// they belong in the global gitignore rather than this repo's layer.
+fn discovered_fix(path: &str, category: PatternCategory) -> String {
+    if category == PatternCategory::OsFiles {
+        format!("layer global add {}", path.rsplit('/').next().unwrap_or(path))
+    } else {
+        format!("layer add {path}")
+    }
+}
+
+fn print_secrets_note(paths: &[String], categories: &HashMap<String, PatternCategory>) {
+    if paths.iter().any(|path| categories[path] == PatternCategory::Secrets) {
+        outln!("    {} {}", ui::exposed(), ui::warn_text(SECRETS_NOTE));
+    }
+}
+
+fn print_artifacts_hint(count: usize) {
+    if count == 0 {
+        return;
+    }
+    outln!();
+    outln!(
+        "  {}",
+        ui::dim_text(&format!(
+            "{count} agent {} to review with 'layer scan'",
+            if count == 1 { "artifact" } else { "artifacts" }
+        ))
+    );
+}
+
+fn print_acked_summary(hidden: usize) {
+    if hidden == 0 {
+        return;
+    }
+    outln!();
+    outln!(
+        "  {}",
+        ui::dim_text(&format!(
+            "{hidden} acknowledged {} hidden (layer status --show-acked)",
+            if hidden == 1 { "warning" } else { "warnings" }
+        ))
+    );
+}
+
+/// Audit another ref without checking it out: which context files are
+/// committed there, and which layered entries would be exposed by it.
+pub fn run_rev(rev: &str, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let config = config::load(Some(&ctx.root))?;
+    let depth = config.get_int("scan.depth") as usize;
+    let tree = git::list_tree(&ctx.root, rev)?;
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+
+    let committed = discovery::known_paths_in_tree(&tree, depth, &enabled_patterns(&config));
+    let count_files = |path: &str| {
+        if path.ends_with('/') {
+            tree.iter().filter(|f| f.starts_with(path)).count()
+        } else {
+            1
+        }
+    };
+    let layered_tracked: Vec<String> = exclude
+        .entries()
+        .into_iter()
+        .map(|e| e.value)
+        .filter(|entry| tree.iter().any(|path| entry_matches_path(entry, path)))
+        .collect();
+
+    if format.is_porcelain() {
+        for (path, pattern) in &committed {
+            ui::porcelain(&["committed", path, pattern.label]);
+        }
+        for entry in &layered_tracked {
+            ui::porcelain(&["layered_tracked", entry]);
+        }
+        return Ok(if committed.is_empty() && layered_tracked.is_empty() { 0 } else { 1 });
+    }
+
+    if format.is_json() {
+        let value = Json::object(vec![
+            ("rev", Json::String(rev.to_string())),
+            (
+                "committed",
+                Json::Array(
+                    committed
+                        .iter()
+                        .map(|(path, pattern)| {
+                            Json::object(vec![
+                                ("path", Json::String(path.clone())),
+                                ("tool", Json::String(pattern.label.to_string())),
+                                ("files", Json::Number(count_files(path) as f64)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("layered_tracked", Json::strings(layered_tracked.iter().cloned())),
+        ]);
+        outln!("{}", value.pretty());
+        return Ok(if committed.is_empty() && layered_tracked.is_empty() { 0 } else { 1 });
+    }
+
+    if committed.is_empty() && layered_tracked.is_empty() {
+        outln!("  {} No context files are committed at {}.", ui::ok(), ui::brand(rev));
+        return Ok(0);
+    }
+
+    if !committed.is_empty() {
+        outln!(
+            "  {} Committed at {} ({}):",
+            ui::exposed(),
+            ui::brand(rev),
+            committed.len()
+        );
+        let width = committed.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+        for (path, pattern) in &committed {
+            let files = count_files(path);
+            let detail = if path.ends_with('/') {
+                format!("{} · {} {}", pattern.label, files, if files == 1 { "file" } else { "files" })
+            } else {
+                pattern.label.to_string()
+            };
+            outln!("    {:<width$}  {}", path, ui::dim_text(&detail), width = width);
+        }
+    }
+
+    if !layered_tracked.is_empty() {
+        if !committed.is_empty() {
+            outln!();
+        }
+        outln!(
+            "  {} Layered here but tracked at {} ({}):",
+            ui::exposed(),
+            ui::brand(rev),
+            layered_tracked.len()
+        );
+        for entry in &layered_tracked {
+            outln!("    {}", entry);
+        }
     }
 
-    if !exposed.is_empty() || !tracked_ctx.is_empty() {
-        return Ok(1);
+    Ok(1)
+}
+
+/// Whether a layered entry would match a tracked path, approximating git's
+/// rules for the three entry shapes layer writes.
+pub fn entry_matches_path(entry: &str, path: &str) -> bool {
+    let anchored = entry.strip_prefix('/');
+    let entry = anchored.unwrap_or(entry);
+    if let Some(dir) = entry.strip_suffix('/') {
+        return path.starts_with(&format!("{dir}/"))
+            || (anchored.is_none() && !dir.contains('/') && path.contains(&format!("/{dir}/")));
     }
+    if entry.contains('/') || anchored.is_some() {
+        return if git::contains_glob(entry) { discovery::wildcard_match(entry, path) } else { path == entry };
+    }
+    let basename = path.rsplit('/').next().unwrap_or(path);
+    discovery::wildcard_match(entry, basename)
+}
 
-    Ok(0)
+/// Everything `status --format json|porcelain` reports, borrowed from the
+/// text dashboard's data.
+struct StatusReport<'a> {
+    demo: bool,
+    layered: &'a [String],
+    disabled: &'a [String],
+    exposed: &'a [(String, String, Vec<String>)],
+    pattern_index: &'a HashMap<String, PatternMatchSummary>,
+    discovered: &'a [String],
+    tracked_ctx: &'a [String],
+    categories: &'a HashMap<String, PatternCategory>,
+    gitignored: usize,
+    patterns: &'a [&'static KnownPattern],
 }
 
-fn classify_entry(
-    repo_root: &std::path::Path,
+impl StatusReport<'_> {
+    /// One `state path` line per item; discovered files are never truncated.
+    fn print_porcelain(&self) {
+        for entry in self.layered {
+            ui::porcelain(&["layered", entry]);
+        }
+        for entry in self.disabled {
+            ui::porcelain(&["disabled", entry]);
+        }
+        for (entry, _, _) in self.exposed {
+            ui::porcelain(&["exposed", entry]);
+        }
+        for path in self.discovered {
+            ui::porcelain(&["discovered", path]);
+        }
+        for path in self.tracked_ctx {
+            ui::porcelain(&["tracked", path]);
+        }
+    }
+
+    /// One `--format` template line per item, in porcelain order. `matches`
+    /// counts the files an entry hides, or the files at a discovered path.
+    fn print_template(&self, root: &Path) {
+        let hidden = |entry: &str| self.pattern_index.get(entry).map_or(0, |s| s.total);
+        let on_disk = |path: &str| {
+            let full = root.join(path.trim_end_matches('/'));
+            if full.is_dir() {
+                WalkDir::new(&full).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()).count()
+            } else {
+                usize::from(full.exists())
+            }
+        };
+        let items = self
+            .layered
+            .iter()
+            .map(|entry| ("layered", entry, hidden(entry)))
+            .chain(self.disabled.iter().map(|entry| ("disabled", entry, 0)))
+            .chain(self.exposed.iter().map(|(entry, _, _)| ("exposed", entry, hidden(entry))))
+            .chain(self.discovered.iter().map(|path| ("discovered", path, on_disk(path))))
+            .chain(self.tracked_ctx.iter().map(|path| ("tracked", path, on_disk(path))));
+        for (state, entry, matches) in items {
+            let tool = known_pattern_for(entry.trim_start_matches('/'), self.patterns).map_or("", |p| p.label);
+            ui::template_line(&[
+                ("status", state),
+                ("entry", entry),
+                ("tool", tool),
+                ("matches", &matches.to_string()),
+            ]);
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        let exposed = self
+            .exposed
+            .iter()
+            .map(|(entry, _, files)| {
+                let (reason, files) = if git::contains_glob(entry) {
+                    let files = self
+                        .pattern_index
+                        .get(entry)
+                        .map(|s| s.tracked_files.clone())
+                        .unwrap_or_default();
+                    ("pattern matches tracked files", files)
+                } else if entry.ends_with('/') {
+                    ("directory contains tracked files", files.clone())
+                } else {
+                    ("file is tracked", vec![entry.clone()])
+                };
+                Json::object(vec![
+                    ("entry", Json::String(entry.clone())),
+                    ("reason", Json::String(reason.to_string())),
+                    ("tracked_files", Json::strings(files.iter().cloned())),
+                    ("fixes", Json::strings(files.iter().map(|f| format!("git rm --cached {f}")))),
+                ])
+            })
+            .collect();
+        let with_fix = |paths: &[String], fix: &dyn Fn(&str) -> String| {
+            Json::Array(
+                paths
+                    .iter()
+                    .map(|path| {
+                        let category = self.categories.get(path).map_or(Json::Null, |c| Json::String(c.as_str().to_string()));
+                        Json::object(vec![
+                            ("path", Json::String(path.clone())),
+                            ("category", category),
+                            ("fix", Json::String(fix(path))),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+
+        Json::object(vec![
+            ("demo", Json::Bool(self.demo)),
+            ("layered", Json::strings(self.layered.iter().cloned())),
+            ("disabled", Json::strings(self.disabled.iter().cloned())),
+            ("exposed", Json::Array(exposed)),
+            ("discovered", with_fix(self.discovered, &|p| discovered_fix(p, self.categories[p]))),
+            (
+                "tracked_context",
+                with_fix(self.tracked_ctx, &|p| {
+                    format!("git rm --cached {}", p.trim_end_matches('/'))
+                }),
+            ),
+            ("gitignored", Json::Number(self.gitignored as f64)),
+        ])
+    }
+}
+
+/// Condensed status for git hooks: one line only when something needs
+/// attention, never a non-zero exit unless `strict` is set.
+pub fn run_hook(strict: bool) -> Result<i32> {
+    let strict = strict
+        || git::ensure_repo()
+            .and_then(|ctx| config::load(Some(&ctx.root)))
+            .is_ok_and(|config| config.get_bool("status.hook_strict"));
+    let line = match hook_summary() {
+        Ok(line) => line,
+        Err(err) if strict => return Err(err),
+        Err(_) => return Ok(0),
+    };
+
+    match line {
+        Some(line) => {
+            outln!("{line}");
+            Ok(if strict { 1 } else { 0 })
+        }
+        None => Ok(0),
+    }
+}
+
+/// The `--hook` line, from the cache when its inputs haven't changed.
+/// Recomputing also refreshes the cache.
+pub fn hook_summary() -> Result<Option<String>> {
+    let ctx = git::ensure_repo()?;
+    let cache_path = ctx.git_dir.join(HOOK_CACHE_FILE);
+    let config = config::load(Some(&ctx.root))?;
+    let depth = config.get_int("scan.depth");
+    let dirs = discovery::scanned_dirs_mtime(&ctx.root, depth as usize, config.get_list("scan.skip_dirs"));
+    let state = render_cache::repo_state(&ctx);
+    let key = format!("{depth}:{dirs}:{:016x}", fnv1a(state.as_bytes()));
+
+    if let Ok(cached) = fs::read_to_string(&cache_path) {
+        if let Some((cached_key, cached_line)) = cached.split_once('\n') {
+            if cached_key == key {
+                let line = cached_line.trim_end();
+                return Ok((!line.is_empty()).then(|| line.to_string()));
+            }
+        }
+    }
+
+    let exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let tracked = git::list_tracked(&ctx.root)?;
+
+    // Glob entries need the full pattern index, which is too slow for a hook.
+    let no_patterns = HashMap::new();
+    let mut layered = Vec::new();
+    let mut exposed = Vec::new();
+    for entry in exclude.entries() {
+        classify_entry(
+            &ctx.root,
+            &entry.value,
+            &tracked,
+            &no_patterns,
+            &mut layered,
+            &mut exposed,
+        );
+    }
+
+    if config.get_bool("notify.exposure") {
+        let names: Vec<String> = exposed.iter().map(|(entry, _, _)| entry.clone()).collect();
+        let fresh = notify::track_exposed(&ctx.git_dir, &names);
+        if !fresh.is_empty() {
+            let (summary, body) = notify::exposure_notification(&fresh);
+            let _ = notify::send(&summary, &body);
+        }
+    }
+
+    let mut discovered = discovery::known_files(&ctx, &exclude.entry_set(), &tracked, Filter::Visible)?;
+    discovered.retain(|item| !item.category.is_optional());
+    let acked = acks::load(&ctx.git_dir);
+    let mut n_acked = 0;
+    let mut unacked = |id: String| {
+        let fresh = !acked.contains(&id);
+        if !fresh {
+            n_acked += 1;
+        }
+        fresh
+    };
+    let n_exposed = exposed.iter().filter(|(entry, _, _)| unacked(acks::exposed_id(entry))).count()
+        + discovered.iter().filter(|i| i.is_tracked && unacked(acks::tracked_id(&i.path))).count();
+    let n_discovered = discovered
+        .iter()
+        .filter(|i| !i.is_tracked && unacked(acks::discovered_id(&i.path)))
+        .count();
+
+    let mut parts = Vec::new();
+    if n_exposed > 0 {
+        parts.push(format!("{n_exposed} exposed"));
+    }
+    if n_discovered > 0 {
+        parts.push(format!("{n_discovered} discovered"));
+    }
+    if !parts.is_empty() && n_acked > 0 {
+        parts.push(format!("{n_acked} acked"));
+    }
+    let line = if parts.is_empty() {
+        String::new()
+    } else {
+        format!("layer: {} — run 'layer status'", parts.join(" · "))
+    };
+
+    let _ = fs::write(&cache_path, format!("{key}\n{line}\n"));
+
+    Ok((!line.is_empty()).then_some(line))
+}
+
+/// `status --short`: counts on one line, e.g. `3 layered · 1 exposed`.
+fn short_line(layered: usize, disabled: usize, exposed: usize, discovered: usize) -> String {
+    let mut parts = vec![format!("{layered} layered")];
+    if disabled > 0 {
+        parts.push(ui::dim_text(&format!("{disabled} off")));
+    }
+    if exposed > 0 {
+        parts.push(ui::warn_text(&format!("{exposed} exposed")));
+    }
+    if discovered > 0 {
+        parts.push(format!("{discovered} discovered"));
+    }
+    parts.join(" · ")
+}
+
+/// How many discovered entries to list; a limit of 0 means no limit.
+fn visible_count(total: usize, limit: usize, full: bool) -> usize {
+    if full || limit == 0 {
+        total
+    } else {
+        total.min(limit)
+    }
+}
+
+pub fn classify_entry(
+    repo_root: &Path,
     entry: &str,
     tracked: &HashSet<String>,
     pattern_index: &HashMap<String, PatternMatchSummary>,
@@ -250,3 +772,19 @@ fn classify_entry(
 
     layered.push(entry.to_string());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_matches_path_handles_entry_shapes() {
+        assert!(entry_matches_path("CLAUDE.md", "CLAUDE.md"));
+        assert!(entry_matches_path("CLAUDE.md", "docs/CLAUDE.md"));
+        assert!(!entry_matches_path("/CLAUDE.md", "docs/CLAUDE.md"));
+        assert!(entry_matches_path(".cursor/", ".cursor/rules/a.md"));
+        assert!(entry_matches_path(".aider*", ".aider.conf.yml"));
+        assert!(entry_matches_path(".github/copilot-instructions.md", ".github/copilot-instructions.md"));
+        assert!(!entry_matches_path(".cursor/", ".cursorrules"));
+    }
+}
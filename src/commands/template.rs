@@ -0,0 +1,111 @@
+use crate::commands::add::apply_add_entries;
+use crate::config;
+use crate::exclude_file::{ensure_exclude_file, ensure_exclude_file_for_write};
+use crate::git;
+use crate::meta;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `layer template save`: keep this repo's layered entries as a named
+/// template in `~/.config/layer/templates/<name>.txt`.
+pub fn save(name: &str, force: bool) -> Result<i32> {
+    check_name(name)?;
+    let ctx = git::ensure_repo()?;
+    let entries: Vec<String> = ensure_exclude_file(&ctx.exclude_path)?
+        .entries()
+        .into_iter()
+        .map(|entry| entry.value)
+        .collect();
+    if entries.is_empty() {
+        outln!("Nothing layered to save. Layer some files first with {}.", ui::brand("layer add"));
+        return Ok(2);
+    }
+
+    let dir = templates_dir()?;
+    let path = dir.join(format!("{name}.txt"));
+    if path.exists() && !force {
+        bail!("template '{name}' already exists. Use --force to replace it");
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut out = String::from("# layer template\n");
+    for entry in &entries {
+        out.push_str(entry);
+        out.push('\n');
+    }
+    fs::write(&path, out).with_context(|| format!("failed to write {}", path.display()))?;
+    outln!(
+        "  {} Saved {} {} as template '{name}' ({})",
+        ui::ok(),
+        entries.len(),
+        if entries.len() == 1 { "entry" } else { "entries" },
+        path.display()
+    );
+    Ok(0)
+}
+
+/// `layer template apply`: layer every entry of a saved template here.
+pub fn apply(name: &str, dry_run: bool) -> Result<i32> {
+    check_name(name)?;
+    let path = templates_dir()?.join(format!("{name}.txt"));
+    if !path.is_file() {
+        bail!("no template named '{name}'. Run 'layer template list' to see saved ones");
+    }
+    let entries = read_template(&path)?;
+    let ctx = git::ensure_repo()?;
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    meta::set_source(&entries, &format!("template {name}"));
+    let summary = apply_add_entries(&ctx, &mut exclude, &entries, dry_run)?;
+    Ok(if summary.added == 0 { 2 } else { 0 })
+}
+
+/// `layer template list`: each saved template and its entries.
+pub fn list() -> Result<i32> {
+    let dir = templates_dir()?;
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    paths.retain(|path| path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("txt"));
+    paths.sort();
+    if paths.is_empty() {
+        outln!("No templates saved. Use 'layer template save <name>' in a repo whose layer you want to reuse.");
+        return Ok(2);
+    }
+    for path in &paths {
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let entries = read_template(path)?;
+        outln!("{}  {}", ui::heading(name), ui::dim_text(&format!("{} entries", entries.len())));
+        outln!("  {}", entries.join(" "));
+    }
+    Ok(0)
+}
+
+/// Where templates live: `templates/` next to the user config file.
+fn templates_dir() -> Result<PathBuf> {
+    let config = config::user_config_path()
+        .ok_or_else(|| anyhow!("can't find your config directory. Set HOME or XDG_CONFIG_HOME"))?;
+    Ok(config.with_file_name("templates"))
+}
+
+fn read_template(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Template names become file names, so keep them to one plain path segment.
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        bail!("'{name}' isn't a valid template name. Use letters, digits, '-', '_', and '.'");
+    }
+    Ok(())
+}
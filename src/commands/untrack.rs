@@ -0,0 +1,77 @@
+use crate::commands::{add, why_cmd};
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+use dialoguer::Confirm;
+
+/// Untrack files with `git rm --cached` (they stay on disk), then make sure
+/// an ignore rule covers each one, layering it when nothing does.
+pub fn run(files: Vec<String>, yes: bool) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let tracked = git::list_tracked(&ctx.root)?;
+
+    let mut targets: Vec<String> = Vec::new();
+    for file in &files {
+        let path = file.trim().trim_start_matches("./").trim_end_matches('/').to_string();
+        if path.is_empty() || targets.contains(&path) {
+            continue;
+        }
+        let prefix = format!("{path}/");
+        if tracked.contains(&path) || tracked.iter().any(|t| t.starts_with(&prefix)) {
+            targets.push(path);
+        } else {
+            outln!("  {} '{path}' is not tracked by git", ui::info());
+        }
+    }
+    if targets.is_empty() {
+        return Ok(2);
+    }
+
+    if !yes {
+        ui::require_tty("interactive confirmation requires a TTY. Re-run in a terminal or use --yes")?;
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Untrack {} with git rm --cached? Files stay on disk.",
+                if targets.len() == 1 { format!("'{}'", targets[0]) } else { format!("{} paths", targets.len()) }
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            outln!("No changes made.");
+            return Ok(2);
+        }
+    }
+
+    for path in &targets {
+        git::untrack(&ctx.root, path)?;
+        outln!("  {} Untracked '{path}'", ui::ok());
+    }
+
+    let rules = git::check_ignore_bulk(&ctx.root, &targets, false)?;
+    let mut uncovered = Vec::new();
+    for path in &targets {
+        match rules.get(path).filter(|m| !m.pattern.starts_with('!')) {
+            Some(rule) => outln!(
+                "  {} '{path}' is hidden by '{}' ({}:{})",
+                ui::ok(),
+                rule.pattern,
+                why_cmd::relativize(&ctx.root, &rule.source),
+                rule.line
+            ),
+            None if ctx.root.join(path).is_dir() => uncovered.push(format!("{path}/")),
+            None => uncovered.push(path.clone()),
+        }
+    }
+    if !uncovered.is_empty() {
+        let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+        add::apply_add_entries(&ctx, &mut exclude, &uncovered, false)?;
+    }
+
+    outln!(
+        "  {}",
+        ui::dim_text("Files stay on disk. Commit to remove them from the repository.")
+    );
+    Ok(0)
+}
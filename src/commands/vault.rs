@@ -0,0 +1,173 @@
+use crate::commands::add::apply_add_entries;
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git::{self, RepoContext};
+use crate::ui;
+use crate::ui::outln;
+use crate::vault;
+use anyhow::{bail, Context, Result};
+use dialoguer::Password;
+use std::fs;
+
+/// `layer vault add`: encrypt files into `.git/layer-vault/`, remove the
+/// plaintext, and layer the paths so decrypted copies stay out of git.
+pub fn add(files: Vec<String>) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let vaulted = vault::list(&ctx.git_dir);
+    let tracked = git::list_tracked(&ctx.root)?;
+
+    let mut targets: Vec<String> = Vec::new();
+    for file in &files {
+        let rel = normalize(file);
+        if rel.is_empty() || targets.contains(&rel) {
+            continue;
+        }
+        if vaulted.contains(&rel) {
+            outln!("  {} '{rel}' is already in the vault", ui::info());
+        } else if !ctx.root.join(&rel).is_file() {
+            outln!("  {} '{rel}' is not a file", ui::info());
+        } else if tracked.contains(&rel) {
+            bail!("'{rel}' is tracked by git, so its plaintext is already in history. Run `layer untrack {rel}` first");
+        } else {
+            targets.push(rel);
+        }
+    }
+    if targets.is_empty() {
+        return Ok(2);
+    }
+
+    // One passphrase per vault: check it against a file already in there.
+    let passphrase = match vaulted.first() {
+        Some(existing) => {
+            let passphrase = passphrase(false)?;
+            vault::decrypt(&vault::vault_path(&ctx.git_dir, existing), &passphrase)?;
+            passphrase
+        }
+        None => passphrase(true)?,
+    };
+
+    for rel in &targets {
+        let plain = ctx.root.join(rel);
+        vault::encrypt(&plain, &vault::vault_path(&ctx.git_dir, rel), &passphrase)?;
+        fs::remove_file(&plain).with_context(|| format!("failed to remove {}", plain.display()))?;
+        outln!("  {} Vaulted '{rel}'", ui::ok());
+    }
+    layer(&ctx, &targets)?;
+    outln!("Run `layer vault open` to get decrypted copies back.");
+    Ok(0)
+}
+
+/// `layer vault open`: write decrypted copies of vaulted files (all of them
+/// without arguments), readable only by you.
+pub fn open(files: Vec<String>) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let targets: Vec<String> = select(&ctx, &files)?
+        .into_iter()
+        .filter(|rel| {
+            let open = ctx.root.join(rel).exists();
+            if open {
+                outln!("  {} '{rel}' is already open", ui::info());
+            }
+            !open
+        })
+        .collect();
+    if targets.is_empty() {
+        return Ok(2);
+    }
+
+    let passphrase = passphrase(false)?;
+    for rel in &targets {
+        let content = vault::decrypt(&vault::vault_path(&ctx.git_dir, rel), &passphrase)?;
+        vault::write_private(&ctx.root.join(rel), &content)?;
+        outln!("  {} Opened '{rel}'", ui::ok());
+    }
+    layer(&ctx, &targets)?;
+    Ok(0)
+}
+
+/// `layer vault lock`: save edits to open files back into the vault and
+/// remove the decrypted copies.
+pub fn lock(files: Vec<String>) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    let targets: Vec<String> = select(&ctx, &files)?
+        .into_iter()
+        .filter(|rel| ctx.root.join(rel).is_file())
+        .collect();
+    if targets.is_empty() {
+        outln!("No open vault files.");
+        return Ok(2);
+    }
+
+    let passphrase = passphrase(false)?;
+    for rel in &targets {
+        let plain = ctx.root.join(rel);
+        let vaulted = vault::vault_path(&ctx.git_dir, rel);
+        let saved = vault::decrypt(&vaulted, &passphrase)?;
+        let current = fs::read(&plain).with_context(|| format!("failed to read {}", plain.display()))?;
+        let changed = saved != current;
+        if changed {
+            vault::encrypt(&plain, &vaulted, &passphrase)?;
+        }
+        fs::remove_file(&plain).with_context(|| format!("failed to remove {}", plain.display()))?;
+        let note = if changed { " (saved changes)" } else { "" };
+        outln!("  {} Locked '{rel}'{note}", ui::ok());
+    }
+    Ok(0)
+}
+
+/// The named vaulted files, or every one when none are named.
+fn select(ctx: &RepoContext, files: &[String]) -> Result<Vec<String>> {
+    let vaulted = vault::list(&ctx.git_dir);
+    if vaulted.is_empty() {
+        bail!("the vault is empty. Add files with `layer vault add <files...>`");
+    }
+    if files.is_empty() {
+        return Ok(vaulted);
+    }
+    let mut selected = Vec::new();
+    for file in files {
+        let rel = normalize(file);
+        if !vaulted.contains(&rel) {
+            bail!("'{rel}' is not in the vault. Vaulted files: {}", vaulted.join(", "));
+        }
+        if !selected.contains(&rel) {
+            selected.push(rel);
+        }
+    }
+    Ok(selected)
+}
+
+fn normalize(file: &str) -> String {
+    file.trim().trim_start_matches("./").replace('\\', "/")
+}
+
+/// Layer decrypted copies' paths; quiet when they already are.
+fn layer(ctx: &RepoContext, paths: &[String]) -> Result<()> {
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let excluded = exclude.entry_set();
+    let missing: Vec<String> = paths.iter().filter(|rel| !excluded.contains(*rel)).cloned().collect();
+    if !missing.is_empty() {
+        apply_add_entries(ctx, &mut exclude, &missing, false)?;
+    }
+    Ok(())
+}
+
+/// `LAYER_VAULT_PASSPHRASE` when set, otherwise a prompt; `confirm` asks
+/// twice, for a vault's first passphrase.
+fn passphrase(confirm: bool) -> Result<String> {
+    if let Ok(value) = std::env::var(vault::PASSPHRASE_ENV) {
+        if value.is_empty() {
+            bail!("{} is set but empty", vault::PASSPHRASE_ENV);
+        }
+        return Ok(value);
+    }
+    ui::require_tty(&format!(
+        "the vault passphrase is read from a TTY. Re-run in a terminal or set {}",
+        vault::PASSPHRASE_ENV
+    ))?;
+    let theme = ui::layer_theme();
+    let mut prompt = Password::with_theme(&theme).with_prompt("Vault passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Repeat passphrase", "Passphrases don't match");
+    }
+    Ok(prompt.interact()?)
+}
@@ -0,0 +1,41 @@
+use crate::commands::why_cmd::relativize;
+use crate::exclude_file::{ensure_exclude_file, normalize_entry};
+use crate::git;
+use crate::json::Json;
+use crate::ui;
+use crate::ui::outln;
+use anyhow::Result;
+
+/// Print just the `source:line` of the rule that ignores `file`, or
+/// "not ignored". Rules are matched as if the path were untracked, so this
+/// points at the rule even when tracking makes it ineffective (see `why`).
+pub fn run(file: String, format: ui::Format) -> Result<i32> {
+    let ctx = git::ensure_repo()?;
+    // Side effect: creates .git/info/exclude if missing so check-ignore works.
+    let _exclude = ensure_exclude_file(&ctx.exclude_path)?;
+    let normalized = normalize_entry(&file).trim_end_matches('/').to_string();
+
+    let matched = git::check_ignore_verbose_no_index(&ctx.root, &normalized)?;
+    let location = matched
+        .as_ref()
+        .map(|m| format!("{}:{}", relativize(&ctx.root, &m.source.replace('\\', "/")), m.line));
+    let ignored = matched.as_ref().is_some_and(|m| !m.pattern.starts_with('!'));
+
+    if format.is_json() {
+        let value = Json::object(vec![
+            ("path", Json::String(normalized)),
+            ("ignored", Json::Bool(ignored)),
+            ("source", location.clone().map_or(Json::Null, Json::String)),
+            ("pattern", matched.map_or(Json::Null, |m| Json::String(m.pattern))),
+        ]);
+        outln!("{}", value.pretty());
+    } else {
+        match (&location, ignored) {
+            (Some(location), true) => outln!("{location}"),
+            (Some(location), false) => outln!("not ignored (re-included at {location})"),
+            (None, _) => outln!("not ignored"),
+        }
+    }
+
+    Ok(if ignored { 0 } else { 1 })
+}
@@ -1,6 +1,9 @@
 use crate::exclude_file::{ensure_exclude_file, normalize_entry};
+use crate::commands::backup::format_backup_date;
 use crate::git;
+use crate::meta;
 use crate::ui;
+use crate::ui::outln;
 use anyhow::Result;
 use std::path::Path;
 
@@ -18,23 +21,25 @@ pub fn run(file: String, verbose: bool) -> Result<i32> {
     if let Some(matched) = ignore_no_index {
         if git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &matched.source) {
             if tracked {
-                println!("'{}' is {} — excluded but still tracked by git.", normalized, ui::warn_text("exposed"));
-                println!(
+                outln!("'{}' is {} — excluded but still tracked by git.", normalized, ui::warn_text("exposed"));
+                outln!(
                     "  Layered in: .git/info/exclude (line {})",
                     matched.line
                 );
-                println!("  Tracked:  YES — this is why git still sees it");
-                println!("  Fix:      git rm --cached {}", normalized);
+                print_meta(&ctx.exclude_path, &matched.pattern, 10);
+                outln!("  Tracked:  YES — this is why git still sees it");
+                outln!("  Fix:      git rm --cached {}", normalized);
                 return finish(1, verbose);
             }
 
-            println!("'{}' is {} — hidden from git.", normalized, ui::brand("layered"));
-            println!(
+            outln!("'{}' is {} — hidden from git.", normalized, ui::brand("layered"));
+            outln!(
                 "  Layered in: .git/info/exclude (line {})",
                 matched.line
             );
-            println!("  Tracked:   no");
-            println!("  Exists:    {}", if exists { "yes" } else { "no" });
+            print_meta(&ctx.exclude_path, &matched.pattern, 11);
+            outln!("  Tracked:   no");
+            outln!("  Exists:    {}", if exists { "yes" } else { "no" });
             return finish(0, verbose);
         }
     }
@@ -43,38 +48,56 @@ pub fn run(file: String, verbose: bool) -> Result<i32> {
         let source = matched.source.replace('\\', "/");
         if source.ends_with(".gitignore") {
             let source_path = relativize(&ctx.root, &source);
-            println!("'{}' is ignored by .gitignore — already handled — no need to layer.", normalized);
-            println!("  Ignored by: {} (line {})", source_path, matched.line);
-            println!("  Tracked:    {}", yes_no(tracked));
-            println!("  Exists:     {}", yes_no(exists));
+            outln!("'{}' is ignored by .gitignore — already handled — no need to layer.", normalized);
+            outln!("  Ignored by: {} (line {})", source_path, matched.line);
+            outln!("  Tracked:    {}", yes_no(tracked));
+            outln!("  Exists:     {}", yes_no(exists));
             return finish(if tracked { 1 } else { 0 }, verbose);
         }
 
         if !git::is_local_exclude_source(&ctx.root, &ctx.exclude_path, &source) {
-            println!("'{}' is ignored by global gitignore — already handled — no need to layer.", normalized);
-            println!("  Ignored by: {} (line {})", source, matched.line);
-            println!("  Tracked:    {}", yes_no(tracked));
-            println!("  Exists:     {}", yes_no(exists));
+            outln!("'{}' is ignored by global gitignore — already handled — no need to layer.", normalized);
+            outln!("  Ignored by: {} (line {})", source, matched.line);
+            outln!("  Tracked:    {}", yes_no(tracked));
+            outln!("  Exists:     {}", yes_no(exists));
             return finish(if tracked { 1 } else { 0 }, verbose);
         }
     }
 
     if tracked {
-        println!("'{}' is {} — tracked and not layered.", normalized, ui::warn_text("exposed"));
-        println!("  Layered:  no");
-        println!("  Tracked:  yes");
-        println!("  Exists:   {}", yes_no(exists));
+        outln!("'{}' is {} — tracked and not layered.", normalized, ui::warn_text("exposed"));
+        outln!("  Layered:  no");
+        outln!("  Tracked:  yes");
+        outln!("  Exists:   {}", yes_no(exists));
         return finish(1, verbose);
     }
 
-    println!("'{}' is {} — untracked and not in any layer.", normalized, ui::brand("discovered"));
-    println!("  Layered:  no");
-    println!("  Tracked:  no");
-    println!("  Exists:   {}", yes_no(exists));
-    println!("  Fix:      layer add {}", normalized);
+    outln!("'{}' is {} — untracked and not in any layer.", normalized, ui::brand("discovered"));
+    outln!("  Layered:  no");
+    outln!("  Tracked:  no");
+    outln!("  Exists:   {}", yes_no(exists));
+    outln!("  Fix:      layer add {}", normalized);
     finish(2, verbose)
 }
 
+/// When and how the layer entry was added, and its note, if recorded.
+fn print_meta(exclude_path: &Path, entry: &str, width: usize) {
+    let metas = meta::load(exclude_path);
+    let Some(meta) = metas.get(entry) else {
+        return;
+    };
+    outln!(
+        "  {:<width$}{} by {}{}",
+        "Added:",
+        format_backup_date(&Some(meta.added.clone())),
+        meta.origin,
+        meta.source.as_deref().map(|source| format!(" from {source}")).unwrap_or_default()
+    );
+    if let Some(note) = &meta.note {
+        outln!("  {:<width$}{note}", "Note:");
+    }
+}
+
 fn yes_no(value: bool) -> &'static str {
     if value {
         "yes"
@@ -83,7 +106,7 @@ fn yes_no(value: bool) -> &'static str {
     }
 }
 
-fn relativize(root: &Path, source: &str) -> String {
+pub fn relativize(root: &Path, source: &str) -> String {
     let source_path = Path::new(source);
     if let Ok(rel) = source_path.strip_prefix(root) {
         return rel.to_string_lossy().to_string();
@@ -93,12 +116,12 @@ fn relativize(root: &Path, source: &str) -> String {
 
 fn finish(code: i32, verbose: bool) -> Result<i32> {
     if verbose {
-        println!();
-        println!("{}", ui::dim_text("How git decides to ignore files (checked in order):"));
-        println!("{}", ui::dim_text("  1. .git/info/exclude     — local to this repo clone, not shared (this is what layer manages)"));
-        println!("{}", ui::dim_text("  2. .gitignore            - tracked and shared with the team"));
-        println!("{}", ui::dim_text("  3. ~/.config/git/ignore  - global, applies to all repos on this machine"));
-        println!("{}", ui::dim_text("A file must not be tracked for any ignore rule to take effect."));
+        outln!();
+        outln!("{}", ui::dim_text("How git decides to ignore files (checked in order):"));
+        outln!("{}", ui::dim_text("  1. .git/info/exclude     — local to this repo clone, not shared (this is what layer manages)"));
+        outln!("{}", ui::dim_text("  2. .gitignore            - tracked and shared with the team"));
+        outln!("{}", ui::dim_text("  3. ~/.config/git/ignore  - global, applies to all repos on this machine"));
+        outln!("{}", ui::dim_text("A file must not be tracked for any ignore rule to take effect."));
     }
 
     Ok(code)
@@ -0,0 +1,808 @@
+use crate::ui;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Repo-local config file, relative to the repository root.
+pub const REPO_CONFIG_FILE: &str = ".layer.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<String>),
+}
+
+impl fmt::Display for Value {
+    /// Formats the value as a TOML literal.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Str(s) => write!(f, "{}", quote(s)),
+            Value::List(items) => {
+                let quoted: Vec<String> = items.iter().map(|s| quote(s)).collect();
+                write!(f, "[{}]", quoted.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    Int,
+    Str,
+    List,
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Bool => "a boolean (true/false)",
+            Kind::Int => "an integer",
+            Kind::Str => "a string",
+            Kind::List => "a list of strings",
+        }
+    }
+}
+
+/// A known configuration key. `default` is written as a TOML literal.
+pub struct KeySpec {
+    pub key: &'static str,
+    pub kind: Kind,
+    pub default: &'static str,
+    pub range: Option<(i64, i64)>,
+    pub choices: Option<&'static [&'static str]>,
+}
+
+pub const KEYS: &[KeySpec] = &[
+    KeySpec {
+        key: "scan.depth",
+        kind: Kind::Int,
+        default: "0",
+        range: Some((0, 32)),
+        choices: None,
+    },
+    KeySpec {
+        key: "add.strict_exposed",
+        kind: Kind::Bool,
+        default: "false",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "clean.all",
+        kind: Kind::Bool,
+        default: "false",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "status.hook_strict",
+        kind: Kind::Bool,
+        default: "false",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "notify.exposure",
+        kind: Kind::Bool,
+        default: "false",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "status.max_discovered",
+        kind: Kind::Int,
+        default: "10",
+        range: Some((0, 10_000)),
+        choices: None,
+    },
+    KeySpec {
+        key: "exclude.order",
+        kind: Kind::Str,
+        default: "\"insertion\"",
+        range: None,
+        choices: Some(&["insertion", "alphabetical", "tool"]),
+    },
+    KeySpec {
+        key: "exclude.symlink",
+        kind: Kind::Str,
+        default: "\"follow\"",
+        range: None,
+        choices: Some(&["follow", "replace", "refuse"]),
+    },
+    KeySpec {
+        key: "maintain.stale_days",
+        kind: Kind::Int,
+        default: "30",
+        range: Some((0, 3650)),
+        choices: None,
+    },
+    KeySpec {
+        key: "ui.color",
+        kind: Kind::Str,
+        default: "\"auto\"",
+        range: None,
+        choices: Some(&["auto", "always", "never"]),
+    },
+    KeySpec {
+        key: "backup.dir",
+        kind: Kind::Str,
+        default: "\"\"",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "backup.machine",
+        kind: Kind::Str,
+        default: "\"\"",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "backup.auto",
+        kind: Kind::Bool,
+        default: "true",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "scan.patterns",
+        kind: Kind::List,
+        default: "[]",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "scan.skip_dirs",
+        kind: Kind::List,
+        default: "[]",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "patterns.disabled",
+        kind: Kind::List,
+        default: "[]",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "patterns.os_files",
+        kind: Kind::Bool,
+        default: "false",
+        range: None,
+        choices: None,
+    },
+    KeySpec {
+        key: "new.templates",
+        kind: Kind::Str,
+        default: "\"\"",
+        range: None,
+        choices: None,
+    },
+];
+
+pub fn key_spec(key: &str) -> Option<&'static KeySpec> {
+    KEYS.iter().find(|spec| spec.key == key)
+}
+
+/// Where an effective value came from, lowest precedence first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Repo(PathBuf),
+    Env(String),
+    Flag,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::System(path) => write!(f, "system: {}", path.display()),
+            Origin::User(path) => write!(f, "user: {}", path.display()),
+            Origin::Repo(path) => write!(f, "repo: {}", path.display()),
+            Origin::Env(var) => write!(f, "env: {var}"),
+            Origin::Flag => write!(f, "flag: -c"),
+        }
+    }
+}
+
+/// Effective configuration after merging every source.
+#[derive(Debug, Clone)]
+pub struct Config {
+    values: BTreeMap<&'static str, (Value, Origin)>,
+}
+
+impl Config {
+    fn defaults() -> Self {
+        let mut values = BTreeMap::new();
+        for spec in KEYS {
+            let value = parse_value(spec.default).expect("built-in config default must parse");
+            values.insert(spec.key, (value, Origin::Default));
+        }
+        Self { values }
+    }
+
+    fn set(&mut self, spec: &'static KeySpec, value: Value, origin: Origin) {
+        self.values.insert(spec.key, (value, origin));
+    }
+
+    pub fn get(&self, key: &str) -> Option<&(Value, Origin)> {
+        self.values.get(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        matches!(self.get(key), Some((Value::Bool(true), _)))
+    }
+
+    pub fn get_int(&self, key: &str) -> i64 {
+        match self.get(key) {
+            Some((Value::Int(i), _)) => *i,
+            _ => 0,
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> &str {
+        match self.get(key) {
+            Some((Value::Str(s), _)) => s,
+            _ => "",
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> &[String] {
+        match self.get(key) {
+            Some((Value::List(items), _)) => items,
+            _ => &[],
+        }
+    }
+
+    /// All effective values in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Value, &Origin)> {
+        self.values.iter().map(|(k, (v, o))| (*k, v, o))
+    }
+}
+
+/// `-c key=value` overrides from the command line, applied last.
+static CLI_OVERRIDES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Validate and record `-c key=value` overrides. Call once from main.
+pub fn set_cli_overrides(raw: &[String]) -> Result<()> {
+    let mut parsed = Vec::new();
+    for item in raw {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid -c '{item}': expected key=value"))?;
+        let key = key.trim();
+        let spec = key_spec(key).ok_or_else(|| unknown_key(key))?;
+        parse_for_key(spec, value.trim())?;
+        parsed.push((key.to_string(), value.trim().to_string()));
+    }
+    let _ = CLI_OVERRIDES.set(parsed);
+    Ok(())
+}
+
+/// Resolve configuration: defaults < system < user < repo < env < `-c` flags.
+pub fn load(repo_root: Option<&Path>) -> Result<Config> {
+    let mut config = Config::defaults();
+
+    if let Some(path) = system_config_path() {
+        apply_file(&mut config, &path, Origin::System(path.clone()))?;
+    }
+    if let Some(path) = user_config_path() {
+        apply_file(&mut config, &path, Origin::User(path.clone()))?;
+    }
+    if let Some(root) = repo_root {
+        let path = root.join(REPO_CONFIG_FILE);
+        apply_file(&mut config, &path, Origin::Repo(PathBuf::from(REPO_CONFIG_FILE)))?;
+    }
+
+    for spec in KEYS {
+        let var = env_var_name(spec.key);
+        if let Ok(raw) = std::env::var(&var) {
+            let value = parse_for_key(spec, raw.trim())
+                .with_context(|| format!("invalid value in ${var}"))?;
+            config.set(spec, value, Origin::Env(var));
+        }
+    }
+
+    if let Some(overrides) = CLI_OVERRIDES.get() {
+        for (key, raw) in overrides {
+            if let Some(spec) = key_spec(key) {
+                config.set(spec, parse_for_key(spec, raw)?, Origin::Flag);
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// `/etc/layer/config.toml`, or `$LAYER_SYSTEM_CONFIG` when set.
+pub fn system_config_path() -> Option<PathBuf> {
+    match std::env::var("LAYER_SYSTEM_CONFIG") {
+        Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+        _ => Some(PathBuf::from("/etc/layer/config.toml")),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/layer/config.toml`, falling back to `~/.config/layer/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        if !xdg.trim().is_empty() {
+            return Some(PathBuf::from(xdg).join("layer").join("config.toml"));
+        }
+    }
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()?;
+    Some(PathBuf::from(home).join(".config").join("layer").join("config.toml"))
+}
+
+/// `scan.depth` → `LAYER_SCAN_DEPTH`.
+pub fn env_var_name(key: &str) -> String {
+    format!("LAYER_{}", key.replace('.', "_").to_ascii_uppercase())
+}
+
+fn apply_file(config: &mut Config, path: &Path, origin: Origin) -> Result<()> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let pairs = parse_toml(&content).with_context(|| format!("invalid config {}", path.display()))?;
+
+    for (key, value) in pairs {
+        let Some(spec) = key_spec(&key) else {
+            ui::print_stderr_warning(&format!("unknown config key '{key}' in {}", path.display()));
+            continue;
+        };
+        check_value(spec, &value).with_context(|| format!("invalid config {}", path.display()))?;
+        config.set(spec, value, origin.clone());
+    }
+
+    Ok(())
+}
+
+pub fn unknown_key(key: &str) -> anyhow::Error {
+    let known: Vec<&str> = KEYS.iter().map(|s| s.key).collect();
+    anyhow!("unknown config key '{key}'. Known keys: {}", known.join(", "))
+}
+
+/// Parse a raw value for a key. Bare words are accepted as strings so
+/// `LAYER_FOO=bar` and `-c foo=bar` work without quoting.
+pub fn parse_for_key(spec: &KeySpec, raw: &str) -> Result<Value> {
+    let value = match parse_value(raw) {
+        Ok(v) => v,
+        Err(_) if spec.kind == Kind::Str => Value::Str(raw.to_string()),
+        Err(_) if spec.kind == Kind::List => Value::List(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(ToOwned::to_owned)
+                .collect(),
+        ),
+        Err(err) => return Err(err).with_context(|| format!("{} expects {}", spec.key, spec.kind.describe())),
+    };
+    check_value(spec, &value)?;
+    Ok(value)
+}
+
+fn check_value(spec: &KeySpec, value: &Value) -> Result<()> {
+    let matches = matches!(
+        (spec.kind, value),
+        (Kind::Bool, Value::Bool(_))
+            | (Kind::Int, Value::Int(_))
+            | (Kind::Str, Value::Str(_))
+            | (Kind::List, Value::List(_))
+    );
+    if !matches {
+        bail!("{} expects {}, got {value}", spec.key, spec.kind.describe());
+    }
+    if let (Some((min, max)), Value::Int(i)) = (spec.range, value) {
+        if *i < min || *i > max {
+            bail!("{} must be between {min} and {max}, got {i}", spec.key);
+        }
+    }
+    if let (Some(choices), Value::Str(s)) = (spec.choices, value) {
+        if !choices.contains(&s.as_str()) {
+            bail!("{} must be one of {}, got '{s}'", spec.key, choices.join(", "));
+        }
+    }
+    if let ("scan.patterns", Value::List(items)) = (spec.key, value) {
+        for item in items {
+            crate::patterns::parse_custom_pattern(item).with_context(|| format!("invalid {}", spec.key))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse the TOML subset layer needs: `[table]` headers, `key = value` pairs
+/// (dotted keys allowed), and string / integer / boolean / string-array values.
+/// Arrays may span several lines. Returns fully qualified `table.key` names in
+/// file order.
+pub fn parse_toml(content: &str) -> Result<Vec<(String, Value)>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut table = String::new();
+    let mut out = Vec::new();
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line_no = idx + 1;
+        let line = strip_comment(lines[idx]).trim();
+        idx += 1;
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .ok_or_else(|| anyhow!("line {line_no}: unterminated table header"))?
+                .trim();
+            if name.is_empty() {
+                bail!("line {line_no}: empty table header");
+            }
+            table = name.to_string();
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("line {line_no}: expected key = value"))?;
+        let key = key.trim().trim_matches('"');
+        if key.is_empty() {
+            bail!("line {line_no}: missing key");
+        }
+        let (value, used) = value_text(&lines, line_no - 1, value);
+        idx += used - 1;
+        let value = parse_value(&value).with_context(|| format!("line {line_no}"))?;
+        let full_key = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{table}.{key}")
+        };
+        out.push((full_key, value));
+    }
+
+    Ok(out)
+}
+
+/// The value that starts as `first` on line `start` (0-based), with the lines
+/// after it joined on while it's an array whose `]` hasn't come yet. Returns
+/// the value and how many lines it spans.
+fn value_text<S: AsRef<str>>(lines: &[S], start: usize, first: &str) -> (String, usize) {
+    let mut value = first.trim().to_string();
+    let mut used = 1;
+    while value.starts_with('[') && !value.ends_with(']') {
+        let Some(next) = lines.get(start + used) else {
+            break;
+        };
+        value.push(' ');
+        value.push_str(strip_comment(next.as_ref()).trim());
+        used += 1;
+    }
+    (value, used)
+}
+
+/// Set `key` (`table.name`) in TOML `content`, keeping comments and layout.
+/// Replaces an existing assignment in place, otherwise appends to the
+/// key's table, creating the table at the end of the file if needed.
+pub fn set_toml_value(content: &str, key: &str, value: &Value) -> String {
+    let (table, name) = key.split_once('.').unwrap_or(("", key));
+    let mut lines: Vec<String> = content.lines().map(str::to_owned).collect();
+    let mut current = String::new();
+    let mut insert_at = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let raw = &lines[i];
+        let code = strip_comment(raw);
+        let line = code.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            current = header.trim_end_matches(']').trim().to_string();
+            if current == table {
+                insert_at = Some(i + 1);
+            }
+            i += 1;
+            continue;
+        }
+        let Some((k, rest)) = line.split_once('=') else {
+            i += 1;
+            continue;
+        };
+        let used = value_text(&lines, i, rest).1;
+        let k = k.trim().trim_matches('"');
+        let full = if current.is_empty() {
+            k.to_string()
+        } else {
+            format!("{current}.{k}")
+        };
+        if full == key {
+            let indent = &raw[..raw.len() - raw.trim_start().len()];
+            let comment = raw[code.len()..].trim();
+            let mut replaced = format!("{indent}{k} = {value}");
+            if !comment.is_empty() {
+                replaced.push_str("  ");
+                replaced.push_str(comment);
+            }
+            lines.splice(i..i + used, [replaced]);
+            return lines.join("\n") + "\n";
+        }
+        i += used;
+        if current == table {
+            insert_at = Some(i);
+        }
+    }
+
+    match insert_at {
+        Some(idx) => lines.insert(idx, format!("{name} = {value}")),
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{table}]"));
+            lines.push(format!("{name} = {value}"));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// What the config file at `path` itself sets `key` to, ignoring every other source.
+pub fn read_value(path: &Path, key: &str) -> Result<Option<Value>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let pairs = parse_toml(&content).with_context(|| format!("invalid config {}", path.display()))?;
+    Ok(pairs.into_iter().rev().find(|(k, _)| k == key).map(|(_, value)| value))
+}
+
+/// Write one key into the config file at `path`, creating it if needed.
+pub fn write_value(path: &Path, key: &str, value: &Value) -> Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    parse_toml(&content).with_context(|| format!("invalid config {}", path.display()))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(path, set_toml_value(&content, key, value))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn parse_value(raw: &str) -> Result<Value> {
+    match raw {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    if raw.starts_with('"') {
+        return parse_string(raw).map(Value::Str);
+    }
+    if let Some(inner) = raw.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| anyhow!("unterminated array"))?;
+        let mut items = Vec::new();
+        for part in split_array(inner) {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+            items.push(parse_string(part)?);
+        }
+        return Ok(Value::List(items));
+    }
+    if let Ok(i) = raw.replace('_', "").parse::<i64>() {
+        return Ok(Value::Int(i));
+    }
+    bail!("unsupported value '{raw}' (use a \"string\", number, true/false, or [\"list\"])")
+}
+
+fn parse_string(raw: &str) -> Result<String> {
+    let inner = raw
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| anyhow!("expected a quoted string, got {raw}"))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            other => bail!("unsupported escape '\\{}'", other.map(String::from).unwrap_or_default()),
+        }
+    }
+    Ok(out)
+}
+
+/// Split array contents on commas that are outside quotes.
+fn split_array(inner: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            ',' if !in_string => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    parts.push(&inner[start..]);
+    parts
+}
+
+/// Drop a trailing `# comment`, ignoring `#` inside quoted strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_toml_tables_and_dotted_keys() {
+        let pairs = parse_toml(
+            "# comment\nclean.all = true\n\n[scan]\ndepth = 4 # inline\nname = \"a # b\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                ("clean.all".to_string(), Value::Bool(true)),
+                ("scan.depth".to_string(), Value::Int(4)),
+                ("scan.name".to_string(), Value::Str("a # b".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_toml_string_arrays() {
+        let pairs = parse_toml("dirs = [\"node_modules\", \"a,b\"]").unwrap();
+        assert_eq!(
+            pairs[0].1,
+            Value::List(vec!["node_modules".to_string(), "a,b".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_toml_multiline_arrays() {
+        let content = "[scan]\npatterns = [\n  \"a = b\",  # first\n  \"c\",\n]\ndepth = 2\n";
+        let pairs = parse_toml(content).unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                ("scan.patterns".to_string(), Value::List(vec!["a = b".to_string(), "c".to_string()])),
+                ("scan.depth".to_string(), Value::Int(2)),
+            ]
+        );
+        let err = parse_toml("dirs = [\n  \"a\",\n").unwrap_err();
+        assert!(format!("{err:#}").contains("line 1: unterminated array"));
+
+        let updated = set_toml_value(content, "scan.patterns", &Value::List(vec!["d".to_string()]));
+        assert_eq!(updated, "[scan]\npatterns = [\"d\"]\ndepth = 2\n");
+        let added = set_toml_value(content, "scan.name", &Value::Str("x".to_string()));
+        assert!(added.ends_with("depth = 2\nname = \"x\"\n"), "{added}");
+    }
+
+    #[test]
+    fn parse_toml_reports_line_numbers() {
+        let err = parse_toml("a = 1\nb = nope").unwrap_err();
+        assert!(format!("{err:#}").contains("line 2"));
+    }
+
+    #[test]
+    fn value_display_roundtrips() {
+        for raw in ["true", "42", "\"x \\\"y\\\"\"", "[\"a\", \"b\"]"] {
+            let value = parse_value(raw).unwrap();
+            assert_eq!(parse_value(&value.to_string()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn parse_for_key_checks_kind_and_range() {
+        let depth = key_spec("scan.depth").unwrap();
+        assert_eq!(parse_for_key(depth, "4").unwrap(), Value::Int(4));
+        assert!(parse_for_key(depth, "true").is_err());
+        assert!(parse_for_key(depth, "-1").is_err());
+        let all = key_spec("clean.all").unwrap();
+        assert!(parse_for_key(all, "maybe").is_err());
+    }
+
+    #[test]
+    fn env_var_name_uppercases_and_joins() {
+        assert_eq!(env_var_name("scan.depth"), "LAYER_SCAN_DEPTH");
+        assert_eq!(env_var_name("status.hook_strict"), "LAYER_STATUS_HOOK_STRICT");
+    }
+
+    #[test]
+    fn defaults_cover_every_key() {
+        let config = Config::defaults();
+        for spec in KEYS {
+            let (value, origin) = config.get(spec.key).unwrap();
+            assert_eq!(*origin, Origin::Default);
+            assert!(check_value(spec, value).is_ok());
+        }
+    }
+
+    #[test]
+    fn set_toml_value_replaces_in_place_keeping_comments() {
+        let content = "# top\n[scan]\ndepth = 1  # shallow\n\n[clean]\nall = false\n";
+        let out = set_toml_value(content, "scan.depth", &Value::Int(4));
+        assert_eq!(out, "# top\n[scan]\ndepth = 4  # shallow\n\n[clean]\nall = false\n");
+
+        let dotted = set_toml_value("clean.all = false\n", "clean.all", &Value::Bool(true));
+        assert_eq!(dotted, "clean.all = true\n");
+    }
+
+    #[test]
+    fn set_toml_value_appends_to_table_or_creates_it() {
+        let out = set_toml_value("[scan]\ndepth = 1\n\n[clean]\nall = true\n", "scan.extra", &Value::Bool(true));
+        assert_eq!(out, "[scan]\ndepth = 1\nextra = true\n\n[clean]\nall = true\n");
+
+        let out = set_toml_value("[scan]\ndepth = 1\n", "clean.all", &Value::Bool(true));
+        assert_eq!(out, "[scan]\ndepth = 1\n\n[clean]\nall = true\n");
+
+        assert_eq!(set_toml_value("", "scan.depth", &Value::Int(2)), "[scan]\ndepth = 2\n");
+    }
+}
@@ -0,0 +1,625 @@
+use crate::config;
+use crate::exclude_file::normalize_entry;
+use crate::git;
+use crate::git::RepoContext;
+use crate::layerignore;
+use crate::patterns::{enabled_patterns, label_matches_tool, matches_at_any_depth, KnownPattern, PatternCategory};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// A path a known pattern matched, and what git currently makes of it.
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    pub path: String,
+    pub label: String,
+    pub category: PatternCategory,
+    pub description: &'static str,
+    pub already_excluded: bool,
+    pub is_gitignored: bool,
+    pub is_tracked: bool,
+}
+
+impl Discovery {
+    /// Git still sees it: it isn't layered or ignored.
+    pub fn is_visible(&self) -> bool {
+        !self.already_excluded && !self.is_gitignored
+    }
+
+    /// Visible and untracked, so layering it would hide it.
+    pub fn is_new(&self) -> bool {
+        self.is_visible() && !self.is_tracked
+    }
+}
+
+/// Which pattern matches a discovery pass keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Every match, with its flags set.
+    #[default]
+    All,
+    /// Only [`Discovery::is_visible`] matches.
+    Visible,
+    /// Only [`Discovery::is_new`] matches.
+    New,
+}
+
+/// What a discovery pass looks for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options<'a> {
+    pub filter: Filter,
+    /// Also collect untracked files no pattern matches.
+    pub untracked: bool,
+    /// Only this tool's patterns, named as `layer patterns` lists them.
+    pub tool: Option<&'a str>,
+}
+
+/// The result of a discovery pass.
+#[derive(Debug, Default)]
+pub struct Found {
+    pub known: Vec<Discovery>,
+    /// Untracked, unlayered files that aren't among `known`, when asked for.
+    pub untracked: Vec<String>,
+}
+
+/// One row in a file picker: a path and what it is.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: String,
+    pub category: String,
+}
+
+impl Found {
+    /// What a picker offers to layer: new known files tagged with their
+    /// tool, then the other untracked files.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        let known = self.known.iter().filter(|item| item.is_new()).map(|item| Candidate {
+            path: item.path.clone(),
+            category: item.label.clone(),
+        });
+        let untracked = self.untracked.iter().map(|path| Candidate {
+            path: path.clone(),
+            category: "untracked".to_string(),
+        });
+        known.chain(untracked).collect()
+    }
+}
+
+/// The one discovery pipeline: match known patterns against the working
+/// tree, flag each match, keep what `options.filter` asks for, and collect
+/// untracked files on request. Paths dismissed in `.layerignore` are left
+/// out unless they're layered anyway. `scan`, `status`, `add -i`, `patterns`, and
+/// everything else that looks for context files go through here.
+pub fn discover(
+    ctx: &RepoContext,
+    excluded: &HashSet<String>,
+    tracked: &HashSet<String>,
+    options: Options,
+) -> Result<Found> {
+    let dismissed = layerignore::load(&ctx.root);
+    let mut hits = known_pattern_hits(ctx, excluded, tracked, &dismissed, options.tool)?;
+    hits.retain(|item| item.already_excluded || !layerignore::is_dismissed(&dismissed, &item.path));
+
+    let mut untracked = Vec::new();
+    if options.untracked {
+        let mut seen: HashSet<String> = hits.iter().map(|item| item.path.clone()).collect();
+        for file in git::list_untracked(&ctx.root)? {
+            let normalized = normalize_entry(&file);
+            if !normalized.is_empty()
+                && !excluded.contains(&normalized)
+                && !layerignore::is_dismissed(&dismissed, &normalized)
+                && seen.insert(normalized.clone())
+            {
+                untracked.push(normalized);
+            }
+        }
+    }
+
+    let known = hits
+        .into_iter()
+        .filter(|item| match options.filter {
+            Filter::All => true,
+            Filter::Visible => item.is_visible(),
+            Filter::New => item.is_new(),
+        })
+        .collect();
+    Ok(Found { known, untracked })
+}
+
+/// Known-pattern matches alone, for callers that don't need untracked files.
+pub fn known_files(
+    ctx: &RepoContext,
+    excluded: &HashSet<String>,
+    tracked: &HashSet<String>,
+    filter: Filter,
+) -> Result<Vec<Discovery>> {
+    Ok(discover(ctx, excluded, tracked, Options { filter, ..Options::default() })?.known)
+}
+
+/// Every path a known pattern matches, with its layered/ignored/tracked flags.
+/// With `tool`, only that tool's patterns are matched; `dismissed` directories
+/// aren't walked into.
+fn known_pattern_hits(
+    ctx: &RepoContext,
+    excluded: &HashSet<String>,
+    tracked: &HashSet<String>,
+    dismissed: &[String],
+    tool: Option<&str>,
+) -> Result<Vec<Discovery>> {
+    let mut seen = HashSet::new();
+
+    // First pass: collect all candidate paths with their pattern metadata.
+    struct Hit {
+        normalized: String,
+        label: String,
+        category: PatternCategory,
+        description: &'static str,
+    }
+    let mut candidates = Vec::new();
+    let mut check_ignore_paths = Vec::new();
+    let config = config::load(Some(&ctx.root))?;
+    let depth = config.get_int("scan.depth") as usize;
+    let mut patterns = enabled_patterns(&config);
+    if let Some(tool) = tool {
+        let mut labels: Vec<&str> = Vec::new();
+        for pattern in &patterns {
+            if !labels.contains(&pattern.label) {
+                labels.push(pattern.label);
+            }
+        }
+        patterns.retain(|pattern| label_matches_tool(pattern.label, tool));
+        if patterns.is_empty() {
+            bail!("unknown tool '{}'. Known tools: {}", tool, labels.join(", "));
+        }
+    }
+    let mut discovered = discover_paths(&ctx.root, depth, config.get_list("scan.skip_dirs"), dismissed);
+    let deep: Vec<&str> = patterns
+        .iter()
+        .map(|pattern| pattern.entry)
+        .filter(|entry| matches_at_any_depth(entry))
+        .collect();
+    discovered.extend(deep_paths(&ctx.root, &deep, depth, config.get_list("scan.skip_dirs")));
+
+    for pattern in patterns {
+        let paths = discovered
+            .iter()
+            .filter(|item| pattern_matches_path(pattern.entry, item, depth) && !pattern.spares(&item.match_path));
+        for item in paths {
+            let normalized = normalize_entry(&item.display);
+            if normalized.is_empty() || !seen.insert(normalized.clone()) {
+                continue;
+            }
+            let ignore_target = normalized.trim_end_matches('/').to_string();
+            check_ignore_paths.push(ignore_target);
+            candidates.push(Hit {
+                normalized,
+                label: pattern.label.to_string(),
+                category: pattern.category,
+                description: pattern.description,
+            });
+        }
+    }
+
+    // Batch check-ignore call instead of per-file.
+    let ignore_results = git::check_ignore_bulk(&ctx.root, &check_ignore_paths, false)?;
+
+    let mut out = Vec::new();
+    debug_assert_eq!(candidates.len(), check_ignore_paths.len());
+    for (candidate, ignore_target) in candidates.into_iter().zip(check_ignore_paths.iter()) {
+        let tracked_match = if candidate.normalized.ends_with('/') {
+            tracked.iter().any(|p| p.starts_with(&candidate.normalized))
+        } else {
+            tracked.contains(&candidate.normalized)
+        };
+
+        let is_gitignored = ignore_results.contains_key(ignore_target);
+
+        out.push(Discovery {
+            path: candidate.normalized.clone(),
+            label: candidate.label,
+            category: candidate.category,
+            description: candidate.description,
+            already_excluded: excluded.contains(&candidate.normalized),
+            is_gitignored,
+            is_tracked: tracked_match,
+        });
+    }
+
+    // Second pass: for directory candidates not yet ignored, check if all
+    // files inside are already covered by ignore rules (e.g. global gitignore
+    // covers every file individually).
+    let dir_indices: Vec<usize> = out
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| {
+            item.path.ends_with('/') && !item.already_excluded && !item.is_gitignored
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    // Collect every directory's files first so one check-ignore call covers
+    // them all, instead of one git process per directory.
+    let mut dir_files: Vec<(usize, Vec<String>)> = Vec::new();
+    for idx in dir_indices {
+        let dir_path = ctx.root.join(out[idx].path.trim_end_matches('/'));
+        let Ok(mut children) = fs::read_dir(&dir_path) else {
+            continue;
+        };
+        if children.next().is_none() {
+            out[idx].is_gitignored = true;
+            continue;
+        }
+
+        let mut files_in_dir = Vec::new();
+        for entry in WalkDir::new(&dir_path).min_depth(1) {
+            let entry = match entry {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            if entry.file_type().is_file() {
+                if let Ok(rel) = entry.path().strip_prefix(&ctx.root) {
+                    files_in_dir.push(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+
+        if files_in_dir.is_empty() {
+            out[idx].is_gitignored = true;
+            continue;
+        }
+        dir_files.push((idx, files_in_dir));
+    }
+
+    if !dir_files.is_empty() {
+        let mut all_files: Vec<String> = dir_files.iter().flat_map(|(_, files)| files.iter().cloned()).collect();
+        all_files.sort();
+        all_files.dedup();
+        let file_ignore_results = git::check_ignore_bulk(&ctx.root, &all_files, false)?;
+        for (idx, files_in_dir) in dir_files {
+            if files_in_dir.iter().all(|f| file_ignore_results.contains_key(f)) {
+                out[idx].is_gitignored = true;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Known context files among `paths` (e.g. a tree listed with `git ls-tree`),
+/// matched the same way a scan of the working tree would match them.
+pub fn known_paths_in_tree(
+    paths: &HashSet<String>,
+    depth: usize,
+    patterns: &[&'static KnownPattern],
+) -> Vec<(String, &'static KnownPattern)> {
+    let mut items = Vec::new();
+    let mut dirs = HashSet::new();
+    for path in paths {
+        let parts: Vec<&str> = path.split('/').collect();
+        for n in 1..parts.len() {
+            let dir = parts[..n].join("/");
+            if dirs.insert(dir.clone()) {
+                items.push(DiscoveredPath {
+                    display: format!("{dir}/"),
+                    match_path: dir,
+                    depth: n,
+                    is_dir: true,
+                });
+            }
+        }
+        items.push(DiscoveredPath {
+            display: path.clone(),
+            match_path: path.clone(),
+            depth: parts.len(),
+            is_dir: false,
+        });
+    }
+    items.sort_by(|a, b| a.display.cmp(&b.display));
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for &pattern in patterns {
+        for item in &items {
+            if pattern_matches_path(pattern.entry, item, depth)
+                && !pattern.spares(&item.match_path)
+                && seen.insert(item.display.clone())
+            {
+                out.push((item.display.clone(), pattern));
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone)]
+struct DiscoveredPath {
+    display: String,
+    match_path: String,
+    depth: usize,
+    is_dir: bool,
+}
+
+/// Never walked into: dependencies, build output, and git's own data can hold
+/// hundreds of thousands of files and none of the repo's context files.
+const HEAVY_DIRS: &[&str] = &["node_modules", "target", "vendor", ".git"];
+
+/// Paths under the root that known patterns are matched against. `skip_dirs`
+/// (`scan.skip_dirs`) names directories not to descend into, either by name
+/// at any level (`fixtures`) or by path from the root (`packages/legacy/`),
+/// on top of [`HEAVY_DIRS`]. Directories `dismissed` in `.layerignore` are
+/// still listed, since they may be layered, but not walked into.
+fn discover_paths(
+    repo_root: &Path,
+    depth: usize,
+    skip_dirs: &[String],
+    dismissed: &[String],
+) -> Vec<DiscoveredPath> {
+    let mut out = Vec::new();
+    let skipped = |path: &Path| {
+        let rel = path.strip_prefix(repo_root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        is_skipped_dir(&rel, skip_dirs)
+    };
+
+    // AI and config files live at the repo root or known subdirs like .github/.
+    // `scan.depth` adds nested directory levels for monorepos.
+    let walker = WalkDir::new(repo_root).min_depth(1).max_depth(depth + 2);
+    let mut entries = walker.into_iter().filter_entry(|e| !(e.file_type().is_dir() && skipped(e.path())));
+    while let Some(entry) = entries.next() {
+        let entry = match entry {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let path = entry.path();
+        if path
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy() == ".git")
+        {
+            continue;
+        }
+
+        let rel = match path.strip_prefix(repo_root) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let mut rel_str = rel.to_string_lossy().replace('\\', "/");
+        let is_dir = entry.file_type().is_dir();
+        if is_dir && !rel_str.ends_with('/') {
+            rel_str.push('/');
+        }
+        let depth = rel.components().count();
+        if is_dir && layerignore::is_dismissed(dismissed, &rel_str) {
+            entries.skip_current_dir();
+        }
+
+        out.push(DiscoveredPath {
+            display: rel_str.clone(),
+            match_path: rel_str.trim_end_matches('/').to_string(),
+            depth,
+            is_dir,
+        });
+    }
+
+    out
+}
+
+/// Whether the directory at `rel` (from the root) is one discovery doesn't
+/// descend into: a [`HEAVY_DIRS`] name or one listed in `scan.skip_dirs`.
+fn is_skipped_dir(rel: &str, skip_dirs: &[String]) -> bool {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    HEAVY_DIRS.contains(&name)
+        || skip_dirs.iter().any(|dir| {
+            let dir = dir.trim_end_matches('/');
+            if dir.contains('/') { rel == dir.trim_start_matches('/') } else { name == dir }
+        })
+}
+
+/// Files below the walk's depth that [`matches_at_any_depth`] `entries`
+/// match, from git's untracked and tracked lists rather than a walk of the
+/// whole tree, which would also cover gitignored build output.
+fn deep_paths(repo_root: &Path, entries: &[&str], depth: usize, skip_dirs: &[String]) -> Vec<DiscoveredPath> {
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    let mut args = vec!["ls-files", "--cached", "--others", "--exclude-standard", "--"];
+    args.extend_from_slice(entries);
+    let listed = git::git_stdout(&args, Some(repo_root)).unwrap_or_default();
+    let mut paths: Vec<&str> = listed.lines().map(str::trim).filter(|path| !path.is_empty()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+    paths
+        .into_iter()
+        .map(|path| DiscoveredPath {
+            display: path.to_string(),
+            match_path: path.to_string(),
+            depth: path.split('/').count(),
+            is_dir: false,
+        })
+        .filter(|item| item.depth > depth + 2)
+        .filter(|item| {
+            let dirs: Vec<&str> = item.match_path.split('/').collect();
+            !(1..dirs.len()).any(|n| is_skipped_dir(&dirs[..n].join("/"), skip_dirs))
+        })
+        .collect()
+}
+
+/// The newest modification time among the root and the directories a scan
+/// walks, so a cache can tell when a file came or went anywhere discovery looks.
+pub fn scanned_dirs_mtime(repo_root: &Path, depth: usize, skip_dirs: &[String]) -> u128 {
+    discover_paths(repo_root, depth, skip_dirs, &[])
+        .iter()
+        .filter(|item| item.is_dir)
+        .map(|item| repo_root.join(&item.match_path))
+        .chain([repo_root.to_path_buf()])
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_nanos())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Paths `entry` would discover in the working tree, honoring the repo's
+/// `scan.depth` and `scan.skip_dirs`.
+pub fn paths_matching(ctx: &RepoContext, entry: &str) -> Result<Vec<String>> {
+    let config = config::load(Some(&ctx.root))?;
+    let depth = config.get_int("scan.depth") as usize;
+    let mut walked = discover_paths(&ctx.root, depth, config.get_list("scan.skip_dirs"), &[]);
+    if matches_at_any_depth(entry) {
+        walked.extend(deep_paths(&ctx.root, &[entry], depth, config.get_list("scan.skip_dirs")));
+    }
+    let mut paths: Vec<String> = walked
+        .iter()
+        .filter(|item| pattern_matches_path(entry, item, depth))
+        .map(|item| normalize_entry(&item.display))
+        .filter(|path| !path.is_empty())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+fn pattern_matches_path(pattern: &str, item: &DiscoveredPath, depth: usize) -> bool {
+    let pattern_trimmed = pattern.trim_end_matches('/');
+    let wants_dir = pattern.ends_with('/');
+
+    if wants_dir && !item.is_dir {
+        return false;
+    }
+    if matches_at_any_depth(pattern) {
+        return !item.is_dir && wildcard_match(pattern, item.match_path.rsplit('/').next().unwrap_or(""));
+    }
+    if item.depth > depth + 2 || (!pattern.contains('/') && item.depth > depth + 1) {
+        return false;
+    }
+
+    if git::contains_glob(pattern_trimmed) {
+        if pattern.contains('/') {
+            return wildcard_match(pattern_trimmed, &item.match_path);
+        }
+        return wildcard_match(pattern_trimmed, item.match_path.rsplit('/').next().unwrap_or(""));
+    }
+
+    if pattern.contains('/') {
+        return item.match_path == pattern_trimmed;
+    }
+
+    item.match_path.rsplit('/').next().unwrap_or("") == pattern_trimmed
+}
+
+/// Match `*` and `?` against a path or file name. Known patterns stay within
+/// that syntax, and `parse_custom_pattern` rejects anything richer in
+/// `scan.patterns`, so this doesn't need git's full glob rules.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p = pattern.as_bytes();
+    let t = text.as_bytes();
+    let (mut pi, mut ti) = (0usize, 0usize);
+    let mut star_idx = None;
+    let mut match_idx = 0usize;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == b'?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == b'*' {
+            star_idx = Some(pi);
+            pi += 1;
+            match_idx = ti;
+        } else if let Some(star) = star_idx {
+            pi = star + 1;
+            match_idx += 1;
+            ti = match_idx;
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == b'*' {
+        pi += 1;
+    }
+
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery(path: &str, already_excluded: bool, is_tracked: bool) -> Discovery {
+        Discovery {
+            path: path.to_string(),
+            label: "Claude Code".to_string(),
+            category: PatternCategory::AiConfig,
+            description: "",
+            already_excluded,
+            is_gitignored: false,
+            is_tracked,
+        }
+    }
+
+    #[test]
+    fn candidates_offer_new_known_files_then_untracked_ones() {
+        let found = Found {
+            known: vec![
+                discovery("CLAUDE.md", false, false),
+                discovery(".claude/", true, false),
+                discovery("AGENTS.md", false, true),
+            ],
+            untracked: vec!["notes.txt".to_string()],
+        };
+        let rows: Vec<(String, String)> =
+            found.candidates().into_iter().map(|c| (c.path, c.category)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("CLAUDE.md".to_string(), "Claude Code".to_string()),
+                ("notes.txt".to_string(), "untracked".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wildcard_exact_match() {
+        assert!(wildcard_match("CLAUDE.md", "CLAUDE.md"));
+        assert!(!wildcard_match("CLAUDE.md", "claude.md"));
+    }
+
+    #[test]
+    fn wildcard_star() {
+        assert!(wildcard_match(".aider*", ".aider"));
+        assert!(wildcard_match(".aider*", ".aider.conf.yml"));
+        assert!(wildcard_match(".env.*", ".env.local"));
+        assert!(wildcard_match(".env.*", ".env.production"));
+        assert!(!wildcard_match(".env.*", ".env"));
+    }
+
+    #[test]
+    fn wildcard_question_mark() {
+        assert!(wildcard_match("file?.txt", "file1.txt"));
+        assert!(!wildcard_match("file?.txt", "file12.txt"));
+    }
+
+    #[test]
+    fn wildcard_empty_strings() {
+        assert!(wildcard_match("", ""));
+        assert!(!wildcard_match("a", ""));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*", "anything"));
+    }
+
+    #[test]
+    fn known_paths_in_tree_matches_files_and_dirs() {
+        let paths: HashSet<String> = ["CLAUDE.md", ".cursor/rules/a.md", "src/main.rs", "deep/a/b/CLAUDE.md"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let patterns: Vec<_> = crate::patterns::KNOWN_SCAN_PATTERNS.iter().collect();
+        let found: Vec<String> = known_paths_in_tree(&paths, 0, &patterns).into_iter().map(|(p, _)| p).collect();
+        assert!(found.contains(&"CLAUDE.md".to_string()));
+        assert!(found.contains(&".cursor/".to_string()));
+        assert!(!found.iter().any(|p| p.starts_with("src") || p.starts_with("deep")));
+    }
+}
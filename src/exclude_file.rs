@@ -1,12 +1,269 @@
-use anyhow::{Context, Result};
+use crate::config;
+use crate::git;
+use crate::journal;
+use crate::patterns::{enabled_patterns, known_pattern_for, tool_labels_in, KnownPattern, KNOWN_SCAN_PATTERNS};
+use crate::ui;
+use anyhow::{bail, Context, Result};
 use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
+/// Default section markers. Overridable via `layer.sectionStart` / `layer.sectionEnd`.
 pub const SECTION_START: &str = "# managed by layer";
 pub const SECTION_END: &str = "# end layer";
 pub const DISABLED_PREFIX: &str = "# [off] ";
 
+/// Comment lines delimiting the layer-owned block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Markers {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Self {
+            start: SECTION_START.to_string(),
+            end: SECTION_END.to_string(),
+        }
+    }
+}
+
+impl Markers {
+    /// Read custom markers from git config so orgs with their own block
+    /// comments can have layer adopt that block. Invalid values fall back to
+    /// the defaults with a warning.
+    fn from_git_config() -> Self {
+        let defaults = Self::default();
+        let start = git_config_value("layer.sectionStart");
+        let end = git_config_value("layer.sectionEnd");
+        if start.is_none() && end.is_none() {
+            return defaults;
+        }
+
+        let markers = Self {
+            start: start.unwrap_or(defaults.start.clone()),
+            end: end.unwrap_or(defaults.end.clone()),
+        };
+        if let Err(reason) = markers.validate() {
+            ui::print_stderr_warning(&format!("ignoring custom section markers: {reason}"));
+            return defaults;
+        }
+        markers
+    }
+
+    fn validate(&self) -> std::result::Result<(), &'static str> {
+        if !self.start.starts_with('#') || !self.end.starts_with('#') {
+            return Err("markers must be comments starting with '#'");
+        }
+        if self.start == self.end {
+            return Err("start and end markers must differ");
+        }
+        if self.start.starts_with(DISABLED_PREFIX.trim_end())
+            || self.end.starts_with(DISABLED_PREFIX.trim_end())
+        {
+            return Err("markers can't look like disabled entries");
+        }
+        Ok(())
+    }
+
+    /// Whether a line is a section marker, configured or default.
+    pub fn is_marker(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        trimmed == self.start || trimmed == self.end || trimmed == SECTION_START || trimmed == SECTION_END
+    }
+}
+
+/// Markers in effect for this process, resolved once from git config.
+pub fn markers() -> &'static Markers {
+    static MARKERS: OnceLock<Markers> = OnceLock::new();
+    MARKERS.get_or_init(Markers::from_git_config)
+}
+
+fn git_config_value(key: &str) -> Option<String> {
+    let output = git::output(Command::new("git").args(["config", "--get", key])).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?;
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// How managed entries are ordered when the exclude file is written
+/// (`exclude.order`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryOrder {
+    /// Keep entries where they were added.
+    Insertion,
+    Alphabetical,
+    /// Group entries by the tool they belong to, in scan-pattern order.
+    Tool,
+}
+
+impl EntryOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "insertion" => Some(Self::Insertion),
+            "alphabetical" => Some(Self::Alphabetical),
+            "tool" => Some(Self::Tool),
+            _ => None,
+        }
+    }
+}
+
+/// What writes do when `.git/info/exclude` is a symlink (`exclude.symlink`),
+/// e.g. to a dotfiles-managed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkMode {
+    /// Write the link's target and keep the link.
+    Follow,
+    /// Replace the link with a regular file.
+    Replace,
+    /// Refuse to write.
+    Refuse,
+}
+
+impl SymlinkMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "follow" => Some(Self::Follow),
+            "replace" => Some(Self::Replace),
+            "refuse" => Some(Self::Refuse),
+            _ => None,
+        }
+    }
+}
+
+/// Config that shapes how the exclude file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteSettings {
+    pub order: EntryOrder,
+    pub symlink: SymlinkMode,
+}
+
+impl Default for WriteSettings {
+    fn default() -> Self {
+        Self {
+            order: EntryOrder::Insertion,
+            symlink: SymlinkMode::Follow,
+        }
+    }
+}
+
+/// Config for the repo this process runs in, for settings resolved once.
+fn process_config() -> Result<config::Config> {
+    let root = git::output(Command::new("git").args(["rev-parse", "--show-toplevel"]))
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| std::path::PathBuf::from(s.trim()));
+    config::load(root.as_deref())
+}
+
+impl WriteSettings {
+    fn from_config() -> Self {
+        match process_config() {
+            Ok(config) => {
+                let defaults = Self::default();
+                Self {
+                    order: EntryOrder::parse(config.get_str("exclude.order")).unwrap_or(defaults.order),
+                    symlink: SymlinkMode::parse(config.get_str("exclude.symlink")).unwrap_or(defaults.symlink),
+                }
+            }
+            Err(err) => {
+                ui::print_stderr_warning(&format!("using default write settings: {err:#}"));
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Write settings in effect for this process, resolved once from config.
+pub fn write_settings() -> WriteSettings {
+    static SETTINGS: OnceLock<WriteSettings> = OnceLock::new();
+    *SETTINGS.get_or_init(WriteSettings::from_config)
+}
+
+/// Entry order in effect for this process.
+pub fn entry_order() -> EntryOrder {
+    write_settings().order
+}
+
+/// Where a write to `path` should land. A symlinked exclude file is
+/// followed, replaced, or refused depending on `mode`.
+fn write_target(path: &Path, mode: SymlinkMode) -> Result<PathBuf> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if !is_link {
+        return Ok(path.to_path_buf());
+    }
+    match mode {
+        SymlinkMode::Follow => {
+            if let Ok(resolved) = fs::canonicalize(path) {
+                return Ok(resolved);
+            }
+            // Dangling link: write where it points so the link comes alive.
+            let target = fs::read_link(path)
+                .with_context(|| format!("failed to read symlink {}", path.display()))?;
+            Ok(match path.parent() {
+                Some(parent) if target.is_relative() => parent.join(target),
+                _ => target,
+            })
+        }
+        SymlinkMode::Replace => {
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove symlink {}", path.display()))?;
+            Ok(path.to_path_buf())
+        }
+        SymlinkMode::Refuse => {
+            let target = fs::read_link(path).unwrap_or_default();
+            bail!(
+                "{} is a symlink to {}; set exclude.symlink to 'follow' or 'replace' to let layer write it",
+                path.display(),
+                target.display()
+            )
+        }
+    }
+}
+
+/// The entry a managed line stands for, active or disabled. Blank lines and
+/// other comments return `None`.
+pub fn line_entry(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if let Some(value) = trimmed.strip_prefix(DISABLED_PREFIX) {
+        return Some(value.trim()).filter(|v| !v.is_empty());
+    }
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        None
+    } else {
+        Some(trimmed)
+    }
+}
+
+/// The repo's enabled patterns, which `exclude.order = "tool"` groups by,
+/// resolved once like the write settings.
+fn tool_patterns() -> &'static [&'static KnownPattern] {
+    static PATTERNS: OnceLock<Vec<&'static KnownPattern>> = OnceLock::new();
+    PATTERNS.get_or_init(|| match process_config() {
+        Ok(config) => enabled_patterns(&config),
+        Err(_) => KNOWN_SCAN_PATTERNS.iter().collect(),
+    })
+}
+
+/// Position of the entry's tool among `labels`; unknown entries last.
+fn tool_rank(entry: &str, patterns: &[&'static KnownPattern], labels: &[&str]) -> usize {
+    known_pattern_for(entry, patterns)
+        .and_then(|known| labels.iter().position(|label| *label == known.label))
+        .unwrap_or(labels.len())
+}
+
 #[derive(Debug, Clone)]
 pub struct Entry {
     pub value: String,
@@ -35,53 +292,96 @@ impl ExcludeFile {
     }
 
     pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::load_with_content(path)?.0)
+    }
+
+    /// Load the file along with the content it was parsed from, for
+    /// [`ExcludeFile::write_if_unchanged`].
+    pub fn load_with_content(path: &Path) -> Result<(Self, String)> {
+        Self::load_with_markers(path, markers())
+    }
+
+    /// [`ExcludeFile::load_with_content`] for files shared by every repo, such
+    /// as the global ignore file, which always use the default markers.
+    pub fn load_global_with_content(path: &Path) -> Result<(Self, String)> {
+        Self::load_with_markers(path, &Markers::default())
+    }
+
+    fn load_with_markers(path: &Path, markers: &Markers) -> Result<(Self, String)> {
         if !path.exists() {
-            return Ok(Self::empty());
+            return Ok((Self::empty(), String::new()));
         }
 
         let content = fs::read_to_string(path)
             .with_context(|| format!("failed to read {}", path.display()))?;
-        Ok(Self::parse(&content))
+        let (file, duplicates) = Self::parse_sections(&content, markers);
+        if duplicates > 0 {
+            ui::print_stderr_warning(&format!(
+                "{} has {} duplicate layer section(s); treating them as one (merged on next write)",
+                path.display(),
+                duplicates
+            ));
+        }
+        Ok((file, content))
     }
 
-    fn parse(content: &str) -> Self {
-        let lines: Vec<String> = content.lines().map(ToOwned::to_owned).collect();
+    pub fn parse(content: &str) -> Self {
+        Self::parse_sections(content, markers()).0
+    }
 
-        let start_idx = lines.iter().position(|l| l.trim() == SECTION_START);
+    #[cfg(test)]
+    fn parse_with_markers(content: &str, markers: &Markers) -> Self {
+        Self::parse_sections(content, markers).0
+    }
 
-        let Some(start) = start_idx else {
-            // No section found — all lines are user-owned prefix
-            return Self {
-                prefix: lines,
-                managed: Vec::new(),
-                suffix: Vec::new(),
-            };
-        };
+    /// Split content into prefix / managed / suffix. Extra managed sections
+    /// (bad backup merges, manual pastes) are folded into the first one with
+    /// duplicate lines dropped. Returns the number of extra sections merged.
+    fn parse_sections(content: &str, markers: &Markers) -> (Self, usize) {
+        let lines: Vec<String> = content.lines().map(ToOwned::to_owned).collect();
 
-        let end_idx = lines[start + 1..]
-            .iter()
-            .position(|l| l.trim() == SECTION_END)
-            .map(|i| i + start + 1);
+        // Migration: when custom markers are configured but absent, adopt a
+        // block written with the default markers. It is rewritten on save.
+        let legacy = Markers::default();
+        let active = if lines.iter().any(|l| l.trim() == markers.start) {
+            markers
+        } else {
+            &legacy
+        };
 
-        let prefix = lines[..start].to_vec();
+        let mut file = Self::empty();
+        let mut sections = 0usize;
+        let mut inside = false;
 
-        match end_idx {
-            Some(end) => {
-                let managed = lines[start + 1..end].to_vec();
-                let suffix = lines[end + 1..].to_vec();
-                Self { prefix, managed, suffix }
+        for line in lines {
+            let trimmed = line.trim();
+            if trimmed == active.start {
+                sections += 1;
+                inside = true;
+                continue;
             }
-            None => {
-                // Migration: start marker exists but no end marker.
-                // Treat everything after the start marker as managed.
-                let managed = lines[start + 1..].to_vec();
-                Self {
-                    prefix,
-                    managed,
-                    suffix: Vec::new(),
+            if inside && trimmed == active.end {
+                inside = false;
+                continue;
+            }
+
+            if inside {
+                // A start marker without an end marker (older versions) makes
+                // everything after it managed.
+                let duplicate = sections > 1
+                    && !trimmed.is_empty()
+                    && file.managed.iter().any(|m| m.trim() == trimmed);
+                if !duplicate {
+                    file.managed.push(line);
                 }
+            } else if sections == 0 {
+                file.prefix.push(line);
+            } else {
+                file.suffix.push(line);
             }
         }
+
+        (file, sections.saturating_sub(1))
     }
 
     /// Returns entries within the layer-managed section only.
@@ -253,26 +553,203 @@ impl ExcludeFile {
         self.managed.clear();
     }
 
-    /// Write the file, reconstructing: prefix + section markers + managed + suffix.
-    pub fn write(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
+    /// Reorder managed entries. Blank lines and comments stay where they are
+    /// and split the section into runs; each run is sorted on its own with a
+    /// stable sort, so equal keys keep their insertion order.
+    pub fn apply_order(&mut self, order: EntryOrder) {
+        if order == EntryOrder::Insertion {
+            return;
+        }
+        let mut start = 0;
+        while start < self.managed.len() {
+            if line_entry(&self.managed[start]).is_none() {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < self.managed.len() && line_entry(&self.managed[end]).is_some() {
+                end += 1;
+            }
+            let run = &mut self.managed[start..end];
+            match order {
+                EntryOrder::Alphabetical => run.sort_by(|a, b| line_entry(a).cmp(&line_entry(b))),
+                EntryOrder::Tool => {
+                    let patterns = tool_patterns();
+                    let labels = tool_labels_in(patterns);
+                    run.sort_by_key(|line| {
+                        line_entry(line).map_or(usize::MAX, |entry| tool_rank(entry, patterns, &labels))
+                    })
+                }
+                EntryOrder::Insertion => {}
+            }
+            start = end;
         }
+    }
 
+    /// Render the file contents: prefix + section markers + managed + suffix.
+    pub fn render(&self) -> String {
+        self.render_with(markers())
+    }
+
+    fn render_with(&self, markers: &Markers) -> String {
         let mut out = Vec::new();
         out.extend(self.prefix.iter().cloned());
-        out.push(SECTION_START.to_string());
+        out.push(markers.start.clone());
         out.extend(self.managed.iter().cloned());
-        out.push(SECTION_END.to_string());
+        out.push(markers.end.clone());
         out.extend(self.suffix.iter().cloned());
 
         let mut content = out.join("\n");
         if !content.is_empty() {
             content.push('\n');
         }
+        content
+    }
 
-        fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+    /// Write the file, reconstructing: prefix + section markers + managed + suffix.
+    /// Managed entries are put in the configured `exclude.order` first, and
+    /// changes to them are recorded in the operation journal.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        self.write_with(path, write_settings(), markers())
+    }
+
+    /// Write the file only if it still holds `loaded`, holding `<path>.lock`
+    /// meanwhile. For files other tools or other repos' layer runs may edit
+    /// at the same time, so neither side's changes get silently dropped.
+    pub fn write_if_unchanged(&self, path: &Path, loaded: &str) -> Result<()> {
+        self.write_locked(path, loaded, write_settings(), markers())
+    }
+
+    /// [`ExcludeFile::write_if_unchanged`] for files shared by every repo:
+    /// written with the default settings and markers, not the current repo's.
+    pub fn write_global_if_unchanged(&self, path: &Path, loaded: &str) -> Result<()> {
+        self.write_locked(path, loaded, WriteSettings::default(), &Markers::default())
+    }
+
+    fn write_locked(&self, path: &Path, loaded: &str, settings: WriteSettings, markers: &Markers) -> Result<()> {
+        let _lock = FileLock::acquire(path, LOCK_WAIT)?;
+        let current = fs::read_to_string(path).unwrap_or_default();
+        if current != loaded {
+            bail!(
+                "{} changed while layer was editing it; nothing was written. Run the command again",
+                path.display()
+            );
+        }
+        self.write_with(path, settings, markers)
+    }
+
+    fn write_with(&self, path: &Path, settings: WriteSettings, markers: &Markers) -> Result<()> {
+        let before = fs::read_to_string(path).unwrap_or_default();
+        let mut ordered = self.clone();
+        ordered.apply_order(settings.order);
+        let after = ordered.render_with(markers);
+        write_contents(path, &after, settings.symlink)?;
+        if let Err(err) = ordered.verify_written(path, markers) {
+            write_contents(path, &before, settings.symlink)
+                .with_context(|| format!("{err:#}, and restoring the previous content failed"))?;
+            bail!("{err:#}; restored the previous content. This is a bug in layer, please report it");
+        }
+        journal::record_change(path, &before, &after);
+        Ok(())
+    }
+
+    /// Re-read `path` after a write and check that the layer section and the
+    /// lines around it parse back exactly as they were written, so a bad
+    /// write fails right away instead of corrupting the file unnoticed.
+    fn verify_written(&self, path: &Path, markers: &Markers) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read back {}", path.display()))?;
+        let (written, _) = Self::parse_sections(&content, markers);
+        let regions = [
+            ("the lines before the layer section", &self.prefix, &written.prefix),
+            ("the layer section", &self.managed, &written.managed),
+            ("the lines after the layer section", &self.suffix, &written.suffix),
+        ];
+        for (region, expected, actual) in regions {
+            if expected != actual {
+                bail!("{} did not read back as written: {region} changed", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write raw exclude-file content, honoring `mode` when `path` is a symlink.
+/// The content goes to a temporary file first and is renamed into place, so
+/// readers never see a half-written file.
+pub fn write_contents(path: &Path, content: &str, mode: SymlinkMode) -> Result<()> {
+    let target = write_target(path, mode)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let tmp = sibling(&target, ".layer-tmp");
+    fs::write(&tmp, content).with_context(|| format!("failed to write {}", tmp.display()))?;
+    if let Err(err) = fs::rename(&tmp, &target) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("failed to write {}", target.display()));
+    }
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// How long to wait for another process's lock before giving up.
+const LOCK_WAIT: Duration = Duration::from_secs(3);
+
+/// A lock older than this belongs to a process that died mid-write.
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// An exclusive `<path>.lock` file, removed on drop, like git's own
+/// `index.lock`.
+struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    fn acquire(target: &Path, wait: Duration) -> Result<Self> {
+        let path = sibling(target, ".lock");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let started = Instant::now();
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > STALE_LOCK));
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if started.elapsed() >= wait {
+                        bail!(
+                            "{} is locked by another layer process. If none is running, remove {}",
+                            target.display(),
+                            path.display()
+                        );
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| format!("failed to create {}", path.display()));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
     }
 }
 
@@ -326,6 +803,60 @@ pub fn normalize_entry(input: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn write_if_unchanged_refuses_after_a_concurrent_edit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("ignore");
+        fs::write(&path, "*.log\n").expect("write");
+        let (mut file, loaded) = ExcludeFile::load_with_content(&path).expect("load");
+        file.append_entry(".DS_Store");
+
+        fs::write(&path, "*.log\n*.swp\n").expect("write");
+        let err = file.write_if_unchanged(&path, &loaded).unwrap_err();
+        assert!(err.to_string().contains("changed while layer was editing it"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "*.log\n*.swp\n");
+
+        let (mut file, loaded) = ExcludeFile::load_with_content(&path).expect("load");
+        file.append_entry(".DS_Store");
+        file.write_if_unchanged(&path, &loaded).expect("write");
+        assert!(fs::read_to_string(&path).unwrap().contains(".DS_Store"));
+        assert!(!sibling(&path, ".lock").exists());
+        assert!(!sibling(&path, ".layer-tmp").exists());
+    }
+
+    #[test]
+    fn write_restores_the_file_when_it_does_not_read_back_as_written() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("exclude");
+        let before = "mine.txt\n# managed by layer\nCLAUDE.md\n# end layer\n";
+        fs::write(&path, before).expect("write");
+
+        // An entry that looks like the end marker would cut the section short.
+        let mut file = ExcludeFile::load(&path).expect("load");
+        file.managed.push(format!("  {SECTION_END}"));
+        file.managed.push("AGENTS.md".into());
+        let err = file.write_with(&path, WriteSettings::default(), markers()).unwrap_err();
+        assert!(err.to_string().contains("the layer section changed"));
+        assert!(err.to_string().contains("restored the previous content"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+        let mut file = ExcludeFile::load(&path).expect("load");
+        file.append_entry("AGENTS.md");
+        file.write_with(&path, WriteSettings::default(), markers()).expect("write");
+        assert!(fs::read_to_string(&path).unwrap().contains("AGENTS.md"));
+    }
+
+    #[test]
+    fn file_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("ignore");
+        let lock = FileLock::acquire(&path, Duration::ZERO).expect("lock");
+        let err = FileLock::acquire(&path, Duration::ZERO).err().expect("second lock fails");
+        assert!(err.to_string().contains("locked by another layer process"));
+        drop(lock);
+        FileLock::acquire(&path, Duration::ZERO).expect("lock again");
+    }
+
     #[test]
     fn normalize_strips_dot_slash() {
         assert_eq!(normalize_entry("./CLAUDE.md"), "CLAUDE.md");
@@ -407,6 +938,71 @@ mod tests {
         assert!(file.suffix.is_empty());
     }
 
+    #[test]
+    fn parse_custom_markers() {
+        let markers = Markers {
+            start: "# BEGIN local".to_string(),
+            end: "# END local".to_string(),
+        };
+        let file = ExcludeFile::parse_with_markers(
+            "user\n# BEGIN local\nCLAUDE.md\n# END local\nafter",
+            &markers,
+        );
+        assert_eq!(file.prefix, vec!["user"]);
+        assert_eq!(file.managed, vec!["CLAUDE.md"]);
+        assert_eq!(file.suffix, vec!["after"]);
+    }
+
+    #[test]
+    fn parse_custom_markers_migrates_default_block() {
+        let markers = Markers {
+            start: "# BEGIN local".to_string(),
+            end: "# END local".to_string(),
+        };
+        let file = ExcludeFile::parse_with_markers(
+            "# managed by layer\nCLAUDE.md\n# end layer",
+            &markers,
+        );
+        assert_eq!(file.managed, vec!["CLAUDE.md"]);
+        assert!(file.prefix.is_empty());
+    }
+
+    #[test]
+    fn markers_validate_rejects_bad_values() {
+        let not_comment = Markers {
+            start: "BEGIN".to_string(),
+            end: "# END".to_string(),
+        };
+        assert!(not_comment.validate().is_err());
+        let same = Markers {
+            start: "# x".to_string(),
+            end: "# x".to_string(),
+        };
+        assert!(same.validate().is_err());
+        assert!(Markers::default().validate().is_ok());
+    }
+
+    #[test]
+    fn parse_merges_duplicate_sections() {
+        let (file, duplicates) = ExcludeFile::parse_sections(
+            "a\n# managed by layer\nCLAUDE.md\n# end layer\nmine.txt\n# managed by layer\nCLAUDE.md\nAgents.md\n# end layer\nb",
+            &Markers::default(),
+        );
+        assert_eq!(duplicates, 1);
+        assert_eq!(file.prefix, vec!["a"]);
+        assert_eq!(file.managed, vec!["CLAUDE.md", "Agents.md"]);
+        assert_eq!(file.suffix, vec!["mine.txt", "b"]);
+    }
+
+    #[test]
+    fn parse_single_section_reports_no_duplicates() {
+        let (_, duplicates) = ExcludeFile::parse_sections(
+            "# managed by layer\nCLAUDE.md\n# end layer",
+            &Markers::default(),
+        );
+        assert_eq!(duplicates, 0);
+    }
+
     // --- entries / user_entries ---
 
     #[test]
@@ -610,4 +1206,93 @@ mod tests {
         file.enable_all();
         assert_eq!(file.managed, original);
     }
+
+    // --- ordering ---
+
+    fn managed(lines: &[&str]) -> ExcludeFile {
+        ExcludeFile {
+            prefix: Vec::new(),
+            managed: lines.iter().map(|l| l.to_string()).collect(),
+            suffix: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn apply_order_insertion_is_noop() {
+        let mut file = managed(&["b.md", "a.md"]);
+        file.apply_order(EntryOrder::Insertion);
+        assert_eq!(file.managed, vec!["b.md", "a.md"]);
+    }
+
+    #[test]
+    fn apply_order_alphabetical_includes_disabled() {
+        let mut file = managed(&["c.md", "# [off] b.md", "a.md"]);
+        file.apply_order(EntryOrder::Alphabetical);
+        assert_eq!(file.managed, vec!["a.md", "# [off] b.md", "c.md"]);
+    }
+
+    #[test]
+    fn apply_order_keeps_comments_and_blanks_in_place() {
+        let mut file = managed(&["z.md", "y.md", "", "# notes", "b.md", "a.md"]);
+        file.apply_order(EntryOrder::Alphabetical);
+        assert_eq!(file.managed, vec!["y.md", "z.md", "", "# notes", "a.md", "b.md"]);
+    }
+
+    #[test]
+    fn apply_order_tool_groups_and_is_stable() {
+        let mut file = managed(&["notes.txt", ".cursorrules", "CLAUDE.md", ".cursor/", ".claude/"]);
+        file.apply_order(EntryOrder::Tool);
+        assert_eq!(
+            file.managed,
+            vec!["CLAUDE.md", ".claude/", ".cursorrules", ".cursor/", "notes.txt"]
+        );
+    }
+
+    #[cfg(unix)]
+    fn symlinked_exclude() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("dotfiles").join("exclude");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "*.log\n").unwrap();
+        let link = dir.path().join("exclude");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        (dir, link, target)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_follows_symlink_by_default() {
+        let (_dir, link, target) = symlinked_exclude();
+        let file = managed(&["CLAUDE.md"]);
+        file.write_with(&link, WriteSettings::default(), markers()).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert!(fs::read_to_string(&target).unwrap().contains("CLAUDE.md"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_replace_and_refuse_symlink_modes() {
+        let (_dir, link, target) = symlinked_exclude();
+        let file = managed(&["CLAUDE.md"]);
+        let refuse = WriteSettings {
+            symlink: SymlinkMode::Refuse,
+            ..WriteSettings::default()
+        };
+        assert!(file.write_with(&link, refuse, markers()).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "*.log\n");
+
+        let replace = WriteSettings {
+            symlink: SymlinkMode::Replace,
+            ..WriteSettings::default()
+        };
+        file.write_with(&link, replace, markers()).unwrap();
+        assert!(!fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "*.log\n");
+    }
+
+    #[test]
+    fn entry_order_parse() {
+        assert_eq!(EntryOrder::parse("tool"), Some(EntryOrder::Tool));
+        assert_eq!(EntryOrder::parse("random"), None);
+    }
 }
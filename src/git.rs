@@ -1,13 +1,60 @@
+use crate::wsl;
 use anyhow::{anyhow, Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+static TRACE: AtomicBool = AtomicBool::new(false);
+
+/// Log every git invocation, its duration, and exit status to stderr
+/// (`--verbose`).
+pub fn set_trace(enabled: bool) {
+    TRACE.store(enabled, Ordering::Relaxed);
+}
+
+/// Run `cmd` to completion, tracing it when `--verbose` is on. Every git
+/// call layer makes should go through here or `trace`.
+pub fn output(cmd: &mut Command) -> std::io::Result<Output> {
+    let start = Instant::now();
+    let result = cmd.output();
+    trace(cmd, start, result.as_ref().ok().map(|o| o.status));
+    result
+}
+
+/// Trace a command that was started at `start` and has finished with `status`
+/// (`None` when it could not be run).
+pub fn trace(cmd: &Command, start: Instant, status: Option<ExitStatus>) {
+    if !TRACE.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut line = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            line.push_str(&format!(" '{arg}'"));
+        } else {
+            line.push(' ');
+            line.push_str(&arg);
+        }
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        line.push_str(&format!(" (in {})", dir.display()));
+    }
+    let outcome = match status.and_then(|s| s.code()) {
+        Some(code) => format!("exit {code}"),
+        None if status.is_some() => "killed".to_string(),
+        None => "failed to start".to_string(),
+    };
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    crate::ui::print_trace(&format!("{line} → {outcome} in {elapsed:.1}ms"));
+}
 
 #[derive(Debug, Clone)]
 pub struct RepoContext {
     pub root: PathBuf,
-    #[allow(dead_code)]
     pub git_dir: PathBuf,
     pub exclude_path: PathBuf,
 }
@@ -16,19 +63,26 @@ pub struct RepoContext {
 pub struct IgnoreMatch {
     pub source: String,
     pub line: usize,
-    #[allow(dead_code)]
     pub pattern: String,
 }
 
+/// The repository containing the working directory (set by `-C`).
 pub fn ensure_repo() -> Result<RepoContext> {
-    let git_dir_raw = git_stdout(&["rev-parse", "--git-dir"], None)
+    let cwd = std::env::current_dir().context("failed to read the current directory")?;
+    ensure_repo_in(&cwd)
+}
+
+/// The repository containing `dir`.
+pub fn ensure_repo_in(dir: &Path) -> Result<RepoContext> {
+    let git_dir_raw = git_stdout(&["rev-parse", "--git-dir"], Some(dir))
         .map_err(|_| anyhow!("Error: not a git repository"))?;
 
-    let root_raw = git_stdout(&["rev-parse", "--show-toplevel"], None)
+    let root_raw = git_stdout(&["rev-parse", "--show-toplevel"], Some(dir))
         .map_err(|_| anyhow!("Error: not a git repository"))?;
 
-    let root = PathBuf::from(root_raw.trim());
-    let git_dir = resolve_git_dir(&root, git_dir_raw.trim());
+    let root = wsl::normalize_git_path(root_raw.trim());
+    // `--git-dir` is relative to `dir` when it isn't absolute.
+    let git_dir = resolve_git_dir(dir, wsl::normalize_git_path(git_dir_raw.trim()));
     let exclude_path = git_dir.join("info").join("exclude");
 
     Ok(RepoContext {
@@ -38,12 +92,11 @@ pub fn ensure_repo() -> Result<RepoContext> {
     })
 }
 
-fn resolve_git_dir(root: &Path, git_dir_raw: &str) -> PathBuf {
-    let path = PathBuf::from(git_dir_raw);
+fn resolve_git_dir(base: &Path, path: PathBuf) -> PathBuf {
     if path.is_absolute() {
         path
     } else {
-        root.join(path)
+        base.join(path)
     }
 }
 
@@ -54,9 +107,7 @@ pub fn git_stdout(args: &[&str], cwd: Option<&Path>) -> Result<String> {
         cmd.current_dir(cwd);
     }
 
-    let output = cmd
-        .output()
-        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    let output = output(&mut cmd).with_context(|| format!("failed to run git {}", args.join(" ")))?;
 
     if !output.status.success() {
         return Err(anyhow!(
@@ -74,15 +125,22 @@ pub fn is_tracked(repo_root: &Path, file: &str) -> Result<bool> {
         return Ok(false);
     }
 
-    let output = Command::new("git")
-        .args(["ls-files", "--error-unmatch", "--", file])
-        .current_dir(repo_root)
-        .output()
-        .with_context(|| format!("failed to run git ls-files for {file}"))?;
+    let output = output(
+        Command::new("git")
+            .args(["ls-files", "--error-unmatch", "--", file])
+            .current_dir(repo_root),
+    )
+    .with_context(|| format!("failed to run git ls-files for {file}"))?;
 
     Ok(output.status.success())
 }
 
+/// Remove a path from the index, keeping the working-tree copy.
+pub fn untrack(repo_root: &Path, path: &str) -> Result<()> {
+    git_stdout(&["rm", "--cached", "-r", "-q", "--", path], Some(repo_root))?;
+    Ok(())
+}
+
 pub fn list_untracked(repo_root: &Path) -> Result<Vec<String>> {
     let out = git_stdout(&["ls-files", "--others", "--exclude-standard"], Some(repo_root))?;
     Ok(out
@@ -93,6 +151,17 @@ pub fn list_untracked(repo_root: &Path) -> Result<Vec<String>> {
         .collect())
 }
 
+/// Every untracked file, ignored ones included.
+pub fn list_all_untracked(repo_root: &Path) -> Result<Vec<String>> {
+    let out = git_stdout(&["ls-files", "--others"], Some(repo_root))?;
+    Ok(out
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
 pub fn list_tracked(repo_root: &Path) -> Result<HashSet<String>> {
     let out = git_stdout(&["ls-files"], Some(repo_root))?;
     Ok(out
@@ -103,6 +172,19 @@ pub fn list_tracked(repo_root: &Path) -> Result<HashSet<String>> {
         .collect())
 }
 
+/// Files in the tree at `rev` (a branch, tag, or commit), without checking it out.
+pub fn list_tree(repo_root: &Path, rev: &str) -> Result<HashSet<String>> {
+    git_stdout(&["rev-parse", "--verify", "--quiet", &format!("{rev}^{{tree}}")], Some(repo_root))
+        .map_err(|_| anyhow!("unknown revision '{rev}'"))?;
+    let out = git_stdout(&["ls-tree", "-r", "--name-only", rev], Some(repo_root))?;
+    Ok(out
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
 pub fn check_ignore_verbose(repo_root: &Path, path: &str) -> Result<Option<IgnoreMatch>> {
     check_ignore_verbose_with_mode(repo_root, path, false)
 }
@@ -122,10 +204,7 @@ fn check_ignore_verbose_with_mode(
     }
     args.extend(["--", path]);
 
-    let output = Command::new("git")
-        .args(args)
-        .current_dir(repo_root)
-        .output()
+    let output = output(Command::new("git").args(args).current_dir(repo_root))
         .with_context(|| format!("failed to run git check-ignore for {path}"))?;
 
     if !output.status.success() {
@@ -165,6 +244,7 @@ pub fn check_ignore_bulk(
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
+    let start = Instant::now();
     let mut child = cmd.spawn().context("failed to spawn git check-ignore")?;
     {
         let stdin = child
@@ -182,6 +262,7 @@ pub fn check_ignore_bulk(
     let output = child
         .wait_with_output()
         .context("failed waiting for git check-ignore output")?;
+    trace(&cmd, start, Some(output.status));
 
     // check-ignore exits 0 when any path matched, 1 when none matched.
     if !output.status.success() && output.status.code() != Some(1) {
@@ -206,6 +287,73 @@ pub fn check_ignore_bulk(
     Ok(out)
 }
 
+/// How git's verdict for a layered path differs from what the exclude rule
+/// alone would suggest, found by comparing `check-ignore` with and without
+/// `--no-index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreDiscrepancy {
+    /// The exclude rule matches, but git ignores it because the path is tracked.
+    Tracked,
+    /// A higher-precedence negation (e.g. `!CLAUDE.md` in `.gitignore`)
+    /// re-includes the path, so the exclude rule has no effect.
+    Negated { source: String, line: usize, pattern: String },
+}
+
+impl IgnoreDiscrepancy {
+    pub fn describe(&self) -> String {
+        match self {
+            IgnoreDiscrepancy::Tracked => {
+                "rule matches but is ineffective because the file is tracked".to_string()
+            }
+            IgnoreDiscrepancy::Negated { source, line, pattern } => {
+                format!("re-included by {pattern} ({}:{line})", source.replace('\\', "/"))
+            }
+        }
+    }
+}
+
+/// Paths to compare for layered entries: literal files and directories.
+/// Glob entries are summarized separately by the pattern match index.
+pub fn discrepancy_targets<'a>(entries: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    entries
+        .into_iter()
+        .filter(|e| !contains_glob(e))
+        .map(|e| e.trim_end_matches('/').to_string())
+        .collect()
+}
+
+pub fn ignore_discrepancies(
+    repo_root: &Path,
+    exclude_path: &Path,
+    paths: &[String],
+) -> Result<HashMap<String, IgnoreDiscrepancy>> {
+    let without_index = check_ignore_bulk(repo_root, paths, true)?;
+    let with_index = check_ignore_bulk(repo_root, paths, false)?;
+
+    let mut out = HashMap::new();
+    for path in paths {
+        let Some(hit) = without_index.get(path) else {
+            continue;
+        };
+        if hit.pattern.starts_with('!') {
+            out.insert(
+                path.clone(),
+                IgnoreDiscrepancy::Negated {
+                    source: hit.source.clone(),
+                    line: hit.line,
+                    pattern: hit.pattern.clone(),
+                },
+            );
+        } else if is_local_exclude_source(repo_root, exclude_path, &hit.source)
+            && !with_index.contains_key(path)
+        {
+            out.insert(path.clone(), IgnoreDiscrepancy::Tracked);
+        }
+    }
+
+    Ok(out)
+}
+
 pub fn list_ignored_untracked_from_exclude(
     repo_root: &Path,
     exclude_path: &Path,
@@ -258,6 +406,44 @@ pub(crate) fn parse_check_ignore_line(line: &str) -> Result<Option<(IgnoreMatch,
     )))
 }
 
+/// A line in a tracked file found by `grep_tracked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepHit {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+}
+
+/// Lines of tracked text files that contain any of `needles` literally.
+pub fn grep_tracked(repo_root: &Path, needles: &[String]) -> Result<Vec<GrepHit>> {
+    if needles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut cmd = Command::new("git");
+    cmd.args(["grep", "-n", "-z", "-I", "-F", "--full-name", "--no-color"]);
+    for needle in needles {
+        cmd.arg("-e").arg(needle);
+    }
+    cmd.current_dir(repo_root);
+    let output = output(&mut cmd).context("failed to run git grep")?;
+    // Exit status 1 means nothing matched.
+    if !output.status.success() && output.status.code() != Some(1) {
+        return Err(anyhow!("git grep failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|record| {
+            let mut fields = record.splitn(3, '\0');
+            let path = fields.next()?.to_string();
+            let line = fields.next()?.parse().ok()?;
+            let text = fields.next()?.to_string();
+            Some(GrepHit { path, line, text })
+        })
+        .collect())
+}
+
 pub fn contains_glob(s: &str) -> bool {
     s.contains('*') || s.contains('?') || s.contains('[')
 }
@@ -335,7 +521,9 @@ pub fn read_root_gitignore_entries(repo_root: &Path) -> Result<HashSet<String>>
 }
 
 pub fn is_local_exclude_source(repo_root: &Path, exclude_path: &Path, source: &str) -> bool {
-    let normalized_source = source.replace('\\', "/");
+    let normalized_source = wsl::normalize_git_path(source)
+        .to_string_lossy()
+        .replace('\\', "/");
     if normalized_source.ends_with("/info/exclude") {
         return true;
     }
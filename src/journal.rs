@@ -0,0 +1,272 @@
+use crate::exclude_file::ExcludeFile;
+use crate::json::{self, Json};
+use crate::meta;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Append-only log of state-changing operations, kept in the git dir.
+pub const JOURNAL_FILE: &str = "LAYER_JOURNAL";
+
+/// Oldest records are dropped once the journal grows past this.
+const MAX_RECORDS: usize = 1000;
+
+static COMMAND: OnceLock<String> = OnceLock::new();
+
+/// Remember the invoking command line so records can say what made a change.
+pub fn set_command<I: IntoIterator<Item = String>>(args: I) {
+    let mut words = vec!["layer".to_string()];
+    words.extend(args.into_iter().skip(1));
+    let _ = COMMAND.set(words.join(" "));
+}
+
+fn command() -> String {
+    COMMAND.get().cloned().unwrap_or_else(|| "layer".to_string())
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    pub time: String,
+    pub command: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub disabled: Vec<String>,
+    pub enabled: Vec<String>,
+}
+
+impl Record {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.disabled.is_empty()
+            && self.enabled.is_empty()
+    }
+
+    /// Changes as `(symbol, entry)` pairs, in a stable order.
+    pub fn changes(&self) -> Vec<(char, &str)> {
+        let mut out = Vec::new();
+        out.extend(self.added.iter().map(|e| ('+', e.as_str())));
+        out.extend(self.removed.iter().map(|e| ('-', e.as_str())));
+        out.extend(self.disabled.iter().map(|e| ('~', e.as_str())));
+        out.extend(self.enabled.iter().map(|e| ('^', e.as_str())));
+        out
+    }
+
+    pub fn touches(&self, entry: &str) -> bool {
+        self.changes().iter().any(|(_, e)| *e == entry)
+    }
+
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("time", Json::String(self.time.clone())),
+            ("command", Json::String(self.command.clone())),
+            ("added", Json::strings(self.added.iter().cloned())),
+            ("removed", Json::strings(self.removed.iter().cloned())),
+            ("disabled", Json::strings(self.disabled.iter().cloned())),
+            ("enabled", Json::strings(self.enabled.iter().cloned())),
+        ])
+    }
+
+    fn from_json(value: &Json) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(Json::as_str).map(ToOwned::to_owned);
+        let list = |key: &str| {
+            value
+                .get(key)
+                .and_then(Json::as_array)
+                .map(|items| items.iter().filter_map(Json::as_str).map(ToOwned::to_owned).collect())
+                .unwrap_or_default()
+        };
+        Some(Self {
+            time: text("time")?,
+            command: text("command")?,
+            added: list("added"),
+            removed: list("removed"),
+            disabled: list("disabled"),
+            enabled: list("enabled"),
+        })
+    }
+}
+
+/// Compare two renderings of the exclude file and describe what changed in
+/// the managed section.
+pub fn diff(before: &str, after: &str) -> Record {
+    let old = ExcludeFile::parse(before);
+    let new = ExcludeFile::parse(after);
+    let old_active = old.entry_set();
+    let old_disabled = old.disabled_entry_set();
+    let new_active = new.entry_set();
+    let new_disabled = new.disabled_entry_set();
+
+    let mut record = Record::default();
+    let mut seen = HashSet::new();
+    for entry in new.entries() {
+        if !seen.insert(entry.value.clone()) {
+            continue;
+        }
+        if old_disabled.contains(&entry.value) && !old_active.contains(&entry.value) {
+            record.enabled.push(entry.value);
+        } else if !old_active.contains(&entry.value) {
+            record.added.push(entry.value);
+        }
+    }
+    for entry in new.disabled_entries() {
+        if !seen.insert(entry.value.clone()) {
+            continue;
+        }
+        if old_active.contains(&entry.value) {
+            record.disabled.push(entry.value);
+        } else if !old_disabled.contains(&entry.value) {
+            record.added.push(entry.value);
+        }
+    }
+    for entry in old.entries().into_iter().chain(old.disabled_entries()) {
+        if !new_active.contains(&entry.value)
+            && !new_disabled.contains(&entry.value)
+            && !record.removed.contains(&entry.value)
+        {
+            record.removed.push(entry.value);
+        }
+    }
+    record
+}
+
+/// Journal location for an exclude file. Only a repo's own `info/exclude` is
+/// journaled; global ignore files and scratch copies are not.
+pub fn journal_path(exclude_path: &Path) -> Option<PathBuf> {
+    let info = exclude_path.parent()?;
+    if info.file_name()? != "info" {
+        return None;
+    }
+    Some(info.parent()?.join(JOURNAL_FILE))
+}
+
+/// Record the difference between two versions of an exclude file. Journal
+/// failures never fail the operation that triggered them.
+pub fn record_change(exclude_path: &Path, before: &str, after: &str) {
+    let Some(path) = journal_path(exclude_path) else {
+        return;
+    };
+    let mut record = diff(before, after);
+    if record.is_empty() {
+        return;
+    }
+    meta::record_change(exclude_path, &record);
+    record.time = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_default();
+    record.command = command();
+    let _ = append(&path, &record);
+}
+
+fn append(path: &Path, record: &Record) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let count = existing.lines().count();
+    if count >= MAX_RECORDS {
+        let keep: Vec<&str> = existing.lines().skip(count + 1 - MAX_RECORDS).collect();
+        let mut content = keep.join("\n");
+        content.push('\n');
+        content.push_str(&record.to_json().to_string());
+        content.push('\n');
+        return fs::write(path, content).with_context(|| format!("failed to write {}", path.display()));
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", record.to_json())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Drop unreadable lines from the journal, returning how many were removed.
+pub fn compact(git_dir: &Path) -> Result<usize> {
+    let path = git_dir.join(JOURNAL_FILE);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(0);
+    };
+    let readable: Vec<&str> = content
+        .lines()
+        .filter(|line| json::parse(line).ok().as_ref().and_then(Record::from_json).is_some())
+        .collect();
+    let dropped = content.lines().count() - readable.len();
+    if dropped > 0 {
+        let mut kept = readable.join("\n");
+        if !kept.is_empty() {
+            kept.push('\n');
+        }
+        fs::write(&path, kept).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(dropped)
+}
+
+/// Read all records, oldest first. Unreadable lines are skipped.
+pub fn read(git_dir: &Path) -> Result<Vec<Record>> {
+    let path = git_dir.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| json::parse(line).ok())
+        .filter_map(|value| Record::from_json(&value))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "# managed by layer";
+    const END: &str = "# end layer";
+
+    fn file(managed: &[&str]) -> String {
+        format!("{START}\n{}\n{END}\n", managed.join("\n"))
+    }
+
+    #[test]
+    fn diff_reports_each_kind_of_change() {
+        let before = file(&["CLAUDE.md", ".cursor/", "# [off] AGENTS.md", "GEMINI.md"]);
+        let after = file(&["# [off] CLAUDE.md", "AGENTS.md", "GEMINI.md", ".aider*"]);
+        let record = diff(&before, &after);
+        assert_eq!(record.added, vec![".aider*"]);
+        assert_eq!(record.removed, vec![".cursor/"]);
+        assert_eq!(record.disabled, vec!["CLAUDE.md"]);
+        assert_eq!(record.enabled, vec!["AGENTS.md"]);
+    }
+
+    #[test]
+    fn diff_ignores_unmanaged_lines() {
+        let before = format!("*.log\n{}", file(&["CLAUDE.md"]));
+        let after = format!("*.tmp\n{}", file(&["CLAUDE.md"]));
+        assert!(diff(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn record_roundtrips_through_json() {
+        let record = Record {
+            time: "2026-02-08T12:00:00Z".to_string(),
+            command: "layer add CLAUDE.md".to_string(),
+            added: vec!["CLAUDE.md".to_string()],
+            ..Record::default()
+        };
+        let parsed = Record::from_json(&json::parse(&record.to_json().to_string()).unwrap());
+        assert_eq!(parsed, Some(record));
+    }
+
+    #[test]
+    fn journal_path_only_for_info_exclude() {
+        assert_eq!(
+            journal_path(Path::new("/repo/.git/info/exclude")),
+            Some(PathBuf::from("/repo/.git/LAYER_JOURNAL"))
+        );
+        assert_eq!(journal_path(Path::new("/home/me/.config/git/ignore")), None);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::commands::status::entry_matches_path;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Discovered files you've decided not to layer, one pattern per line in
+/// the repo root, written by `layer dismiss`.
+pub const LAYERIGNORE_FILE: &str = ".layerignore";
+
+/// Patterns in the repo's `.layerignore`, without blank lines and comments.
+pub fn load(root: &Path) -> Vec<String> {
+    fs::read_to_string(root.join(LAYERIGNORE_FILE))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether discovery should stop suggesting `path`. Patterns follow the
+/// same rules as exclude entries: `name` anywhere, `dir/` below a
+/// directory, `a/b` from the root.
+pub fn is_dismissed(patterns: &[String], path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    patterns.iter().any(|pattern| {
+        entry_matches_path(pattern, path) || entry_matches_path(pattern, &format!("{path}/"))
+    })
+}
+
+/// Append `patterns` to `.layerignore`, creating it with a header comment.
+pub fn append(root: &Path, patterns: &[String]) -> Result<()> {
+    let path = root.join(LAYERIGNORE_FILE);
+    let mut content = fs::read_to_string(&path).unwrap_or_else(|_| {
+        "# Discovered files layer should stop suggesting (layer dismiss <path>)\n".to_string()
+    });
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for pattern in patterns {
+        content.push_str(pattern);
+        content.push('\n');
+    }
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dismissed_patterns_match_files_and_directories() {
+        let patterns = vec!["AGENTS.md".to_string(), ".cursor/".to_string(), "docs/CONTEXT.md".to_string()];
+        assert!(is_dismissed(&patterns, "AGENTS.md"));
+        assert!(is_dismissed(&patterns, "web/AGENTS.md"));
+        assert!(is_dismissed(&patterns, ".cursor/"));
+        assert!(is_dismissed(&patterns, "docs/CONTEXT.md"));
+        assert!(!is_dismissed(&patterns, "CONTEXT.md"));
+        assert!(!is_dismissed(&patterns, "CLAUDE.md"));
+    }
+}
@@ -1,12 +1,27 @@
+mod acks;
 mod commands;
+mod config;
+mod discovery;
 mod exclude_file;
 mod git;
+mod journal;
+mod json;
+mod layerignore;
+mod meta;
+mod notify;
 mod patterns;
+mod render_cache;
+mod session;
+mod signing;
 mod tree_picker;
 mod ui;
+mod vault;
+mod wsl;
 
-use anyhow::Result;
-use clap::{Args, CommandFactory, Parser, Subcommand};
+use anyhow::{bail, Context, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use ui::{ColorChoice, Format, FormatArg};
 
 #[derive(Parser, Debug)]
 #[command(name = "layer")]
@@ -14,43 +29,240 @@ use clap::{Args, CommandFactory, Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Run as if layer was started in <path> instead of the current directory
+    #[arg(short = 'C', global = true, value_name = "PATH")]
+    directory: Vec<PathBuf>,
+    /// Override a config value for this run (e.g. -c scan.depth=4)
+    #[arg(short = 'c', long = "config", global = true, value_name = "KEY=VALUE")]
+    config: Vec<String>,
+    /// Output format for reports: pretty, json, porcelain (tab-separated, for scripts), gha (check only),
+    /// or a template such as '{status}\t{entry}' (ls and status)
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = FormatArg::parse, default_value = "pretty")]
+    format: FormatArg,
+    /// When to use colors: auto (terminal and no NO_COLOR), always, or never (default: ui.color)
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<ColorChoice>,
+    /// Same as --color never
+    #[arg(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+    /// Trace every git call (command line, duration, exit status) to stderr;
+    /// with `why`, also explain git's ignore precedence; with `ls`, show when
+    /// and by which command each entry was added
+    #[arg(short, long, global = true)]
+    verbose: bool,
+}
+
+impl Cli {
+    /// The effective output format. A command's own `--json` flag is
+    /// shorthand for `--format json`.
+    fn format(&self) -> Format {
+        let json_flag = match &self.command {
+            Some(Commands::Add(args)) => args.json,
+            Some(Commands::Scan(args)) => args.json,
+            Some(Commands::Doctor(args)) => args.json,
+            Some(Commands::Status(args)) => args.json,
+            Some(Commands::Patterns(args)) => args.json,
+            _ => false,
+        };
+        let in_actions = std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true");
+        let format = match &self.format {
+            FormatArg::Named(format) => *format,
+            FormatArg::Template(_) => Format::Template,
+        };
+        if json_flag {
+            Format::Json
+        } else if format == Format::Pretty && in_actions && matches!(self.command, Some(Commands::Check(_))) {
+            Format::Gha
+        } else {
+            format
+        }
+    }
+
+    /// Commands whose output is a report that can be rendered as JSON.
+    /// Everything else only changes state and prints progress lines.
+    fn supports_json(&self) -> bool {
+        match &self.command {
+            Some(Commands::Status(args)) => !args.hook,
+            Some(Commands::Patterns(args)) => args.command.is_none(),
+            Some(
+                Commands::Add(_)
+                | Commands::Scan(_)
+                | Commands::Doctor(_)
+                | Commands::Ls(_)
+                | Commands::Log(_)
+                | Commands::Which(_)
+                | Commands::Recent(_)
+                | Commands::Check(_),
+            ) => true,
+            // Each child run checks the format against its own subcommand.
+            Some(Commands::Foreach(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// The global flags a `foreach` child run gets, so each repo's output
+    /// follows the same `-c`, `--format`, color, and `-v` choices as this run.
+    fn forwarded_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        for value in &self.config {
+            flags.push("-c".to_string());
+            flags.push(value.clone());
+        }
+        match &self.format {
+            FormatArg::Named(Format::Pretty) => {}
+            FormatArg::Named(format) => {
+                let name = format.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+                flags.push(format!("--format={name}"));
+            }
+            FormatArg::Template(template) => flags.push(format!("--format={template}")),
+        }
+        if self.no_color {
+            flags.push("--no-color".to_string());
+        } else if let Some(color) = self.color.and_then(|color| color.to_possible_value()) {
+            flags.push(format!("--color={}", color.get_name()));
+        }
+        if self.verbose {
+            flags.push("--verbose".to_string());
+        }
+        flags
+    }
+
+    /// What `layer-meta.json` records as the origin of entries this run adds.
+    fn origin(&self) -> &'static str {
+        match &self.command {
+            Some(Commands::Add(_)) => "add",
+            Some(Commands::Pick) => "pick",
+            Some(Commands::Scan(_)) => "scan",
+            Some(Commands::Restore(_)) => "restore",
+            Some(Commands::Init(_)) => "init",
+            Some(Commands::Untrack(_)) => "untrack",
+            Some(Commands::Demote(_)) => "demote",
+            Some(Commands::Import(_)) => "import",
+            Some(Commands::Preset(_)) => "preset",
+            Some(Commands::Template(_)) => "template",
+            Some(Commands::New(_)) => "new",
+            Some(Commands::Edit(_) | Commands::OpenExclude(_)) => "edit",
+            Some(Commands::Demo(_)) => "demo",
+            Some(Commands::Vault(_)) => "vault",
+            _ => "layer",
+        }
+    }
+
+    /// Whether to drop entries from ended `add --session` sessions first.
+    /// Skipped where startup time matters or no layer is involved.
+    fn prunes_sessions(&self) -> bool {
+        match &self.command {
+            Some(Commands::Status(args)) => !args.hook,
+            Some(Commands::Session(_) | Commands::Serve(_) | Commands::Man(_) | Commands::Hook(_) | Commands::Report(_) | Commands::Foreach(_)) | None => false,
+            _ => true,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Seed a fresh clone's layer from another clone, a backup file, or a remote URL
+    Init(InitArgs),
     /// Add files or patterns to your local layer
     Add(AddArgs),
     /// Remove layered entries
     Rm(RmArgs),
+    /// Move lines from the shared .gitignore into your local layer
+    Demote(DemoteArgs),
+    /// Layer the patterns from other AI tools' ignore files (.cursorignore, .aiderignore, .codeiumignore)
+    Import(ImportArgs),
+    /// Layer a tool's known files before they exist (claude, cursor, full-agents, ...)
+    Preset(PresetArgs),
+    /// Save this repo's layered entries as a named template and apply it in other repos
+    Template(TemplateArgs),
+    /// Create a starter CLAUDE.md, AGENTS.md, or Cursor rules file and layer it
+    New(NewArgs),
+    /// Stop tracking files (git rm --cached) and make sure they stay hidden
+    Untrack(UntrackArgs),
     /// List all layered entries with status
     #[command(alias = "list")]
-    Ls,
+    Ls(LsArgs),
     /// Scan for context files and layer them
-    Scan,
+    Scan(ScanArgs),
     /// List all known context-file patterns
     Patterns(PatternsArgs),
+    /// Fuzzy-find an entry and run why, add, rm, off, on, or open on it
+    Pick,
     /// Diagnose layered entries for issues
-    Doctor,
+    Doctor(DoctorArgs),
+    /// Write an offline diagnostic report to attach to bug reports
+    Report(ReportArgs),
     /// Remove stale entries that no longer match files
     Clean(CleanArgs),
     /// Remove all layered entries
     Clear(ClearArgs),
+    /// Back up, drop long-stale entries, and refresh caches in one quiet pass (for cron)
+    Maintain,
     /// Temporarily disable layered entries (files become visible to git)
     Off(OffArgs),
     /// Re-enable disabled layered entries
     On(OnArgs),
+    /// Disable everything for a screenshare, then restore it exactly
+    Demo(DemoArgs),
+    /// List or end entries added with `add --session`
+    Session(SessionArgs),
+    /// Keep context files encrypted in .git/layer-vault/ and decrypt them on demand
+    Vault(VaultArgs),
     /// Dashboard showing layered, exposed, and discovered files
-    Status,
+    Status(StatusArgs),
+    /// Exit non-zero when files are exposed or context files aren't layered (CI, hooks)
+    Check(CheckArgs),
+    /// Acknowledge warnings so they're summarized as a count instead of repeated
+    Ack(AckArgs),
+    /// Stop scan and status from suggesting discovered files you've decided not to layer
+    Dismiss(DismissArgs),
     /// Backup layered entries
-    Backup,
+    Backup(BackupArgs),
     /// Restore layered entries from backup
     Restore(RestoreArgs),
+    /// Show the history of changes to layered entries
+    Log(LogArgs),
+    /// List the most recently added entries, with where they came from
+    Recent(RecentArgs),
     /// Manage global gitignore entries
     Global(GlobalArgs),
+    /// Install or remove layer's git hooks
+    Hook(HookArgs),
     /// Explain why a file is or isn't ignored by git
     Why(WhyArgs),
+    /// Print only the source:line of the rule that ignores a file
+    Which(WhichArgs),
     /// Open .git/info/exclude in your editor
-    Edit,
+    Edit(EditArgs),
+    /// Open .git/info/exclude in your editor at a given entry's line
+    OpenExclude(OpenExcludeArgs),
+    /// Reorder layered entries by the exclude.order config
+    Fmt(FmtArgs),
+    /// Inspect and change layer configuration
+    Config(ConfigArgs),
+    /// Run a JSON-RPC server for editor integrations (stdio or unix socket)
+    Serve(ServeArgs),
+    /// Write roff man pages for layer and every subcommand
+    Man(ManArgs),
+    /// Run a layer subcommand in every repo listed in a manifest, then summarize
+    Foreach(ForeachArgs),
+}
+
+#[derive(Args, Debug)]
+struct ForeachArgs {
+    /// File listing one repo path per line (# comments, ~/ and paths relative to the file are fine)
+    #[arg(short, long, value_name = "FILE")]
+    manifest: PathBuf,
+    /// The layer subcommand and its arguments, after `--`
+    #[arg(last = true, required = true, value_name = "SUBCOMMAND")]
+    args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct InitArgs {
+    /// Another local clone, a backup/exported file, or the remote URL of a saved backup
+    #[arg(long, value_name = "REPO_OR_URL")]
+    from: String,
 }
 
 #[derive(Args, Debug)]
@@ -63,6 +275,143 @@ struct AddArgs {
     /// Preview changes without writing
     #[arg(long)]
     dry_run: bool,
+    /// Check that each entry would take effect (not tracked, redundant, or negated) without writing
+    #[arg(long, conflicts_with_all = ["interactive", "dry_run"])]
+    check_only: bool,
+    /// Print the --dry-run or --check-only result as JSON, including why entries would be skipped (same as --format json)
+    #[arg(long, conflicts_with = "interactive")]
+    json: bool,
+    /// Layer tracked files even when add.strict_exposed is on
+    #[arg(long)]
+    force: bool,
+    /// Also untrack (git rm --cached) entries git is tracking, so they're actually hidden
+    #[arg(long, conflicts_with_all = ["interactive", "check_only"])]
+    untrack: bool,
+    /// Only for this terminal session: removed once the shell exits
+    #[arg(long, conflicts_with_all = ["interactive", "check_only"])]
+    session: bool,
+    /// Why you're adding these, shown by `layer ls` and `layer recent`
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["interactive", "check_only"])]
+    note: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct UntrackArgs {
+    /// Tracked files or directories to untrack
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Untrack without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args, Debug)]
+struct DemoteArgs {
+    /// .gitignore lines to move, exactly as written there
+    #[arg(required = true)]
+    patterns: Vec<String>,
+    /// Preview changes without writing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct ImportArgs {
+    /// Ignore file to read (repeatable; default: whichever of .cursorignore, .aiderignore, .codeiumignore exist)
+    #[arg(long, value_name = "FILE")]
+    from: Vec<PathBuf>,
+    /// Preview changes without writing
+    #[arg(long)]
+    dry_run: bool,
+    /// Import every pattern without asking
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args, Debug)]
+struct PresetArgs {
+    #[command(subcommand)]
+    command: PresetSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum PresetSubcommand {
+    /// Show each preset and the entries it layers
+    List,
+    /// Layer every entry of a preset, whether or not the files exist yet
+    Apply(PresetApplyArgs),
+}
+
+#[derive(Args, Debug)]
+struct PresetApplyArgs {
+    /// Preset name, as `layer preset list` shows it
+    name: String,
+    /// Preview changes without writing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct TemplateArgs {
+    #[command(subcommand)]
+    command: TemplateSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum TemplateSubcommand {
+    /// Save the layered entries as ~/.config/layer/templates/<name>.txt
+    Save(TemplateSaveArgs),
+    /// Layer every entry of a saved template
+    Apply(TemplateApplyArgs),
+    /// Show saved templates and their entries
+    List,
+}
+
+#[derive(Args, Debug)]
+struct TemplateSaveArgs {
+    /// Template name
+    name: String,
+    /// Replace an existing template of the same name
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args, Debug)]
+struct TemplateApplyArgs {
+    /// Template name, as `layer template list` shows it
+    name: String,
+    /// Preview changes without writing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct NewArgs {
+    /// Which context file to create
+    #[arg(value_enum)]
+    kind: commands::new::ContextKind,
+    /// Create the file without layering it
+    #[arg(long)]
+    no_layer: bool,
+}
+
+#[derive(Args, Debug)]
+struct LsArgs {
+    /// Ask git whether each entry really ignores a file it should match, and flag the ones it doesn't
+    #[arg(long)]
+    verify: bool,
+    /// Order entries within each section
+    #[arg(long, value_enum, value_name = "KEY")]
+    sort: Option<commands::ls::SortKey>,
+    /// Only show entries in these states (repeatable or comma-separated)
+    #[arg(long, value_enum, value_name = "STATE", value_delimiter = ',')]
+    status: Vec<commands::ls::StateFilter>,
+    /// Print only the entries, one per line, for xargs, fzf, and other pipelines
+    #[arg(long)]
+    paths_only: bool,
+    /// Show each entry's size on disk, file count, and most recent modification
+    #[arg(short, long, conflicts_with = "paths_only")]
+    long: bool,
 }
 
 #[derive(Args, Debug)]
@@ -74,6 +423,42 @@ struct RmArgs {
     dry_run: bool,
 }
 
+#[derive(Args, Debug)]
+struct ScanArgs {
+    /// Untrack tracked context files (git rm --cached) and layer them
+    #[arg(long)]
+    fix_tracked: bool,
+    /// Print discovered files and their flags as JSON instead of prompting (same as --format json)
+    #[arg(long, conflicts_with_all = ["fix_tracked", "untracked_all"])]
+    json: bool,
+    /// Pick from every untracked file, not just known context files, with discoveries tagged by tool
+    #[arg(long, visible_alias = "all")]
+    untracked_all: bool,
+    /// Only look for one tool's files, named as `layer patterns` lists them (e.g. aider)
+    #[arg(long, value_name = "NAME", conflicts_with = "untracked_all")]
+    tool: Option<String>,
+    /// Layer every new discovered file without prompting (bootstrap scripts, dotfile installers)
+    #[arg(short, long, conflicts_with_all = ["untracked_all", "json"])]
+    yes: bool,
+}
+
+#[derive(Args, Debug)]
+struct DoctorArgs {
+    /// Print each diagnosis, its suggested fix, and a summary as JSON (same as --format json)
+    #[arg(long)]
+    json: bool,
+    /// Also search tracked files for mentions of layered paths (e.g. a build script reading PROMPT.md)
+    #[arg(long)]
+    references: bool,
+}
+
+#[derive(Args, Debug)]
+struct ReportArgs {
+    /// Where to write the report
+    #[arg(short, long, value_name = "FILE", default_value = "layer-report.json")]
+    output: PathBuf,
+}
+
 #[derive(Args, Debug)]
 struct CleanArgs {
     /// Preview changes without writing
@@ -82,6 +467,9 @@ struct CleanArgs {
     /// Also clean stale entries you added manually to the exclude file
     #[arg(long)]
     all: bool,
+    /// Remove stale entries without asking for confirmation
+    #[arg(short, long, conflicts_with = "dry_run")]
+    yes: bool,
 }
 
 #[derive(Args, Debug)]
@@ -89,12 +477,49 @@ struct ClearArgs {
     /// Preview changes without writing
     #[arg(long)]
     dry_run: bool,
+    /// Remove all entries without asking for confirmation
+    #[arg(short, long, conflicts_with = "dry_run")]
+    yes: bool,
+}
+
+#[derive(Args, Debug)]
+struct FmtArgs {
+    /// Preview changes without writing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct LogArgs {
+    /// Only show the history of this entry
+    #[arg(long, value_name = "ENTRY")]
+    entry: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct EditArgs {
+    /// Edit only the layer-managed section, validated before it is merged back
+    #[arg(long)]
+    managed_only: bool,
+    /// Allow negation (`!pattern`) lines in the managed section
+    #[arg(long, requires = "managed_only")]
+    allow_negation: bool,
+}
+
+#[derive(Args, Debug)]
+struct OpenExcludeArgs {
+    /// Entry to jump to, as shown by `layer ls`
+    #[arg(long, value_name = "ENTRY")]
+    line: String,
 }
 
 #[derive(Args, Debug)]
 struct OffArgs {
     /// Entries to disable (all if omitted)
     files: Vec<String>,
+    /// Disable everything except these entries
+    #[arg(long, num_args = 1.., value_name = "ENTRY", conflicts_with = "files")]
+    except: Vec<String>,
     /// Preview changes without writing
     #[arg(long)]
     dry_run: bool,
@@ -104,11 +529,225 @@ struct OffArgs {
 struct OnArgs {
     /// Entries to enable (all if omitted)
     files: Vec<String>,
+    /// Enable everything except these entries
+    #[arg(long, num_args = 1.., value_name = "ENTRY", conflicts_with = "files")]
+    except: Vec<String>,
     /// Preview changes without writing
     #[arg(long)]
     dry_run: bool,
 }
 
+#[derive(Args, Debug)]
+struct StatusArgs {
+    /// Quiet, fast mode for git hooks: one line only when something needs attention
+    #[arg(long)]
+    hook: bool,
+    /// With --hook, exit 1 when something needs attention
+    #[arg(long, requires = "hook")]
+    strict: bool,
+    /// Show every discovered file instead of the first status.max_discovered
+    #[arg(long, conflicts_with = "hook")]
+    full: bool,
+    /// Print the dashboard as JSON, with fix commands as structured fields (same as --format json)
+    #[arg(long, conflicts_with = "hook")]
+    json: bool,
+    /// Audit a branch, tag, or commit for committed context files without checking it out
+    #[arg(long, value_name = "REF", conflicts_with_all = ["hook", "full"])]
+    rev: Option<String>,
+    /// Also show warnings acknowledged with `layer ack`
+    #[arg(long, conflicts_with_all = ["hook", "rev"])]
+    show_acked: bool,
+    /// One line of counts for shell prompts, served from a cache while nothing has changed
+    #[arg(long, conflicts_with_all = ["hook", "rev", "full", "json"])]
+    short: bool,
+}
+
+#[derive(Args, Debug)]
+struct AckArgs {
+    /// Warning ids to acknowledge (exposed:<entry>, tracked:<path>, discovered:<path>, wsl); lists acknowledged ids if omitted
+    ids: Vec<String>,
+    /// Un-acknowledge the given ids
+    #[arg(long, requires = "ids")]
+    remove: bool,
+}
+
+#[derive(Args, Debug)]
+struct DismissArgs {
+    /// Paths or patterns to add to .layerignore; lists dismissed ones if omitted
+    paths: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct DemoArgs {
+    #[command(subcommand)]
+    command: DemoSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum DemoSubcommand {
+    /// Snapshot the exclude file and disable all entries
+    On,
+    /// Restore the exclude file from the demo snapshot
+    Off(DemoOffArgs),
+}
+
+#[derive(Args, Debug)]
+struct VaultArgs {
+    #[command(subcommand)]
+    command: VaultSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum VaultSubcommand {
+    /// Encrypt files into the vault, remove the plaintext, and layer the paths
+    Add(VaultAddArgs),
+    /// Write decrypted copies of vaulted files (all of them without arguments)
+    Open(VaultFilesArgs),
+    /// Save changes to open files back into the vault and remove the decrypted copies
+    Lock(VaultFilesArgs),
+}
+
+#[derive(Args, Debug)]
+struct VaultAddArgs {
+    /// Files, relative to the repo root
+    #[arg(required = true)]
+    files: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct VaultFilesArgs {
+    /// Files, relative to the repo root
+    files: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct SessionArgs {
+    #[command(subcommand)]
+    command: SessionSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum SessionSubcommand {
+    /// List session entries and the session that owns each
+    Ls,
+    /// Remove this session's entries now (for a shell EXIT trap)
+    End,
+}
+
+#[derive(Args, Debug)]
+struct DemoOffArgs {
+    /// Restore even if the exclude file changed during demo mode
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Listen on a unix socket instead of stdio
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ManArgs {
+    /// Directory to write the pages into (created if missing)
+    #[arg(long, value_name = "DIR", default_value = "man")]
+    out_dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigSubcommand {
+    /// Show effective config values
+    List(ConfigListArgs),
+    /// Print the effective value of a key
+    Get(ConfigGetArgs),
+    /// Write a key to .layer.toml (or your user config with --user)
+    Set(ConfigSetArgs),
+}
+
+#[derive(Args, Debug)]
+struct ConfigGetArgs {
+    /// Config key (e.g. scan.depth)
+    key: String,
+}
+
+#[derive(Args, Debug)]
+struct ConfigSetArgs {
+    /// Config key (e.g. scan.depth)
+    key: String,
+    /// New value (e.g. 4, true, "text")
+    value: String,
+    /// Write to ~/.config/layer/config.toml instead of the repo's .layer.toml
+    #[arg(long)]
+    user: bool,
+}
+
+#[derive(Args, Debug)]
+struct ConfigListArgs {
+    /// Show which file, env var, or flag set each value
+    #[arg(long)]
+    show_origin: bool,
+}
+
+#[derive(Args, Debug)]
+struct HookArgs {
+    #[command(subcommand)]
+    command: HookSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum HookSubcommand {
+    /// Install hooks, keeping any commands already in them
+    Install(HookSelectArgs),
+    /// Remove layer's part of installed hooks
+    Uninstall(HookSelectArgs),
+    /// Print a devcontainer postCreateCommand that restores your layer after a rebuild
+    Devcontainer(DevcontainerArgs),
+    /// Run by the post-merge hook: warn about context files upstream started tracking
+    #[command(hide = true)]
+    PostMerge,
+    /// Run by the pre-commit hook: block commits that add context files
+    #[command(hide = true)]
+    PreCommit,
+}
+
+#[derive(Args, Debug)]
+struct DevcontainerArgs {
+    /// Backup file in the workspace to restore from (see layer backup -o)
+    #[arg(long, value_name = "FILE", default_value = ".layer-backup.txt")]
+    from: String,
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = true)]
+struct HookSelectArgs {
+    /// After pull/merge, warn when upstream starts tracking context files
+    #[arg(long)]
+    post_merge: bool,
+    /// Block commits that add known context files or layered paths
+    #[arg(long)]
+    pre_commit: bool,
+}
+
+impl HookSelectArgs {
+    fn kinds(&self) -> Vec<commands::hook::HookKind> {
+        let mut kinds = Vec::new();
+        if self.post_merge {
+            kinds.push(commands::hook::HookKind::PostMerge);
+        }
+        if self.pre_commit {
+            kinds.push(commands::hook::HookKind::PreCommit);
+        }
+        kinds
+    }
+}
+
 #[derive(Args, Debug)]
 struct GlobalArgs {
     #[command(subcommand)]
@@ -137,16 +776,50 @@ struct GlobalRmArgs {
     files: Vec<String>,
 }
 
+#[derive(Args, Debug)]
+struct BackupArgs {
+    /// Write the backup to this file instead of ~/.layer-backups/ (for sharing)
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Sign the backup with this SSH private key or minisign secret key
+    #[arg(long, value_name = "KEY")]
+    sign: Option<PathBuf>,
+}
+
 #[derive(Args, Debug)]
 struct RestoreArgs {
+    /// Backup to restore, as shown by --list (defaults to this repo's)
+    #[arg(conflicts_with_all = ["list", "from"])]
+    name: Option<String>,
     /// List available backups
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["from", "verify"])]
     list: bool,
+    /// Restore from a shared backup file instead of ~/.layer-backups/
+    #[arg(long, value_name = "FILE")]
+    from: Option<PathBuf>,
+    /// Require a valid signature, checked against an SSH allowed_signers file or minisign public key
+    #[arg(long, value_name = "TRUSTED")]
+    verify: Option<PathBuf>,
+    /// Restore without asking for confirmation
+    #[arg(short, long, conflicts_with = "list")]
+    yes: bool,
+    /// Make the layer match the backup, removing entries it doesn't have; each change can be unchecked
+    #[arg(long, conflicts_with = "list")]
+    replace: bool,
+    /// With --list, show backup times in UTC instead of local time
+    #[arg(long, requires = "list")]
+    utc: bool,
+    /// Use the backups another machine saved (see --list); with --list, show only those
+    #[arg(long, value_name = "NAME", conflicts_with = "from")]
+    machine: Option<String>,
 }
 
 #[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 struct PatternsArgs {
-    /// Output as JSON
+    #[command(subcommand)]
+    command: Option<PatternsSubcommand>,
+    /// Output as JSON (same as --format json)
     #[arg(long)]
     json: bool,
     /// Show only patterns that match files in the current repo
@@ -155,39 +828,248 @@ struct PatternsArgs {
     /// Show matched file paths (requires --matched)
     #[arg(long)]
     show_files: bool,
+    /// Describe what each tool keeps under the pattern
+    #[arg(long)]
+    long: bool,
+    /// Only show patterns for one tool (e.g. "Claude Code", aider)
+    #[arg(long, value_name = "NAME")]
+    tool: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum PatternsSubcommand {
+    /// Register a custom pattern in scan.patterns and show what it matches here
+    Add(PatternsAddArgs),
+    /// Remove a custom pattern from scan.patterns
+    Rm(PatternsRmArgs),
+}
+
+#[derive(Args, Debug)]
+struct PatternsAddArgs {
+    /// File, directory (trailing /), or glob to look for
+    entry: String,
+    /// Tool or group to list it under (default: Project)
+    #[arg(long)]
+    label: Option<String>,
+    /// Category: secrets, ai_config, local_notes, editor_config, or build_artifacts
+    #[arg(long)]
+    category: Option<String>,
+    /// Write to your user config instead of the repo's .layer.toml
+    #[arg(long)]
+    user: bool,
+}
+
+#[derive(Args, Debug)]
+struct PatternsRmArgs {
+    /// The custom pattern's entry
+    entry: String,
+    /// Remove it from your user config instead of the repo's .layer.toml
+    #[arg(long)]
+    user: bool,
+}
+
+#[derive(Args, Debug)]
+struct CheckArgs {
+    /// Only fail on exposed entries and tracked context files
+    #[arg(long, conflicts_with = "discovered_only")]
+    exposed_only: bool,
+    /// Only fail on context files that aren't layered yet
+    #[arg(long)]
+    discovered_only: bool,
+    /// Only check the given files, as the pre-commit framework passes them
+    #[arg(long, conflicts_with_all = ["exposed_only", "discovered_only"])]
+    hook: bool,
+    /// Staged files to check with --hook
+    #[arg(requires = "hook")]
+    files: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct RecentArgs {
+    /// How many entries to show
+    #[arg(short = 'n', long, default_value_t = 10)]
+    limit: usize,
+}
+
+#[derive(Args, Debug)]
+struct WhichArgs {
+    /// A single file path to resolve
+    file: String,
 }
 
 #[derive(Args, Debug)]
 struct WhyArgs {
     /// A single file path to diagnose
     file: String,
-    /// Show extra explanation about git ignore precedence
-    #[arg(short, long)]
-    verbose: bool,
+}
+
+/// Apply `-C` options in order, each relative to the previous one, like git.
+fn change_directory(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        std::env::set_current_dir(path)
+            .with_context(|| format!("cannot change to '{}'", path.display()))?;
+    }
+    Ok(())
 }
 
 fn dispatch(cli: Cli) -> Result<i32> {
+    git::set_trace(cli.verbose);
+    change_directory(&cli.directory)?;
+    config::set_cli_overrides(&cli.config)?;
+    if cli.color.is_none() && !cli.no_color {
+        // A personal preference: read from user and system config, env, and -c only.
+        if let Ok(configured) = config::load(None) {
+            if let Ok(choice) = ColorChoice::from_str(configured.get_str("ui.color"), true) {
+                ui::set_color(choice);
+            }
+        }
+    }
+    let format = cli.format();
+    if format.is_json() && !cli.supports_json() {
+        bail!("--format json is only available for reports (ls, status, check, scan, doctor, patterns, log, recent, which, add --dry-run, add --check-only)");
+    }
+    if format == Format::Gha && !matches!(cli.command, Some(Commands::Check(_) | Commands::Foreach(_))) {
+        bail!("--format gha is only available for check");
+    }
+    if let FormatArg::Template(template) = &cli.format {
+        let supported = match &cli.command {
+            Some(Commands::Status(args)) => !args.hook && args.rev.is_none(),
+            Some(Commands::Ls(_) | Commands::Foreach(_)) => true,
+            _ => false,
+        };
+        if !supported {
+            bail!("--format templates are only available for ls and status");
+        }
+        ui::set_template(template);
+    }
+    if cli.prunes_sessions() {
+        session::prune_ended();
+    }
     match cli.command {
-        Some(Commands::Add(args)) => commands::add::run(args.files, args.interactive, args.dry_run),
+        Some(Commands::Init(args)) => commands::init::run(&args.from),
+        Some(Commands::Add(args)) => {
+            let options = commands::add::AddOptions {
+                interactive: args.interactive,
+                dry_run: args.dry_run,
+                check_only: args.check_only,
+                force: args.force,
+                untrack: args.untrack,
+                session: args.session,
+                note: args.note,
+            };
+            commands::add::run(args.files, options, format)
+        },
         Some(Commands::Rm(args)) => commands::rm::run(args.files, args.dry_run),
-        Some(Commands::Ls) => commands::ls::run(),
-        Some(Commands::Scan) => commands::scan::run(),
-        Some(Commands::Patterns(args)) => commands::patterns::run(args.json, args.matched, args.show_files),
-        Some(Commands::Doctor) => commands::doctor::run(),
-        Some(Commands::Clean(args)) => commands::clean::run(args.dry_run, args.all),
-        Some(Commands::Clear(args)) => commands::clear::run(args.dry_run),
-        Some(Commands::Off(args)) => commands::on_off::run_off(args.files, args.dry_run),
-        Some(Commands::On(args)) => commands::on_off::run_on(args.files, args.dry_run),
-        Some(Commands::Status) => commands::status::run(),
-        Some(Commands::Backup) => commands::backup::backup(),
-        Some(Commands::Restore(args)) => commands::backup::restore(args.list),
+        Some(Commands::Untrack(args)) => commands::untrack::run(args.files, args.yes),
+        Some(Commands::Demote(args)) => commands::demote::run(args.patterns, args.dry_run),
+        Some(Commands::Import(args)) => commands::import::run(args.from, args.dry_run, args.yes),
+        Some(Commands::Preset(args)) => match args.command {
+            PresetSubcommand::List => commands::preset::list(),
+            PresetSubcommand::Apply(apply) => commands::preset::apply(&apply.name, apply.dry_run),
+        },
+        Some(Commands::Template(args)) => match args.command {
+            TemplateSubcommand::Save(save) => commands::template::save(&save.name, save.force),
+            TemplateSubcommand::Apply(apply) => commands::template::apply(&apply.name, apply.dry_run),
+            TemplateSubcommand::List => commands::template::list(),
+        },
+        Some(Commands::New(args)) => commands::new::run(args.kind, args.no_layer),
+        // Sizes change without anything the cache watches, so --long always recomputes.
+        Some(Commands::Ls(args)) if args.long => {
+            commands::ls::run(args.verify, cli.verbose, args.sort, &args.status, args.paths_only, true, format)
+        }
+        Some(Commands::Ls(args)) => render_cache::cached(|| {
+            commands::ls::run(args.verify, cli.verbose, args.sort, &args.status, args.paths_only, false, format)
+        }),
+        Some(Commands::Pick) => commands::pick::run(),
+        Some(Commands::Scan(args)) => commands::scan::run(args.fix_tracked, args.untracked_all, args.tool.as_deref(), args.yes, format),
+        Some(Commands::Patterns(args)) => match args.command {
+            Some(PatternsSubcommand::Add(add)) => {
+                commands::patterns::add(&add.entry, add.label.as_deref(), add.category.as_deref(), add.user)
+            }
+            Some(PatternsSubcommand::Rm(rm)) => commands::patterns::rm(&rm.entry, rm.user),
+            None => commands::patterns::run(format, args.matched, args.show_files, args.long, args.tool),
+        },
+        Some(Commands::Doctor(args)) => commands::doctor::run(args.references, format),
+        Some(Commands::Report(args)) => commands::report::run(&args.output),
+        Some(Commands::Clean(args)) => commands::clean::run(args.dry_run, args.all, args.yes),
+        Some(Commands::Maintain) => commands::maintain::run(),
+        Some(Commands::Clear(args)) => commands::clear::run(args.dry_run, args.yes),
+        Some(Commands::Off(args)) => commands::on_off::run_off(args.files, args.except, args.dry_run),
+        Some(Commands::On(args)) => commands::on_off::run_on(args.files, args.except, args.dry_run),
+        Some(Commands::Session(args)) => match args.command {
+            SessionSubcommand::Ls => commands::session::list(),
+            SessionSubcommand::End => commands::session::end(),
+        },
+        Some(Commands::Vault(args)) => match args.command {
+            VaultSubcommand::Add(add) => commands::vault::add(add.files),
+            VaultSubcommand::Open(open) => commands::vault::open(open.files),
+            VaultSubcommand::Lock(lock) => commands::vault::lock(lock.files),
+        },
+        Some(Commands::Demo(args)) => match args.command {
+            DemoSubcommand::On => commands::demo::on(),
+            DemoSubcommand::Off(off) => commands::demo::off(off.force),
+        },
+        Some(Commands::Status(args)) if args.hook => commands::status::run_hook(args.strict),
+        Some(Commands::Status(args)) => match args.rev {
+            Some(rev) => commands::status::run_rev(&rev, format),
+            None if args.short => render_cache::cached(|| commands::status::run(false, args.show_acked, true, format)),
+            None => commands::status::run(args.full, args.show_acked, false, format),
+        },
+        Some(Commands::Backup(args)) => commands::backup::backup(args.output, args.sign),
+        Some(Commands::Restore(args)) => {
+            let options = commands::backup::RestoreOptions {
+                list: args.list,
+                from: args.from,
+                verify: args.verify,
+                yes: args.yes,
+                replace: args.replace,
+                utc: args.utc,
+                machine: args.machine,
+            };
+            commands::backup::restore(args.name, options)
+        },
+        Some(Commands::Log(args)) => commands::log::run(args.entry, format),
         Some(Commands::Global(args)) => match args.command {
             GlobalSubcommand::Add(add) => commands::global::add(add.files),
             GlobalSubcommand::Ls => commands::global::ls(),
             GlobalSubcommand::Rm(rm) => commands::global::rm(rm.files),
         },
-        Some(Commands::Why(args)) => commands::why_cmd::run(args.file, args.verbose),
-        Some(Commands::Edit) => commands::edit::run(),
+        Some(Commands::Hook(args)) => match args.command {
+            HookSubcommand::Install(select) => run_each(select.kinds(), commands::hook::install),
+            HookSubcommand::Uninstall(select) => run_each(select.kinds(), commands::hook::uninstall),
+            HookSubcommand::Devcontainer(args) => commands::hook::devcontainer(&args.from),
+            HookSubcommand::PostMerge => commands::hook::post_merge(),
+            HookSubcommand::PreCommit => commands::hook::pre_commit(),
+        },
+        Some(Commands::Why(args)) => commands::why_cmd::run(args.file, cli.verbose),
+        Some(Commands::Ack(args)) => commands::ack::run(args.ids, args.remove),
+        Some(Commands::Dismiss(args)) => commands::dismiss::run(args.paths),
+        Some(Commands::Check(args)) if args.hook => commands::check::run_hook(args.files, format),
+        Some(Commands::Check(args)) => commands::check::run(
+            commands::check::CheckScope {
+                exposed: !args.discovered_only,
+                discovered: !args.exposed_only,
+            },
+            format,
+        ),
+        Some(Commands::Which(args)) => commands::which::run(args.file, format),
+        Some(Commands::Recent(args)) => commands::recent::run(args.limit, format),
+        Some(Commands::Edit(args)) => commands::edit::run(args.managed_only, args.allow_negation),
+        Some(Commands::OpenExclude(args)) => commands::edit::open_at(&args.line),
+        Some(Commands::Fmt(args)) => commands::fmt::run(args.dry_run),
+        Some(Commands::Config(args)) => match args.command {
+            ConfigSubcommand::List(list) => commands::config::list(list.show_origin),
+            ConfigSubcommand::Get(get) => commands::config::get(&get.key),
+            ConfigSubcommand::Set(set) => commands::config::set(&set.key, &set.value, set.user),
+        },
+        Some(Commands::Serve(args)) => commands::serve::run(args.socket),
+        Some(Commands::Foreach(ref args)) => {
+            commands::foreach::run(&args.manifest, args.args.clone(), &cli.forwarded_flags(), format)
+        }
+        Some(Commands::Man(args)) => commands::man::run(Cli::command(), args.out_dir),
         None => {
             let mut cmd = Cli::command();
             cmd.print_help()?;
@@ -196,24 +1078,49 @@ fn dispatch(cli: Cli) -> Result<i32> {
     }
 }
 
+/// Run `f` for each item; the result is the lowest exit code, so one
+/// successful install makes the whole run succeed.
+fn run_each<T>(items: Vec<T>, f: fn(T) -> Result<i32>) -> Result<i32> {
+    let mut code = 2;
+    for item in items {
+        code = code.min(f(item)?);
+    }
+    Ok(code)
+}
+
 fn main() {
     let cli = match Cli::try_parse() {
         Ok(cli) => cli,
         Err(e) => match e.kind() {
             clap::error::ErrorKind::DisplayHelp
             | clap::error::ErrorKind::DisplayVersion => {
-                println!();
+                ui::outln!();
                 let _ = e.print();
-                println!();
+                ui::outln!();
                 std::process::exit(0);
             }
             _ => e.exit(),
         },
     };
-    println!();
+    ui::set_color(if cli.no_color { ColorChoice::Never } else { cli.color.unwrap_or_default() });
+    if cli.format() != Format::Pretty
+        || matches!(&cli.command, Some(Commands::Status(args)) if args.hook)
+        || matches!(&cli.command, Some(Commands::Serve(_)))
+        || matches!(&cli.command, Some(Commands::Hook(args)) if matches!(args.command, HookSubcommand::PostMerge | HookSubcommand::PreCommit))
+    {
+        ui::force_plain();
+    }
+    journal::set_command(std::env::args());
+    meta::set_origin(cli.origin());
+    let plain = ui::is_plain();
+    if !plain {
+        ui::outln!();
+    }
     let code = match dispatch(cli) {
         Ok(code) => {
-            println!();
+            if !plain {
+                ui::outln!();
+            }
             code
         }
         Err(err) => {
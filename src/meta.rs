@@ -0,0 +1,176 @@
+use crate::journal::Record;
+use crate::json::{self, Json};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Sidecar next to `info/exclude` saying when and how each entry was added.
+/// Unlike the journal it is never trimmed, so it still answers "why is this
+/// here?" months later.
+pub const META_FILE: &str = "layer-meta.json";
+
+static ORIGIN: OnceLock<&'static str> = OnceLock::new();
+
+static SOURCES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Remember which command is running, e.g. `add`, `scan`, or `restore`.
+pub fn set_origin(origin: &'static str) {
+    let _ = ORIGIN.set(origin);
+}
+
+/// Remember where bulk-applied entries came from, e.g. `backup 2025-01-03`
+/// or `.cursorignore`, so the next write records it alongside the origin.
+pub fn set_source<'a>(entries: impl IntoIterator<Item = &'a String>, source: &str) {
+    let mut sources = SOURCES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for entry in entries {
+        sources.insert(entry.clone(), source.to_string());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryMeta {
+    /// RFC 3339 time the entry was first added.
+    pub added: String,
+    /// The command that added it.
+    pub origin: String,
+    /// The backup or file a restore or import took it from.
+    pub source: Option<String>,
+    pub note: Option<String>,
+}
+
+impl EntryMeta {
+    fn to_json(&self) -> Json {
+        let mut fields = vec![
+            ("added", Json::String(self.added.clone())),
+            ("origin", Json::String(self.origin.clone())),
+        ];
+        if let Some(source) = &self.source {
+            fields.push(("source", Json::String(source.clone())));
+        }
+        if let Some(note) = &self.note {
+            fields.push(("note", Json::String(note.clone())));
+        }
+        Json::object(fields)
+    }
+
+    fn from_json(value: &Json) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(Json::as_str).map(ToOwned::to_owned);
+        Some(Self {
+            added: text("added")?,
+            origin: text("origin").unwrap_or_else(|| "unknown".to_string()),
+            source: text("source"),
+            note: text("note"),
+        })
+    }
+}
+
+pub fn meta_path(exclude_path: &Path) -> PathBuf {
+    exclude_path.with_file_name(META_FILE)
+}
+
+/// Metadata per entry. A missing or unreadable file reads as empty.
+pub fn load(exclude_path: &Path) -> BTreeMap<String, EntryMeta> {
+    let Ok(content) = fs::read_to_string(meta_path(exclude_path)) else {
+        return BTreeMap::new();
+    };
+    let Ok(value) = json::parse(&content) else {
+        return BTreeMap::new();
+    };
+    match value.get("entries") {
+        Some(Json::Object(fields)) => fields
+            .iter()
+            .filter_map(|(entry, meta)| Some((entry.clone(), EntryMeta::from_json(meta)?)))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn save(exclude_path: &Path, metas: &BTreeMap<String, EntryMeta>) -> Result<()> {
+    let path = meta_path(exclude_path);
+    let entries = metas.iter().map(|(entry, meta)| (entry.clone(), meta.to_json())).collect();
+    let value = Json::Object(vec![("entries".to_string(), Json::Object(entries))]);
+    fs::write(&path, format!("{}\n", value.pretty())).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Follow a journal record: new entries get a timestamp and origin, removed
+/// ones are forgotten. Disabling or re-enabling keeps the original metadata.
+/// Failures never fail the operation that triggered them.
+pub fn record_change(exclude_path: &Path, record: &Record) {
+    if record.added.is_empty() && record.removed.is_empty() {
+        return;
+    }
+    let mut metas = load(exclude_path);
+    let now = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+    let origin = ORIGIN.get().copied().unwrap_or("layer");
+    let sources = SOURCES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for entry in &record.added {
+        metas.entry(entry.clone()).or_insert_with(|| EntryMeta {
+            added: now.clone(),
+            origin: origin.to_string(),
+            source: sources.get(entry).cloned(),
+            note: None,
+        });
+    }
+    for entry in &record.removed {
+        metas.remove(entry);
+    }
+    let _ = save(exclude_path, &metas);
+}
+
+/// Attach a note to entries that already have metadata.
+pub fn set_note(exclude_path: &Path, entries: &[String], note: &str) -> Result<()> {
+    let mut metas = load(exclude_path);
+    for entry in entries {
+        if let Some(meta) = metas.get_mut(entry) {
+            meta.note = Some(note.to_string());
+        }
+    }
+    save(exclude_path, &metas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_change_keeps_first_added_time_and_forgets_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let exclude = dir.path().join("exclude");
+        let added = Record {
+            added: vec!["CLAUDE.md".to_string(), "notes/".to_string()],
+            ..Record::default()
+        };
+        record_change(&exclude, &added);
+        let first = load(&exclude);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first["CLAUDE.md"].origin, "layer");
+
+        set_note(&exclude, &["CLAUDE.md".to_string()], "from the onboarding doc").unwrap();
+        record_change(&exclude, &added);
+        let removed = Record {
+            removed: vec!["notes/".to_string()],
+            ..Record::default()
+        };
+        record_change(&exclude, &removed);
+
+        let metas = load(&exclude);
+        assert_eq!(metas.keys().collect::<Vec<_>>(), vec!["CLAUDE.md"]);
+        assert_eq!(metas["CLAUDE.md"].added, first["CLAUDE.md"].added);
+        assert_eq!(metas["CLAUDE.md"].note.as_deref(), Some("from the onboarding doc"));
+        assert_eq!(metas["CLAUDE.md"].source, None);
+    }
+
+    #[test]
+    fn record_change_keeps_the_source_of_bulk_applied_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let exclude = dir.path().join("exclude");
+        let restored = vec![".env.local".to_string()];
+        set_source(&restored, "backup 2025-01-03");
+        record_change(&exclude, &Record { added: restored, ..Record::default() });
+        assert_eq!(load(&exclude)[".env.local"].source.as_deref(), Some("backup 2025-01-03"));
+    }
+}
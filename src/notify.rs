@@ -0,0 +1,94 @@
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Exposed entries seen by the last `status --hook`, kept in the git dir so
+/// only newly exposed entries raise a notification.
+const EXPOSED_STATE_FILE: &str = "LAYER_EXPOSED";
+
+/// Record the currently exposed entries and return the ones that were not
+/// exposed last time. The first run only records state.
+pub fn track_exposed(git_dir: &Path, current: &[String]) -> Vec<String> {
+    let path = git_dir.join(EXPOSED_STATE_FILE);
+    let previous = fs::read_to_string(&path).ok();
+    let current: BTreeSet<&str> = current.iter().map(String::as_str).collect();
+
+    let mut content = current.iter().copied().collect::<Vec<_>>().join("\n");
+    content.push('\n');
+    let _ = fs::write(&path, content);
+
+    let Some(previous) = previous else {
+        return Vec::new();
+    };
+    newly_exposed(&previous, &current)
+}
+
+fn newly_exposed(previous: &str, current: &BTreeSet<&str>) -> Vec<String> {
+    let previous: BTreeSet<&str> = previous.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    current
+        .iter()
+        .filter(|entry| !previous.contains(*entry))
+        .map(|entry| entry.to_string())
+        .collect()
+}
+
+/// Desktop notification for entries that just became exposed.
+pub fn exposure_notification(entries: &[String]) -> (String, String) {
+    let summary = if entries.len() == 1 {
+        format!("layer: {} is now exposed", entries[0])
+    } else {
+        format!("layer: {} layered entries are now exposed", entries.len())
+    };
+    let body = format!(
+        "{} {} tracked by git, so the exclude rule has no effect. Run 'layer status' for fixes.",
+        entries.join(", "),
+        if entries.len() == 1 { "is" } else { "are" }
+    );
+    (summary, body)
+}
+
+#[cfg(feature = "notifications")]
+pub fn send(summary: &str, body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("layer")
+        .summary(summary)
+        .body(body)
+        .show()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "notifications"))]
+pub fn send(_summary: &str, _body: &str) -> Result<()> {
+    anyhow::bail!("layer was built without desktop notification support")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newly_exposed_only_reports_additions() {
+        let current: BTreeSet<&str> = ["CLAUDE.md", ".cursorrules"].into_iter().collect();
+        assert_eq!(newly_exposed("CLAUDE.md\n", &current), vec![".cursorrules"]);
+        assert!(newly_exposed("CLAUDE.md\n.cursorrules\nAGENTS.md\n", &current).is_empty());
+    }
+
+    #[test]
+    fn track_exposed_records_state_before_reporting() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(track_exposed(dir.path(), &["CLAUDE.md".to_string()]).is_empty());
+        assert!(track_exposed(dir.path(), &["CLAUDE.md".to_string()]).is_empty());
+        assert_eq!(
+            track_exposed(dir.path(), &["CLAUDE.md".to_string(), "AGENTS.md".to_string()]),
+            vec!["AGENTS.md"]
+        );
+    }
+
+    #[test]
+    fn exposure_notification_wording() {
+        let (summary, body) = exposure_notification(&["CLAUDE.md".to_string()]);
+        assert_eq!(summary, "layer: CLAUDE.md is now exposed");
+        assert!(body.starts_with("CLAUDE.md is tracked by git"));
+    }
+}
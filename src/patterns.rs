@@ -1,6 +1,73 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::config::Config;
+use anyhow::{bail, Result};
+use std::sync::Mutex;
+
+/// What kind of file a pattern finds. Declared in priority order: scan and
+/// status list secrets first and OS files last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PatternCategory {
+    Secrets,
     AiConfig,
+    LocalNotes,
+    EditorConfig,
+    BuildArtifacts,
+    /// Leftovers from agent sessions, like `PLAN.md` or `*.rej`; see
+    /// [`PatternCategory::is_optional`].
+    AgentArtifacts,
+    /// Files the OS drops in every directory; only looked for with
+    /// `patterns.os_files`, and better off in the global gitignore.
+    OsFiles,
+}
+
+impl PatternCategory {
+    /// Stable identifier used in JSON output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternCategory::Secrets => "secrets",
+            PatternCategory::AiConfig => "ai_config",
+            PatternCategory::LocalNotes => "local_notes",
+            PatternCategory::EditorConfig => "editor_config",
+            PatternCategory::BuildArtifacts => "build_artifacts",
+            PatternCategory::AgentArtifacts => "agent_artifacts",
+            PatternCategory::OsFiles => "os_files",
+        }
+    }
+
+    /// Heading for a group of files in this category.
+    pub fn title(&self) -> &'static str {
+        match self {
+            PatternCategory::Secrets => "Secrets",
+            PatternCategory::AiConfig => "AI context",
+            PatternCategory::LocalNotes => "Local notes",
+            PatternCategory::EditorConfig => "Editor config",
+            PatternCategory::BuildArtifacts => "Build artifacts",
+            PatternCategory::AgentArtifacts => "Agent artifacts",
+            PatternCategory::OsFiles => "OS files",
+        }
+    }
+
+    /// Every category, in priority order.
+    pub const ALL: &'static [PatternCategory] = &[
+        PatternCategory::Secrets,
+        PatternCategory::AiConfig,
+        PatternCategory::LocalNotes,
+        PatternCategory::EditorConfig,
+        PatternCategory::BuildArtifacts,
+        PatternCategory::AgentArtifacts,
+        PatternCategory::OsFiles,
+    ];
+
+    /// Suggestions to take or leave: teams sometimes commit a `PLAN.md` or a
+    /// patch on purpose, so a tracked one isn't flagged as exposed, `check`
+    /// and the hooks let these through, and scan leaves them unticked.
+    pub fn is_optional(&self) -> bool {
+        *self == PatternCategory::AgentArtifacts
+    }
+
+    /// The category named by `as_str`.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|category| category.as_str() == name)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -8,6 +75,8 @@ pub struct KnownPattern {
     pub entry: &'static str,
     pub label: &'static str,
     pub category: PatternCategory,
+    /// What the tool keeps there, shown by `patterns --long` and the scan picker.
+    pub description: &'static str,
 }
 
 pub const KNOWN_SCAN_PATTERNS: &[KnownPattern] = &[
@@ -16,161 +85,631 @@ pub const KNOWN_SCAN_PATTERNS: &[KnownPattern] = &[
         entry: "CLAUDE.md",
         label: "Claude Code",
         category: PatternCategory::AiConfig,
+        description: "Project instructions Claude Code loads into every session",
     },
     KnownPattern {
         entry: ".claude/",
         label: "Claude Code",
         category: PatternCategory::AiConfig,
+        description: "Claude Code settings, custom commands, and agents for this project",
     },
     KnownPattern {
         entry: ".claude.json",
         label: "Claude Code",
         category: PatternCategory::AiConfig,
+        description: "Claude Code project state such as MCP servers and allowed tools",
     },
     KnownPattern {
         entry: "Agents.md",
         label: "Claude Code",
         category: PatternCategory::AiConfig,
+        description: "Agent instructions read by Claude Code and other agents",
     },
     // Cursor / PearAI
     KnownPattern {
         entry: ".cursorrules",
         label: "Cursor / PearAI",
         category: PatternCategory::AiConfig,
+        description: "Legacy single-file Cursor rules for this project",
     },
     KnownPattern {
         entry: ".cursor/",
         label: "Cursor / PearAI",
         category: PatternCategory::AiConfig,
+        description: "Cursor rules, MCP config, and editor state",
     },
     KnownPattern {
         entry: ".cursorignore",
         label: "Cursor / PearAI",
         category: PatternCategory::AiConfig,
+        description: "Files Cursor should keep out of its index and AI context",
     },
     KnownPattern {
         entry: ".pearai/",
         label: "Cursor / PearAI",
         category: PatternCategory::AiConfig,
+        description: "PearAI settings and rules",
     },
     // Windsurf
     KnownPattern {
         entry: ".windsurfrules",
         label: "Windsurf",
         category: PatternCategory::AiConfig,
+        description: "Windsurf (Cascade) rules for this project",
     },
     KnownPattern {
         entry: ".windsurf/",
         label: "Windsurf",
         category: PatternCategory::AiConfig,
+        description: "Windsurf rules and workflows",
     },
     // Aider
     KnownPattern {
         entry: ".aider*",
         label: "Aider",
         category: PatternCategory::AiConfig,
+        description: "Aider config, chat history, and tag caches",
     },
     KnownPattern {
         entry: ".aider.conf.yml",
         label: "Aider",
         category: PatternCategory::AiConfig,
+        description: "Aider options such as model and edit format",
     },
     KnownPattern {
         entry: ".aiderignore",
         label: "Aider",
         category: PatternCategory::AiConfig,
+        description: "Files Aider should not add to the chat",
     },
     // Cline / Roo Code
     KnownPattern {
         entry: ".clinerules",
         label: "Cline / Roo Code",
         category: PatternCategory::AiConfig,
+        description: "Cline custom instructions for this project",
     },
     KnownPattern {
         entry: ".cline/",
         label: "Cline / Roo Code",
         category: PatternCategory::AiConfig,
+        description: "Cline settings and task state",
     },
     KnownPattern {
         entry: ".roocodes/",
         label: "Cline / Roo Code",
         category: PatternCategory::AiConfig,
+        description: "Roo Code modes and rules",
     },
     KnownPattern {
         entry: ".roocoderules",
         label: "Cline / Roo Code",
         category: PatternCategory::AiConfig,
+        description: "Roo Code custom instructions for this project",
     },
     // GitHub Copilot
     KnownPattern {
         entry: ".github/copilot-instructions.md",
         label: "GitHub Copilot",
         category: PatternCategory::AiConfig,
+        description: "Repository instructions Copilot Chat adds to every request",
     },
     KnownPattern {
         entry: ".github/copilot-custom-instructions.md",
         label: "GitHub Copilot",
         category: PatternCategory::AiConfig,
+        description: "Custom Copilot instructions for this repository",
     },
     // OpenAI Codex
     KnownPattern {
         entry: "AGENTS.md",
         label: "OpenAI Codex",
         category: PatternCategory::AiConfig,
+        description: "Agent instructions read by Codex and other agents",
     },
     // Generic AI Context
     KnownPattern {
         entry: "agents.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Agent instructions in lowercase, read by some agents",
     },
     KnownPattern {
         entry: "AI.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Free-form notes for AI assistants",
     },
     KnownPattern {
         entry: "AI_CONTEXT.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Background context you paste or point AI assistants at",
     },
     KnownPattern {
         entry: "CONTEXT.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Project context notes for AI assistants",
     },
     KnownPattern {
         entry: "INSTRUCTIONS.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Instructions you give AI assistants",
     },
     KnownPattern {
         entry: "PROMPT.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "Saved prompts",
     },
     KnownPattern {
         entry: "SYSTEM.md",
         label: "Generic AI Context",
         category: PatternCategory::AiConfig,
+        description: "A system prompt for AI assistants",
     },
     // Continue / Void
     KnownPattern {
         entry: ".continue/",
         label: "Continue / Void",
         category: PatternCategory::AiConfig,
+        description: "Continue config, prompts, and rules",
     },
     KnownPattern {
         entry: ".void/",
         label: "Continue / Void",
         category: PatternCategory::AiConfig,
+        description: "Void editor settings and rules",
+    },
+    // Gemini CLI
+    KnownPattern {
+        entry: "GEMINI.md",
+        label: "Gemini CLI",
+        category: PatternCategory::AiConfig,
+        description: "Project instructions Gemini CLI loads as context",
+    },
+    KnownPattern {
+        entry: ".gemini/",
+        label: "Gemini CLI",
+        category: PatternCategory::AiConfig,
+        description: "Gemini CLI settings, custom commands, and extensions",
+    },
+    // Zed AI
+    KnownPattern {
+        entry: ".zed/",
+        label: "Zed AI",
+        category: PatternCategory::AiConfig,
+        description: "Zed project settings, including assistant and agent config",
+    },
+    // Sourcegraph Cody
+    KnownPattern {
+        entry: ".cody/",
+        label: "Sourcegraph Cody",
+        category: PatternCategory::AiConfig,
+        description: "Cody custom commands and ignore rules",
+    },
+    // Amazon Q
+    KnownPattern {
+        entry: ".amazonq/",
+        label: "Amazon Q",
+        category: PatternCategory::AiConfig,
+        description: "Amazon Q Developer rules and saved prompts",
+    },
+    // Goose
+    KnownPattern {
+        entry: ".goosehints",
+        label: "Goose",
+        category: PatternCategory::AiConfig,
+        description: "Project hints Goose adds to every session",
+    },
+    // opencode
+    KnownPattern {
+        entry: ".opencode/",
+        label: "opencode",
+        category: PatternCategory::AiConfig,
+        description: "opencode agents, commands, and project config",
+    },
+    // Secrets
+    KnownPattern {
+        entry: ".env",
+        label: "Secrets",
+        category: PatternCategory::Secrets,
+        description: "Environment variables, often API keys and passwords",
+    },
+    KnownPattern {
+        entry: ".env.*",
+        label: "Secrets",
+        category: PatternCategory::Secrets,
+        description: "Per-environment variables such as .env.local",
+    },
+    KnownPattern {
+        entry: "*.pem",
+        label: "Secrets",
+        category: PatternCategory::Secrets,
+        description: "Private keys and certificates",
+    },
+    KnownPattern {
+        entry: "credentials.json",
+        label: "Secrets",
+        category: PatternCategory::Secrets,
+        description: "Service account or OAuth credentials",
+    },
+    // Local notes
+    KnownPattern {
+        entry: ".notes/",
+        label: "Local notes",
+        category: PatternCategory::LocalNotes,
+        description: "Notes about the repo meant only for you",
+    },
+    KnownPattern {
+        entry: ".scratch/",
+        label: "Local notes",
+        category: PatternCategory::LocalNotes,
+        description: "Scratch files kept next to the code",
+    },
+    // Editor config
+    KnownPattern {
+        entry: ".idea/workspace.xml",
+        label: "Editor config",
+        category: PatternCategory::EditorConfig,
+        description: "JetBrains per-user window layout, run configs, and history",
+    },
+    KnownPattern {
+        entry: "*.code-workspace",
+        label: "Editor config",
+        category: PatternCategory::EditorConfig,
+        description: "VS Code workspace with your folders and settings",
+    },
+    // Build artifacts
+    KnownPattern {
+        entry: ".direnv/",
+        label: "Build artifacts",
+        category: PatternCategory::BuildArtifacts,
+        description: "Environments direnv builds and caches for this directory",
+    },
+    KnownPattern {
+        entry: ".pytest_cache/",
+        label: "Build artifacts",
+        category: PatternCategory::BuildArtifacts,
+        description: "Test results pytest caches between runs",
+    },
+    // Agent artifacts
+    KnownPattern {
+        entry: "PLAN.md",
+        label: "Agent artifacts",
+        category: PatternCategory::AgentArtifacts,
+        description: "Plan an agent drafted before making changes",
+    },
+    KnownPattern {
+        entry: "TODO-agent.md",
+        label: "Agent artifacts",
+        category: PatternCategory::AgentArtifacts,
+        description: "Task list an agent kept while it worked",
+    },
+    KnownPattern {
+        entry: "*_SUMMARY.md",
+        label: "Agent artifacts",
+        category: PatternCategory::AgentArtifacts,
+        description: "Summary an agent wrote at the end of a session",
+    },
+    KnownPattern {
+        entry: "*.patch",
+        label: "Agent artifacts",
+        category: PatternCategory::AgentArtifacts,
+        description: "Patch saved instead of applied",
+    },
+    KnownPattern {
+        entry: "*.rej",
+        label: "Agent artifacts",
+        category: PatternCategory::AgentArtifacts,
+        description: "Hunks a patch failed to apply",
+    },
+    // OS files
+    KnownPattern {
+        entry: ".DS_Store",
+        label: "OS files",
+        category: PatternCategory::OsFiles,
+        description: "Finder folder view settings (macOS)",
+    },
+    KnownPattern {
+        entry: "Thumbs.db",
+        label: "OS files",
+        category: PatternCategory::OsFiles,
+        description: "Explorer thumbnail cache (Windows)",
+    },
+    KnownPattern {
+        entry: "desktop.ini",
+        label: "OS files",
+        category: PatternCategory::OsFiles,
+        description: "Explorer folder view settings (Windows)",
     },
 ];
 
+/// Shown wherever discovery turns up secrets: layering only affects what
+/// git sees from now on.
+pub const SECRETS_NOTE: &str =
+    "Layering keeps secrets out of future commits but can't remove one that was already committed; rotate it if so";
+
+/// Suffixes of the placeholder files teams commit on purpose, like `.env.example`.
+const SECRET_TEMPLATE_SUFFIXES: &[&str] = &[".example", ".sample", ".template", ".dist"];
+
+impl KnownPattern {
+    /// Whether this pattern lets `path` go despite matching it: secrets
+    /// patterns leave committed placeholders such as `.env.example` alone.
+    pub fn spares(&self, path: &str) -> bool {
+        let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+        self.category == PatternCategory::Secrets
+            && SECRET_TEMPLATE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+    }
+}
+
+/// Whether discovery looks for `entry` below `scan.depth` too. Patch rejects
+/// land next to whatever file failed to apply, anywhere in the tree.
+pub fn matches_at_any_depth(entry: &str) -> bool {
+    entry == "*.rej"
+}
+
+/// The pattern among `patterns` (usually [`enabled_patterns`]) a path falls
+/// under, if any, so custom `scan.patterns` keep their label and category and
+/// disabled tools stay out.
+pub fn known_pattern_for(path: &str, patterns: &[&'static KnownPattern]) -> Option<&'static KnownPattern> {
+    let basename = path.rsplit('/').next().unwrap_or(path);
+    if let Some(exact) = patterns.iter().find(|p| p.entry == path) {
+        return Some(exact);
+    }
+    patterns.iter().copied().find(|pattern| {
+        let entry = pattern.entry;
+        if pattern.spares(path) {
+            false
+        } else if entry.ends_with('/') {
+            path.starts_with(entry) || path.contains(&format!("/{entry}"))
+        } else if crate::git::contains_glob(entry) {
+            crate::discovery::wildcard_match(entry, basename)
+        } else if entry.contains('/') {
+            path == entry
+        } else {
+            basename == entry
+        }
+    })
+}
+
+/// Whether a pattern's tool label matches a `--tool` filter.
+///
+/// Matching is case-insensitive and also accepts either half of combined
+/// labels, so `cursor`, `pearai`, and `Cursor / PearAI` all select the same group.
+pub fn label_matches_tool(label: &str, tool: &str) -> bool {
+    let tool = tool.trim();
+    if tool.is_empty() {
+        return false;
+    }
+    label.eq_ignore_ascii_case(tool)
+        || label
+            .split('/')
+            .any(|part| part.trim().eq_ignore_ascii_case(tool))
+}
+
+/// Label of `scan.patterns` entries that don't name their own.
+pub const PROJECT_LABEL: &str = "Project";
+
+/// Patterns discovery looks for: the built-ins plus the configured
+/// `scan.patterns`, minus the tools listed in `patterns.disabled` and, unless
+/// `patterns.os_files` is on, the built-in OS files. Sorted by category, so a
+/// path two patterns match is claimed by the higher-priority one.
+pub fn enabled_patterns(config: &Config) -> Vec<&'static KnownPattern> {
+    let disabled = config.get_list("patterns.disabled");
+    let os_files = config.get_bool("patterns.os_files");
+    let mut patterns: Vec<&'static KnownPattern> = KNOWN_SCAN_PATTERNS
+        .iter()
+        .filter(|pat| os_files || pat.category != PatternCategory::OsFiles)
+        .chain(config.get_list("scan.patterns").iter().filter_map(|item| custom_pattern(item).ok()))
+        .filter(|pat| !disabled.iter().any(|tool| label_matches_tool(pat.label, tool)))
+        .collect();
+    patterns.sort_by_key(|pat| pat.category);
+    patterns
+}
+
+/// Split a `scan.patterns` item, `entry [| label [| category]]`, into its
+/// parts. The label defaults to "Project" and the category to `ai_config`.
+pub fn parse_custom_pattern(item: &str) -> Result<(&str, &str, PatternCategory)> {
+    let mut parts = item.split('|').map(str::trim);
+    let entry = parts.next().unwrap_or_default();
+    let label = parts.next().filter(|label| !label.is_empty()).unwrap_or(PROJECT_LABEL);
+    let category = match parts.next() {
+        None | Some("") => PatternCategory::AiConfig,
+        Some(name) => match PatternCategory::parse(name) {
+            Some(category) => category,
+            None => {
+                let known: Vec<&str> = PatternCategory::ALL.iter().map(PatternCategory::as_str).collect();
+                bail!("unknown category '{name}' in '{item}'. Known categories: {}", known.join(", "));
+            }
+        },
+    };
+    if entry.is_empty() {
+        bail!("'{item}' has no pattern before the first '|'");
+    }
+    if parts.next().is_some() {
+        bail!("'{item}' has more than entry | label | category");
+    }
+    check_scan_glob(entry)?;
+    Ok((entry, label, category))
+}
+
+/// Discovery matches with `*` and `?` only, so refuse the rest of gitignore's
+/// glob syntax up front instead of accepting a pattern that never matches.
+fn check_scan_glob(entry: &str) -> Result<()> {
+    if entry.starts_with('!') {
+        bail!("'{entry}' is a negation; scan patterns say which files to look for");
+    }
+    let unsupported = if entry.contains("**") {
+        Some("'**'")
+    } else if entry.contains(['[', ']']) {
+        Some("character classes")
+    } else if entry.contains('\\') {
+        Some("escapes")
+    } else {
+        None
+    };
+    if let Some(what) = unsupported {
+        bail!("'{entry}' uses {what}, which discovery can't match. Only '*' and '?' are supported");
+    }
+    Ok(())
+}
+
+/// A `scan.patterns` item as a known pattern. Each distinct item is
+/// allocated once and reused, so `layer serve` doesn't grow with every scan.
+fn custom_pattern(item: &str) -> Result<&'static KnownPattern> {
+    static INTERNED: Mutex<Vec<&'static KnownPattern>> = Mutex::new(Vec::new());
+    let (entry, label, category) = parse_custom_pattern(item)?;
+    let mut interned = INTERNED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(existing) = interned
+        .iter()
+        .find(|pat| pat.entry == entry && pat.label == label && pat.category == category)
+    {
+        return Ok(existing);
+    }
+    let leak = |s: &str| -> &'static str { Box::leak(s.to_string().into_boxed_str()) };
+    let pattern: &'static KnownPattern = Box::leak(Box::new(KnownPattern {
+        entry: leak(entry),
+        label: if label == PROJECT_LABEL { PROJECT_LABEL } else { leak(label) },
+        category,
+        description: "Listed in scan.patterns",
+    }));
+    interned.push(pattern);
+    Ok(pattern)
+}
+
+/// Known tool labels in declaration order, without duplicates.
+pub fn tool_labels() -> Vec<&'static str> {
+    let mut labels: Vec<&'static str> = Vec::new();
+    for pat in KNOWN_SCAN_PATTERNS {
+        if !labels.contains(&pat.label) {
+            labels.push(pat.label);
+        }
+    }
+    labels
+}
+
+/// Tool labels among `patterns`: built-in tools in declaration order, then
+/// custom labels in the order `scan.patterns` lists them.
+pub fn tool_labels_in(patterns: &[&'static KnownPattern]) -> Vec<&'static str> {
+    let mut labels: Vec<&'static str> =
+        tool_labels().into_iter().filter(|label| patterns.iter().any(|p| p.label == *label)).collect();
+    for pattern in patterns {
+        if !labels.contains(&pattern.label) {
+            labels.push(pattern.label);
+        }
+    }
+    labels
+}
+
+/// A named bundle of known entries that `layer preset apply` layers up front,
+/// before a tool has written any of its files.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Tool labels whose patterns the preset layers; empty for every AI tool.
+    tools: &'static [&'static str],
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset { name: "claude", description: "Claude Code instructions and settings", tools: &["Claude Code"] },
+    Preset { name: "cursor", description: "Cursor rules and settings", tools: &["Cursor / PearAI"] },
+    Preset { name: "copilot", description: "GitHub Copilot instructions", tools: &["GitHub Copilot"] },
+    Preset { name: "windsurf", description: "Windsurf rules", tools: &["Windsurf"] },
+    Preset { name: "cline", description: "Cline and Roo Code rules", tools: &["Cline / Roo Code"] },
+    Preset { name: "aider", description: "Aider config, history, and conventions", tools: &["Aider"] },
+    Preset { name: "codex", description: "AGENTS.md for Codex and other agents", tools: &["OpenAI Codex"] },
+    Preset { name: "gemini", description: "Gemini CLI instructions and settings", tools: &["Gemini CLI"] },
+    Preset { name: "full-agents", description: "Every AI tool's files layer knows about", tools: &[] },
+];
+
+impl Preset {
+    /// The entries to layer, in the order `layer patterns` lists them.
+    pub fn entries(&self) -> Vec<&'static str> {
+        KNOWN_SCAN_PATTERNS
+            .iter()
+            .filter(|pat| {
+                if self.tools.is_empty() {
+                    pat.category == PatternCategory::AiConfig
+                } else {
+                    self.tools.contains(&pat.label)
+                }
+            })
+            .map(|pat| pat.entry)
+            .collect()
+    }
+}
+
+/// The preset called `name`, ignoring case.
+pub fn find_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn presets_name_known_tools_and_have_entries() {
+        let labels = tool_labels();
+        for preset in PRESETS {
+            assert!(preset.tools.iter().all(|tool| labels.contains(tool)), "{}", preset.name);
+            assert!(!preset.entries().is_empty(), "{}", preset.name);
+        }
+        let claude = find_preset("Claude").unwrap().entries();
+        assert!(claude.contains(&"CLAUDE.md") && claude.contains(&".claude/"));
+        let all = find_preset("full-agents").unwrap().entries();
+        assert!(all.contains(&".cursorrules") && all.contains(&"AGENTS.md"));
+        assert!(!all.contains(&".env") && !all.contains(&".DS_Store"));
+    }
+
+    #[test]
+    fn parse_custom_pattern_defaults_label_and_category() {
+        assert_eq!(
+            parse_custom_pattern("NOTES.local.md").unwrap(),
+            ("NOTES.local.md", PROJECT_LABEL, PatternCategory::AiConfig)
+        );
+        assert_eq!(
+            parse_custom_pattern(" scratch/ | Team notes | ai_config ").unwrap(),
+            ("scratch/", "Team notes", PatternCategory::AiConfig)
+        );
+        assert!(parse_custom_pattern("x | y | nope").unwrap_err().to_string().contains("Known categories"));
+        assert!(parse_custom_pattern(" | Team").is_err());
+        assert!(parse_custom_pattern("a | b | ai_config | d").is_err());
+        assert!(parse_custom_pattern("!keep.md").is_err());
+        assert!(parse_custom_pattern("**/notes.md").unwrap_err().to_string().contains("'**'"));
+        assert!(parse_custom_pattern("notes[0-9].md | Notes").unwrap_err().to_string().contains("character classes"));
+        assert!(parse_custom_pattern(r"\#notes.md").is_err());
+        assert!(parse_custom_pattern("notes-?.md").is_ok());
+    }
+
+    fn known_pattern_for_builtin(path: &str) -> Option<&'static KnownPattern> {
+        let patterns: Vec<&'static KnownPattern> = KNOWN_SCAN_PATTERNS.iter().collect();
+        known_pattern_for(path, &patterns)
+    }
+
+    #[test]
+    fn known_pattern_for_uses_the_given_patterns() {
+        let mut patterns: Vec<&'static KnownPattern> =
+            KNOWN_SCAN_PATTERNS.iter().filter(|p| p.label != "Aider").collect();
+        patterns.push(custom_pattern("NOTES.local.md | Team notes | local_notes").unwrap());
+        let notes = known_pattern_for("NOTES.local.md", &patterns).unwrap();
+        assert_eq!((notes.label, notes.category), ("Team notes", PatternCategory::LocalNotes));
+        assert!(known_pattern_for(".aider.conf.yml", &patterns).is_none());
+        assert_eq!(tool_labels_in(&patterns).last(), Some(&"Team notes"));
+    }
+
+    #[test]
+    fn known_pattern_for_matches_files_dirs_and_globs() {
+        assert_eq!(known_pattern_for_builtin("CLAUDE.md").map(|p| p.entry), Some("CLAUDE.md"));
+        assert_eq!(known_pattern_for_builtin(".cursor/rules/a.md").map(|p| p.entry), Some(".cursor/"));
+        assert_eq!(known_pattern_for_builtin(".aider*").map(|p| p.entry), Some(".aider*"));
+        assert!(known_pattern_for_builtin(".aider.chat.history.md").is_some());
+        assert!(known_pattern_for_builtin("src/main.rs").is_none());
+    }
+
     #[test]
     fn includes_required_patterns() {
         let entries = KNOWN_SCAN_PATTERNS.iter().map(|p| p.entry).collect::<Vec<_>>();
@@ -180,13 +719,39 @@ mod tests {
         assert!(entries.contains(&".aider*"));
         assert!(entries.contains(&".roocodes/"));
         assert!(entries.contains(&".continue/"));
+        assert!(entries.contains(&"GEMINI.md"));
+        assert!(entries.contains(&".goosehints"));
+        assert_eq!(known_pattern_for_builtin(".amazonq/rules/style.md").map(|p| p.label), Some("Amazon Q"));
+        assert_eq!(known_pattern_for_builtin(".opencode/agent/review.md").map(|p| p.label), Some("opencode"));
     }
 
     #[test]
-    fn all_patterns_are_ai_config() {
-        assert!(KNOWN_SCAN_PATTERNS
-            .iter()
-            .all(|p| p.category == PatternCategory::AiConfig));
+    fn every_pattern_has_a_short_description() {
+        for pattern in KNOWN_SCAN_PATTERNS {
+            assert!(!pattern.description.is_empty(), "{}", pattern.entry);
+            assert!(pattern.description.len() <= 70, "{}", pattern.entry);
+        }
+    }
+
+    #[test]
+    fn patterns_outside_ai_config_are_labeled_by_category() {
+        assert!(KNOWN_SCAN_PATTERNS.iter().all(|p| match p.category {
+            PatternCategory::Secrets => p.label == "Secrets",
+            PatternCategory::LocalNotes => p.label == "Local notes",
+            PatternCategory::EditorConfig => p.label == "Editor config",
+            PatternCategory::BuildArtifacts => p.label == "Build artifacts",
+            PatternCategory::AgentArtifacts => p.label == "Agent artifacts",
+            PatternCategory::OsFiles => p.label == "OS files",
+            category => category == PatternCategory::AiConfig,
+        }));
+    }
+
+    #[test]
+    fn secrets_patterns_spare_committed_placeholders() {
+        assert_eq!(known_pattern_for_builtin(".env.local").map(|p| p.label), Some("Secrets"));
+        assert_eq!(known_pattern_for_builtin("config/credentials.json").map(|p| p.label), Some("Secrets"));
+        assert!(known_pattern_for_builtin(".env.example").is_none());
+        assert!(known_pattern_for_builtin("deploy/.env.sample").is_none());
     }
 
     #[test]
@@ -196,4 +761,28 @@ mod tests {
         let entries = KNOWN_SCAN_PATTERNS.iter().map(|p| p.entry).collect::<Vec<_>>();
         assert!(!entries.contains(&"AI_INSTRUCTIONS.md"));
     }
+
+    #[test]
+    fn label_matches_tool_case_insensitive() {
+        assert!(label_matches_tool("Claude Code", "claude code"));
+        assert!(label_matches_tool("Aider", "AIDER"));
+        assert!(!label_matches_tool("Aider", "claude"));
+        assert!(!label_matches_tool("Aider", ""));
+    }
+
+    #[test]
+    fn label_matches_tool_combined_label_parts() {
+        assert!(label_matches_tool("Cursor / PearAI", "cursor"));
+        assert!(label_matches_tool("Cursor / PearAI", "pearai"));
+        assert!(label_matches_tool("Cursor / PearAI", "Cursor / PearAI"));
+        assert!(!label_matches_tool("Cursor / PearAI", "pear"));
+    }
+
+    #[test]
+    fn tool_labels_are_unique() {
+        let labels = tool_labels();
+        let unique = labels.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(labels.len(), unique.len());
+        assert_eq!(labels[0], "Claude Code");
+    }
 }
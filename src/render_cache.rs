@@ -0,0 +1,157 @@
+use crate::commands::backup::fnv1a;
+use crate::config;
+use crate::exclude_file::ensure_exclude_file;
+use crate::git::{self, RepoContext};
+use crate::json::{self, Json};
+use crate::ui;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Cached output of read-only reports (`ls`, `status --short`), so prompt
+/// integrations that run them on every keystroke don't pay for git.
+pub const RENDER_CACHE_FILE: &str = "LAYER_RENDER_CACHE";
+
+/// Renders kept, most recent first: one per distinct command line.
+const MAX_RENDERS: usize = 8;
+
+/// Run `render` or replay its output from the last run with the same command
+/// line, when the exclude file, index, HEAD, config, and the layered paths
+/// themselves haven't changed since. Failures are never cached.
+pub fn cached(render: impl FnOnce() -> Result<i32>) -> Result<i32> {
+    let Ok(ctx) = git::ensure_repo() else {
+        return render();
+    };
+    let key = render_key();
+    let state = format!("{:016x}", fnv1a(state_inputs(&ctx).as_bytes()));
+    let path = ctx.git_dir.join(RENDER_CACHE_FILE);
+    let renders = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| json::parse(&content).ok())
+        .and_then(|value| value.as_array().map(<[Json]>::to_vec))
+        .unwrap_or_default();
+
+    let hit = renders.iter().find(|render| {
+        render.get("key").and_then(Json::as_str) == Some(&key)
+            && render.get("state").and_then(Json::as_str) == Some(&state)
+    });
+    if let Some(render) = hit {
+        if let (Some(Json::Number(code)), Some(output)) = (render.get("code"), render.get("output").and_then(Json::as_str)) {
+            ui::replay(output);
+            return Ok(*code as i32);
+        }
+    }
+
+    let (result, output) = ui::capture(render);
+    ui::replay(&output);
+    let code = result?;
+
+    let mut kept = vec![Json::object(vec![
+        ("key", Json::String(key.clone())),
+        ("state", Json::String(state)),
+        ("code", Json::Number(f64::from(code))),
+        ("output", Json::String(output)),
+    ])];
+    kept.extend(
+        renders
+            .into_iter()
+            .filter(|render| render.get("key").and_then(Json::as_str) != Some(&key))
+            .take(MAX_RENDERS - 1),
+    );
+    let _ = fs::write(&path, format!("{}\n", Json::Array(kept)));
+    Ok(code)
+}
+
+/// What makes two runs print the same thing besides repo state: the
+/// arguments, the working directory, and whether output is plain or colored.
+fn render_key() -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cwd = std::env::current_dir().map(|dir| dir.display().to_string()).unwrap_or_default();
+    format!("{}|{cwd}|{}|{}", args.join("\u{1f}"), ui::is_plain(), console::colors_enabled())
+}
+
+/// What any cached report depends on beyond its own inputs, as one string:
+/// the layer version, mtimes of git's index, HEAD, and config, the exclude
+/// file, layer's state and config files, the global excludes file and every
+/// `.gitignore`, and `LAYER_*` variables. Shared with the `status --hook`
+/// cache, so both notice the same changes.
+pub fn repo_state(ctx: &RepoContext) -> String {
+    let mut files = vec![
+        ctx.exclude_path.clone(),
+        ctx.git_dir.join("index"),
+        ctx.git_dir.join("HEAD"),
+        common_dir(&ctx.git_dir).join("config"),
+        ctx.git_dir.join("info").join(crate::meta::META_FILE),
+        ctx.git_dir.join(crate::acks::ACKS_FILE),
+        ctx.root.join(config::REPO_CONFIG_FILE),
+        ctx.root.join(crate::layerignore::LAYERIGNORE_FILE),
+        ctx.root.join(".gitignore"),
+    ];
+    files.extend(config::user_config_path());
+    files.extend(config::system_config_path());
+    files.extend(crate::commands::global::effective_path());
+    let gitignores = git::git_stdout(
+        &["ls-files", "--cached", "--others", "--exclude-standard", "--", ":(glob)**/.gitignore"],
+        Some(&ctx.root),
+    )
+    .unwrap_or_default();
+    files.extend(gitignores.lines().map(|path| ctx.root.join(path)));
+
+    let mut inputs = vec![env!("CARGO_PKG_VERSION").to_string()];
+    inputs.extend(files.iter().map(|path| mtime(path).to_string()));
+    let mut vars: Vec<String> = std::env::vars()
+        .filter(|(name, _)| (name.starts_with("LAYER_") && name != crate::vault::PASSPHRASE_ENV) || name == "NO_COLOR")
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    vars.sort();
+    inputs.extend(vars);
+    inputs.join("\n")
+}
+
+/// Everything a cached render depends on, as one string to hash:
+/// `repo_state`, the exclude file's content, each layered path (or its
+/// parent, for one that doesn't exist yet), and the untracked files layered
+/// globs match.
+fn state_inputs(ctx: &RepoContext) -> String {
+    let mut inputs = vec![repo_state(ctx), fs::read_to_string(&ctx.exclude_path).unwrap_or_default()];
+    let mut files = vec![ctx.root.clone()];
+    let mut globs = Vec::new();
+    if let Ok(exclude) = ensure_exclude_file(&ctx.exclude_path) {
+        for entry in exclude.entries().into_iter().chain(exclude.user_entries()) {
+            if git::contains_glob(&entry.value) {
+                if !entry.value.starts_with('!') {
+                    globs.push(entry.value.trim_start_matches('/').to_string());
+                }
+                continue;
+            }
+            let path = ctx.root.join(entry.value.trim_matches('/'));
+            files.push(if path.exists() { path } else { path.parent().unwrap_or(&ctx.root).to_path_buf() });
+        }
+    }
+    inputs.extend(files.iter().map(|path| mtime(path).to_string()));
+    // A file a layered glob matches can turn up in any directory, so list them.
+    if !globs.is_empty() {
+        let mut args = vec!["ls-files", "--others", "--ignored", "--exclude-standard", "--"];
+        args.extend(globs.iter().map(String::as_str));
+        inputs.push(git::git_stdout(&args, Some(&ctx.root)).unwrap_or_default());
+    }
+    inputs.join("\n")
+}
+
+fn mtime(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos())
+}
+
+/// Where a worktree's shared files such as `config` live: the main repo's
+/// git directory, or `git_dir` itself outside a linked worktree.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(rel) => git_dir.join(rel.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
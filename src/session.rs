@@ -0,0 +1,182 @@
+use crate::exclude_file::ensure_exclude_file_for_write;
+use crate::git;
+use crate::git::RepoContext;
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Entries added with `add --session`, one `session<TAB>entry` per line in
+/// the git dir.
+const SESSION_FILE: &str = "LAYER_SESSIONS";
+
+/// The terminal session this run belongs to: `LAYER_SESSION` when set,
+/// otherwise the pid of whatever started layer. That is the shell when run
+/// by hand; under a wrapper it's the wrapper, see [`fallback_warning`].
+pub fn current_id() -> Result<String> {
+    if let Some(id) = std::env::var("LAYER_SESSION").ok().filter(|v| !v.trim().is_empty()) {
+        return Ok(id.trim().to_string());
+    }
+    #[cfg(unix)]
+    {
+        Ok(std::os::unix::process::parent_id().to_string())
+    }
+    #[cfg(not(unix))]
+    {
+        bail!("can't tell which terminal session this is. Set LAYER_SESSION (e.g. to your shell's pid) and retry")
+    }
+}
+
+/// Shells whose pid is a sensible stand-in for the terminal session.
+const SHELLS: &[&str] = &["bash", "zsh", "fish", "sh", "dash", "ksh", "mksh", "tcsh", "csh", "nu", "pwsh", "elvish", "xonsh"];
+
+/// A warning for when `current_id` falls back to a parent that isn't an
+/// interactive shell, such as make, `sh -c`, a git alias, or `layer
+/// foreach`: session entries would end as soon as that wrapper exits.
+pub fn fallback_warning() -> Option<String> {
+    if std::env::var("LAYER_SESSION").is_ok_and(|v| !v.trim().is_empty()) {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        let args = process_args(std::os::unix::process::parent_id())?;
+        let program = args[0].trim_start_matches('-');
+        let name = program.rsplit('/').next().unwrap_or(program);
+        let wrapper = !SHELLS.contains(&name) || args.iter().skip(1).any(|arg| arg == "-c");
+        wrapper.then(|| {
+            format!(
+                "layer was started by '{name}' rather than your interactive shell, so these entries are removed once it exits. Set LAYER_SESSION to keep them longer"
+            )
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Command line of `pid`, from /proc where there is one and `ps` elsewhere.
+#[cfg(unix)]
+fn process_args(pid: u32) -> Option<Vec<String>> {
+    let args: Vec<String> = match fs::read(format!("/proc/{pid}/cmdline")) {
+        Ok(raw) => raw
+            .split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+        Err(_) => {
+            let output = Command::new("ps").args(["-o", "args=", "-p", &pid.to_string()]).output().ok()?;
+            String::from_utf8(output.stdout).ok()?.split_whitespace().map(str::to_string).collect()
+        }
+    };
+    Some(args).filter(|args| !args.is_empty())
+}
+
+/// Remember that `session` added `entries`, so they can be dropped when it ends.
+pub fn record(git_dir: &Path, session: &str, entries: &[String]) -> Result<()> {
+    if session.contains(['\t', '\n']) {
+        bail!("LAYER_SESSION can't contain tabs or newlines");
+    }
+    let path = git_dir.join(SESSION_FILE);
+    let mut content = fs::read_to_string(&path).unwrap_or_default();
+    for entry in entries {
+        content.push_str(&format!("{session}\t{entry}\n"));
+    }
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Session entries recorded in this repo, as `(session, entry)` pairs.
+pub fn list(git_dir: &Path) -> Vec<(String, String)> {
+    fs::read_to_string(git_dir.join(SESSION_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(session, entry)| (session.to_string(), entry.to_string()))
+        .collect()
+}
+
+/// Drop the entries of every session `ended` says is over, from both the
+/// layer and the record. Returns the removed entries.
+pub fn remove_sessions(ctx: &RepoContext, ended: impl Fn(&str) -> bool) -> Result<Vec<String>> {
+    let records = list(&ctx.git_dir);
+    if records.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (done, keep): (Vec<_>, Vec<_>) = records.into_iter().partition(|(session, _)| ended(session));
+    if done.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // An entry another live session still wants stays layered.
+    let kept: HashSet<&str> = keep.iter().map(|(_, entry)| entry.as_str()).collect();
+    let targets: HashSet<String> = done
+        .iter()
+        .map(|(_, entry)| entry.clone())
+        .filter(|entry| !kept.contains(entry.as_str()))
+        .collect();
+    let mut exclude = ensure_exclude_file_for_write(&ctx.exclude_path)?;
+    let removed = exclude.remove_exact(&targets);
+    if !removed.is_empty() {
+        exclude.write(&ctx.exclude_path)?;
+    }
+
+    let path = ctx.git_dir.join(SESSION_FILE);
+    if keep.is_empty() {
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+    } else {
+        let content: String = keep.iter().map(|(session, entry)| format!("{session}\t{entry}\n")).collect();
+        fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(removed)
+}
+
+/// Drop entries whose shell has exited. Sessions named through
+/// `LAYER_SESSION` that aren't a pid only end with `layer session end`.
+/// Best effort: runs before most commands and never fails them.
+pub fn prune_ended() {
+    let Ok(ctx) = git::ensure_repo() else {
+        return;
+    };
+    if !ctx.git_dir.join(SESSION_FILE).exists() {
+        return;
+    }
+    let _ = remove_sessions(&ctx, |session| session.parse::<u32>().is_ok_and(|pid| !is_alive(pid)));
+}
+
+fn is_alive(pid: u32) -> bool {
+    if Path::new("/proc/self").exists() {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_appends_and_list_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        record(dir.path(), "42", &["notes.md".to_string()]).unwrap();
+        record(dir.path(), "work", &["scratch/".to_string()]).unwrap();
+        assert_eq!(
+            list(dir.path()),
+            vec![
+                ("42".to_string(), "notes.md".to_string()),
+                ("work".to_string(), "scratch/".to_string())
+            ]
+        );
+        assert!(record(dir.path(), "a\tb", &[]).is_err());
+    }
+
+    #[test]
+    fn own_process_is_alive() {
+        assert!(is_alive(std::process::id()));
+    }
+}
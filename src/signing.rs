@@ -0,0 +1,171 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Namespace passed to `ssh-keygen -Y` so layer signatures can't be replayed
+/// as signatures for other purposes (e.g. git commits).
+const SSH_NAMESPACE: &str = "layer";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheme {
+    Ssh,
+    Minisign,
+}
+
+impl Scheme {
+    fn tool(self) -> &'static str {
+        match self {
+            Scheme::Ssh => "ssh-keygen",
+            Scheme::Minisign => "minisign",
+        }
+    }
+
+    fn signature_path(self, file: &Path) -> PathBuf {
+        let ext = match self {
+            Scheme::Ssh => "sig",
+            Scheme::Minisign => "minisig",
+        };
+        let mut name = file.as_os_str().to_owned();
+        name.push(".");
+        name.push(ext);
+        PathBuf::from(name)
+    }
+}
+
+/// minisign keys start with an "untrusted comment:" line; anything else is
+/// treated as an SSH key or allowed_signers file.
+fn detect_scheme(key_content: &str) -> Scheme {
+    if key_content.trim_start().starts_with("untrusted comment:") {
+        Scheme::Minisign
+    } else {
+        Scheme::Ssh
+    }
+}
+
+/// Sign `file` with a private key, writing `<file>.sig` (ssh) or
+/// `<file>.minisig` (minisign). Returns the signature path.
+pub fn sign(file: &Path, key: &Path) -> Result<PathBuf> {
+    let content = fs::read_to_string(key).unwrap_or_default();
+    let scheme = detect_scheme(&content);
+    let sig_path = scheme.signature_path(file);
+
+    let mut cmd = Command::new(scheme.tool());
+    match scheme {
+        Scheme::Ssh => {
+            // ssh-keygen refuses to overwrite an existing signature.
+            let _ = fs::remove_file(&sig_path);
+            cmd.args(["-Y", "sign", "-n", SSH_NAMESPACE, "-f"]).arg(key).arg(file);
+        }
+        Scheme::Minisign => {
+            cmd.args(["-S", "-s"]).arg(key).arg("-m").arg(file);
+        }
+    }
+    run(cmd, scheme, "sign")?;
+    Ok(sig_path)
+}
+
+/// Whether `file` has a signature next to it.
+pub fn has_signature(file: &Path) -> bool {
+    [Scheme::Ssh, Scheme::Minisign]
+        .iter()
+        .any(|scheme| scheme.signature_path(file).exists())
+}
+
+/// Verify `file` against `trusted`: an ssh `allowed_signers` file or a
+/// minisign public key. Returns who signed it.
+pub fn verify(file: &Path, trusted: &Path) -> Result<String> {
+    let content = fs::read_to_string(trusted)
+        .with_context(|| format!("failed to read {}", trusted.display()))?;
+    let scheme = detect_scheme(&content);
+    let sig_path = scheme.signature_path(file);
+    if !sig_path.exists() {
+        bail!(
+            "no signature found for {} (expected {})",
+            file.display(),
+            sig_path.display()
+        );
+    }
+
+    match scheme {
+        Scheme::Ssh => {
+            let mut find = Command::new("ssh-keygen");
+            find.args(["-Y", "find-principals", "-f"])
+                .arg(trusted)
+                .arg("-s")
+                .arg(&sig_path);
+            let principals = run(find, scheme, "verify")
+                .map_err(|_| anyhow!("signature on {} is not from an allowed signer", file.display()))?;
+            let principal = principals
+                .lines()
+                .next()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| anyhow!("signature on {} is not from an allowed signer", file.display()))?
+                .to_string();
+
+            let mut check = Command::new("ssh-keygen");
+            check
+                .args(["-Y", "verify", "-n", SSH_NAMESPACE, "-f"])
+                .arg(trusted)
+                .args(["-I", &principal, "-s"])
+                .arg(&sig_path)
+                .stdin(fs::File::open(file).with_context(|| format!("failed to read {}", file.display()))?);
+            run(check, scheme, "verify")
+                .map_err(|_| anyhow!("signature verification failed for {}", file.display()))?;
+            Ok(principal)
+        }
+        Scheme::Minisign => {
+            let mut check = Command::new("minisign");
+            check.args(["-V", "-q", "-p"]).arg(trusted).arg("-m").arg(file);
+            run(check, scheme, "verify")
+                .map_err(|_| anyhow!("signature verification failed for {}", file.display()))?;
+            Ok(format!("minisign key {}", trusted.display()))
+        }
+    }
+}
+
+fn run(mut cmd: Command, scheme: Scheme, action: &str) -> Result<String> {
+    let output = cmd
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow!("{} is required to {action} but was not found on PATH", scheme.tool())
+            }
+            _ => anyhow!("failed to run {}: {err}", scheme.tool()),
+        })?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            scheme.tool(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_minisign_keys() {
+        assert_eq!(
+            detect_scheme("untrusted comment: minisign public key ABC\nRWQ..."),
+            Scheme::Minisign
+        );
+        assert_eq!(detect_scheme("alice@example.com ssh-ed25519 AAAA..."), Scheme::Ssh);
+        assert_eq!(detect_scheme(""), Scheme::Ssh);
+    }
+
+    #[test]
+    fn signature_paths_append_extension() {
+        let file = Path::new("/tmp/team.txt");
+        assert_eq!(Scheme::Ssh.signature_path(file), PathBuf::from("/tmp/team.txt.sig"));
+        assert_eq!(
+            Scheme::Minisign.signature_path(file),
+            PathBuf::from("/tmp/team.txt.minisig")
+        );
+    }
+}
@@ -120,10 +120,8 @@ pub fn run(nodes: &[TreeNode]) -> io::Result<Option<Vec<String>>> {
             Key::ArrowUp => {
                 cursor = cursor.saturating_sub(1);
             }
-            Key::ArrowDown => {
-                if cursor + 1 < items.len() {
-                    cursor += 1;
-                }
+            Key::ArrowDown if cursor + 1 < items.len() => {
+                cursor += 1;
             }
             Key::Char(' ') => {
                 let path = items[cursor].path().to_string();
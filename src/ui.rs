@@ -1,47 +1,294 @@
-use console::{style, Style, Term};
+use console::{style, Style, StyledObject, Term};
 use dialoguer::theme::ColorfulTheme;
+use std::cell::RefCell;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+// ── Output mode ────────────────────────────────────────────────
+
+static FORCE_PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Output format chosen with the global `--format` flag. `json` and
+/// `porcelain` are meant for scripts: uncolored, no padding lines, and a
+/// layout that stays stable across releases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human-readable dashboard output
+    #[default]
+    Pretty,
+    /// A single JSON document
+    Json,
+    /// One tab-separated record per line
+    Porcelain,
+    /// GitHub Actions annotations (`check` only)
+    Gha,
+    /// A user template with `{field}` placeholders (`ls` and `status` only)
+    #[value(skip)]
+    Template,
+}
+
+/// `--format` as typed: one of the named formats, or a template such as
+/// `{status}\t{entry}`, recognized by its `{`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatArg {
+    Named(Format),
+    Template(String),
+}
+
+impl FormatArg {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if raw.contains('{') {
+            return Ok(FormatArg::Template(raw.to_string()));
+        }
+        <Format as clap::ValueEnum>::from_str(raw, true).map(FormatArg::Named).map_err(|_| {
+            "expected pretty, json, porcelain, gha, or a template like '{status}\\t{entry}'".to_string()
+        })
+    }
+}
+
+impl Format {
+    pub fn is_json(self) -> bool {
+        self == Format::Json
+    }
+
+    pub fn is_porcelain(self) -> bool {
+        self == Format::Porcelain
+    }
+}
+
+static TEMPLATE: OnceLock<String> = OnceLock::new();
+
+/// Remember the `--format` template for `template_line`.
+pub fn set_template(template: &str) {
+    let _ = TEMPLATE.set(template.to_string());
+}
+
+/// Fail before any output when the template names a field the command
+/// doesn't have.
+pub fn check_template(fields: &[&str]) -> anyhow::Result<()> {
+    let empty: Vec<(&str, &str)> = fields.iter().map(|f| (*f, "")).collect();
+    render_template(TEMPLATE.get().map_or("", String::as_str), &empty)
+        .map(|_| ())
+        .map_err(|field| anyhow::anyhow!("unknown field {{{field}}} in --format. Available: {}", fields.join(", ")))
+}
+
+/// Print one record through the `--format` template.
+pub fn template_line(fields: &[(&str, &str)]) {
+    if let Ok(line) = render_template(TEMPLATE.get().map_or("", String::as_str), fields) {
+        write_stdout(&line);
+    }
+}
+
+/// Fill `{field}` placeholders and the `\t`, `\n`, and `\\` escapes a shell
+/// leaves in single quotes. `{{` and `}}` are literal braces. An unknown
+/// field is returned as the error.
+fn render_template(template: &str, fields: &[(&str, &str)]) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                match fields.iter().find(|(field, _)| *field == name) {
+                    Some((_, value)) => out.push_str(value),
+                    None => return Err(name),
+                }
+            }
+            '\\' => match chars.next() {
+                Some('t') => out.push('\t'),
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Print one porcelain record: fields joined by tabs, never decorated.
+pub fn porcelain(fields: &[&str]) {
+    write_stdout(&fields.join("\t"));
+}
+
+/// Print one line of streamed JSON as-is; unlike `outln!`, its indentation
+/// survives plain output.
+pub fn json_line(line: &str) {
+    write_stdout(line);
+}
+
+/// Print a GitHub Actions workflow command, e.g. `::error file=a.md,title=T::msg`,
+/// which the runner turns into an annotation on that file.
+pub fn gha_annotation(level: &str, file: Option<&str>, title: &str, message: &str) {
+    let escape_data = |s: &str| s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    let escape_property = |s: &str| escape_data(s).replace(':', "%3A").replace(',', "%2C");
+    let mut properties = Vec::new();
+    if let Some(file) = file {
+        properties.push(format!("file={}", escape_property(file)));
+    }
+    properties.push(format!("title={}", escape_property(title)));
+    write_stdout(&format!("::{level} {}::{}", properties.join(","), escape_data(message)));
+}
+
+// ── Output sink ────────────────────────────────────────────────
+
+thread_local! {
+    /// Buffer that replaces stdout while `capture` runs on this thread.
+    static CAPTURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Write one line of command output to the current sink: the capture
+/// buffer inside `capture`, stdout otherwise. Every stdout line a command
+/// prints goes through here.
+fn write_stdout(line: &str) {
+    let captured = CAPTURE.with(|capture| match capture.borrow_mut().as_mut() {
+        Some(buffer) => {
+            buffer.push_str(line);
+            buffer.push('\n');
+            true
+        }
+        None => false,
+    });
+    if !captured {
+        let _ = writeln!(io::stdout().lock(), "{line}");
+    }
+}
+
+/// Run `f` with command output redirected into a string instead of stdout,
+/// so commands can be tested in-process and renders cached. Captures nest;
+/// the outer buffer is restored afterwards.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, String) {
+    let previous = CAPTURE.with(|capture| capture.borrow_mut().replace(String::new()));
+    let result = f();
+    let output = CAPTURE.with(|capture| std::mem::replace(&mut *capture.borrow_mut(), previous));
+    (result, output.unwrap_or_default())
+}
+
+/// Write output collected by `capture` to the current sink as-is.
+pub fn replay(output: &str) {
+    for line in output.lines() {
+        write_stdout(line);
+    }
+}
+
+/// Plain mode — stdout is piped, so drop indents, glyphs, and padding lines.
+pub fn is_plain() -> bool {
+    FORCE_PLAIN.load(Ordering::Relaxed) || !is_stdout_tty()
+}
+
+/// When to color output, chosen with `--color` (or `--no-color`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and NO_COLOR is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Apply the color choice to stdout and stderr. `auto` keeps console's
+/// terminal and CLICOLOR detection and also honors NO_COLOR
+/// (<https://no-color.org>): any non-empty value turns color off.
+pub fn set_color(choice: ColorChoice) {
+    let (stdout, stderr) = match choice {
+        ColorChoice::Always => (true, true),
+        ColorChoice::Never => (false, false),
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            (
+                console::colors_enabled() && !no_color,
+                console::colors_enabled_stderr() && !no_color,
+            )
+        }
+    };
+    console::set_colors_enabled(stdout && !FORCE_PLAIN.load(Ordering::Relaxed));
+    console::set_colors_enabled_stderr(stderr);
+}
+
+/// Force plain, uncolored output regardless of the terminal (hook mode).
+pub fn force_plain() {
+    FORCE_PLAIN.store(true, Ordering::Relaxed);
+    console::set_colors_enabled(false);
+}
+
+/// Print a line of command output. In plain mode the leading layout
+/// (indentation and the status glyph slot) is stripped.
+macro_rules! outln {
+    () => {
+        $crate::ui::emit_line("")
+    };
+    ($($arg:tt)*) => {
+        $crate::ui::emit_line(&format!($($arg)*))
+    };
+}
+pub(crate) use outln;
+
+#[doc(hidden)]
+pub fn emit_line(line: &str) {
+    if is_plain() {
+        write_stdout(line.trim_start());
+    } else {
+        write_stdout(line);
+    }
+}
 
 // ── Status indicators ──────────────────────────────────────────
 
+fn glyph(styled: StyledObject<&str>) -> String {
+    if is_plain() {
+        String::new()
+    } else {
+        styled.to_string()
+    }
+}
+
 /// Layered — file is in your local layer. Dim because no action needed.
 pub fn layered() -> String {
-    style("✓").dim().to_string()
+    glyph(style("✓").dim())
 }
 
 /// Exposed — file is excluded but still tracked. Needs attention.
 pub fn exposed() -> String {
-    style("!").yellow().bold().to_string()
+    glyph(style("!").yellow().bold())
 }
 
 /// Discovered — known context file found on disk that isn't layered yet.
 pub fn discovered() -> String {
-    style("+").cyan().to_string()
+    glyph(style("+").cyan())
 }
 
 /// Stale — entry points to nothing. Should be cleaned.
 pub fn stale() -> String {
-    style("x").red().to_string()
+    glyph(style("x").red())
 }
 
 /// Info — secondary/redundant note.
 pub fn info() -> String {
-    style("-").dim().to_string()
+    glyph(style("-").dim())
 }
 
 /// Manual — user-added entry outside layer section.
 pub fn manual() -> String {
-    style("~").dim().to_string()
+    glyph(style("~").dim())
 }
 
 /// Disabled — entry is temporarily turned off.
 pub fn disabled() -> String {
-    style("○").dim().to_string()
+    glyph(style("○").dim())
 }
 
 /// Success — action completed. Cyan brand accent.
 pub fn ok() -> String {
-    style("✓").cyan().bold().to_string()
+    glyph(style("✓").cyan().bold())
 }
 
 // ── Text styling ───────────────────────────────────────────────
@@ -75,12 +322,29 @@ pub fn err_text(text: &str) -> String {
 
 /// Print to stderr with red "error:" prefix.
 pub fn print_error(msg: &str) {
-    let _ = writeln!(io::stderr(), "{} {}", style("error:").red().bold(), msg);
+    let _ = writeln!(io::stderr(), "{} {}", style("error:").for_stderr().red().bold(), msg);
+}
+
+/// Print to stderr with yellow "warning:" prefix. Used for problems noticed
+/// while loading files, so they never mix into a command's stdout.
+pub fn print_stderr_warning(msg: &str) {
+    let _ = writeln!(io::stderr(), "{} {}", style("warning:").for_stderr().yellow().bold(), msg);
+}
+
+/// Print a plain line to stderr, for commentary that must stay out of
+/// machine-readable stdout.
+pub fn print_stderr(msg: &str) {
+    let _ = writeln!(io::stderr(), "{msg}");
+}
+
+/// Print a dim `trace:` line to stderr (`--verbose`).
+pub fn print_trace(msg: &str) {
+    let _ = writeln!(io::stderr(), "{}", style(format!("trace: {msg}")).for_stderr().dim());
 }
 
 /// Print a warning line with yellow "!" prefix.
 pub fn print_warning(msg: &str) {
-    println!("{} {}", exposed(), style(msg).yellow());
+    outln!("{} {}", exposed(), style(msg).yellow());
 }
 
 /// Check if stdout is a TTY.
@@ -98,7 +362,7 @@ pub fn require_tty(message: &str) -> anyhow::Result<()> {
 
 /// Print the standard dry-run footer.
 pub fn print_dry_run_notice() {
-    println!("{}", dim_text("(dry run — no changes made)"));
+    outln!("{}", dim_text("(dry run — no changes made)"));
 }
 
 /// Print keyboard guide for MultiSelect prompts.
@@ -131,3 +395,45 @@ pub fn layer_theme() -> ColorfulTheme {
         ..ColorfulTheme::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_collects_output_and_nests() {
+        let ((code, inner), outer) = capture(|| {
+            outln!("outer line");
+            let inner = capture(|| {
+                porcelain(&["layered", "CLAUDE.md"]);
+                7
+            });
+            outln!();
+            inner
+        });
+        assert_eq!(code, 7);
+        assert_eq!(inner, "layered\tCLAUDE.md\n");
+        assert_eq!(outer, "outer line\n\n");
+    }
+
+    #[test]
+    fn gha_annotation_escapes_properties_and_message() {
+        let ((), out) = capture(|| gha_annotation("error", Some("a,b:c.md"), "Exposed", "50% done\nnext"));
+        assert_eq!(out, "::error file=a%2Cb%3Ac.md,title=Exposed::50%25 done%0Anext\n");
+    }
+
+    #[test]
+    fn render_template_fills_fields_and_escapes() {
+        let fields = [("status", "layered"), ("entry", "CLAUDE.md")];
+        assert_eq!(render_template("{status}\\t{entry}", &fields).unwrap(), "layered\tCLAUDE.md");
+        assert_eq!(render_template("{{{entry}}}", &fields).unwrap(), "{CLAUDE.md}");
+        assert_eq!(render_template("{nope}", &fields).unwrap_err(), "nope");
+    }
+
+    #[test]
+    fn format_arg_parses_names_and_templates() {
+        assert_eq!(FormatArg::parse("JSON").unwrap(), FormatArg::Named(Format::Json));
+        assert_eq!(FormatArg::parse("{entry}").unwrap(), FormatArg::Template("{entry}".to_string()));
+        assert!(FormatArg::parse("template").is_err());
+    }
+}
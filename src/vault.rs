@@ -0,0 +1,145 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+/// Encrypted copies of vaulted files, under the git dir so they never reach a commit.
+pub const VAULT_DIR: &str = "layer-vault";
+
+/// Read instead of prompting, for scripts.
+pub const PASSPHRASE_ENV: &str = "LAYER_VAULT_PASSPHRASE";
+
+const EXTENSION: &str = ".gpg";
+
+/// Where the encrypted copy of repo-relative `rel` lives.
+pub fn vault_path(git_dir: &Path, rel: &str) -> PathBuf {
+    git_dir.join(VAULT_DIR).join(format!("{rel}{EXTENSION}"))
+}
+
+/// Repo-relative paths of every vaulted file, sorted.
+pub fn list(git_dir: &Path) -> Vec<String> {
+    let dir = git_dir.join(VAULT_DIR);
+    let mut paths: Vec<String> = WalkDir::new(&dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let rel = entry.path().strip_prefix(&dir).ok()?.to_string_lossy().replace('\\', "/");
+            rel.strip_suffix(EXTENSION).map(str::to_owned)
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Encrypt `plain` into `vaulted` with `gpg --symmetric` (AES-256). The old
+/// copy is only replaced once gpg succeeds.
+pub fn encrypt(plain: &Path, vaulted: &Path, passphrase: &str) -> Result<()> {
+    if let Some(parent) = vaulted.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let mut tmp = vaulted.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut cmd = gpg();
+    cmd.args(["--symmetric", "--cipher-algo", "AES256", "-o"]).arg(&tmp).arg(plain);
+    if let Err(err) = run(cmd, passphrase, "encrypt") {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+    fs::rename(&tmp, vaulted).with_context(|| format!("failed to write {}", vaulted.display()))
+}
+
+/// Decrypt `vaulted` into memory. A wrong passphrase is an error.
+pub fn decrypt(vaulted: &Path, passphrase: &str) -> Result<Vec<u8>> {
+    let mut cmd = gpg();
+    cmd.args(["--decrypt", "-o", "-"]).arg(vaulted);
+    run(cmd, passphrase, "decrypt").map_err(|err| {
+        if err.to_string().contains("Bad session key") {
+            anyhow!("wrong vault passphrase")
+        } else {
+            err
+        }
+    })
+}
+
+/// Write a decrypted copy readable only by the current user.
+pub fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|| format!("failed to write {}", path.display()))?;
+    file.write_all(content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// The passphrase goes to gpg on stdin, never on its command line, and
+/// isn't cached by gpg-agent so every run checks it.
+fn gpg() -> Command {
+    let mut cmd = Command::new("gpg");
+    cmd.args([
+        "--batch",
+        "--yes",
+        "--quiet",
+        "--no-symkey-cache",
+        "--pinentry-mode",
+        "loopback",
+        "--passphrase-fd",
+        "0",
+    ]);
+    cmd
+}
+
+fn run(mut cmd: Command, passphrase: &str, action: &str) -> Result<Vec<u8>> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => anyhow!("gpg is required to {action} vault files but was not found on PATH"),
+            _ => anyhow!("failed to run gpg: {err}"),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{passphrase}").context("failed to pass the passphrase to gpg")?;
+    }
+    let output = child.wait_with_output().context("failed to run gpg")?;
+    if !output.status.success() {
+        bail!("gpg failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vault_paths_mirror_the_repo_layout() {
+        let git_dir = Path::new("/repo/.git");
+        assert_eq!(
+            vault_path(git_dir, "notes/CLAUDE.md"),
+            PathBuf::from("/repo/.git/layer-vault/notes/CLAUDE.md.gpg")
+        );
+    }
+
+    #[test]
+    fn list_returns_repo_relative_paths() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for rel in ["CLAUDE.md", "notes/prompt.md"] {
+            let path = vault_path(dir.path(), rel);
+            fs::create_dir_all(path.parent().unwrap()).expect("mkdir");
+            fs::write(path, "x").expect("write");
+        }
+        assert_eq!(list(dir.path()), vec!["CLAUDE.md", "notes/prompt.md"]);
+        assert!(list(&dir.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// True when running inside Windows Subsystem for Linux.
+pub fn is_wsl() -> bool {
+    static IS_WSL: OnceLock<bool> = OnceLock::new();
+    *IS_WSL.get_or_init(|| {
+        if !cfg!(target_os = "linux") {
+            return false;
+        }
+        if std::env::var_os("WSL_DISTRO_NAME").is_some() {
+            return true;
+        }
+        std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .map(|release| osrelease_is_wsl(&release))
+            .unwrap_or(false)
+    })
+}
+
+fn osrelease_is_wsl(release: &str) -> bool {
+    let lower = release.to_ascii_lowercase();
+    lower.contains("microsoft") || lower.contains("wsl")
+}
+
+/// `C:\Users\me` or `C:/Users/me` → `/mnt/c/Users/me`.
+pub fn windows_to_wsl(raw: &str) -> Option<String> {
+    let bytes = raw.as_bytes();
+    if bytes.len() < 2 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' {
+        return None;
+    }
+    if bytes.len() > 2 && bytes[2] != b'/' && bytes[2] != b'\\' {
+        return None;
+    }
+    let drive = (bytes[0] as char).to_ascii_lowercase();
+    let rest = raw[2..].replace('\\', "/");
+    Some(format!("/mnt/{drive}{rest}"))
+}
+
+/// `/mnt/c/Users/me` → `C:/Users/me`.
+pub fn wsl_to_windows(raw: &str) -> Option<String> {
+    let rest = raw.strip_prefix("/mnt/")?;
+    let mut chars = rest.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let tail = chars.as_str();
+    if !tail.is_empty() && !tail.starts_with('/') {
+        return None;
+    }
+    let tail = if tail.is_empty() { "/" } else { tail };
+    Some(format!("{}:{tail}", drive.to_ascii_uppercase()))
+}
+
+/// Rewrite a path reported by git into the form this process uses. From WSL,
+/// `git` may resolve to `git.exe` and report `C:/...` while walkdir sees
+/// `/mnt/c/...`; on Windows the reverse can happen.
+pub fn normalize_git_path(raw: &str) -> PathBuf {
+    let converted = if is_wsl() {
+        windows_to_wsl(raw)
+    } else if cfg!(windows) {
+        wsl_to_windows(raw)
+    } else {
+        None
+    };
+    PathBuf::from(converted.unwrap_or_else(|| raw.to_string()))
+}
+
+/// Describe a known performance cliff for a repo root, if any.
+pub fn cross_filesystem_warning(root: &Path, in_wsl: bool) -> Option<String> {
+    let display = root.to_string_lossy().replace('\\', "/");
+    if in_wsl && wsl_to_windows(&display).is_some() {
+        return Some(format!(
+            "{} is on a Windows drive; git and file scans through /mnt are slow from WSL. \
+             Clone into the Linux filesystem (e.g. ~/src) for faster layer and git runs",
+            root.display()
+        ));
+    }
+    let lower = display.to_ascii_lowercase();
+    if lower.starts_with("//wsl$/") || lower.starts_with("//wsl.localhost/") {
+        return Some(format!(
+            "{} is inside WSL but accessed from Windows; file scans over \\\\wsl$ are slow. \
+             Run layer from inside WSL instead",
+            root.display()
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osrelease_detection() {
+        assert!(osrelease_is_wsl("5.15.90.1-microsoft-standard-WSL2\n"));
+        assert!(osrelease_is_wsl("4.4.0-19041-Microsoft"));
+        assert!(!osrelease_is_wsl("6.5.0-14-generic"));
+    }
+
+    #[test]
+    fn windows_paths_map_to_mnt() {
+        assert_eq!(windows_to_wsl("C:/Users/me/repo").as_deref(), Some("/mnt/c/Users/me/repo"));
+        assert_eq!(windows_to_wsl("D:\\work\\repo").as_deref(), Some("/mnt/d/work/repo"));
+        assert_eq!(windows_to_wsl("/home/me/repo"), None);
+        assert_eq!(windows_to_wsl("C:repo"), None);
+    }
+
+    #[test]
+    fn mnt_paths_map_to_drive_letters() {
+        assert_eq!(wsl_to_windows("/mnt/c/Users/me").as_deref(), Some("C:/Users/me"));
+        assert_eq!(wsl_to_windows("/mnt/d").as_deref(), Some("D:/"));
+        assert_eq!(wsl_to_windows("/mnt/data/repo"), None);
+        assert_eq!(wsl_to_windows("/home/me"), None);
+    }
+
+    #[test]
+    fn cross_filesystem_warnings() {
+        assert!(cross_filesystem_warning(Path::new("/mnt/c/Users/me/repo"), true).is_some());
+        assert!(cross_filesystem_warning(Path::new("/mnt/c/Users/me/repo"), false).is_none());
+        assert!(cross_filesystem_warning(Path::new("/home/me/repo"), true).is_none());
+        assert!(cross_filesystem_warning(Path::new("//wsl$/Ubuntu/home/me/repo"), false).is_some());
+    }
+}
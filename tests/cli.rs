@@ -221,143 +221,251 @@ fn ls_shows_tracked_warning() {
         .stdout(predicate::str::contains("exposed"));
 }
 
-// --- doctor integration tests ---
-
 #[test]
-fn doctor_empty_shows_hint() {
+fn ls_status_filters_and_sort_orders_entries() {
     let repo = init_repo();
+    fs::write(repo.path().join("small.md"), "x").expect("write");
+    fs::write(repo.path().join("big.md"), "x".repeat(4096)).expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("doctor")
+        .args(["add", "small.md", "big.md", "gone.md"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "ls", "--status", "layered", "--sort", "size"])
+        .output()
+        .expect("run ls");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "layered\tbig.md\nlayered\tsmall.md\n");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "ls", "--sort", "status"])
+        .output()
+        .expect("run ls");
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("stale\tgone.md\n"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--status", "exposed,manual"])
         .assert()
         .code(2)
-        .stdout(predicate::str::contains("No layered entries"));
+        .stdout(predicate::str::contains("No exposed or manual entries."));
 }
 
 #[test]
-fn doctor_healthy_entry() {
+fn ls_paths_only_prints_bare_entries() {
     let repo = init_repo();
-    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
-
+    fs::write(repo.path().join("CLAUDE.md"), "x").expect("write");
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md"])
+        .args(["add", "CLAUDE.md", "gone.md"])
         .assert()
         .success();
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("doctor")
+        .args(["--color", "always", "ls", "--paths-only"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("layered"))
-        .stdout(predicate::str::contains("1 layered"));
+        .stdout("CLAUDE.md\ngone.md\n");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--paths-only", "--status", "stale"])
+        .assert()
+        .success()
+        .stdout("gone.md\n");
 }
 
 #[test]
-fn doctor_stale_entry() {
+fn ls_long_shows_size_and_modified() {
     let repo = init_repo();
-
+    fs::create_dir_all(repo.path().join("notes")).expect("mkdir");
+    fs::write(repo.path().join("notes/a.md"), "x".repeat(2048)).expect("write");
+    fs::write(repo.path().join("notes/b.md"), "x".repeat(1024)).expect("write");
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "gone.md"])
+        .args(["add", "notes/", "gone.md"])
         .assert()
         .success();
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("doctor")
+        .args(["ls", "--long"])
         .assert()
-        .code(1)
-        .stdout(predicate::str::contains("stale"))
-        .stdout(predicate::str::contains("1 stale"));
+        .stdout(predicate::str::contains("3.0 KB, 2 files, modified "))
+        .stdout(predicate::str::is_match(r"gone\.md .*KB").expect("regex").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "ls", "--long"])
+        .assert()
+        .stdout(predicate::str::contains("\"size\": 3072"))
+        .stdout(predicate::str::contains("\"files\": 2"))
+        .stdout(predicate::str::contains("\"modified\": \""));
 }
 
 #[test]
-fn doctor_tracked_entry() {
+fn ls_and_status_short_replay_until_something_changes() {
     let repo = init_repo();
-    fs::write(repo.path().join("tracked.md"), "x").expect("write");
-
-    Command::new("git")
-        .args(["add", "tracked.md"])
+    fs::write(repo.path().join("CLAUDE.md"), "x").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
         .assert()
         .success();
 
+    for _ in 0..2 {
+        Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+            .current_dir(repo.path())
+            .arg("ls")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("CLAUDE.md  layered"));
+        Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+            .current_dir(repo.path())
+            .args(["status", "--short"])
+            .assert()
+            .success()
+            .stdout("1 layered\n");
+    }
+    assert!(repo.path().join(".git/LAYER_RENDER_CACHE").is_file());
+
+    fs::remove_file(repo.path().join("CLAUDE.md")).expect("remove");
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "tracked.md"])
+        .arg("ls")
         .assert()
-        .success();
+        .stdout(predicate::str::contains("CLAUDE.md  stale"));
 
+    fs::write(repo.path().join("AGENTS.md"), "x").expect("write");
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("doctor")
+        .args(["status", "--short"])
         .assert()
-        .code(1)
-        .stdout(predicate::str::contains("exposed"))
-        .stdout(predicate::str::contains("1 exposed"));
+        .stdout("0 layered · 1 discovered\n");
 }
 
-// --- scan integration tests ---
-
 #[test]
-fn scan_no_ai_files() {
+fn ls_replay_notices_new_glob_matches_and_nested_gitignores() {
     let repo = init_repo();
-
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("scan")
+        .args(["add", "*.log"])
         .assert()
-        .code(2)
-        .stdout(predicate::str::contains("No context files found"));
+        .success();
+    let ls = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+        cmd.current_dir(repo.path()).arg("ls");
+        cmd
+    };
+    ls().assert().stdout(predicate::str::contains("*.log  stale"));
+
+    fs::create_dir_all(repo.path().join("sub")).expect("mkdir");
+    fs::write(repo.path().join("sub/a.log"), "x").expect("write");
+    ls().assert().stdout(predicate::str::contains("*.log  layered"));
+
+    // A new nested .gitignore can change what's ignored, so it re-renders.
+    let cache_path = repo.path().join(".git/LAYER_RENDER_CACHE");
+    let cached = fs::read_to_string(&cache_path).expect("read");
+    fs::write(repo.path().join("sub/.gitignore"), "a.log\n").expect("write");
+    ls().assert().success();
+    assert_ne!(cached, fs::read_to_string(&cache_path).expect("read"));
 }
 
 #[test]
-fn scan_finds_ai_files() {
+fn ls_groups_entries_under_their_tool() {
     let repo = init_repo();
-    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
-    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "notes.md", ".cursorrules", "CLAUDE.md"])
+        .assert()
+        .success();
 
     let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("scan")
+        .arg("ls")
         .output()
-        .expect("failed to run scan");
+        .expect("run ls");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let claude = stdout.find("Claude Code").expect("Claude Code heading");
+    let cursor = stdout.find("Cursor / PearAI").expect("Cursor heading");
+    let other = stdout.find("Other").expect("Other heading");
+    assert!(claude < stdout.find("CLAUDE.md").unwrap() && cursor < stdout.find(".cursorrules").unwrap());
+    assert!(claude < cursor && cursor < other && other < stdout.find("notes.md").unwrap(), "{stdout}");
 
+    // --sort keeps its order instead of being split up by tool.
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--sort", "status"])
+        .output()
+        .expect("run ls");
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("CLAUDE.md"), "should find CLAUDE.md");
-    assert!(stdout.contains(".cursorrules"), "should find .cursorrules");
+    assert!(!stdout.contains("Claude Code") && !stdout.contains("Other"), "{stdout}");
+    let positions: Vec<usize> =
+        [".cursorrules", "notes.md", "CLAUDE.md"].iter().map(|e| stdout.find(e).expect("entry")).collect();
+    assert!(positions[0] < positions[1] && positions[1] < positions[2], "{stdout}");
 }
 
-// --- clean integration test ---
-
 #[test]
-fn clean_dry_run_shows_stale() {
+fn ls_and_status_render_format_templates() {
     let repo = init_repo();
-
+    fs::create_dir_all(repo.path().join(".cursor")).expect("mkdir");
+    fs::write(repo.path().join(".cursor/a.md"), "a").expect("write");
+    fs::write(repo.path().join(".cursor/b.md"), "b").expect("write");
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "gone.md"])
+        .args(["add", ".cursor/"])
         .assert()
         .success();
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["clean", "--dry-run"])
+        .args(["ls", "--format", "{status}\\t{entry}\\t{matches}\\t{tool}"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Would remove 1 stale entries"))
-        .stdout(predicate::str::contains("gone.md"))
-        .stdout(predicate::str::contains("dry run"));
+        .stdout("layered\t.cursor/\t2\tCursor / PearAI\n");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--format", "{entry}={matches}"])
+        .assert()
+        .success()
+        .stdout(".cursor/=2\n");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--format", "{size}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown field {size}"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["doctor", "--format", "{entry}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only available for ls and status"));
 }
 
-// --- rm dry-run integration test ---
+// --- doctor integration tests ---
 
 #[test]
-fn rm_dry_run_does_not_modify_file() {
+fn doctor_empty_shows_hint() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("doctor")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No layered entries"));
+}
+
+#[test]
+fn doctor_healthy_entry() {
     let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
@@ -367,403 +475,3900 @@ fn rm_dry_run_does_not_modify_file() {
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["rm", "--dry-run", "CLAUDE.md"])
+        .arg("doctor")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Would remove 'CLAUDE.md'"))
-        .stdout(predicate::str::contains("dry run"));
-
-    // Verify file was NOT modified
-    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
-    assert!(content.contains("CLAUDE.md"), "entry should still be present after dry run");
+        .stdout(predicate::str::contains("layered"))
+        .stdout(predicate::str::contains("1 layered"));
 }
 
-// --- section-based ownership tests ---
-
 #[test]
-fn add_preserves_user_entries_in_exclude() {
+fn doctor_stale_entry() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    // Pre-populate with user-owned entries (no layer section)
-    fs::write(&exclude, "# my custom excludes\nmy-notes.txt\n").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md"])
+        .args(["add", "gone.md"])
         .assert()
         .success();
 
-    let content = fs::read_to_string(&exclude).expect("read");
-    // User entry preserved in prefix
-    assert!(content.contains("my-notes.txt"), "user entry should be preserved");
-    assert!(content.contains("# my custom excludes"), "user comment should be preserved");
-    // Section markers present
-    assert!(content.contains("# managed by layer"), "start marker should be present");
-    assert!(content.contains("# end layer"), "end marker should be present");
-    // layer entry added
-    assert!(content.contains("CLAUDE.md"), "layer entry should be present");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("doctor")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("stale"))
+        .stdout(predicate::str::contains("1 stale"));
 }
 
 #[test]
-fn clear_preserves_user_entries() {
+fn doctor_references_finds_tracked_files_that_mention_layered_paths() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(
-        &exclude,
-        "my-notes.txt\n# managed by layer\nCLAUDE.md\n# end layer\n",
-    )
-    .expect("write");
+    fs::write(repo.path().join("PROMPT.md"), "prompt").expect("write");
+    fs::write(repo.path().join("build.sh"), "#!/bin/sh\ncat PROMPT.md > out.txt\ncat MYPROMPT.md\n").expect("write");
+    git(repo.path(), &["add", "build.sh"]);
 
-    // clear requires TTY confirmation — use dry-run to test the count
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["clear", "--dry-run"])
+        .args(["add", "PROMPT.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("doctor")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Would remove all 1 entries"));
+        .stdout(predicate::str::contains("build.sh").not());
 
-    // Verify file was NOT modified
-    let content = fs::read_to_string(&exclude).expect("read");
-    assert!(content.contains("my-notes.txt"), "user entry should still be present");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["doctor", "--references"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tracked files mention"))
+        .stdout(predicate::str::contains("PROMPT.md — build.sh:2"))
+        .stdout(predicate::str::contains("build.sh:3").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "doctor", "--references"])
+        .assert()
+        .stdout(predicate::str::contains("referenced\tPROMPT.md\tbuild.sh:2\n"));
 }
 
 #[test]
-fn ls_shows_manual_entries() {
+fn doctor_flags_personal_gitignore_lines_and_demote_moves_them() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write file");
-    fs::write(
-        &exclude,
-        "my-notes.txt\n# managed by layer\nCLAUDE.md\n# end layer\n",
-    )
-    .expect("write");
+    fs::write(repo.path().join(".gitignore"), "target/\n.idea/\n*.local.json\n").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("ls")
+        .arg("doctor")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("look personal"))
+        .stdout(predicate::str::contains("layer demote .idea/"))
+        .stdout(predicate::str::contains("layer demote *.local.json"))
+        .stdout(predicate::str::contains("layer demote target/").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demote", ".idea/"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("CLAUDE.md"))
-        .stdout(predicate::str::contains("layered"))
-        .stdout(predicate::str::contains("my-notes.txt"))
-        .stdout(predicate::str::contains("(manual)"));
-}
+        .stdout(predicate::str::contains("Moved '.idea/'"));
 
-// --- clean --all integration test ---
+    let gitignore = fs::read_to_string(repo.path().join(".gitignore")).expect("read");
+    assert_eq!(gitignore, "target/\n*.local.json\n");
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == ".idea/"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demote", "missing/"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("not a line in .gitignore"));
+}
 
 #[test]
-fn clean_all_dry_run_shows_user_stale() {
+fn doctor_recommends_one_owner_for_entries_the_global_gitignore_covers() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    // User entry "gone-user.md" doesn't exist on disk → stale
-    // Managed entry "gone-managed.md" doesn't exist on disk → stale
+    fs::write(repo.path().join(".test-global-ignore"), ".DS_Store\n*.log\nconfig/local.yml\n").expect("write");
+    fs::create_dir_all(repo.path().join("config")).expect("mkdir");
+    for file in [".DS_Store", "debug.log", "config/local.yml", "CLAUDE.md"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
     fs::write(
-        &exclude,
-        "gone-user.md\n# managed by layer\ngone-managed.md\n# end layer\n",
+        exclude_path(repo.path()),
+        "# managed by layer\n.DS_Store\ndebug.log\nconfig/local.yml\nCLAUDE.md\n# end layer\n",
     )
     .expect("write");
 
-    // Without --all, only managed stale entries shown
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["clean", "--dry-run"])
+        .arg("doctor")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Would remove 1 stale entries"))
-        .stdout(predicate::str::contains("gone-managed.md"))
-        .stdout(predicate::str::contains("gone-user.md").not());
+        .stdout(predicate::str::contains("Overlap with your global gitignore"))
+        .stdout(predicate::str::contains("which covers every repo: layer rm .DS_Store"))
+        .stdout(predicate::str::contains("covered by global *.log: layer rm debug.log"))
+        .stdout(predicate::str::contains("keep it layered: layer global rm config/local.yml"))
+        .stdout(predicate::str::contains("layer rm CLAUDE.md").not());
 
-    // With --all, both managed and user stale entries shown
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["clean", "--all", "--dry-run"])
+        .args(["doctor", "--json"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Would remove 2 stale entries"))
-        .stdout(predicate::str::contains("gone-managed.md"))
-        .stdout(predicate::str::contains("gone-user.md"))
-        .stdout(predicate::str::contains("(manual)"));
+        .stdout(predicate::str::contains("\"global_overlap\""))
+        .stdout(predicate::str::contains("\"owner\": \"local\""));
 }
 
-// --- backup/restore integration tests ---
-
 #[test]
-fn backup_creates_file_and_restore_list_shows_it() {
+fn preset_apply_layers_a_tools_entries_before_its_files_exist() {
     let repo = init_repo();
-    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md"])
+        .args(["preset", "apply", "Claude"])
         .assert()
-        .success();
-
-    // Use isolated HOME so backup goes to temp dir, not user's real backups
-    let backup_home = tempfile::tempdir().expect("backup home");
+        .success()
+        .stdout(predicate::str::contains("Layered 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("Layered '.claude/'"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+    assert!(!exclude.lines().any(|line| line == ".cursorrules"));
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
-        .env("HOME", backup_home.path())
         .current_dir(repo.path())
-        .arg("backup")
+        .args(["preset", "apply", "claude"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Backed up 1 entries"));
-
-    // Verify backup directory was created
-    let backup_dir = backup_home.path().join(".layer-backups");
-    assert!(backup_dir.exists(), "backup dir should exist");
+        .code(2);
 
-    // restore --list should show the backup
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
-        .env("HOME", backup_home.path())
         .current_dir(repo.path())
-        .args(["restore", "--list"])
+        .args(["preset", "apply", "emacs"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("1 entries"));
+        .failure()
+        .stderr(predicate::str::contains("unknown preset 'emacs'. Available presets: claude, cursor"));
 }
 
-// --- add dry-run integration test ---
+#[test]
+fn template_save_and_apply_carry_a_layer_to_another_repo() {
+    let home = tempfile::tempdir().expect("tempdir");
+    let first = init_repo();
+    let second = init_repo();
 
-// --- off/on integration tests ---
+    config_cmd(first.path(), home.path())
+        .args(["template", "list"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No templates saved"));
+
+    config_cmd(first.path(), home.path()).args(["add", "CLAUDE.md", "notes/"]).assert().success();
+    config_cmd(first.path(), home.path())
+        .args(["template", "save", "mine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved 2 entries as template 'mine'"));
+    assert!(home.path().join("layer/templates/mine.txt").is_file());
+    config_cmd(first.path(), home.path())
+        .args(["template", "save", "mine"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Use --force to replace it"));
+
+    config_cmd(second.path(), home.path())
+        .args(["template", "apply", "mine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layered 'notes/'"));
+    let exclude = fs::read_to_string(exclude_path(second.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+
+    config_cmd(second.path(), home.path())
+        .args(["template", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mine  2 entries"));
+    config_cmd(second.path(), home.path())
+        .args(["template", "apply", "../mine"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't a valid template name"));
+}
 
 #[test]
-fn off_disables_all_entries() {
+fn import_layers_patterns_from_tool_ignore_files() {
     let repo = init_repo();
+    fs::write(repo.path().join(".cursorignore"), "# cursor\n.env\nnotes/\n!keep.md\n**/*\n").expect("write");
+    fs::create_dir_all(repo.path().join("web")).expect("mkdir");
+    fs::write(repo.path().join("web/.aiderignore"), "/build/\n*.log\n").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md", "Agents.md"])
+        .args(["import", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped '!keep.md'"))
+        .stdout(predicate::str::contains("Skipped '**/*'"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).unwrap_or_default();
+    assert!(!exclude.lines().any(|line| line == ".env"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["import", "--yes", "--from", ".cursorignore", "--from", "web/.aiderignore"])
         .assert()
         .success();
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    for entry in [".env", "notes/", "web/build/", "web/**/*.log"] {
+        assert!(exclude.lines().any(|line| line == entry), "missing {entry}");
+    }
+    assert!(!exclude.lines().any(|line| line.contains("keep.md") || line == "**/*"));
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("off")
+        .args(["ls", "--long"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Disabled CLAUDE.md"))
-        .stdout(predicate::str::contains("Disabled Agents.md"));
+        .stdout(predicate::str::is_match(r"notes/ .*from \.cursorignore").unwrap())
+        .stdout(predicate::str::is_match(r"web/build/ .*from web/\.aiderignore").unwrap());
 
-    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
-    assert!(content.contains("# [off] CLAUDE.md"));
-    assert!(content.contains("# [off] Agents.md"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["import", "--yes"])
+        .assert()
+        .code(2);
 }
 
 #[test]
-fn on_enables_all_entries() {
+fn new_creates_a_context_file_from_a_template_and_layers_it() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(
-        &exclude,
-        "# managed by layer\n# [off] CLAUDE.md\n# [off] Agents.md\n# end layer\n",
-    )
-    .expect("write");
+    let home = tempfile::tempdir().expect("tempdir");
+    let templates = home.path().join("templates");
+    fs::create_dir_all(&templates).expect("mkdir");
+    fs::write(templates.join("AGENTS.md"), "# {project} agents\n").expect("write");
 
-    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
-        .current_dir(repo.path())
-        .arg("on")
+    config_cmd(repo.path(), home.path()).args(["new", "claude"]).assert().success();
+    assert!(fs::read_to_string(repo.path().join("CLAUDE.md")).expect("read").starts_with("# "));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", &format!("new.templates={}", templates.display()), "new", "agents", "--no-layer"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Enabled CLAUDE.md"))
-        .stdout(predicate::str::contains("Enabled Agents.md"));
+        .success();
+    let project = repo.path().file_name().expect("name").to_string_lossy().into_owned();
+    assert_eq!(fs::read_to_string(repo.path().join("AGENTS.md")).expect("read"), format!("# {project} agents\n"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!exclude.lines().any(|line| line == "AGENTS.md"));
 
-    let content = fs::read_to_string(&exclude).expect("read");
-    assert!(!content.contains("# [off]"));
-    assert!(content.contains("CLAUDE.md"));
-    assert!(content.contains("Agents.md"));
+    config_cmd(repo.path(), home.path())
+        .args(["new", "claude"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
 }
 
 #[test]
-fn off_specific_entry() {
+fn doctor_tracked_entry() {
     let repo = init_repo();
+    fs::write(repo.path().join("tracked.md"), "x").expect("write");
 
-    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+    Command::new("git")
+        .args(["add", "tracked.md"])
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md", "Agents.md"])
         .assert()
         .success();
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["off", "CLAUDE.md"])
+        .args(["add", "tracked.md"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Disabled CLAUDE.md"));
+        .success();
 
-    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
-    assert!(content.contains("# [off] CLAUDE.md"));
-    assert!(content.contains("\nAgents.md\n"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("doctor")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("exposed"))
+        .stdout(predicate::str::contains("1 exposed"));
 }
 
+// --- scan integration tests ---
+
 #[test]
-fn on_specific_entry() {
+fn scan_no_ai_files() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(
-        &exclude,
-        "# managed by layer\n# [off] CLAUDE.md\n# [off] Agents.md\n# end layer\n",
-    )
-    .expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["on", "CLAUDE.md"])
+        .arg("scan")
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Enabled CLAUDE.md"));
+        .code(2)
+        .stdout(predicate::str::contains("No context files found"));
+}
 
-    let content = fs::read_to_string(&exclude).expect("read");
-    assert!(content.contains("CLAUDE.md"));
-    assert!(content.contains("# [off] Agents.md"));
+#[test]
+fn scan_finds_ai_files() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("scan")
+        .output()
+        .expect("failed to run scan");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("CLAUDE.md"), "should find CLAUDE.md");
+    assert!(stdout.contains(".cursorrules"), "should find .cursorrules");
 }
 
 #[test]
-fn off_nothing_to_disable_exits_2() {
+fn scan_and_status_warn_about_secrets_but_skip_placeholders() {
     let repo = init_repo();
+    fs::write(repo.path().join(".env"), "TOKEN=x").expect("write");
+    fs::write(repo.path().join(".env.example"), "TOKEN=").expect("write");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("scan")
+        .output()
+        .expect("scan");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(".env (Secrets)"), "{stdout}");
+    assert!(!stdout.contains(".env.example"), "{stdout}");
+    assert!(stdout.contains("can't remove one that was already committed"), "{stdout}");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("off")
+        .arg("status")
         .assert()
-        .code(2)
-        .stdout(predicate::str::contains("No active entries to disable"));
+        .stdout(predicate::str::contains("layer add .env"))
+        .stdout(predicate::str::contains("rotate it if so"));
 }
 
 #[test]
-fn on_nothing_to_enable_exits_2() {
+fn scan_yes_layers_every_new_discovered_file_without_prompting() {
     let repo = init_repo();
+    for file in ["CLAUDE.md", ".cursorrules", "AGENTS.md"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
+    git(repo.path(), &["add", "AGENTS.md"]);
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("on")
+        .args(["scan", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Discovered (2)"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+    assert!(exclude.lines().any(|line| line == ".cursorrules"));
+    assert!(!exclude.lines().any(|line| line == "AGENTS.md"), "tracked files need --fix-tracked");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--yes"])
         .assert()
         .code(2)
-        .stdout(predicate::str::contains("No disabled entries to enable"));
+        .stdout(predicate::str::contains("No new context files found."));
 }
 
 #[test]
-fn off_dry_run_does_not_modify() {
+fn scan_tool_looks_only_for_that_tools_files() {
     let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "x").expect("write");
+    fs::write(repo.path().join(".aider.conf.yml"), "x").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md"])
+        .args(["scan", "--tool", "aider", "--json"])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains(".aider.conf.yml"))
+        .stdout(predicate::str::contains("CLAUDE.md").not());
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["off", "--dry-run"])
+        .args(["scan", "--tool", "windsurf"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Would disable CLAUDE.md"))
-        .stdout(predicate::str::contains("dry run"));
+        .code(2)
+        .stdout(predicate::str::contains("No windsurf files found"));
 
-    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
-    assert!(!content.contains("# [off]"), "file should not be modified after dry run");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--tool", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown tool 'nope'. Known tools: Secrets, Claude Code"));
 }
 
 #[test]
-fn on_dry_run_does_not_modify() {
+fn scan_untracked_all_offers_untracked_files_without_context_files() {
     let repo = init_repo();
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(
-        &exclude,
-        "# managed by layer\n# [off] CLAUDE.md\n# end layer\n",
-    )
-    .expect("write");
+    fs::write(repo.path().join("scratch.txt"), "x").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["on", "--dry-run"])
+        .args(["scan", "--untracked-all"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Would enable CLAUDE.md"))
-        .stdout(predicate::str::contains("dry run"));
+        .failure()
+        .stdout(predicate::str::contains("No context files found").not())
+        .stderr(predicate::str::contains("interactive mode requires a TTY"));
 
-    let content = fs::read_to_string(&exclude).expect("read");
-    assert!(content.contains("# [off] CLAUDE.md"), "file should not be modified after dry run");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "scan", "--untracked-all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--untracked-all opens a picker"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("interactive mode requires a TTY"));
 }
 
+// --- clean integration test ---
+
 #[test]
-fn ls_shows_disabled_entries() {
+fn clean_dry_run_shows_stale() {
     let repo = init_repo();
-    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
-    let exclude = exclude_path(repo.path());
-    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
-    fs::write(
-        &exclude,
-        "# managed by layer\nCLAUDE.md\n# [off] Agents.md\n# end layer\n",
-    )
-    .expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("ls")
+        .args(["add", "gone.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clean", "--dry-run"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("CLAUDE.md"))
-        .stdout(predicate::str::contains("layered"))
-        .stdout(predicate::str::contains("Agents.md"))
-        .stdout(predicate::str::contains("(disabled)"));
+        .stdout(predicate::str::contains("Would remove 1 stale entries"))
+        .stdout(predicate::str::contains("gone.md"))
+        .stdout(predicate::str::contains("dry run"));
 }
 
 #[test]
-fn roundtrip_off_then_on() {
+fn clean_yes_removes_stale_without_tty() {
     let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "CLAUDE.md", "Agents.md"])
+        .args(["add", "gone.md", "CLAUDE.md"])
         .assert()
         .success();
 
-    let before = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clean", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gone.md"));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!content.contains("gone.md"));
+    assert!(content.contains("CLAUDE.md"));
+}
+
+// --- rm dry-run integration test ---
+
+#[test]
+fn rm_dry_run_does_not_modify_file() {
+    let repo = init_repo();
 
-    // Disable all
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("off")
+        .args(["add", "CLAUDE.md"])
         .assert()
         .success();
 
-    // Re-enable all
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .arg("on")
+        .args(["rm", "--dry-run", "CLAUDE.md"])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("Would remove 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("dry run"));
 
-    let after = fs::read_to_string(exclude_path(repo.path())).expect("read");
-    assert_eq!(before, after, "roundtrip should restore original file");
+    // Verify file was NOT modified
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(content.contains("CLAUDE.md"), "entry should still be present after dry run");
 }
 
-// --- add dry-run integration test ---
+// --- section-based ownership tests ---
 
 #[test]
-fn add_dry_run_does_not_write() {
+fn add_preserves_user_entries_in_exclude() {
     let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    // Pre-populate with user-owned entries (no layer section)
+    fs::write(&exclude, "# my custom excludes\nmy-notes.txt\n").expect("write");
 
     Command::new(assert_cmd::cargo::cargo_bin!("layer"))
         .current_dir(repo.path())
-        .args(["add", "--dry-run", "CLAUDE.md"])
+        .args(["add", "CLAUDE.md"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Would layer 'CLAUDE.md'"))
-        .stdout(predicate::str::contains("dry run"));
+        .success();
 
-    // Verify file was NOT created with the entry
-    let exclude = exclude_path(repo.path());
-    if exclude.exists() {
-        let content = fs::read_to_string(&exclude).expect("read");
-        assert!(!content.contains("CLAUDE.md"), "entry should not be present after dry run");
-    }
+    let content = fs::read_to_string(&exclude).expect("read");
+    // User entry preserved in prefix
+    assert!(content.contains("my-notes.txt"), "user entry should be preserved");
+    assert!(content.contains("# my custom excludes"), "user comment should be preserved");
+    // Section markers present
+    assert!(content.contains("# managed by layer"), "start marker should be present");
+    assert!(content.contains("# end layer"), "end marker should be present");
+    // layer entry added
+    assert!(content.contains("CLAUDE.md"), "layer entry should be present");
+}
+
+#[test]
+fn clear_preserves_user_entries() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "my-notes.txt\n# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    // clear requires TTY confirmation — use dry-run to test the count
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clear", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove all 1 entries"));
+
+    // Verify file was NOT modified
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert!(content.contains("my-notes.txt"), "user entry should still be present");
+}
+
+#[test]
+fn clear_yes_skips_confirmation() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "my-notes.txt\n# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clear", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All entries removed"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert!(content.contains("my-notes.txt"), "user entry should still be present");
+    assert!(!content.contains("CLAUDE.md"));
+}
+
+#[test]
+fn ls_shows_manual_entries() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write file");
+    fs::write(
+        &exclude,
+        "my-notes.txt\n# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CLAUDE.md"))
+        .stdout(predicate::str::contains("layered"))
+        .stdout(predicate::str::contains("my-notes.txt"))
+        .stdout(predicate::str::contains("(manual)"));
+}
+
+// --- clean --all integration test ---
+
+#[test]
+fn clean_all_dry_run_shows_user_stale() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    // User entry "gone-user.md" doesn't exist on disk → stale
+    // Managed entry "gone-managed.md" doesn't exist on disk → stale
+    fs::write(
+        &exclude,
+        "gone-user.md\n# managed by layer\ngone-managed.md\n# end layer\n",
+    )
+    .expect("write");
+
+    // Without --all, only managed stale entries shown
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clean", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove 1 stale entries"))
+        .stdout(predicate::str::contains("gone-managed.md"))
+        .stdout(predicate::str::contains("gone-user.md").not());
+
+    // With --all, both managed and user stale entries shown
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["clean", "--all", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove 2 stale entries"))
+        .stdout(predicate::str::contains("gone-managed.md"))
+        .stdout(predicate::str::contains("gone-user.md"))
+        .stdout(predicate::str::contains("(manual)"));
+}
+
+// --- backup/restore integration tests ---
+
+#[test]
+fn backup_creates_file_and_restore_list_shows_it() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    // Use isolated HOME so backup goes to temp dir, not user's real backups
+    let backup_home = tempfile::tempdir().expect("backup home");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", backup_home.path())
+        .current_dir(repo.path())
+        .arg("backup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backed up 1 entries"));
+
+    // Verify backup directory was created
+    let backup_dir = backup_home.path().join(".layer-backups");
+    assert!(backup_dir.exists(), "backup dir should exist");
+
+    // restore --list should show the backup
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", backup_home.path())
+        .current_dir(repo.path())
+        .args(["restore", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 entries"));
+}
+
+/// A repo with `CLAUDE.md` layered and backed up into an isolated HOME,
+/// returned as (repo, home).
+fn backed_up_repo() -> (TempDir, TempDir) {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("backup home");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(repo.path())
+        .arg("backup")
+        .assert()
+        .success();
+    (repo, home)
+}
+
+#[test]
+fn restore_list_shows_age_and_delta_against_the_layer() {
+    let (repo, home) = backed_up_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(repo.path())
+        .args(["restore", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("just now"))
+        .stdout(predicate::str::contains("matches your layer"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "AGENTS.md"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(repo.path())
+        .args(["restore", "--list", "--utc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" UTC)"))
+        .stdout(predicate::str::contains("+0 -1 vs. your layer"));
+}
+
+#[test]
+fn restore_by_name_in_a_fresh_clone_with_yes() {
+    let (repo, home) = backed_up_repo();
+    let name = repo.path().file_name().unwrap().to_str().unwrap().to_string();
+    let other = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(other.path())
+        .args(["restore", &name, "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 entries"));
+    assert!(fs::read_to_string(exclude_path(other.path())).expect("read").contains("CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(other.path())
+        .args(["restore", "missing", "--yes"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("no backup named 'missing'"));
+}
+
+#[test]
+fn restore_replace_drops_entries_the_backup_lacks() {
+    let (repo, home) = backed_up_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "mine.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", home.path())
+        .current_dir(repo.path())
+        .args(["restore", "--replace", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 0 entries, removed 1."));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.contains("CLAUDE.md") && !exclude.contains("mine.md"));
+}
+
+// --- add dry-run integration test ---
+
+// --- off/on integration tests ---
+
+#[test]
+fn off_disables_all_entries() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "Agents.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("off")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disabled CLAUDE.md"))
+        .stdout(predicate::str::contains("Disabled Agents.md"));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(content.contains("# [off] CLAUDE.md"));
+    assert!(content.contains("# [off] Agents.md"));
+}
+
+#[test]
+fn on_enables_all_entries() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "# managed by layer\n# [off] CLAUDE.md\n# [off] Agents.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("on")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Enabled CLAUDE.md"))
+        .stdout(predicate::str::contains("Enabled Agents.md"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert!(!content.contains("# [off]"));
+    assert!(content.contains("CLAUDE.md"));
+    assert!(content.contains("Agents.md"));
+}
+
+#[test]
+fn off_specific_entry() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "Agents.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["off", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disabled CLAUDE.md"));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(content.contains("# [off] CLAUDE.md"));
+    assert!(content.contains("\nAgents.md\n"));
+}
+
+#[test]
+fn on_specific_entry() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "# managed by layer\n# [off] CLAUDE.md\n# [off] Agents.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["on", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Enabled CLAUDE.md"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert!(content.contains("CLAUDE.md"));
+    assert!(content.contains("# [off] Agents.md"));
+}
+
+#[test]
+fn off_nothing_to_disable_exits_2() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("off")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No active entries to disable"));
+}
+
+#[test]
+fn on_nothing_to_enable_exits_2() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("on")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No disabled entries to enable"));
+}
+
+#[test]
+fn off_dry_run_does_not_modify() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["off", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would disable CLAUDE.md"))
+        .stdout(predicate::str::contains("dry run"));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!content.contains("# [off]"), "file should not be modified after dry run");
+}
+
+#[test]
+fn on_dry_run_does_not_modify() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "# managed by layer\n# [off] CLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["on", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would enable CLAUDE.md"))
+        .stdout(predicate::str::contains("dry run"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert!(content.contains("# [off] CLAUDE.md"), "file should not be modified after dry run");
+}
+
+#[test]
+fn ls_shows_disabled_entries() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "# managed by layer\nCLAUDE.md\n# [off] Agents.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CLAUDE.md"))
+        .stdout(predicate::str::contains("layered"))
+        .stdout(predicate::str::contains("Agents.md"))
+        .stdout(predicate::str::contains("(disabled)"));
+}
+
+#[test]
+fn roundtrip_off_then_on() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "Agents.md"])
+        .assert()
+        .success();
+
+    let before = fs::read_to_string(exclude_path(repo.path())).expect("read");
+
+    // Disable all
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("off")
+        .assert()
+        .success();
+
+    // Re-enable all
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("on")
+        .assert()
+        .success();
+
+    let after = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert_eq!(before, after, "roundtrip should restore original file");
+}
+
+// --- add dry-run integration test ---
+
+#[test]
+fn add_dry_run_does_not_write() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "--dry-run", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would layer 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("dry run"));
+
+    // Verify file was NOT created with the entry
+    let exclude = exclude_path(repo.path());
+    if exclude.exists() {
+        let content = fs::read_to_string(&exclude).expect("read");
+        assert!(!content.contains("CLAUDE.md"), "entry should not be present after dry run");
+    }
+}
+
+// --- patterns integration tests ---
+
+#[test]
+fn patterns_tool_filter_limits_json_output() {
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["patterns", "--json", "--tool", "aider"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"tool\": \"Aider\""))
+        .stdout(predicate::str::contains("Claude Code").not());
+}
+
+#[test]
+fn patterns_unknown_tool_errors() {
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["patterns", "--tool", "nope"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unknown tool 'nope'"));
+}
+
+// --- piped output tests ---
+
+#[test]
+fn piped_output_has_no_decoration() {
+    let repo = init_repo();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .output()
+        .expect("failed to run add");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "Layered 'CLAUDE.md'\n");
+}
+
+// --- edit --managed-only integration tests ---
+
+#[cfg(unix)]
+fn write_editor_script(dir: &Path, body: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    let script = dir.join("fake-editor.sh");
+    fs::write(&script, format!("#!/bin/sh\n{body}\n")).expect("write editor script");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+    script
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_managed_only_merges_back_into_section() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(&exclude, "user.txt\n# managed by layer\nCLAUDE.md\n# end layer\n").expect("write");
+    let editor_dir = tempfile::tempdir().expect("editor dir");
+    let editor = write_editor_script(editor_dir.path(), "echo 'NOTES.md' >> \"$1\"");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("VISUAL", &editor)
+        .args(["edit", "--managed-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated managed section (2 entries)"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert_eq!(
+        content,
+        "user.txt\n# managed by layer\nCLAUDE.md\nNOTES.md\n# end layer\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_managed_only_rejects_markers() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    let original = "# managed by layer\nCLAUDE.md\n# end layer\n";
+    fs::write(&exclude, original).expect("write");
+    let editor_dir = tempfile::tempdir().expect("editor dir");
+    let editor = write_editor_script(editor_dir.path(), "echo '# end layer' >> \"$1\"");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("VISUAL", &editor)
+        .args(["edit", "--managed-only"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("section markers"))
+        .stderr(predicate::str::contains("Your edits are kept in"));
+
+    assert_eq!(fs::read_to_string(&exclude).expect("read"), original);
+    let scratch = repo.path().join(".git/LAYER_EDIT");
+    assert_eq!(fs::read_to_string(&scratch).expect("scratch kept"), "CLAUDE.md\n# end layer\n");
+
+    // The next run reopens the kept edits; once they validate, the scratch goes.
+    let editor = write_editor_script(editor_dir.path(), "grep -v '# end layer' \"$1\" > \"$1.new\"; echo 'NOTES.md' >> \"$1.new\"; mv \"$1.new\" \"$1\"");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("VISUAL", &editor)
+        .args(["edit", "--managed-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reopening your previous edits"));
+    assert_eq!(
+        fs::read_to_string(&exclude).expect("read"),
+        "# managed by layer\nCLAUDE.md\nNOTES.md\n# end layer\n"
+    );
+    assert!(!scratch.exists());
+}
+
+#[test]
+fn off_except_keeps_named_entries_active() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "Agents.md", "NOTES.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["off", "--except", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disabled Agents.md"))
+        .stdout(predicate::str::contains("Disabled NOTES.md"))
+        .stdout(predicate::str::contains("Disabled CLAUDE.md").not());
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(content.contains("\nCLAUDE.md\n"));
+    assert!(content.contains("# [off] Agents.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["on", "--except", "NOTES.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Enabled Agents.md"))
+        .stdout(predicate::str::contains("Enabled NOTES.md").not());
+
+    // A mistyped --except stops everything rather than toggling the entry it meant.
+    let before = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["off", "--except", "CLAUDE.md", "--except", "NOTES.mdd", "--except", "Agent.md"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--except 'NOTES.mdd', 'Agent.md' are not in .git/info/exclude"));
+    assert_eq!(fs::read_to_string(exclude_path(repo.path())).expect("read"), before);
+}
+
+// --- demo mode integration tests ---
+
+#[test]
+fn demo_roundtrip_restores_exact_bytes() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    let original = "# user\nmine.txt\n# managed by layer\nCLAUDE.md\n# [off] Agents.md\n\n# end layer\n";
+    fs::write(&exclude, original).expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "on"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Demo mode on"));
+
+    let during = fs::read_to_string(&exclude).expect("read");
+    assert!(during.contains("# [off] CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "on"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("already on"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "off"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(&exclude).expect("read"), original);
+}
+
+#[test]
+fn demo_roundtrip_with_alphabetical_order() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    let original = "# managed by layer\nzeta.md\nCLAUDE.md\nalpha.md\n# end layer\n";
+    fs::write(&exclude, original).expect("write");
+
+    for args in [["demo", "on"], ["demo", "off"]] {
+        Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+            .current_dir(repo.path())
+            .env("LAYER_EXCLUDE_ORDER", "alphabetical")
+            .args(args)
+            .assert()
+            .success();
+    }
+
+    assert_eq!(fs::read_to_string(&exclude).expect("read"), original);
+}
+
+#[test]
+fn demo_off_refuses_when_file_changed() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "on"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "NOTES.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "off"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--force"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["demo", "off", "--force"])
+        .assert()
+        .success();
+}
+
+// --- custom section marker tests ---
+
+#[test]
+fn custom_markers_adopt_existing_block() {
+    let repo = init_repo();
+    for (key, value) in [("layer.sectionStart", "# BEGIN org-local"), ("layer.sectionEnd", "# END org-local")] {
+        Command::new("git")
+            .args(["config", key, value])
+            .current_dir(repo.path())
+            .assert()
+            .success();
+    }
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(&exclude, "# BEGIN org-local\nNOTES.md\n# END org-local\n").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert_eq!(content, "# BEGIN org-local\nNOTES.md\nCLAUDE.md\n# END org-local\n");
+}
+
+#[test]
+fn custom_markers_migrate_default_block() {
+    let repo = init_repo();
+    for (key, value) in [("layer.sectionStart", "# BEGIN org-local"), ("layer.sectionEnd", "# END org-local")] {
+        Command::new("git")
+            .args(["config", key, value])
+            .current_dir(repo.path())
+            .assert()
+            .success();
+    }
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(&exclude, "mine.txt\n# managed by layer\nNOTES.md\n# end layer\n").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert_eq!(
+        content,
+        "mine.txt\n# BEGIN org-local\nNOTES.md\nCLAUDE.md\n# END org-local\n"
+    );
+}
+
+#[test]
+fn global_gitignore_ignores_the_current_repos_markers_and_order() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("home");
+    for (key, value) in [("layer.sectionStart", "# BEGIN org-local"), ("layer.sectionEnd", "# END org-local")] {
+        git(repo.path(), &["config", key, value]);
+    }
+    let global = home.path().join(".config/git/ignore");
+    fs::create_dir_all(global.parent().unwrap()).expect("mkdir");
+    fs::write(&global, "*.log
+# managed by layer
+.DS_Store
+# end layer
+").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GIT_CONFIG_GLOBAL", home.path().join(".gitconfig"))
+        .env("LAYER_EXCLUDE_ORDER", "alphabetical")
+        .args(["global", "add", "zeta.txt", "alpha.txt"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&global).expect("read"),
+        "*.log\n# managed by layer\n.DS_Store\nzeta.txt\nalpha.txt\n# end layer\n"
+    );
+}
+
+#[test]
+fn duplicate_sections_are_merged_on_write() {
+    let repo = init_repo();
+    let exclude = exclude_path(repo.path());
+    fs::create_dir_all(exclude.parent().unwrap()).expect("mkdir");
+    fs::write(
+        &exclude,
+        "# managed by layer\nCLAUDE.md\n# end layer\nmine.txt\n# managed by layer\nCLAUDE.md\nAgents.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "NOTES.md"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 duplicate layer section"));
+
+    let content = fs::read_to_string(&exclude).expect("read");
+    assert_eq!(
+        content,
+        "# managed by layer\nCLAUDE.md\nAgents.md\nNOTES.md\n# end layer\nmine.txt\n"
+    );
+}
+
+// --- status --hook integration tests ---
+
+#[test]
+fn status_hook_is_silent_when_nothing_needs_attention() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn status_hook_prints_one_line_and_never_fails_unless_strict() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout("layer: 1 discovered — run 'layer status'\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--hook", "--strict"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("1 discovered"));
+}
+
+#[test]
+fn status_hook_cache_notices_files_added_in_subdirectories() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join(".cursor/rules")).expect("mkdir");
+    fs::create_dir_all(repo.path().join("sub")).expect("mkdir");
+    let hook = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+        cmd.current_dir(repo.path()).env("LAYER_SCAN_DEPTH", "1").args(["status", "--hook"]);
+        cmd
+    };
+    hook().assert().success().stdout("");
+
+    fs::write(repo.path().join(".cursor/rules/x.md"), "rules").expect("write");
+    hook().assert().success().stdout("layer: 1 discovered — run 'layer status'\n");
+
+    fs::write(repo.path().join("sub/CLAUDE.md"), "notes").expect("write");
+    hook().assert().success().stdout("layer: 2 discovered — run 'layer status'\n");
+}
+
+#[test]
+fn status_hook_cache_notices_config_and_gitignore_edits() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join("sub")).expect("mkdir");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("sub/CLAUDE.md"), "notes").expect("write");
+    // Created up front so editing them leaves the directory mtimes alone.
+    fs::write(repo.path().join(".layer.toml"), "").expect("write");
+    fs::write(repo.path().join(".gitignore"), "").expect("write");
+    let hook = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+        cmd.current_dir(repo.path()).env("LAYER_SCAN_DEPTH", "1").args(["status", "--hook"]);
+        cmd
+    };
+    hook().assert().success().stdout("layer: 2 discovered — run 'layer status'\n");
+
+    fs::write(repo.path().join(".layer.toml"), "[scan]\nskip_dirs = [\"sub\"]\n").expect("write");
+    hook().assert().success().stdout("layer: 1 discovered — run 'layer status'\n");
+
+    fs::write(repo.path().join(".gitignore"), "CLAUDE.md\n").expect("write");
+    hook().assert().success().stdout("");
+}
+
+fn config_cmd(repo: &Path, home: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+    cmd.current_dir(repo)
+        .env("XDG_CONFIG_HOME", home)
+        .env("LAYER_SYSTEM_CONFIG", home.join("missing.toml"))
+        .env_remove("LAYER_SCAN_DEPTH")
+        .env_remove("LAYER_CLEAN_ALL");
+    cmd
+}
+
+#[test]
+fn config_list_resolves_user_repo_env_and_flag_layers() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(home.path().join("layer")).expect("mkdir");
+    fs::write(
+        home.path().join("layer").join("config.toml"),
+        "[scan]\ndepth = 3\n\n[clean]\nall = true\n",
+    )
+    .expect("write user config");
+    fs::write(repo.path().join(".layer.toml"), "scan.depth = 4\n").expect("write repo config");
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "list", "--show-origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("scan.depth"))
+        .stdout(predicate::str::contains("= 4"))
+        .stdout(predicate::str::contains("repo: .layer.toml"))
+        .stdout(predicate::str::contains("user: "))
+        .stdout(predicate::str::contains("default"));
+
+    config_cmd(repo.path(), home.path())
+        .env("LAYER_SCAN_DEPTH", "5")
+        .args(["config", "list", "--show-origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("env: LAYER_SCAN_DEPTH"));
+
+    config_cmd(repo.path(), home.path())
+        .env("LAYER_SCAN_DEPTH", "5")
+        .args(["-c", "scan.depth=6", "config", "list", "--show-origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("= 6"))
+        .stdout(predicate::str::contains("flag: -c"));
+}
+
+#[test]
+fn config_rejects_bad_values_and_unknown_flag_keys() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::write(repo.path().join(".layer.toml"), "[scan]\ndepth = \"deep\"\n").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "list"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("scan.depth expects an integer"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "nope=1", "config", "list"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unknown config key 'nope'"));
+}
+
+#[test]
+fn config_disables_pattern_groups_and_sets_backup_dir_and_color() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "patterns.disabled=Claude Code", "scan", "--json"])
+        .assert()
+        .stdout(predicate::str::contains(".cursorrules"))
+        .stdout(predicate::str::contains("CLAUDE.md").not());
+
+    config_cmd(repo.path(), home.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+    let backups = home.path().join("sync");
+    config_cmd(repo.path(), home.path())
+        .env("HOME", home.path())
+        .env("LAYER_BACKUP_DIR", "~/sync")
+        .arg("backup")
+        .assert()
+        .success();
+    assert!(fs::read_dir(&backups).expect("backup dir").next().is_some());
+
+    config_cmd(repo.path(), home.path())
+        .env("LAYER_BACKUP_AUTO", "false")
+        .env("HOME", home.path())
+        .arg("maintain")
+        .assert()
+        .stdout(predicate::str::contains("backup skipped (backup.auto is off)"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "ui.color=always", "ls"])
+        .assert()
+        .stdout(predicate::str::contains("\u{1b}["));
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "ui.color=always", "--color", "never", "ls"])
+        .assert()
+        .stdout(predicate::str::contains("\u{1b}[").not());
+}
+
+#[test]
+fn repo_config_adds_scan_patterns_and_skip_dirs_and_is_layered() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(repo.path().join("scratch")).expect("mkdir");
+    fs::write(repo.path().join("scratch/idea.txt"), "x").expect("write");
+    fs::write(repo.path().join("NOTES.local.md"), "x").expect("write");
+    for dir in ["fixtures", "pkg"] {
+        fs::create_dir_all(repo.path().join(dir)).expect("mkdir");
+        fs::write(repo.path().join(dir).join("CLAUDE.md"), "x").expect("write");
+    }
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "scan.patterns", "NOTES.local.md,scratch/"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layered .layer.toml"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == ".layer.toml"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "scan.skip_dirs", "fixtures"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layered").not());
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "scan.depth=1", "scan", "--json"])
+        .assert()
+        .stdout(predicate::str::contains("\"path\": \"NOTES.local.md\""))
+        .stdout(predicate::str::contains("\"path\": \"scratch/\""))
+        .stdout(predicate::str::contains("\"label\": \"Project\""))
+        .stdout(predicate::str::contains("pkg/CLAUDE.md"))
+        .stdout(predicate::str::contains("fixtures/CLAUDE.md").not());
+}
+
+#[test]
+fn scan_skips_dependency_dirs_and_walks_past_dismissed_ones() {
+    let repo = init_repo();
+    for dir in ["node_modules/pkg", "target", "vendor", "notes", "web"] {
+        fs::create_dir_all(repo.path().join(dir)).expect("mkdir");
+        fs::write(repo.path().join(dir).join("CLAUDE.md"), "x").expect("write");
+    }
+    fs::write(repo.path().join(".layerignore"), "notes/\n").expect("write");
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-c", "scan.depth=2", "--format", "porcelain", "scan"])
+        .output()
+        .expect("run");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("web/CLAUDE.md"), "{stdout}");
+    for skipped in ["node_modules", "target/", "vendor/", "notes/"] {
+        assert!(!stdout.contains(skipped), "{skipped} in {stdout}");
+    }
+}
+
+#[test]
+fn custom_patterns_carry_their_own_label_into_scan_and_patterns() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(home.path().join("layer")).expect("mkdir");
+    fs::write(
+        home.path().join("layer/config.toml"),
+        "[scan]\npatterns = [\"NOTES.local.md | Team notes\", \"scratch/ | Team notes | ai_config\"]\n",
+    )
+    .expect("write");
+    fs::write(repo.path().join("NOTES.local.md"), "x").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["scan", "--json"])
+        .assert()
+        .stdout(predicate::str::contains("\"path\": \"NOTES.local.md\""))
+        .stdout(predicate::str::contains("\"label\": \"Team notes\""));
+
+    config_cmd(repo.path(), home.path())
+        .args(["--format", "porcelain", "patterns", "--tool", "team notes"])
+        .assert()
+        .success()
+        .stdout("Team notes\tNOTES.local.md\tfile\nTeam notes\tscratch/\tdir\n");
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "--user", "scan.patterns", "x | y | nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Known categories: secrets, ai_config,"));
+}
+
+#[test]
+fn discovery_lists_higher_priority_categories_first() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::write(
+        repo.path().join(".layer.toml"),
+        "[scan]\npatterns = [\"out/ | Build | build_artifacts\", \"deploy.key | Keys | secrets\"]\n",
+    )
+    .expect("write");
+    fs::create_dir_all(repo.path().join("out")).expect("mkdir");
+    fs::write(repo.path().join("out/app.bin"), "x").expect("write");
+    for file in ["deploy.key", "CLAUDE.md"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
+    fs::write(exclude_path(repo.path()), ".layer.toml\n").expect("write");
+
+    let output = config_cmd(repo.path(), home.path()).args(["scan"]).output().expect("scan");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let order: Vec<usize> = ["Secrets", "deploy.key", "AI context", "CLAUDE.md", "Build artifacts", "out/"]
+        .iter()
+        .map(|needle| stdout.find(needle).unwrap_or_else(|| panic!("missing {needle} in {stdout}")))
+        .collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{stdout}");
+
+    config_cmd(repo.path(), home.path())
+        .args(["status", "--json"])
+        .assert()
+        .stdout(predicate::str::contains("\"path\": \"deploy.key\",\n      \"category\": \"secrets\""))
+        .stdout(predicate::str::contains("\"category\": \"build_artifacts\""));
+}
+
+#[test]
+fn os_files_are_opt_in_and_point_at_the_global_gitignore() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::write(repo.path().join(".DS_Store"), "x").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["scan"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No context files found"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "patterns.os_files=true", "scan"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("OS files (1)"))
+        .stdout(predicate::str::contains("layer global add .DS_Store"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "patterns.os_files=true", "status", "--json"])
+        .assert()
+        .stdout(predicate::str::contains("\"fix\": \"layer global add .DS_Store\""));
+}
+
+#[test]
+fn agent_artifacts_are_optional_suggestions() {
+    let repo = init_repo();
+    for file in ["CLAUDE.md", "PLAN.md", "fix.patch"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
+    fs::write(repo.path().join("TODO-agent.md"), "x").expect("write");
+    git(repo.path(), &["add", "TODO-agent.md"]);
+    git(repo.path(), &["commit", "-q", "-m", "todo"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Layered 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("Agent artifacts (2) — optional"))
+        .stdout(predicate::str::contains("layer add PLAN.md fix.patch"))
+        .stdout(predicate::str::contains("TODO-agent.md").not());
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!exclude.lines().any(|line| line == "PLAN.md" || line == "fix.patch"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "scan"])
+        .assert()
+        .stdout(predicate::str::contains("\"category\": \"agent_artifacts\""));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("check")
+        .assert()
+        .success();
+}
+
+#[test]
+fn status_leaves_agent_artifacts_to_scan_and_finds_rejects_at_any_depth() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join("src/deep/er")).expect("mkdir");
+    fs::create_dir_all(repo.path().join("build/out/x")).expect("mkdir");
+    fs::write(repo.path().join(".gitignore"), "build/\n").expect("write");
+    for file in ["CLAUDE.md", "PLAN.md", "src/deep/er/b.rej", "src/old.patch", "build/out/x/c.rej"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("status")
+        .assert()
+        .stdout(predicate::str::contains("Discovered (1)"))
+        .stdout(predicate::str::contains("PLAN.md").not())
+        .stdout(predicate::str::contains("2 agent artifacts to review with 'layer scan'"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--short"])
+        .assert()
+        .stdout(predicate::str::contains("1 discovered"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "status"])
+        .assert()
+        .stdout(predicate::str::contains("PLAN.md").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "scan"])
+        .assert()
+        .stdout(predicate::str::contains("src/deep/er/b.rej"))
+        .stdout(predicate::str::contains("build/out/x/c.rej").not())
+        .stdout(predicate::str::contains("src/old.patch").not());
+}
+
+#[test]
+fn custom_and_disabled_patterns_apply_to_ls_status_hooks_and_tool_order() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(home.path().join("layer")).expect("mkdir");
+    fs::write(
+        home.path().join("layer/config.toml"),
+        "[scan]\npatterns = [\"NOTES.local.md | Team notes | local_notes\"]\n[patterns]\ndisabled = [\"Aider\"]\n",
+    )
+    .expect("write");
+    for file in ["NOTES.local.md", ".aider.conf.yml", "CLAUDE.md"] {
+        fs::write(repo.path().join(file), "x").expect("write");
+    }
+
+    config_cmd(repo.path(), home.path())
+        .args(["add", "NOTES.local.md", ".aider.conf.yml", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    config_cmd(repo.path(), home.path())
+        .args(["--format", "json", "ls"])
+        .assert()
+        .stdout(predicate::str::contains("\"tool\": \"Team notes\""))
+        .stdout(predicate::str::contains("\"category\": \"local_notes\""))
+        .stdout(predicate::str::contains("\"tool\": \"Aider\"").not());
+    config_cmd(repo.path(), home.path())
+        .args(["ls"])
+        .assert()
+        .stdout(predicate::str::contains("Team notes\nNOTES.local.md"));
+    config_cmd(repo.path(), home.path())
+        .args(["--format", "{entry}={tool}", "status"])
+        .assert()
+        .stdout(predicate::str::contains("NOTES.local.md=Team notes"))
+        .stdout(predicate::str::contains(".aider.conf.yml=\n"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["-c", "exclude.order=tool", "fmt"])
+        .assert()
+        .success();
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    let pos = |entry: &str| exclude.lines().position(|line| line == entry).unwrap();
+    assert!(pos("CLAUDE.md") < pos("NOTES.local.md"));
+    assert!(pos("NOTES.local.md") < pos(".aider.conf.yml"));
+
+    config_cmd(repo.path(), home.path()).args(["rm", "NOTES.local.md", ".aider.conf.yml"]).assert().success();
+    git(repo.path(), &["add", "NOTES.local.md", ".aider.conf.yml"]);
+    config_cmd(repo.path(), home.path())
+        .args(["hook", "pre-commit"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("NOTES.local.md (Team notes)"))
+        .stdout(predicate::str::contains(".aider.conf.yml").not());
+}
+
+#[test]
+fn patterns_add_and_rm_edit_scan_patterns() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(repo.path().join("scratch")).expect("mkdir");
+    fs::write(repo.path().join("scratch/idea.txt"), "x").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["patterns", "add", "scratch/", "--label", "Team notes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added 'scratch/' (dir, Team notes)"))
+        .stdout(predicate::str::contains("Matches 1 path here"))
+        .stdout(predicate::str::contains("Layered .layer.toml"));
+    config_cmd(repo.path(), home.path())
+        .args(["patterns", "add", "NOTES.local.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing in this repo matches it yet"));
+    let toml = fs::read_to_string(repo.path().join(".layer.toml")).expect("read");
+    assert!(toml.contains("patterns = [\"scratch/ | Team notes\", \"NOTES.local.md\"]"), "{toml}");
+
+    config_cmd(repo.path(), home.path()).args(["patterns", "add", "!keep.md"]).assert().failure();
+    config_cmd(repo.path(), home.path())
+        .args(["patterns", "add", "**/notes.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Only '*' and '?' are supported"));
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "scan.patterns", "[\"notes[12].md\"]"])
+        .assert()
+        .failure();
+    config_cmd(repo.path(), home.path())
+        .args(["patterns", "add", "x.md", "--category", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Known categories"));
+
+    config_cmd(repo.path(), home.path()).args(["patterns", "rm", "scratch/"]).assert().success();
+    config_cmd(repo.path(), home.path())
+        .args(["patterns", "rm", "CLAUDE.md"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("patterns.disabled"));
+    let toml = fs::read_to_string(repo.path().join(".layer.toml")).expect("read");
+    assert!(toml.contains("patterns = [\"NOTES.local.md\"]"), "{toml}");
+}
+
+#[test]
+fn scan_depth_config_finds_nested_context_files() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+    fs::create_dir_all(repo.path().join("packages").join("api")).expect("mkdir");
+    fs::write(repo.path().join("packages/api/CLAUDE.md"), "notes").expect("write");
+
+    config_cmd(repo.path(), home.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout("");
+
+    fs::write(repo.path().join(".layer.toml"), "[scan]\ndepth = 2\n").expect("write");
+    config_cmd(repo.path(), home.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("discovered"));
+}
+
+#[test]
+fn config_set_writes_repo_and_user_files_and_get_reads_them() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "scan.depth", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Set scan.depth = 4"));
+    assert_eq!(
+        fs::read_to_string(repo.path().join(".layer.toml")).expect("read"),
+        "[scan]\ndepth = 4\n"
+    );
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "--user", "clean.all", "true"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(home.path().join("layer").join("config.toml")).expect("read"),
+        "[clean]\nall = true\n"
+    );
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "get", "scan.depth"])
+        .assert()
+        .success()
+        .stdout("4\n");
+    config_cmd(repo.path(), home.path())
+        .args(["config", "get", "clean.all"])
+        .assert()
+        .success()
+        .stdout("true\n");
+}
+
+#[test]
+fn config_set_validates_key_and_type() {
+    let repo = init_repo();
+    let home = tempfile::tempdir().expect("tempdir");
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "clean.all", "yes"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("clean.all expects a boolean"));
+
+    config_cmd(repo.path(), home.path())
+        .args(["config", "set", "scan.deep", "4"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unknown config key 'scan.deep'"));
+
+    assert!(!repo.path().join(".layer.toml").exists());
+}
+
+#[test]
+fn scan_fix_tracked_untracks_and_layers() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    Command::new("git")
+        .args(["add", "CLAUDE.md"])
+        .current_dir(repo.path())
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["commit", "-q", "-m", "init"])
+        .current_dir(repo.path())
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("scan")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("git rm --cached CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--fix-tracked"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Untracked 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("Layered 'CLAUDE.md'"));
+
+    let tracked = Command::new("git")
+        .args(["ls-files"])
+        .current_dir(repo.path())
+        .output()
+        .expect("git ls-files");
+    assert!(String::from_utf8_lossy(&tracked.stdout).trim().is_empty());
+    assert!(repo.path().join("CLAUDE.md").exists());
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read exclude");
+    assert!(content.contains("CLAUDE.md"));
+}
+
+#[test]
+fn vault_encrypts_opens_and_locks_context_files() {
+    if Command::new("gpg").arg("--version").output().is_err() {
+        return;
+    }
+    let repo = init_repo();
+    let gnupg = tempfile::tempdir().expect("tempdir");
+    let vault = |args: &[&str], passphrase: &str| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+        cmd.current_dir(repo.path())
+            .env("GNUPGHOME", gnupg.path())
+            .env("LAYER_VAULT_PASSPHRASE", passphrase)
+            .arg("vault")
+            .args(args);
+        cmd
+    };
+    let claude = repo.path().join("CLAUDE.md");
+    fs::write(&claude, "api key: hunter2\n").expect("write");
+
+    vault(&["add", "CLAUDE.md"], "s3cret").assert().success().stdout(predicate::str::contains("Vaulted 'CLAUDE.md'"));
+    assert!(!claude.exists());
+    let sealed = fs::read(repo.path().join(".git/layer-vault/CLAUDE.md.gpg")).expect("read vault");
+    assert!(!String::from_utf8_lossy(&sealed).contains("hunter2"));
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+
+    vault(&["open"], "wrong").assert().failure().stderr(predicate::str::contains("wrong vault passphrase"));
+    assert!(!claude.exists());
+    vault(&["open"], "s3cret").assert().success();
+    assert_eq!(fs::read_to_string(&claude).expect("read"), "api key: hunter2\n");
+
+    fs::write(&claude, "api key: rotated\n").expect("write");
+    vault(&["lock"], "s3cret").assert().success().stdout(predicate::str::contains("saved changes"));
+    assert!(!claude.exists());
+    vault(&["lock"], "s3cret").assert().code(2);
+
+    vault(&["open", "CLAUDE.md"], "s3cret").assert().success();
+    assert_eq!(fs::read_to_string(&claude).expect("read"), "api key: rotated\n");
+    let _ = Command::new("gpgconf").env("GNUPGHOME", gnupg.path()).args(["--kill", "gpg-agent"]).output();
+}
+
+#[test]
+fn backup_sign_and_restore_verify_with_ssh_keys() {
+    if Command::new("ssh-keygen").arg("-?").output().is_err() {
+        return;
+    }
+    let repo = init_repo();
+    let keys = tempfile::tempdir().expect("tempdir");
+    let key = keys.path().join("id_ed25519");
+    Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "lead@example.com", "-f"])
+        .arg(&key)
+        .assert()
+        .success();
+    let public = fs::read_to_string(keys.path().join("id_ed25519.pub")).expect("read pub");
+    let allowed = keys.path().join("allowed_signers");
+    fs::write(&allowed, format!("lead@example.com {public}")).expect("write allowed_signers");
+
+    fs::write(exclude_path(repo.path()), "# managed by layer\nCLAUDE.md\n# end layer\n").expect("write");
+    let shared = keys.path().join("team.txt");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["backup", "--output"])
+        .arg(&shared)
+        .arg("--sign")
+        .arg(&key)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 1 entries"))
+        .stdout(predicate::str::contains("team.txt.sig"));
+
+    // Verification passes, then the confirmation prompt needs a TTY.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["restore", "--from"])
+        .arg(&shared)
+        .arg("--verify")
+        .arg(&allowed)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("signed by lead@example.com"))
+        .stderr(predicate::str::contains("requires a TTY"));
+
+    let tampered = fs::read_to_string(&shared).expect("read") + ".env\n";
+    fs::write(&shared, tampered).expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["restore", "--from"])
+        .arg(&shared)
+        .arg("--verify")
+        .arg(&allowed)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("signature verification failed"));
+}
+
+#[test]
+fn add_itemizes_skip_reasons() {
+    let repo = init_repo();
+    fs::create_dir(repo.path().join(".claude")).expect("mkdir");
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\n.claude/\n*.log\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", ".claude/settings.json", "logs/debug.log", "", "AGENTS.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already covered by '.claude/'"))
+        .stdout(predicate::str::contains("already covered by '*.log'"))
+        .stdout(predicate::str::contains("Layered 'AGENTS.md'"))
+        .stdout(predicate::str::contains(
+            "1 added · 4 skipped (1 already layered, 2 covered by a broader entry, 1 empty or invalid)",
+        ));
+}
+
+#[test]
+fn add_dry_run_json_reports_plan() {
+    let repo = init_repo();
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "--dry-run", "--json", "CLAUDE.md", "AGENTS.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "{ \"input\": \"CLAUDE.md\", \"entry\": \"CLAUDE.md\", \"action\": \"skip\", \"reason\": \"already_layered\", \"covered_by\": null, \"tracked\": false }",
+        ))
+        .stdout(predicate::str::contains("\"action\": \"add\""))
+        .stdout(predicate::str::contains(
+            "\"summary\": { \"added\": 1, \"already_layered\": 1, \"covered\": 0, \"invalid\": 0, \"tracked_warnings\": 0 }",
+        ));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!content.contains("AGENTS.md"));
+}
+
+#[test]
+fn serve_answers_json_rpc_and_notifies_on_exclude_changes() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("serve")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn layer serve");
+    let mut stdin = child.stdin.take().expect("stdin");
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout"));
+    let mut notifications = Vec::new();
+    let mut request = |line: &str| -> String {
+        writeln!(stdin, "{line}").expect("write request");
+        loop {
+            let mut response = String::new();
+            stdout.read_line(&mut response).expect("read response");
+            if response.contains(r#""id":"#) {
+                return response;
+            }
+            notifications.push(response);
+        }
+    };
+
+    let added = request(r#"{"jsonrpc":"2.0","id":1,"method":"add","params":{"entries":["CLAUDE.md"]}}"#);
+    assert!(added.contains(r#""added":["CLAUDE.md"]"#), "{added}");
+
+    let classified = request(r#"{"jsonrpc":"2.0","id":2,"method":"classify","params":{"paths":["CLAUDE.md","AGENTS.md"]}}"#);
+    assert!(classified.contains(r#""CLAUDE.md":"layered""#), "{classified}");
+    assert!(classified.contains(r#""AGENTS.md":"discovered""#), "{classified}");
+
+    let unknown = request(r#"{"jsonrpc":"2.0","id":3,"method":"nope"}"#);
+    assert!(unknown.contains("-32601"), "{unknown}");
+
+    // The add above changed the exclude file, so a notification arrives
+    // within the watch interval.
+    std::thread::sleep(std::time::Duration::from_millis(700));
+    let shutdown = request(r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#);
+    assert!(shutdown.contains(r#""result":null"#), "{shutdown}");
+    assert!(child.wait().expect("wait").success());
+
+    assert_eq!(notifications.len(), 1, "{notifications:?}");
+    assert!(notifications[0].contains(r#""method":"layer/excludeChanged""#));
+    assert!(notifications[0].contains(r#""entries":["CLAUDE.md"]"#));
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_socket_clients_editing_at_once_keep_each_others_entries() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let repo = init_repo();
+    let socket = repo.path().join(".git/layer.sock");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["serve", "--socket"])
+        .arg(&socket)
+        .spawn()
+        .expect("spawn layer serve");
+    for _ in 0..250 {
+        if socket.exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    let clients: Vec<_> = (0..8)
+        .map(|n| {
+            let socket = socket.clone();
+            std::thread::spawn(move || {
+                let stream = UnixStream::connect(&socket).expect("connect");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut writer = stream;
+                for m in 0..5 {
+                    let request = format!(
+                        r#"{{"jsonrpc":"2.0","id":{m},"method":"add","params":{{"entries":["note-{n}-{m}.md"]}}}}"#
+                    );
+                    writeln!(writer, "{request}").expect("write request");
+                    let mut response = String::new();
+                    while !response.contains(r#""id":"#) {
+                        response.clear();
+                        reader.read_line(&mut response).expect("read response");
+                    }
+                    assert!(response.contains(r#""added":"#), "{response}");
+                }
+            })
+        })
+        .collect();
+    // Stop the server before reporting a failed client, so it doesn't outlive the test.
+    let results: Vec<_> = clients.into_iter().map(|client| client.join()).collect();
+    child.kill().expect("kill");
+    let _ = child.wait();
+    for result in results {
+        result.expect("client");
+    }
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    for n in 0..8 {
+        for m in 0..5 {
+            assert!(content.lines().any(|line| line == format!("note-{n}-{m}.md")), "{content}");
+        }
+    }
+}
+
+#[test]
+fn ls_and_doctor_report_entries_overridden_by_negation() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".gitignore"), "!CLAUDE.md\n").expect("write gitignore");
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("overridden — re-included by !CLAUDE.md (.gitignore:1)"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("doctor")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("overridden — re-included by !CLAUDE.md"))
+        .stdout(predicate::str::contains("1 overridden"));
+}
+
+#[test]
+fn ls_explains_tracked_entries_as_ineffective_rules() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    Command::new("git")
+        .args(["add", "CLAUDE.md"])
+        .current_dir(repo.path())
+        .assert()
+        .success();
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "rule matches but is ineffective because the file is tracked (git rm --cached CLAUDE.md)",
+        ));
+}
+
+#[test]
+fn log_records_operations_and_filters_by_entry() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+
+    for args in [
+        vec!["add", "CLAUDE.md", "AGENTS.md"],
+        vec!["off", "CLAUDE.md"],
+        vec!["rm", "AGENTS.md"],
+    ] {
+        Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+            .current_dir(repo.path())
+            .args(&args)
+            .assert()
+            .success();
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("layer add CLAUDE.md AGENTS.md"))
+        .stdout(predicate::str::contains("CLAUDE.md disabled"))
+        .stdout(predicate::str::contains("AGENTS.md removed"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["log", "--entry", "AGENTS.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("AGENTS.md added"))
+        .stdout(predicate::str::contains("AGENTS.md removed"))
+        .stdout(predicate::str::contains("layer off").not());
+}
+
+#[test]
+fn log_without_history_exits_two() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("log")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No recorded operations yet."));
+}
+
+#[test]
+fn scan_treats_dirs_with_all_files_ignored_as_gitignored() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join(".claude")).expect("mkdir");
+    fs::write(repo.path().join(".claude/settings.json"), "{}").expect("write");
+    fs::create_dir_all(repo.path().join(".cursor/rules")).expect("mkdir");
+    fs::write(repo.path().join(".cursor/rules/style.md"), "rules").expect("write");
+    fs::write(repo.path().join(".gitignore"), "*.json\n").expect("write");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("scan")
+        .output()
+        .expect("run scan");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ignored = stdout.split("Already ignored by Git").nth(1).expect("ignored section");
+    let ignored = ignored.split("Discovered").next().unwrap();
+    assert!(ignored.contains(".claude/"), "{stdout}");
+    assert!(!ignored.contains(".cursor/"), "{stdout}");
+    assert!(stdout.contains(".cursor/ (Cursor"), "{stdout}");
+}
+
+#[test]
+fn dismiss_stops_scan_and_status_suggesting_a_file() {
+    let repo = init_repo();
+    fs::write(repo.path().join("AGENTS.md"), "team").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "mine").expect("write");
+    git(repo.path(), &["add", "AGENTS.md"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["dismiss", "AGENTS.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dismissed 'AGENTS.md'"))
+        .stdout(predicate::str::contains("Layered .layerignore"));
+    assert!(fs::read_to_string(repo.path().join(".layerignore")).unwrap().contains("AGENTS.md\n"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CLAUDE.md"))
+        .stdout(predicate::str::contains("AGENTS.md").not());
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("status")
+        .assert()
+        .stdout(predicate::str::contains("CLAUDE.md"))
+        .stdout(predicate::str::contains("AGENTS.md").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["dismiss", "./AGENTS.md"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("already dismissed"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("dismiss")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("AGENTS.md"));
+}
+
+#[test]
+fn status_truncates_discovered_list_unless_full() {
+    let repo = init_repo();
+    for name in ["CLAUDE.md", "AGENTS.md", "CONTEXT.md", ".cursorrules"] {
+        fs::write(repo.path().join(name), "notes").expect("write");
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-c", "status.max_discovered=2", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Discovered (4)"))
+        .stdout(predicate::str::contains("… and 2 more"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-c", "status.max_discovered=2", "status", "--full"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("more").not())
+        .stdout(predicate::str::contains("layer add CONTEXT.md"));
+}
+
+#[test]
+fn init_from_another_clone_copies_entries() {
+    let source = init_repo();
+    fs::create_dir_all(exclude_path(source.path()).parent().unwrap()).expect("mkdir");
+    fs::write(
+        exclude_path(source.path()),
+        "# managed by layer\nCLAUDE.md\n# [off] AGENTS.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    let target = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(target.path())
+        .args(["init", "--from"])
+        .arg(source.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Initialized 2 entries"))
+        .stdout(predicate::str::contains("1 kept disabled"));
+
+    let content = fs::read_to_string(exclude_path(target.path())).expect("read exclude");
+    assert!(content.contains("CLAUDE.md\n# [off] AGENTS.md\n"), "{content}");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(target.path())
+        .args(["init", "--from"])
+        .arg(source.path())
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn init_from_exported_file_and_unknown_source() {
+    let repo = init_repo();
+    let shared = repo.path().join("team.txt");
+    fs::write(&shared, "# layer backup\n# repo: demo\nCLAUDE.md\n.cursor/\n").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["init", "--from", "team.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Initialized 2 entries from team.txt"));
+
+    let home = tempfile::tempdir().expect("home");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("HOME", home.path())
+        .args(["init", "--from", "https://example.com/nobody/nothing.git"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no backup"));
+}
+
+fn git(repo: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .assert()
+        .success();
+}
+
+#[test]
+fn hook_install_composes_with_existing_post_merge_hook() {
+    let repo = init_repo();
+    let hook = repo.path().join(".git/hooks/post-merge");
+    fs::create_dir_all(hook.parent().unwrap()).expect("mkdir");
+    fs::write(&hook, "#!/bin/sh\nnpm install\n").expect("write hook");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install", "--post-merge"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added layer to the existing post-merge hook"));
+    let content = fs::read_to_string(&hook).expect("read hook");
+    assert!(content.starts_with("#!/bin/sh\n# >>> layer post-merge >>>\n"), "{content}");
+    assert!(content.ends_with("# <<< layer post-merge <<<\nnpm install\n"), "{content}");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install", "--post-merge"])
+        .assert()
+        .code(2);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "uninstall", "--post-merge"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hook).expect("read hook"), "#!/bin/sh\nnpm install\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn hook_install_runs_before_an_existing_hook_that_ends_in_exec() {
+    let repo = init_repo();
+    let hook = repo.path().join(".git/hooks/pre-commit");
+    fs::create_dir_all(hook.parent().unwrap()).expect("mkdir");
+    fs::write(&hook, "#!/bin/sh\nexec git diff-index --check --cached HEAD --\n").expect("write hook");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).expect("chmod");
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install", "--pre-commit"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added layer to the existing pre-commit hook"));
+    let content = fs::read_to_string(&hook).expect("read hook");
+    let block = content.find("layer hook pre-commit").expect("block");
+    let exec = content.find("exec git diff-index").expect("exec");
+    assert!(block < exec, "{content}");
+
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+    let bin_dir = Path::new(assert_cmd::cargo::cargo_bin!("layer")).parent().unwrap().to_path_buf();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+    let commit = std::process::Command::new("git")
+        .current_dir(repo.path())
+        .env("PATH", &path)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-q", "-m", "ctx"])
+        .output()
+        .expect("git commit");
+    assert!(!commit.status.success());
+    assert!(String::from_utf8_lossy(&commit.stderr).contains("would add local context files"));
+}
+
+#[test]
+fn hook_install_runs_before_an_existing_hook_that_ends_in_exit() {
+    let repo = init_repo();
+    let hook = repo.path().join(".git/hooks/post-merge");
+    fs::create_dir_all(hook.parent().unwrap()).expect("mkdir");
+    fs::write(&hook, "#!/bin/sh\nnpm install\nexit 0\n").expect("write hook");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install", "--post-merge"])
+        .assert()
+        .success();
+    let content = fs::read_to_string(&hook).expect("read hook");
+    assert_eq!(
+        content,
+        "#!/bin/sh\n# >>> layer post-merge >>>\n\
+         command -v layer >/dev/null 2>&1 && layer hook post-merge || true\n\
+         # <<< layer post-merge <<<\nnpm install\nexit 0\n"
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "uninstall", "--post-merge"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hook).expect("read hook"), "#!/bin/sh\nnpm install\nexit 0\n");
+}
+
+#[test]
+fn pre_commit_hook_blocks_commits_that_add_context_files() {
+    let repo = init_repo();
+    let hook = repo.path().join(".git/hooks/pre-commit");
+    fs::create_dir_all(hook.parent().unwrap()).expect("mkdir");
+    fs::write(&hook, "#!/bin/sh\ntrue\n").expect("write hook");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "install", "--pre-commit"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added layer to the existing pre-commit hook"));
+
+    fs::write(repo.path().join("README.md"), "hi").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["add", "README.md", "CLAUDE.md"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "pre-commit"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("CLAUDE.md (Claude Code)"));
+
+    // The installed hook stops a real commit when layer is on PATH.
+    let bin_dir = Path::new(assert_cmd::cargo::cargo_bin!("layer")).parent().unwrap().to_path_buf();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap_or_default());
+    let commit = std::process::Command::new("git")
+        .current_dir(repo.path())
+        .env("PATH", &path)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-q", "-m", "ctx"])
+        .output()
+        .expect("git commit");
+    assert!(!commit.status.success());
+    assert!(String::from_utf8_lossy(&commit.stderr).contains("would add local context files"));
+
+    git(repo.path(), &["rm", "-q", "--cached", "CLAUDE.md"]);
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "pre-commit"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn post_merge_warns_when_upstream_tracks_context_files() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hi").expect("write");
+    git(repo.path(), &["add", "README.md"]);
+    git(repo.path(), &["commit", "-q", "-m", "base"]);
+    git(repo.path(), &["update-ref", "ORIG_HEAD", "HEAD"]);
+
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["add", "-f", ".cursorrules", "CLAUDE.md"]);
+    git(repo.path(), &["commit", "-q", "-m", "upstream"]);
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\n.cursorrules\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "post-merge"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "upstream now tracks .cursorrules — your layer entry '.cursorrules' is ineffective",
+        ))
+        .stdout(predicate::str::contains("upstream now tracks CLAUDE.md (Claude Code)"));
+}
+
+#[test]
+fn status_json_reports_sections_with_fixes() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\nAGENTS.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--json"])
+        .output()
+        .expect("run status");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.trim_start().starts_with('{'), "{stdout}");
+    assert!(stdout.contains("\"layered\": [\n    \"AGENTS.md\"\n  ]"), "{stdout}");
+    assert!(stdout.contains("\"entry\": \"CLAUDE.md\""), "{stdout}");
+    assert!(stdout.contains("\"git rm --cached CLAUDE.md\""), "{stdout}");
+    assert!(stdout.contains("\"path\": \".cursorrules\""), "{stdout}");
+    assert!(stdout.contains("\"fix\": \"layer add .cursorrules\""), "{stdout}");
+}
+
+#[test]
+fn status_rev_audits_another_branch_without_checkout() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hi").expect("write");
+    git(repo.path(), &["add", "README.md"]);
+    git(repo.path(), &["commit", "-q", "-m", "base"]);
+    git(repo.path(), &["branch", "clean"]);
+
+    fs::create_dir_all(repo.path().join(".cursor/rules")).expect("mkdir");
+    fs::write(repo.path().join(".cursor/rules/a.md"), "a").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["checkout", "-q", "-b", "release"]);
+    git(repo.path(), &["add", "CLAUDE.md", ".cursor"]);
+    git(repo.path(), &["commit", "-q", "-m", "oops"]);
+    git(repo.path(), &["checkout", "-q", "clean"]);
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--rev", "release"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Committed at release (2)"))
+        .stdout(predicate::str::contains(".cursor/"))
+        .stdout(predicate::str::contains("Layered here but tracked at release (1)"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--rev", "clean"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No context files are committed at clean."));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--rev", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown revision 'nope'"));
+}
+
+#[test]
+fn doctor_json_reports_kinds_fixes_and_summary() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\nAGENTS.md\nGONE.md\n# end layer\n",
+    )
+    .expect("write exclude");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["doctor", "--json"])
+        .output()
+        .expect("run doctor");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"kind\": \"exposed\""), "{stdout}");
+    assert!(stdout.contains("\"fix\": \"git rm --cached CLAUDE.md\""), "{stdout}");
+    assert!(stdout.contains("\"fix\": \"layer rm GONE.md\""), "{stdout}");
+    assert!(stdout.contains("\"fix\": null"), "{stdout}");
+    assert!(stdout.contains("\"layered\": 1"), "{stdout}");
+    assert!(stdout.contains("\"stale\": 1"), "{stdout}");
+}
+
+#[test]
+fn add_strict_exposed_refuses_tracked_files_unless_forced() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_ADD_STRICT_EXPOSED", "true")
+        .args(["add", "CLAUDE.md", "AGENTS.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refusing to layer tracked file CLAUDE.md"));
+    assert!(!exclude_path(repo.path()).exists()
+        || !fs::read_to_string(exclude_path(repo.path())).unwrap().contains("AGENTS.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_ADD_STRICT_EXPOSED", "true")
+        .args(["add", "--force", "CLAUDE.md", "AGENTS.md"])
+        .assert()
+        .success();
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read exclude");
+    assert!(content.contains("CLAUDE.md\nAGENTS.md\n"), "{content}");
+}
+
+#[test]
+fn scan_json_lists_discoveries_without_a_tty() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    git(repo.path(), &["add", ".cursorrules"]);
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--json"])
+        .output()
+        .expect("run scan");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"path\": \"CLAUDE.md\",\n    \"label\": \"Claude Code\",\n    \"category\": \"ai_config\""), "{stdout}");
+    assert!(stdout.contains("\"path\": \".cursorrules\""), "{stdout}");
+    assert!(stdout.contains("\"is_tracked\": true"), "{stdout}");
+    let exclude = fs::read_to_string(exclude_path(repo.path())).unwrap_or_default();
+    assert!(!exclude.contains("managed by layer"), "{exclude}");
+}
+
+#[test]
+fn fmt_reorders_entries_by_exclude_order() {
+    let repo = init_repo();
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nnotes.txt\n.cursorrules\n# [off] AGENTS.md\nCLAUDE.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_EXCLUDE_ORDER", "alphabetical")
+        .arg("fmt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alphabetical order"));
+    assert_eq!(
+        fs::read_to_string(exclude_path(repo.path())).expect("read"),
+        "# managed by layer\n.cursorrules\n# [off] AGENTS.md\nCLAUDE.md\nnotes.txt\n# end layer\n"
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_EXCLUDE_ORDER", "alphabetical")
+        .arg("fmt")
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn add_writes_entries_in_configured_order() {
+    let repo = init_repo();
+    fs::write(repo.path().join("notes.txt"), "x").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "x").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "x").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_EXCLUDE_ORDER", "tool")
+        .args(["add", "notes.txt", ".cursorrules", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    let claude = content.find("CLAUDE.md").expect("claude");
+    let cursor = content.find(".cursorrules").expect("cursor");
+    let notes = content.find("notes.txt").expect("notes");
+    assert!(claude < cursor && cursor < notes, "{content}");
+}
+
+#[test]
+fn format_porcelain_lists_entries_by_state() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("AGENTS.md"), "notes").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--format", "porcelain"])
+        .assert()
+        .success()
+        .stdout("layered\tCLAUDE.md\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--format", "porcelain"])
+        .assert()
+        .success()
+        .stdout("layered\tCLAUDE.md\ndiscovered\tAGENTS.md\n");
+}
+
+#[test]
+fn format_json_matches_command_json_flag() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+
+    let flag = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["scan", "--json"])
+        .output()
+        .expect("run");
+    let global = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "scan"])
+        .output()
+        .expect("run");
+    assert_eq!(flag.stdout, global.stdout);
+    assert!(String::from_utf8_lossy(&global.stdout).contains("\"path\": \"CLAUDE.md\""));
+}
+
+#[test]
+fn format_json_rejected_for_state_changing_commands() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["rm", "CLAUDE.md", "--format", "json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--format json is only available for reports"));
+}
+
+#[test]
+fn color_flag_controls_ansi_escapes() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("NO_COLOR", "1")
+        .args(["--color", "always", "ls"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}["));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("CLICOLOR_FORCE", "1")
+        .env("NO_COLOR", "1")
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}[").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("CLICOLOR_FORCE", "1")
+        .args(["ls", "--no-color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}[").not());
+}
+
+#[test]
+fn which_prints_only_the_matching_rule() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".gitignore"), "*.log\n").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    let line = exclude.lines().position(|l| l == "CLAUDE.md").expect("entry") + 1;
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["which", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(format!(".git/info/exclude:{line}\n"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["which", "debug.log"])
+        .assert()
+        .success()
+        .stdout(".gitignore:1\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["which", "src/main.rs"])
+        .assert()
+        .code(1)
+        .stdout("not ignored\n");
+}
+
+#[test]
+fn pick_requires_a_terminal() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("pick")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("layer pick is interactive"));
+}
+
+#[cfg(unix)]
+#[test]
+fn add_writes_through_symlinked_exclude() {
+    let repo = init_repo();
+    let dotfiles = tempfile::tempdir().expect("tempdir");
+    let target = dotfiles.path().join("exclude");
+    fs::write(&target, "*.log\n").expect("write");
+    let link = exclude_path(repo.path());
+    fs::remove_file(&link).expect("remove");
+    std::os::unix::fs::symlink(&target, &link).expect("symlink");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-c", "exclude.symlink=refuse", "add", "CLAUDE.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a symlink to"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+    assert!(fs::symlink_metadata(&link).expect("meta").file_type().is_symlink());
+    let content = fs::read_to_string(&target).expect("read");
+    assert!(content.starts_with("*.log\n") && content.contains("CLAUDE.md"), "{content}");
+}
+
+#[test]
+fn dash_c_runs_against_another_repository() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    let elsewhere = tempfile::tempdir().expect("tempdir");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(elsewhere.path())
+        .arg("-C")
+        .arg(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(exclude_path(repo.path())).expect("read").contains("CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(elsewhere.path())
+        .args(["ls", "--format", "porcelain", "-C"])
+        .arg(repo.path())
+        .assert()
+        .success()
+        .stdout("layered\tCLAUDE.md\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(elsewhere.path())
+        .args(["-C", "does-not-exist", "ls"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot change to 'does-not-exist'"));
+}
+
+#[test]
+fn backup_without_home_falls_back_to_repo_dir() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("APPDATA")
+        .arg("backup")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("HOME is not set; saving backups in"));
+
+    let backups = repo.path().join(".git/info/layer-backups");
+    let saved: Vec<_> = fs::read_dir(&backups).expect("backup dir").collect();
+    assert_eq!(saved.len(), 1);
+}
+
+#[test]
+fn verbose_traces_git_calls_to_stderr() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--verbose"])
+        .assert()
+        .stderr(predicate::str::contains("trace: git rev-parse --git-dir"))
+        .stderr(predicate::str::contains("→ exit 0 in"))
+        .stdout(predicate::str::contains("trace:").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ls")
+        .assert()
+        .stderr(predicate::str::contains("trace:").not());
+}
+
+#[test]
+fn add_check_only_reports_ineffective_entries_without_writing() {
+    let repo = init_repo();
+    fs::write(repo.path().join(".gitignore"), "!KEEP.md\nSECRET.md\n").expect("write");
+    fs::write(repo.path().join("README.md"), "readme").expect("write");
+    git(repo.path(), &["add", ".gitignore", "README.md"]);
+    let before = fs::read_to_string(exclude_path(repo.path())).unwrap_or_default();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "add", "--check-only", "CLAUDE.md", "README.md", "SECRET.md", "KEEP.md"])
+        .assert()
+        .code(1)
+        .stdout("ok\tCLAUDE.md\nfail\tREADME.md\ttracked\nfail\tSECRET.md\tignored\t.gitignore:2\nfail\tKEEP.md\tnegated\t.gitignore:1\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "--check-only", "--json", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "{ \"input\": \"CLAUDE.md\", \"entry\": \"CLAUDE.md\", \"effective\": true, \"reason\": null, \"detail\": null }",
+        ));
+
+    assert_eq!(fs::read_to_string(exclude_path(repo.path())).unwrap_or_default(), before);
+}
+
+#[test]
+fn check_fails_on_exposed_or_discovered_files() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env_remove("GITHUB_ACTIONS")
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No exposed or unlayered context files"));
+
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    git(repo.path(), &["add", ".cursorrules"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "check"])
+        .assert()
+        .code(1)
+        .stdout("exposed\t.cursorrules\ndiscovered\tCLAUDE.md\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "check", "--discovered-only"])
+        .assert()
+        .code(1)
+        .stdout("discovered\tCLAUDE.md\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["check", "--discovered-only"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["check", "--exposed-only"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(".cursorrules"));
+}
+
+#[test]
+fn maintain_backs_up_and_drops_long_stale_entries() {
+    let repo = init_repo();
+    let backup_home = tempfile::tempdir().expect("backup home");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "gone.md"])
+        .assert()
+        .success();
+
+    // The first run only starts the clock on stale entries.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", backup_home.path())
+        .current_dir(repo.path())
+        .arg("maintain")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backed up 2 entries"))
+        .stdout(predicate::str::contains("removed 0 long-stale entries (1 stale for less than 30 days)"))
+        .stdout(predicate::str::contains("status cache refreshed"));
+    assert!(fs::read_to_string(exclude_path(repo.path())).expect("read").contains("gone.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", backup_home.path())
+        .current_dir(repo.path())
+        .args(["-c", "maintain.stale_days=0", "maintain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("removed 1 long-stale entry"));
+
+    let content = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!content.contains("gone.md"));
+    assert!(content.contains("CLAUDE.md"));
+}
+
+#[test]
+fn maintain_keeps_entries_added_by_a_preset() {
+    let repo = init_repo();
+    let backup_home = tempfile::tempdir().expect("backup home");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["preset", "apply", "claude"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "gone.md"])
+        .assert()
+        .success();
+    let before = fs::read_to_string(exclude_path(repo.path())).expect("read");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .env("HOME", backup_home.path())
+        .current_dir(repo.path())
+        .args(["-c", "maintain.stale_days=0", "maintain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("removed 1 long-stale entry"));
+
+    let after = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(!after.contains("gone.md"));
+    assert_eq!(after, before.replace("gone.md\n", ""));
+}
+
+#[test]
+fn ls_verify_flags_entries_git_does_not_honor() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join("docs/sub")).expect("mkdir");
+    fs::write(repo.path().join("docs/sub/a.md"), "a").expect("write");
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("KEEP.md"), "keep").expect("write");
+    fs::write(repo.path().join(".gitignore"), "!KEEP.md\n").expect("write");
+    fs::write(
+        exclude_path(repo.path()),
+        "# managed by layer\nCLAUDE.md\ndocs/*.md\nKEEP.md\n# end layer\n",
+    )
+    .expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "ls"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stale\tdocs/*.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls", "--verify"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("git doesn't match docs/sub/a.md"))
+        .stdout(predicate::str::contains("KEEP.md re-included by !KEEP.md (.gitignore:1)"))
+        .stdout(predicate::str::contains("CLAUDE.md  layered"));
+}
+
+#[test]
+fn check_emits_github_actions_annotations() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".cursorrules"), "rules").expect("write");
+    git(repo.path(), &["add", ".cursorrules"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("GITHUB_ACTIONS", "true")
+        .arg("check")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("::error file=.cursorrules,title=Exposed context file::"))
+        .stdout(predicate::str::contains("::warning file=CLAUDE.md,title=Context file not layered::"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "gha", "ls"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only available for check"));
+}
+
+#[test]
+fn check_hook_only_looks_at_given_files() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join("notes.txt"), "mine").expect("write");
+    fs::write(repo.path().join("main.rs"), "fn main() {}").expect("write");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "notes.txt"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env_remove("GITHUB_ACTIONS")
+        .args(["check", "--hook", "main.rs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env_remove("GITHUB_ACTIONS")
+        .args(["check", "--hook", "main.rs", "./CLAUDE.md", "notes.txt"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("CLAUDE.md ("))
+        .stdout(predicate::str::contains("notes.txt (layered by 'notes.txt')"))
+        .stdout(predicate::str::contains("main.rs").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "porcelain", "check", "--hook", "CLAUDE.md"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::starts_with("blocked\tCLAUDE.md\t"));
+}
+
+#[test]
+fn man_writes_a_page_per_subcommand() {
+    let out = tempfile::tempdir().expect("tempdir");
+    let dir = out.path().join("man");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["man", "--out-dir"])
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("man pages to"));
+
+    let top = fs::read_to_string(dir.join("layer.1")).expect("layer.1");
+    assert!(top.starts_with(".ie") && top.contains(".SH SUBCOMMANDS"));
+    let restore = fs::read_to_string(dir.join("layer-restore.1")).expect("layer-restore.1");
+    assert!(restore.contains("\\-\\-replace"));
+    assert!(dir.join("layer-hook-install.1").exists());
+    assert!(!dir.join("layer-hook-post-merge.1").exists(), "hidden subcommands get no page");
+}
+
+#[cfg(unix)]
+#[test]
+fn open_exclude_passes_the_entry_line_to_the_editor() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "a.md", "b.md"])
+        .assert()
+        .success();
+
+    // Named vim so layer passes `+N`
+    let bin = tempfile::tempdir().expect("tempdir");
+    let log = bin.path().join("args");
+    let editor = bin.path().join("vim");
+    let script = write_editor_script(bin.path(), &format!("echo \"$@\" > '{}'", log.display()));
+    fs::rename(script, &editor).expect("rename");
+
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    let line = exclude.lines().position(|l| l == "b.md").expect("b.md line") + 1;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("VISUAL", &editor)
+        .args(["open-exclude", "--line", "b.md"])
+        .assert()
+        .success();
+    let args = fs::read_to_string(&log).expect("editor ran");
+    assert!(args.starts_with(&format!("+{line} ")), "{args}");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("VISUAL", &editor)
+        .args(["open-exclude", "--line", "missing.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'missing.md' is not in .git/info/exclude"));
+}
+
+#[test]
+fn ack_hides_a_repeated_warning_until_show_acked() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 exposed"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ack", "CLAUDE.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a warning id"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ack", "tracked:CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Acknowledged tracked:CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("status")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("git rm --cached CLAUDE.md").not())
+        .stdout(predicate::str::contains("1 acknowledged warning hidden"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--show-acked"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("git rm --cached CLAUDE.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["status", "--hook"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ack", "--remove", "tracked:CLAUDE.md"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("ack")
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("No acknowledged warnings"));
+}
+
+#[test]
+fn untrack_removes_from_index_and_layers_uncovered_files() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    fs::write(repo.path().join(".gitignore"), "*.log\n").expect("write");
+    fs::write(repo.path().join("debug.log"), "x").expect("write");
+    git(repo.path(), &["add", "-f", "CLAUDE.md", "debug.log"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["untrack", "CLAUDE.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--yes"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["untrack", "CLAUDE.md", "debug.log", "nope.md", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("'nope.md' is not tracked by git"))
+        .stdout(predicate::str::contains("Untracked 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("Layered 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("'debug.log' is hidden by '*.log' (.gitignore:1)"));
+
+    let tracked = Command::new("git")
+        .args(["ls-files"])
+        .current_dir(repo.path())
+        .output()
+        .expect("git ls-files");
+    assert!(String::from_utf8_lossy(&tracked.stdout).trim().is_empty());
+    assert!(repo.path().join("CLAUDE.md").exists());
+    let exclude = fs::read_to_string(exclude_path(repo.path())).expect("read");
+    assert!(exclude.lines().any(|l| l == "CLAUDE.md"));
+    assert!(!exclude.contains("debug.log"));
+}
+
+#[test]
+fn add_untrack_hides_tracked_files_right_away() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").expect("write");
+    git(repo.path(), &["add", "CLAUDE.md"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "--untrack", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would untrack 'CLAUDE.md'"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "CLAUDE.md", "--untrack"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Untracked 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("Layered 'CLAUDE.md'"))
+        .stdout(predicate::str::contains("tracked by Git").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .arg("status")
+        .assert()
+        .success();
+}
+
+#[test]
+fn session_entries_go_away_when_the_session_ends() {
+    let repo = init_repo();
+    let layered = |repo: &Path| {
+        fs::read_to_string(exclude_path(repo))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    // A pid that can't be running: the next command drops its entries.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_SESSION", "4194305")
+        .args(["add", "--session", "gone.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Only for this session"));
+    assert!(layered(repo.path()).contains(&"gone.md".to_string()));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_SESSION", "work")
+        .args(["add", "--session", "scratch.md"])
+        .assert()
+        .success();
+    assert!(!layered(repo.path()).contains(&"gone.md".to_string()));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_SESSION", "work")
+        .args(["session", "ls"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("scratch.md"))
+        .stdout(predicate::str::contains("this session"));
+
+    // Named sessions only end explicitly.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env("LAYER_SESSION", "work")
+        .args(["session", "end"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed session entry 'scratch.md'"));
+    assert!(!layered(repo.path()).contains(&"scratch.md".to_string()));
+
+    // Started by something other than a shell (here the test binary), the
+    // session would end with it, so layer says so.
+    #[cfg(unix)]
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .env_remove("LAYER_SESSION")
+        .args(["add", "--session", "brief.md"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("rather than your interactive shell"))
+        .stderr(predicate::str::contains("Set LAYER_SESSION"));
+}
+
+#[test]
+fn meta_records_origin_and_note_and_recent_lists_them() {
+    let repo = init_repo();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "notes.md", "--note", "scratch for the refactor"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["recent"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("notes.md"))
+        .stdout(predicate::str::contains("by add"))
+        .stdout(predicate::str::contains("scratch for the refactor"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["--format", "json", "ls"])
+        .assert()
+        .stdout(predicate::str::contains("\"origin\": \"add\""));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["ls"])
+        .assert()
+        .stdout(predicate::str::contains("— scratch for the refactor"))
+        .stdout(predicate::str::contains("by add").not());
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-v", "ls"])
+        .assert()
+        .stdout(predicate::str::contains("by add — scratch for the refactor"));
+    fs::write(repo.path().join("notes.md"), "x").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["why", "notes.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note:      scratch for the refactor"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["rm", "notes.md"])
+        .assert()
+        .success();
+    let meta = fs::read_to_string(exclude_path(repo.path()).with_file_name("layer-meta.json")).unwrap();
+    assert!(!meta.contains("notes.md"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["recent"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn hook_devcontainer_prints_a_post_create_command_that_restores_the_layer() {
+    let repo = init_repo();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["hook", "devcontainer"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"postCreateCommand\": \"command -v layer >/dev/null 2>&1 && [ -f .layer-backup.txt ] && layer init --from .layer-backup.txt || true\"",
+        ))
+        .stdout(predicate::str::contains("doesn't exist yet"));
+
+    // The printed snippet brings the layer back into a wiped exclude file.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", ".layer-backup.txt", "notes.md"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["backup", "-o", ".layer-backup.txt"])
+        .assert()
+        .success();
+    fs::write(exclude_path(repo.path()), "").unwrap();
+    Command::new("sh")
+        .current_dir(repo.path())
+        .env("PATH", format!(
+            "{}:{}",
+            assert_cmd::cargo::cargo_bin!("layer").parent().unwrap().display(),
+            std::env::var("PATH").unwrap_or_default()
+        ))
+        .args(["-c", "command -v layer >/dev/null 2>&1 && [ -f .layer-backup.txt ] && layer init --from .layer-backup.txt || true"])
+        .assert()
+        .success();
+    let exclude = fs::read_to_string(exclude_path(repo.path())).unwrap();
+    assert!(exclude.contains("notes.md"));
+}
+
+#[test]
+fn report_writes_counts_without_entry_names() {
+    let repo = init_repo();
+    fs::write(repo.path().join("secret-plan.md"), "x").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "secret-plan.md", ".cursor/"])
+        .assert()
+        .success();
+
+    let out = tempfile::tempdir().unwrap();
+    let report_path = out.path().join("report.json");
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["-c", "backup.machine=alice-laptop", "-c", "scan.patterns=TEAM.md", "report", "-o"])
+        .arg(&report_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote diagnostic report"));
+
+    let report = fs::read_to_string(&report_path).unwrap();
+    assert!(report.contains("\"entries\": 2"), "{report}");
+    assert!(!report.contains("alice-laptop") && !report.contains("TEAM.md"), "{report}");
+    assert!(report.contains("\"directories\": 1"), "{report}");
+    assert!(report.contains("\"status_timing_ms\""), "{report}");
+    assert!(report.contains("\"layer\": \""), "{report}");
+    assert!(!report.contains("secret-plan"), "{report}");
+    assert!(!report.contains(&repo.path().display().to_string()), "{report}");
+}
+
+#[test]
+fn backup_follows_the_repo_when_its_directory_is_renamed() {
+    let parent = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hi").unwrap();
+    git(repo.path(), &["add", "README.md"]);
+    git(repo.path(), &["commit", "-q", "-m", "base"]);
+    let old_path = parent.path().join("old-name");
+    fs::rename(repo.path(), &old_path).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(&old_path)
+        .env("HOME", home.path())
+        .args(["add", "notes.md"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(&old_path)
+        .env("HOME", home.path())
+        .env("LAYER_BACKUP_MACHINE", "laptop")
+        .arg("backup")
+        .assert()
+        .success();
+    let backups = home.path().join(".layer-backups/laptop");
+    assert!(fs::read_to_string(backups.join("old-name.txt")).unwrap().contains("# id: "));
+
+    let new_path = parent.path().join("new-name");
+    fs::rename(&old_path, &new_path).unwrap();
+    fs::write(exclude_path(&new_path), "").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(&new_path)
+        .env("HOME", home.path())
+        .env("LAYER_BACKUP_MACHINE", "laptop")
+        .args(["restore", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved this repo's backup").not());
+    assert!(fs::read_to_string(exclude_path(&new_path)).unwrap().contains("notes.md"));
+    assert!(backups.join("old-name.txt").exists(), "restore only reads the backup");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(&new_path)
+        .env("HOME", home.path())
+        .env("LAYER_BACKUP_MACHINE", "laptop")
+        .arg("backup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved this repo's backup"));
+    assert!(backups.join("new-name.txt").exists());
+    assert!(!backups.join("old-name.txt").exists());
+}
+
+#[test]
+fn backups_from_machines_sharing_a_home_stay_apart() {
+    let home = tempfile::tempdir().unwrap();
+    let repo = init_repo();
+    fs::write(repo.path().join("notes.md"), "x").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .current_dir(repo.path())
+        .args(["add", "notes.md"])
+        .assert()
+        .success();
+    let layer = |machine: &str, args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("layer"));
+        cmd.current_dir(repo.path())
+            .env("HOME", home.path())
+            .env("LAYER_BACKUP_MACHINE", machine)
+            .args(args);
+        cmd
+    };
+
+    layer("laptop", &["backup"]).assert().success();
+    fs::write(exclude_path(repo.path()), "").unwrap();
+    layer("desktop", &["backup"]).assert().success();
+    let backups = home.path().join(".layer-backups");
+    assert!(backups.join("laptop").is_dir() && backups.join("desktop").is_dir());
+
+    layer("desktop", &["restore", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("this machine is 'desktop'"))
+        .stdout(predicate::str::contains("laptop"))
+        .stdout(predicate::str::contains("--machine <machine>"));
+
+    // This machine's own (empty) backup wins unless another one is picked.
+    layer("desktop", &["restore", "--yes"]).assert().code(2);
+    layer("desktop", &["restore", "--yes", "--machine", "laptop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 entries"));
+
+    // A machine without a backup of its own falls back to the only one there is.
+    fs::remove_dir_all(backups.join("desktop")).unwrap();
+    fs::write(exclude_path(repo.path()), "").unwrap();
+    layer("server", &["restore", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("using the one from 'laptop'"));
+}
+
+#[test]
+fn foreach_runs_a_subcommand_in_every_manifest_repo() {
+    let first = init_repo();
+    let second = init_repo();
+    let manifests = tempfile::tempdir().unwrap();
+    let manifest = manifests.path().join("repos.txt");
+    fs::write(
+        &manifest,
+        format!("# clones\n{}\n\n{}\n", first.path().display(), second.path().display()),
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["foreach", "--manifest", manifest.to_str().unwrap(), "--", "add", "CLAUDE.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("layer add CLAUDE.md in 2 repos:"));
+    for repo in [&first, &second] {
+        let exclude = fs::read_to_string(exclude_path(repo.path())).unwrap();
+        assert!(exclude.lines().any(|line| line == "CLAUDE.md"));
+    }
+
+    fs::write(&manifest, format!("{}\nmissing\n", first.path().display())).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["foreach", "-m", manifest.to_str().unwrap(), "--", "add", "CLAUDE.md"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("missing — not a directory"));
+}
+
+#[test]
+fn foreach_forwards_format_color_and_verbose_to_each_run() {
+    let repo = init_repo();
+    fs::write(repo.path().join("CLAUDE.md"), "notes").unwrap();
+    let manifests = tempfile::tempdir().unwrap();
+    let manifest = manifests.path().join("repos.txt");
+    fs::write(&manifest, format!("{}\n", repo.path().display())).unwrap();
+
+    // JSON comes back as one array with each repo's own report inside.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["--format", "json", "--no-color", "-v", "foreach", "-m", manifest.to_str().unwrap(), "--", "scan"])
+        .output()
+        .expect("run foreach");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let trimmed = stdout.trim();
+    assert!(trimmed.starts_with('[') && trimmed.ends_with(']'), "{stdout}");
+    assert!(stdout.contains(&format!("\"repo\": \"{}\"", repo.path().display())), "{stdout}");
+    assert!(stdout.contains("\"exit_code\": 0") && stdout.contains("\"path\": \"CLAUDE.md\""), "{stdout}");
+    assert!(!stdout.contains("\x1b[") && !stdout.contains("repos:"), "{stdout}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("git "));
+
+    // Other machine formats keep stdout to the children and comment on stderr.
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["--format", "porcelain", "foreach", "-m", manifest.to_str().unwrap(), "--", "scan"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CLAUDE.md"))
+        .stdout(predicate::str::contains("repos:").not())
+        .stderr(predicate::str::contains("layer scan in 1 repos:"));
+    Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+        .args(["--format", "{entry}", "foreach", "-m", manifest.to_str().unwrap(), "--", "scan"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--format templates are only available for ls and status"));
 }
@@ -0,0 +1,134 @@
+//! Golden output for the commands people and scripts read most. Each test
+//! builds a fixture repo, runs one command in plain mode, and snapshots what
+//! it printed, so UI and format changes show up as a reviewable diff.
+//!
+//! After an intended output change, accept the new snapshots with
+//! `cargo insta review` (or `INSTA_UPDATE=always cargo test --test snapshots`).
+
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A throwaway repo with an isolated config home, built up file by file.
+struct Fixture {
+    repo: TempDir,
+    home: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let home = tempfile::tempdir().expect("tempdir");
+        let global_ignore = home.path().join("global-ignore");
+        fs::write(&global_ignore, "").expect("write");
+        let fixture = Self { repo, home };
+        fixture.git(&["init", "-q"]);
+        fixture.git(&["config", "core.excludesFile", global_ignore.to_str().unwrap()]);
+        fixture.git(&["config", "user.email", "layer@example.com"]);
+        fixture.git(&["config", "user.name", "Layer Test"]);
+        fixture
+    }
+
+    /// Write a file, creating its parent directories.
+    fn file(self, path: &str, content: &str) -> Self {
+        let full = self.repo.path().join(path);
+        fs::create_dir_all(full.parent().unwrap()).expect("mkdir");
+        fs::write(full, content).expect("write");
+        self
+    }
+
+    /// Write a file and commit it.
+    fn tracked(self, path: &str, content: &str) -> Self {
+        let fixture = self.file(path, content);
+        fixture.git(&["add", path]);
+        fixture.git(&["commit", "-q", "-m", path]);
+        fixture
+    }
+
+    /// Layer entries with `layer add`.
+    fn layered(self, entries: &[&str]) -> Self {
+        let mut args = vec!["add"];
+        args.extend_from_slice(entries);
+        self.layer(&args);
+        self
+    }
+
+    fn git(&self, args: &[&str]) {
+        Command::new("git")
+            .args(args)
+            .current_dir(self.repo.path())
+            .assert()
+            .success();
+    }
+
+    /// Run layer and return what it printed to stdout, with the repo path
+    /// replaced so snapshots don't depend on the temp directory.
+    fn layer(&self, args: &[&str]) -> String {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("layer"))
+            .current_dir(self.repo.path())
+            .env("XDG_CONFIG_HOME", self.home.path())
+            .env("LAYER_SYSTEM_CONFIG", self.home.path().join("missing.toml"))
+            .env_remove("LAYER_SCAN_DEPTH")
+            .env_remove("LAYER_CLEAN_ALL")
+            .env("NO_COLOR", "1")
+            .args(args)
+            .output()
+            .expect("run layer");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        redact(&stdout, self.repo.path())
+    }
+}
+
+fn redact(output: &str, repo: &Path) -> String {
+    let canonical = repo.canonicalize().unwrap_or_else(|_| repo.to_path_buf());
+    output
+        .replace(&canonical.display().to_string(), "[REPO]")
+        .replace(&repo.display().to_string(), "[REPO]")
+}
+
+/// A repo with one of each state: layered, exposed, discovered, and stale.
+fn mixed_repo() -> Fixture {
+    Fixture::new()
+        .tracked("README.md", "# demo\n")
+        .tracked("AGENTS.md", "agents\n")
+        .file("CLAUDE.md", "claude\n")
+        .file(".cursorrules", "rules\n")
+        .file(".env", "SECRET=1\n")
+        .layered(&["CLAUDE.md", "AGENTS.md", "notes/"])
+}
+
+#[test]
+fn status_plain() {
+    insta::assert_snapshot!(mixed_repo().layer(&["status"]));
+}
+
+#[test]
+fn status_json() {
+    insta::assert_snapshot!(mixed_repo().layer(&["status", "--format", "json"]));
+}
+
+#[test]
+fn status_porcelain() {
+    insta::assert_snapshot!(mixed_repo().layer(&["status", "--format", "porcelain"]));
+}
+
+#[test]
+fn ls_plain() {
+    insta::assert_snapshot!(mixed_repo().layer(&["ls"]));
+}
+
+#[test]
+fn scan_json() {
+    insta::assert_snapshot!(mixed_repo().layer(&["scan", "--format", "json"]));
+}
+
+#[test]
+fn scan_porcelain() {
+    insta::assert_snapshot!(mixed_repo().layer(&["scan", "--format", "porcelain"]));
+}
+
+#[test]
+fn scan_in_an_empty_repo() {
+    insta::assert_snapshot!(Fixture::new().layer(&["scan"]));
+}